@@ -0,0 +1,92 @@
+//! Plays a hand from fuzzed stacks, deck and actions and checks what must always hold: no
+//! chip is made or lost, no stack goes below zero, the hand ends, someone still in it wins
+//! and nobody wins more from an opponent than they put in against them. The same checks
+//! run as a property test in the crate's own tests.
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use poker_tui::core_engine::Deck;
+use poker_tui::phase::{AwaitingAction, Phase};
+use poker_tui::{GameState, HandState, PokerAction};
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+
+fn chips_on_table(hs: &HandState) -> u32 {
+    let snapshot = hs.spectator_snapshot();
+    snapshot.pot
+        + snapshot
+            .chips
+            .iter()
+            .map(|pc| pc.stack + pc.bet)
+            .sum::<u32>()
+}
+
+/// An action for each byte, with the raise sized into what is legal.
+fn action(hs: &HandState, byte: u8) -> PokerAction {
+    match (byte % 4, hs.min_raise()) {
+        (0, _) => PokerAction::Fold,
+        (1, Some(min)) => PokerAction::Raise((byte as u32 / 4).clamp(min, hs.max_raise())),
+        _ => PokerAction::CallOrCheck,
+    }
+}
+
+fuzz_target!(|input: (u64, Vec<u8>, Vec<u8>)| {
+    let (seed, stacks, bytes) = input;
+    let stacks: Vec<u32> = stacks.iter().take(9).map(|&s| s as u32 + 2).collect();
+    let Ok(gs) = GameState::builder().stacks(stacks.clone()).build() else {
+        return;
+    };
+    let total: u32 = stacks.iter().sum();
+    let deck = Deck::shuffled_with(&mut StdRng::seed_from_u64(seed));
+    let mut hand = AwaitingAction::start(&gs, deck);
+
+    // Once the bytes run out everyone calls. Each raise costs a chip, so a hand can't
+    // last longer than this.
+    let mut bytes = bytes.into_iter();
+    let mut played = 0;
+    let complete = loop {
+        assert!(
+            played <= total as usize + 4 * stacks.len(),
+            "the hand never ends"
+        );
+        let action = bytes
+            .next()
+            .map_or(PokerAction::CallOrCheck, |b| action(hand.hand(), b));
+        let (phase, _) = hand
+            .act(action)
+            .unwrap_or_else(|_| panic!("a legal action was refused"));
+        played += 1;
+        let hs = phase.hand();
+        assert_eq!(chips_on_table(hs), total);
+        assert!(hs
+            .spectator_snapshot()
+            .chips
+            .iter()
+            .all(|pc| pc.stack <= total));
+        hand = match phase {
+            Phase::AwaitingAction(hand) => hand,
+            Phase::StreetComplete(street) => street.next(),
+            Phase::HandComplete(complete) => break complete,
+        };
+    };
+
+    let hs = complete.hand();
+    assert!(hs.active_players().any(|p| p == complete.winner()));
+    let committed = hs.pots().committed;
+    let next = complete.next_game(&gs);
+    for (p, &stack) in stacks.iter().enumerate() {
+        let won = next.current_chips(p).saturating_sub(stack);
+        let covered: u32 = (0..stacks.len())
+            .filter(|&q| q != p)
+            .map(|q| committed[q].min(committed[p]))
+            .sum();
+        assert!(won <= covered, "player {p} won {won} of {covered}");
+    }
+    assert_eq!(
+        (0..stacks.len())
+            .map(|p| next.current_chips(p))
+            .sum::<u32>(),
+        total
+    );
+});
@@ -0,0 +1,240 @@
+use crate::bots::{made_hand, playable, raise_or_call, BotContext, BotPlayer, PlayerView, RuleBot};
+use crate::events::GameEvent;
+use crate::stats::{PlayerStats, SessionStats};
+use crate::{PokerAction, BIG_BLIND_CHIPS};
+
+/// Hands to watch an opponent for before playing differently against them.
+const MIN_HANDS_OBSERVED: u32 = 10;
+/// Percentage of 3-bet chances taken above which a re-raise may well be a bluff.
+const LIGHT_THREE_BET: f64 = 25.0;
+
+/// Plays like a `RuleBot`, adjusted to how its opponents played earlier in the session:
+/// thinner value bets against players who rarely fold, more bluffs against players who
+/// often do, and lighter calls against players who raise a lot or 3-bet light.
+#[derive(Default)]
+pub struct AdaptiveBot {
+    stats: Option<SessionStats>,
+}
+
+/// What the bot has learned about the opponents still in the hand.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Read {
+    /// At least one of them plays many hands and rarely folds to a bet.
+    Station,
+    /// All of them give up to continuation bets.
+    Folder,
+    /// At least one of them raises more often than calls.
+    Maniac,
+}
+
+impl AdaptiveBot {
+    fn read(&self, view: &PlayerView) -> Option<Read> {
+        let stats = self.stats.as_ref()?;
+        let opponents: Vec<&PlayerStats> = (0..view.active.len())
+            .filter(|&p| p != view.player && view.active[p])
+            .map(|p| stats.player(p))
+            .filter(|s| s.hands_played >= MIN_HANDS_OBSERVED)
+            .collect();
+        if opponents.is_empty() {
+            return None;
+        }
+
+        let station = |s: &&PlayerStats| s.vpip() >= 40.0 && s.fold_to_cbet() <= 30.0;
+        let folder = |s: &&PlayerStats| s.cbets_faced >= 3 && s.fold_to_cbet() >= 60.0;
+        if opponents.iter().any(station) {
+            Some(Read::Station)
+        } else if opponents.iter().all(folder) {
+            Some(Read::Folder)
+        } else if opponents.iter().any(|s| s.aggression() > 1.0) {
+            Some(Read::Maniac)
+        } else {
+            None
+        }
+    }
+
+    /// Whether the opponent who put the most in preflop re-raises often enough that a
+    /// re-raise of this player's chips may be light.
+    fn facing_light_three_bet(&self, view: &PlayerView) -> bool {
+        let Some(stats) = &self.stats else {
+            return false;
+        };
+        let raiser = (0..view.bets.len())
+            .filter(|&p| p != view.player)
+            .max_by_key(|&p| view.bets[p]);
+        view.bets[view.player] > BIG_BLIND_CHIPS
+            && raiser.is_some_and(|p| {
+                let s = stats.player(p);
+                s.hands_played >= MIN_HANDS_OBSERVED && s.three_bet() >= LIGHT_THREE_BET
+            })
+    }
+}
+
+impl BotPlayer for AdaptiveBot {
+    fn decide(&mut self, view: &BotContext) -> PokerAction {
+        self.decide_explained(view).0
+    }
+
+    fn decide_explained(&mut self, view: &BotContext) -> (PokerAction, Option<String>) {
+        let (action, reason) = RuleBot.decide_explained(view);
+        if view.board.is_empty() {
+            if action == PokerAction::Fold
+                && playable(view.cards)
+                && self.facing_light_three_bet(view)
+            {
+                let why = "calling a player who 3-bets light";
+                return (PokerAction::CallOrCheck, Some(why.to_owned()));
+            }
+            return (action, reason);
+        }
+
+        let (_, made) = made_hand(view);
+        let checked_to = action == PokerAction::CallOrCheck && view.expected_call == 0;
+        let adjusted = match self.read(view) {
+            Some(Read::Station) if checked_to && made >= 1 => Some((
+                raise_or_call(view, 3),
+                "thin value bet against a calling station",
+            )),
+            Some(Read::Folder) if checked_to => Some((
+                raise_or_call(view, 2),
+                "bluff against players who fold to bets",
+            )),
+            Some(Read::Maniac) if action == PokerAction::Fold && made >= 1 => Some((
+                PokerAction::CallOrCheck,
+                "calling down a player who raises too much",
+            )),
+            _ => None,
+        };
+        match adjusted {
+            Some((action, why)) => (action, Some(why.to_owned())),
+            None => (action, reason),
+        }
+    }
+
+    fn hand_finished(&mut self, events: &[GameEvent]) {
+        let players = events
+            .iter()
+            .filter(|e| matches!(e, GameEvent::HoleCardsDealt { .. }))
+            .count();
+        self.stats
+            .get_or_insert_with(|| SessionStats::init(players))
+            .record_hand(events);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core_engine::Card;
+    use crate::events::Street;
+
+    fn view(cards: &str, board: &str, expected_call: u32) -> BotContext {
+        let cards: Vec<Card> = format!("{cards} {board}")
+            .split_ascii_whitespace()
+            .map(|c| Card::try_from(c).unwrap())
+            .collect();
+        BotContext::from(PlayerView {
+            player: 0,
+            button: 0,
+            cards: (cards[0], cards[1]),
+            street: Street::from_board_size(cards.len() - 2),
+            board: cards[2..].to_vec(),
+            stacks: vec![100, 100],
+            bets: vec![0, expected_call],
+            active: vec![true, true],
+            pot: 20,
+            expected_call,
+            min_raise: expected_call + BIG_BLIND_CHIPS,
+        })
+    }
+
+    /// Player 1 calls preflop and folds to the flop bet of player 0.
+    fn folded_to_cbet() -> Vec<GameEvent> {
+        vec![
+            GameEvent::HoleCardsDealt {
+                player: 0,
+                cards: [Card::try_from("S2").unwrap(), Card::try_from("S3").unwrap()],
+            },
+            GameEvent::HoleCardsDealt {
+                player: 1,
+                cards: [Card::try_from("H2").unwrap(), Card::try_from("H3").unwrap()],
+            },
+            GameEvent::Raised {
+                player: 0,
+                street: Street::Preflop,
+                amount: 6,
+            },
+            GameEvent::Called {
+                player: 1,
+                street: Street::Preflop,
+                amount: 4,
+            },
+            GameEvent::Raised {
+                player: 0,
+                street: Street::Flop,
+                amount: 6,
+            },
+            GameEvent::Folded {
+                player: 1,
+                street: Street::Flop,
+            },
+        ]
+    }
+
+    #[test]
+    fn should_bluff_players_who_fold_to_bets() {
+        let mut bot = AdaptiveBot::default();
+        let nothing = view("S3 D2", "H14 C13 H9", 0);
+        assert_eq!(bot.decide(&nothing), PokerAction::CallOrCheck);
+
+        for _ in 0..MIN_HANDS_OBSERVED {
+            bot.hand_finished(&folded_to_cbet());
+        }
+        let (action, reason) = bot.decide_explained(&nothing);
+        assert!(matches!(action, PokerAction::Raise(_)));
+        assert_eq!(reason.unwrap(), "bluff against players who fold to bets");
+    }
+
+    #[test]
+    fn should_call_players_who_three_bet_light() {
+        let mut bot = AdaptiveBot::default();
+        let mut facing_three_bet = view("S9 H9", "", 8);
+        facing_three_bet.view.bets = vec![4, 12];
+        assert_eq!(bot.decide(&facing_three_bet), PokerAction::Fold);
+
+        let mut three_bets = folded_to_cbet();
+        three_bets[3] = GameEvent::Raised {
+            player: 1,
+            street: Street::Preflop,
+            amount: 14,
+        };
+        for _ in 0..MIN_HANDS_OBSERVED {
+            bot.hand_finished(&three_bets);
+        }
+        let (action, reason) = bot.decide_explained(&facing_three_bet);
+        assert_eq!(action, PokerAction::CallOrCheck);
+        assert_eq!(reason.unwrap(), "calling a player who 3-bets light");
+    }
+
+    #[test]
+    fn should_value_bet_thinner_against_stations() {
+        let mut bot = AdaptiveBot::default();
+        let mut calls_down = folded_to_cbet();
+        calls_down[5] = GameEvent::Called {
+            player: 1,
+            street: Street::Flop,
+            amount: 6,
+        };
+        for _ in 0..MIN_HANDS_OBSERVED {
+            bot.hand_finished(&calls_down);
+        }
+
+        // Bottom pair on a board with a flush draw.
+        let (action, reason) = bot.decide_explained(&view("S9 D2", "H14 H13 H9", 0));
+        assert!(matches!(action, PokerAction::Raise(_)));
+        assert_eq!(reason.unwrap(), "thin value bet against a calling station");
+        assert_eq!(
+            bot.decide(&view("S3 D2", "H14 C13 H9", 0)),
+            PokerAction::CallOrCheck
+        );
+    }
+}
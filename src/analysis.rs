@@ -0,0 +1,185 @@
+use crate::core_engine::{Card, Deck, Hand};
+use crate::equity::for_each_combination;
+
+/// The best five-card hand out of five to seven cards.
+pub fn best_hand(cards: &[Card]) -> Option<Hand> {
+    let mut best: Option<Hand> = None;
+    for_each_combination(cards, 5, &mut vec![], &mut |c| {
+        let hand = Hand([c[0], c[1], c[2], c[3], c[4]]);
+        if best.is_none_or(|b| hand > b) {
+            best = Some(hand);
+        }
+    });
+    best
+}
+
+/// The unseen cards that would improve the kind of hand the player holds on the flop or the
+/// turn. Cards that only improve the board for everyone, like one pairing the board, don't
+/// count.
+pub fn outs(hole: (Card, Card), board: &[Card]) -> Vec<Card> {
+    if !(3..=4).contains(&board.len()) {
+        return vec![];
+    }
+    let mut cards = vec![hole.0, hole.1];
+    cards.extend(board);
+    let current = best_hand(&cards).unwrap().category();
+
+    Deck::ordered_deck()
+        .cards()
+        .iter()
+        .filter(|c| !cards.contains(c))
+        .filter(|&&c| {
+            let mut with_card = cards.clone();
+            with_card.push(c);
+            let improved = best_hand(&with_card).unwrap().category();
+            let board_only = best_hand(&with_card[2..]).map_or(0, |h| h.category());
+            let pairs_board = board.iter().any(|b| b.value == c.value)
+                && hole.0.value != c.value
+                && hole.1.value != c.value;
+            improved > current && improved > board_only && (improved >= 4 || !pairs_board)
+        })
+        .copied()
+        .collect()
+}
+
+/// The Chen formula score of a starting hand, higher is better.
+fn chen_score(cards: (Card, Card)) -> f64 {
+    let (high, low) = (
+        cards.0.value.max(cards.1.value),
+        cards.0.value.min(cards.1.value),
+    );
+    let points = |value: u8| match value {
+        14 => 10.0,
+        13 => 8.0,
+        12 => 7.0,
+        11 => 6.0,
+        v => v as f64 / 2.0,
+    };
+    if high == low {
+        return (points(high) * 2.0).max(5.0);
+    }
+    let gap = high - low - 1;
+    let mut score = points(high)
+        - match gap {
+            0 => 0.0,
+            1 => 1.0,
+            2 => 2.0,
+            3 => 4.0,
+            _ => 5.0,
+        };
+    if cards.0.suit == cards.1.suit {
+        score += 2.0;
+    }
+    if gap <= 1 && high < 12 {
+        score += 1.0;
+    }
+    score
+}
+
+/// The share of starting hands this one is better than, from 0 for the worst to almost 1
+/// for a pair of aces. Ties count half.
+pub fn preflop_strength(cards: (Card, Card)) -> f64 {
+    let score = chen_score(cards);
+    let mut better_than = 0.0;
+    let mut hands = 0;
+    for_each_combination(Deck::ordered_deck().cards(), 2, &mut vec![], &mut |c| {
+        let other = chen_score((c[0], c[1]));
+        if other < score {
+            better_than += 1.0;
+        } else if other == score {
+            better_than += 0.5;
+        }
+        hands += 1;
+    });
+    better_than / hands as f64
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct BoardTexture {
+    /// Two or more board cards share a value.
+    pub paired: bool,
+    /// Three or more board cards share a suit.
+    pub flush_possible: bool,
+    /// Three board cards fit in a straight.
+    pub straight_possible: bool,
+}
+
+pub fn texture(board: &[Card]) -> BoardTexture {
+    let mut values: Vec<u8> = board.iter().map(|c| c.value).collect();
+    if values.contains(&14) {
+        values.push(1);
+    }
+    values.sort();
+    values.dedup();
+
+    BoardTexture {
+        paired: board
+            .iter()
+            .enumerate()
+            .any(|(i, c)| board[i + 1..].iter().any(|o| o.value == c.value)),
+        flush_possible: board
+            .iter()
+            .any(|c| board.iter().filter(|o| o.suit == c.suit).count() >= 3),
+        straight_possible: values.windows(3).any(|w| w[2] - w[0] <= 4),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cards(s: &str) -> Vec<Card> {
+        s.split_ascii_whitespace()
+            .map(|c| Card::try_from(c).unwrap())
+            .collect()
+    }
+
+    fn hole(s: &str) -> (Card, Card) {
+        let c = cards(s);
+        (c[0], c[1])
+    }
+
+    #[test]
+    fn should_find_best_hand_of_six_cards() {
+        let hand = best_hand(&cards("H2 H7 H9 S9 H11 H13")).unwrap();
+        assert_eq!(hand.ranking_name(), "Flush");
+        assert!(best_hand(&cards("H2 H7 H9 S9")).is_none());
+    }
+
+    #[test]
+    fn should_count_flush_draw_outs() {
+        let outs = outs(hole("H14 H13"), &cards("H2 H7 S9"));
+        assert_eq!(outs.iter().filter(|c| c.value < 13).count(), 9);
+        // Aces and kings pair the hole cards as well.
+        assert_eq!(outs.len(), 15);
+    }
+
+    #[test]
+    fn should_not_count_cards_that_only_help_the_board() {
+        let outs = outs(hole("H14 D13"), &cards("S2 C2 S7 C9"));
+        assert!(outs.iter().all(|c| c.value == 14 || c.value == 13));
+    }
+
+    #[test]
+    fn should_have_no_outs_before_the_flop() {
+        assert!(outs(hole("H14 D13"), &[]).is_empty());
+    }
+
+    #[test]
+    fn should_rank_starting_hands() {
+        let aces = preflop_strength(hole("S14 H14"));
+        let suited_connectors = preflop_strength(hole("S9 S8"));
+        let trash = preflop_strength(hole("S7 H2"));
+        assert!(aces > 0.99);
+        assert!(aces > suited_connectors && suited_connectors > trash);
+        assert!(trash < 0.1);
+    }
+
+    #[test]
+    fn should_describe_board_texture() {
+        assert_eq!(texture(&cards("S2 C7 H13")), BoardTexture::default());
+        let wet = texture(&cards("H10 H11 H12 S12"));
+        assert!(wet.paired && wet.flush_possible && wet.straight_possible);
+        assert!(texture(&cards("S14 D2 C3")).straight_possible);
+    }
+}
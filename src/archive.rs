@@ -0,0 +1,220 @@
+//! Every hand a host deals, kept on disk so that disputes can be looked into after the
+//! fact. Each table gets a file of its own in the archive directory, with one hand as JSON
+//! per line, which is rotated once it grows too big: `main.jsonl` becomes `main.1.jsonl`,
+//! that one `main.2.jsonl` and so on, and the oldest is deleted.
+
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, BufRead, BufReader, ErrorKind, Write};
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+
+use crate::events::GameEvent;
+
+/// How big a table's file grows before it is rotated.
+pub const ROTATE_BYTES: u64 = 10 * 1024 * 1024;
+
+/// How many rotated files of a table are kept besides the one being written.
+pub const KEPT_FILES: usize = 5;
+
+/// A hand as the archive keeps it.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ArchivedHand {
+    /// When the game the hand was part of began, in milliseconds since the Unix epoch,
+    /// which tells apart the games played at a table.
+    pub game_ms: u64,
+    /// Counting from 1.
+    pub hand: usize,
+    pub finished_ms: u64,
+    /// The stacks before the blinds were posted.
+    pub stacks: Vec<u32>,
+    /// Everything that happened, everyone's hole cards included.
+    pub events: Vec<GameEvent>,
+}
+
+impl ArchivedHand {
+    /// The hand as `seat` saw it, without the other players' hole cards.
+    pub fn seen_by(&self, seat: usize) -> Self {
+        let events = self
+            .events
+            .iter()
+            .filter(|e| !matches!(e, GameEvent::HoleCardsDealt { player, .. } if *player != seat))
+            .cloned()
+            .collect();
+        Self {
+            events,
+            ..self.clone()
+        }
+    }
+}
+
+/// Milliseconds since the Unix epoch.
+pub fn now_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+/// Milliseconds since the Unix epoch as `2024/01/31 18:05:09`, in UTC.
+pub fn date_time(ms: u64) -> String {
+    let secs = ms / 1000;
+    let (days, time) = (secs / 86_400, secs % 86_400);
+    // Howard Hinnant's civil_from_days, for days since 1970-01-01.
+    let z = days as i64 + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    format!(
+        "{year}/{month:02}/{day:02} {:02}:{:02}:{:02}",
+        time / 3600,
+        time % 3600 / 60,
+        time % 60
+    )
+}
+
+/// The files of one table in an archive directory.
+pub struct HandArchive {
+    dir: PathBuf,
+    /// The table's name as it can go in a file name.
+    stem: String,
+    rotate_bytes: u64,
+    kept_files: usize,
+}
+
+impl HandArchive {
+    /// The archive of `table` in `dir`, which has to exist, rotated at `ROTATE_BYTES`.
+    pub fn new(dir: &Path, table: &str) -> Self {
+        let stem = table
+            .chars()
+            .map(|c| match c {
+                'a'..='z' | 'A'..='Z' | '0'..='9' | '-' | '_' => c,
+                _ => '-',
+            })
+            .collect();
+        Self {
+            dir: dir.to_owned(),
+            stem,
+            rotate_bytes: ROTATE_BYTES,
+            kept_files: KEPT_FILES,
+        }
+    }
+
+    /// Rotates the file once it would grow past `bytes`, keeping `files` old ones.
+    pub fn rotate_at(mut self, bytes: u64, files: usize) -> Self {
+        self.rotate_bytes = bytes;
+        self.kept_files = files;
+        self
+    }
+
+    /// The `age`th file, 0 being the one written to.
+    fn path(&self, age: usize) -> PathBuf {
+        match age {
+            0 => self.dir.join(format!("{}.jsonl", self.stem)),
+            age => self.dir.join(format!("{}.{age}.jsonl", self.stem)),
+        }
+    }
+
+    /// Appends the hand, rotating the files first if it doesn't fit.
+    pub fn record(&self, hand: &ArchivedHand) -> io::Result<()> {
+        let mut line = serde_json::to_vec(hand)?;
+        line.push(b'\n');
+        let size = match fs::metadata(self.path(0)) {
+            Ok(metadata) => metadata.len(),
+            Err(e) if e.kind() == ErrorKind::NotFound => 0,
+            Err(e) => return Err(e),
+        };
+        if size > 0 && size + line.len() as u64 > self.rotate_bytes {
+            self.rotate()?;
+        }
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(self.path(0))?;
+        file.write_all(&line)
+    }
+
+    fn rotate(&self) -> io::Result<()> {
+        if self.kept_files == 0 {
+            return fs::remove_file(self.path(0));
+        }
+        for age in (0..self.kept_files).rev() {
+            match fs::rename(self.path(age), self.path(age + 1)) {
+                Err(e) if e.kind() != ErrorKind::NotFound => return Err(e),
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+
+    /// Every hand still kept, oldest first. Lines that don't read as a hand are skipped.
+    pub fn hands(&self) -> io::Result<Vec<ArchivedHand>> {
+        let mut hands = vec![];
+        for age in (0..=self.kept_files).rev() {
+            let file = match File::open(self.path(age)) {
+                Ok(file) => file,
+                Err(e) if e.kind() == ErrorKind::NotFound => continue,
+                Err(e) => return Err(e),
+            };
+            for line in BufReader::new(file).lines() {
+                hands.extend(serde_json::from_str(&line?).ok());
+            }
+        }
+        Ok(hands)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core_engine::Deck;
+    use crate::{GameState, PokerAction};
+
+    #[test]
+    fn should_keep_hands_in_rotated_files_and_hide_other_players_cards() {
+        let dir = std::env::temp_dir().join(format!("poker-tui-archive-{}", rand::random::<u64>()));
+        fs::create_dir_all(&dir).unwrap();
+        let (mut hs, _) = GameState::init(2)
+            .unwrap()
+            .start_play_hand(Deck::shuffled_deck());
+        hs.play_action(PokerAction::Fold).unwrap();
+        let hand = |number| ArchivedHand {
+            game_ms: 1,
+            hand: number,
+            finished_ms: now_ms(),
+            stacks: vec![100, 100],
+            events: hs.events().to_vec(),
+        };
+        let size = serde_json::to_vec(&hand(1)).unwrap().len() as u64 + 1;
+        let archive = HandArchive::new(&dir, "the club").rotate_at(2 * size, 1);
+        for number in 1..=5 {
+            archive.record(&hand(number)).unwrap();
+        }
+        let kept: Vec<_> = archive.hands().unwrap().iter().map(|h| h.hand).collect();
+        assert_eq!(kept, [3, 4, 5]);
+        assert!(dir.join("the-club.1.jsonl").exists());
+        assert!(!dir.join("the-club.2.jsonl").exists());
+
+        let seen = hand(1).seen_by(1);
+        let dealt: Vec<_> = seen
+            .events
+            .iter()
+            .filter_map(|e| match e {
+                GameEvent::HoleCardsDealt { player, .. } => Some(*player),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(dealt, [1]);
+        assert!(seen
+            .events
+            .iter()
+            .any(|e| matches!(e, GameEvent::PotAwarded { .. })));
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,108 @@
+//! Tokens the server signs, so that it can tell the ones it handed out from made up ones
+//! without keeping track of them: tokens for taking a seat back, and invites to tables
+//! that only let invited players sit down.
+//!
+//! A token is a claim followed by a dot and the hex of its HMAC-SHA256 under the secret.
+
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+
+pub struct Signer {
+    secret: Vec<u8>,
+}
+
+impl Signer {
+    /// Signs with `secret`, so tokens stay good for as long as the secret does.
+    pub fn new(secret: &[u8]) -> Self {
+        Self {
+            secret: secret.to_vec(),
+        }
+    }
+
+    /// Signs with a secret nobody knows, so tokens are only good until the process ends.
+    pub fn random() -> Self {
+        Self::new(&rand::random::<[u8; 32]>())
+    }
+
+    fn mac(&self, claim: &str) -> Hmac<Sha256> {
+        let mut mac = Hmac::<Sha256>::new_from_slice(&self.secret).expect("any key length works");
+        mac.update(claim.as_bytes());
+        mac
+    }
+
+    pub fn sign(&self, claim: &str) -> String {
+        let signature = self.mac(claim).finalize().into_bytes();
+        let hex: String = signature.iter().map(|b| format!("{b:02x}")).collect();
+        format!("{claim}.{hex}")
+    }
+
+    /// The claim of a token signed with this secret, `None` for any other token.
+    pub fn verify<'a>(&self, token: &'a str) -> Option<&'a str> {
+        let (claim, hex) = token.rsplit_once('.')?;
+        if hex.len() % 2 != 0 || !hex.is_ascii() {
+            return None;
+        }
+        let signature = (0..hex.len())
+            .step_by(2)
+            .map(|i| u8::from_str_radix(&hex[i..i + 2], 16).ok())
+            .collect::<Option<Vec<_>>>()?;
+        self.mac(claim).verify_slice(&signature).ok()?;
+        Some(claim)
+    }
+
+    /// An invite to the table called `table` that is good for `valid_for`.
+    pub fn invite(&self, table: &str, valid_for: Duration) -> String {
+        let expires = (SystemTime::now() + valid_for)
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs();
+        self.sign(&format!("invite:{expires}:{table}"))
+    }
+
+    /// The table `invite` says it is to. Only the signer can tell whether it really is.
+    pub fn invite_table(invite: &str) -> Option<&str> {
+        let (claim, _) = invite.rsplit_once('.')?;
+        Some(claim.strip_prefix("invite:")?.split_once(':')?.1)
+    }
+
+    /// Whether `token` is an invite to the table called `table` that is still good.
+    pub fn is_invited(&self, token: &str, table: &str) -> bool {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs();
+        let Some((expires, to)) = self
+            .verify(token)
+            .and_then(|claim| claim.strip_prefix("invite:"))
+            .and_then(|claim| claim.split_once(':'))
+        else {
+            return false;
+        };
+        to == table && expires.parse().is_ok_and(|expires: u64| expires > now)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_only_accept_tokens_signed_with_the_same_secret() {
+        let signer = Signer::new(b"secret");
+        let token = signer.sign("0-seat");
+        assert_eq!(signer.verify(&token), Some("0-seat"));
+        assert_eq!(Signer::new(b"other").verify(&token), None);
+        assert_eq!(signer.verify(&token.replacen("0-", "1-", 1)), None);
+        assert_eq!(signer.verify("0-seat"), None);
+        assert_eq!(signer.verify("0-seat.zz"), None);
+
+        let invite = signer.invite("high", Duration::from_secs(60));
+        assert!(signer.is_invited(&invite, "high"));
+        assert_eq!(Signer::invite_table(&invite), Some("high"));
+        assert!(!signer.is_invited(&invite, "low"));
+        assert!(!signer.is_invited(&signer.invite("high", Duration::ZERO), "high"));
+        assert!(!signer.is_invited(&token, "high"));
+    }
+}
@@ -0,0 +1,170 @@
+//! A player's bankroll across cash game sessions: sitting down buys chips from it, and
+//! leaving the table puts the chips in front of the player back.
+
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use crate::schema::Schema;
+
+const BANKROLL: Schema = Schema {
+    name: "bankroll",
+    version: 1,
+    migrations: &[],
+};
+
+/// What a new profile starts with.
+pub const STARTING_BANKROLL: u64 = 1000;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Transfer {
+    BuyIn,
+    CashOut,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BankrollEntry {
+    /// Milliseconds since the Unix epoch.
+    pub at_ms: u64,
+    pub transfer: Transfer,
+    pub chips: u64,
+    /// The bankroll after the transfer.
+    pub balance: u64,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Bankroll {
+    pub balance: u64,
+    /// Every buy-in and cash-out, oldest first.
+    pub entries: Vec<BankrollEntry>,
+}
+
+impl Default for Bankroll {
+    fn default() -> Self {
+        Self {
+            balance: STARTING_BANKROLL,
+            entries: vec![],
+        }
+    }
+}
+
+impl Bankroll {
+    /// The bankroll saved at `path`, or a new one if nothing is saved there yet.
+    pub fn load(path: impl AsRef<Path>) -> io::Result<Self> {
+        match fs::read_to_string(path) {
+            Ok(text) => BANKROLL.read(&text).map_err(io::Error::other),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(e) => Err(e),
+        }
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        let json = serde_json::to_string_pretty(&BANKROLL.stamp(self))?;
+        fs::write(path, json)
+    }
+
+    /// Takes `chips` out of the bankroll to sit down with, unless there aren't that many.
+    pub fn buy_in(&mut self, chips: u64, at_ms: u64) -> Result<(), String> {
+        if chips > self.balance {
+            return Err(format!(
+                "a bankroll of {} can't cover a buy-in of {chips}",
+                self.balance
+            ));
+        }
+        self.transfer(Transfer::BuyIn, chips, at_ms);
+        Ok(())
+    }
+
+    /// Puts the `chips` the player left the table with back into the bankroll.
+    pub fn cash_out(&mut self, chips: u64, at_ms: u64) {
+        self.transfer(Transfer::CashOut, chips, at_ms);
+    }
+
+    fn transfer(&mut self, transfer: Transfer, chips: u64, at_ms: u64) {
+        self.balance = match transfer {
+            Transfer::BuyIn => self.balance - chips,
+            Transfer::CashOut => self.balance + chips,
+        };
+        self.entries.push(BankrollEntry {
+            at_ms,
+            transfer,
+            chips,
+            balance: self.balance,
+        });
+    }
+
+    /// The bankroll between sessions, starting before the first and ending after the last
+    /// one cashed out.
+    pub fn history(&self) -> Vec<u64> {
+        let start = self
+            .entries
+            .first()
+            .map_or(self.balance, |e| match e.transfer {
+                Transfer::BuyIn => e.balance + e.chips,
+                Transfer::CashOut => e.balance - e.chips,
+            });
+        let mut history = vec![start];
+        history.extend(
+            self.entries
+                .iter()
+                .filter(|e| e.transfer == Transfer::CashOut)
+                .map(|e| e.balance),
+        );
+        history
+    }
+
+    /// The last `width` points of `history` drawn `height` lines high, with the highest
+    /// and lowest bankroll shown on the left.
+    pub fn graph(&self, width: usize, height: usize) -> Vec<String> {
+        let history = self.history();
+        let points = &history[history.len().saturating_sub(width)..];
+        let high = points.iter().copied().max().unwrap_or(0);
+        let low = points.iter().copied().min().unwrap_or(0);
+        let row = |balance: u64| {
+            let range = (high - low).max(1);
+            (((high - balance) * (height as u64 - 1) + range / 2) / range) as usize
+        };
+        let label_width = high.to_string().len();
+        (0..height)
+            .map(|line| {
+                let label = match line {
+                    0 => high.to_string(),
+                    l if l == height - 1 => low.to_string(),
+                    _ => String::new(),
+                };
+                let plot: String = points
+                    .iter()
+                    .map(|&b| if row(b) == line { '*' } else { ' ' })
+                    .collect();
+                format!("{label:>label_width$} |{plot}")
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_track_the_bankroll_across_sessions() {
+        let mut bankroll = Bankroll::default();
+        assert!(bankroll.buy_in(STARTING_BANKROLL + 1, 0).is_err());
+
+        bankroll.buy_in(100, 1).unwrap();
+        assert_eq!(bankroll.balance, 900);
+        bankroll.cash_out(250, 2);
+        bankroll.buy_in(200, 3).unwrap();
+        bankroll.cash_out(0, 4);
+        assert_eq!(bankroll.balance, 950);
+        assert_eq!(bankroll.history(), [1000, 1150, 950]);
+
+        assert_eq!(
+            bankroll.graph(10, 3),
+            ["1150 | * ", "     |   ", " 950 |* *"]
+        );
+        assert_eq!(bankroll.graph(2, 2), ["1150 |* ", " 950 | *"]);
+    }
+}
@@ -0,0 +1,1053 @@
+//! Computer opponents. Every bot implements `BotPlayer` and can be built from its
+//! constructor or by name through `BotKind`, so programs embedding the engine can seat the
+//! same opponents the game uses:
+//!
+//! ```
+//! use poker_tui::bots::{BotKind, BotPlayer, RuleBot};
+//! use poker_tui::core_engine::Deck;
+//! use poker_tui::phase::{AwaitingAction, Phase};
+//! use poker_tui::GameState;
+//!
+//! let mut bots: Vec<Box<dyn BotPlayer>> = vec![
+//!     Box::new(RuleBot),
+//!     BotKind::parse("tag").unwrap().build_seeded(Some(1)).unwrap(),
+//! ];
+//! let mut hand = AwaitingAction::start(&GameState::init(2).unwrap(), Deck::shuffled_deck());
+//! loop {
+//!     let action = bots[hand.player()].decide(&hand.hand().bot_context(hand.player()));
+//!     hand = match hand.act(action) {
+//!         Ok((Phase::AwaitingAction(hand), _)) => hand,
+//!         Ok((Phase::StreetComplete(street), _)) => street.next(),
+//!         Ok((Phase::HandComplete(_), _)) | Err(_) => break,
+//!     };
+//! }
+//! ```
+
+use std::io;
+use std::ops::{Deref, RangeInclusive};
+use std::path::Path;
+
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use serde::{Deserialize, Serialize};
+
+use crate::analysis::{best_hand, outs, preflop_strength, texture};
+use crate::charts::Position;
+use crate::core_engine::{Card, Hand};
+use crate::equity::{equity_vs_random, equity_vs_range};
+use crate::events::{GameEvent, Street};
+use crate::{HandState, PokerAction, BIG_BLIND_CHIPS, MAX_RAISE};
+
+pub use crate::adaptive::AdaptiveBot;
+pub use crate::cfr::CfrBot;
+pub use crate::charts::{ChartBot, PreflopChart};
+pub use crate::external_bot::ExternalBot;
+#[cfg(feature = "onnx")]
+pub use crate::onnx::OnnxBot;
+pub use crate::pushfold::PushFoldBot;
+
+/// What a single player can see when it is their turn to act.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PlayerView {
+    pub player: usize,
+    pub button: usize,
+    pub cards: (Card, Card),
+    pub board: Vec<Card>,
+    pub street: Street,
+    pub stacks: Vec<u32>,
+    pub bets: Vec<u32>,
+    /// Players who have not folded, including this one.
+    pub active: Vec<bool>,
+    pub pot: u32,
+    pub expected_call: u32,
+    /// The fewest chips a raise puts in short of going all in.
+    #[serde(default)]
+    pub min_raise: u32,
+}
+
+/// What a bot decides on: the player's view along with figures derived from it, worked out
+/// once by the engine rather than by every bot.
+#[derive(Clone, Debug, PartialEq)]
+pub struct BotContext {
+    pub view: PlayerView,
+    pub legal: LegalActions,
+    /// Share of the pot after calling that the call costs, 0 when checking is free.
+    pub pot_odds: f64,
+    /// The most this player can win or lose against a single opponent still in the hand.
+    pub effective_stack: u32,
+    /// Stack-to-pot ratio: the effective stack divided by the pot including all bets.
+    pub spr: f64,
+    pub position: Position,
+    /// What happened in the hand so far, without the other players' hole cards. Empty for
+    /// a context made from a bare `PlayerView`.
+    pub history: Vec<GameEvent>,
+}
+
+/// The actions the engine accepts from the player to act. Folding is always allowed.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LegalActions {
+    /// Chips `CallOrCheck` puts in, 0 for a check.
+    pub call: u32,
+    /// The `Raise` amounts that put in more than a call, if the stack allows any.
+    pub raise: Option<RangeInclusive<u32>>,
+}
+
+impl LegalActions {
+    pub fn allows(&self, action: PokerAction) -> bool {
+        match action {
+            PokerAction::Fold | PokerAction::CallOrCheck => true,
+            PokerAction::Raise(amount) => self.raise.as_ref().is_some_and(|r| r.contains(&amount)),
+        }
+    }
+}
+
+impl From<PlayerView> for BotContext {
+    fn from(view: PlayerView) -> Self {
+        let stack = view.stacks[view.player];
+        let max_raise = stack.min(MAX_RAISE);
+        // Short of the smallest raise, going all in is the only one left.
+        let min_raise = view.min_raise.max(view.expected_call + 1).min(stack);
+        let legal = LegalActions {
+            call: view.expected_call.min(stack),
+            raise: (max_raise > view.expected_call && min_raise <= max_raise)
+                .then_some(min_raise..=max_raise),
+        };
+        let pot_odds = view.expected_call as f64 / pot_after_call(&view).max(1) as f64;
+        let biggest_opponent = (0..view.active.len())
+            .filter(|&p| p != view.player && view.active[p])
+            .map(|p| view.stacks[p] + view.bets[p])
+            .max()
+            .unwrap_or(0);
+        let effective_stack = (stack + view.bets[view.player]).min(biggest_opponent);
+        let pot = view.pot + view.bets.iter().sum::<u32>();
+        Self {
+            legal,
+            pot_odds,
+            effective_stack,
+            spr: effective_stack as f64 / pot.max(1) as f64,
+            position: Position::of(&view),
+            history: vec![],
+            view,
+        }
+    }
+}
+
+impl Deref for BotContext {
+    type Target = PlayerView;
+
+    fn deref(&self) -> &PlayerView {
+        &self.view
+    }
+}
+
+pub trait BotPlayer {
+    fn decide(&mut self, view: &BotContext) -> PokerAction;
+
+    /// Like `decide`, along with a short reason for the action if the bot can give one.
+    fn decide_explained(&mut self, view: &BotContext) -> (PokerAction, Option<String>) {
+        (self.decide(view), None)
+    }
+
+    /// Called with everything that happened in a hand once it is over, for bots that learn
+    /// from how the others play.
+    fn hand_finished(&mut self, _events: &[GameEvent]) {}
+}
+
+/// Calls every bet and never raises or folds.
+pub struct CallingStation;
+
+impl BotPlayer for CallingStation {
+    fn decide(&mut self, _view: &BotContext) -> PokerAction {
+        PokerAction::CallOrCheck
+    }
+}
+
+/// A loose bot that mostly calls, sometimes raises and folds to bets it cannot cover.
+pub struct LooseBot {
+    rng: StdRng,
+}
+
+impl LooseBot {
+    pub fn new(seed: Option<u64>) -> Self {
+        Self {
+            rng: seeded_rng(seed),
+        }
+    }
+}
+
+impl BotPlayer for LooseBot {
+    fn decide(&mut self, view: &BotContext) -> PokerAction {
+        let stack = view.stacks[view.player];
+        let to_call = view.expected_call;
+        let raise = (to_call + self.rng.random_range(2..=10)).max(view.min_raise);
+        match self.rng.random_range(0..10) {
+            _ if to_call > stack => PokerAction::Fold,
+            0 if to_call > 0 => PokerAction::Fold,
+            1 | 2 if raise <= stack.min(MAX_RAISE) => PokerAction::Raise(raise),
+            _ => PokerAction::CallOrCheck,
+        }
+    }
+}
+
+/// Plays by simple rules: raises strong hands, calls draws getting the right price and
+/// folds the rest when facing a bet.
+pub struct RuleBot;
+
+impl BotPlayer for RuleBot {
+    fn decide(&mut self, view: &BotContext) -> PokerAction {
+        self.decide_explained(view).0
+    }
+
+    fn decide_explained(&mut self, view: &BotContext) -> (PokerAction, Option<String>) {
+        let (action, reason) = if view.board.is_empty() {
+            self.decide_preflop(view)
+        } else {
+            self.decide_postflop(view)
+        };
+        (action, Some(reason))
+    }
+}
+
+impl RuleBot {
+    fn decide_preflop(&self, view: &BotContext) -> (PokerAction, String) {
+        let (high, low) = if view.cards.0.value > view.cards.1.value {
+            (view.cards.0.value, view.cards.1.value)
+        } else {
+            (view.cards.1.value, view.cards.0.value)
+        };
+        let pair = high == low;
+        let cheap = view.expected_call <= 3 * BIG_BLIND_CHIPS;
+
+        if (pair && high >= 10) || low >= 12 {
+            if cheap {
+                (raise_or_call(view, 1), "premium starting hand".to_owned())
+            } else {
+                (
+                    PokerAction::CallOrCheck,
+                    "premium starting hand, but the pot is already raised".to_owned(),
+                )
+            }
+        } else if playable(view.cards) && cheap {
+            (
+                PokerAction::CallOrCheck,
+                "playable starting hand at a low price".to_owned(),
+            )
+        } else if playable(view.cards) {
+            (
+                check_or_fold(view),
+                "playable starting hand, but too expensive".to_owned(),
+            )
+        } else {
+            (check_or_fold(view), "weak starting hand".to_owned())
+        }
+    }
+
+    fn decide_postflop(&self, view: &BotContext) -> (PokerAction, String) {
+        let (hand, made) = made_hand(view);
+        let board = texture(&view.board);
+        let dangerous = board.paired || board.flush_possible || board.straight_possible;
+        let name = hand.ranking_name().to_lowercase();
+
+        match made {
+            4.. => (raise_or_call(view, 1), format!("strong hand, {name}")),
+            2 | 3 if !dangerous || view.expected_call == 0 => {
+                (raise_or_call(view, 2), format!("{name} on a safe board"))
+            }
+            2 | 3 => (
+                PokerAction::CallOrCheck,
+                format!("{name}, but the board is dangerous"),
+            ),
+            1 if view.pot_odds <= 0.5 => {
+                (PokerAction::CallOrCheck, "pair at a fair price".to_owned())
+            }
+            _ => {
+                // Roughly 2% per out for every card still to come.
+                let cards_to_come = 5 - view.board.len() as u32;
+                let outs = outs(view.cards, &view.board).len() as u32;
+                let equity = outs * 2 * cards_to_come;
+                let price = (100.0 * view.pot_odds).round() as u32;
+                let reason = format!("{outs} outs, about {equity}% vs {price}% pot odds");
+                if equity >= price {
+                    (PokerAction::CallOrCheck, reason)
+                } else {
+                    (check_or_fold(view), reason)
+                }
+            }
+        }
+    }
+}
+
+/// Runs equity simulations each decision and bets in proportion to its share of the pot.
+pub struct MonteCarloBot {
+    /// Simulated deals per decision, the more the stronger and slower the bot.
+    pub samples: usize,
+    rng: StdRng,
+}
+
+impl MonteCarloBot {
+    /// With a `seed` the simulations deal the same cards every time the bot sees the same
+    /// spots.
+    pub fn new(samples: usize, seed: Option<u64>) -> Self {
+        Self {
+            samples,
+            rng: seeded_rng(seed),
+        }
+    }
+}
+
+impl BotPlayer for MonteCarloBot {
+    fn decide(&mut self, view: &BotContext) -> PokerAction {
+        self.decide_explained(view).0
+    }
+
+    fn decide_explained(&mut self, view: &BotContext) -> (PokerAction, Option<String>) {
+        let opponents = view.active.iter().filter(|&&a| a).count() - 1;
+        // Opponents who put in a raise are assumed to hold hands worth playing.
+        let raised = view.bets.iter().any(|&b| b > BIG_BLIND_CHIPS)
+            || (!view.board.is_empty() && view.expected_call > 0);
+        let equity = if raised {
+            equity_vs_range(
+                view.cards,
+                &view.board,
+                opponents,
+                self.samples,
+                playable,
+                &mut self.rng,
+            )
+        } else {
+            equity_vs_random(
+                view.cards,
+                &view.board,
+                opponents,
+                self.samples,
+                &mut self.rng,
+            )
+        };
+
+        let pot = pot_after_call(view);
+        let fair_share = 1.0 / (opponents + 1) as f64;
+        let action = if equity > fair_share + 0.15 {
+            raise_or_call_by(view, (pot as f64 * equity) as u32)
+        } else if equity >= view.pot_odds {
+            PokerAction::CallOrCheck
+        } else {
+            check_or_fold(view)
+        };
+        let range = if raised {
+            "playable hands"
+        } else {
+            "random hands"
+        };
+        let reason = format!(
+            "{:.0}% equity vs {opponents} opponent(s) on {range}",
+            100.0 * equity
+        );
+        (action, Some(reason))
+    }
+}
+
+/// How a `PersonalityBot` plays, every knob between 0 and 1.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Personality {
+    /// The share of starting hands played.
+    pub vpip: f64,
+    /// How often a hand worth playing is raised instead of called.
+    pub aggression: f64,
+    /// How often a hand with nothing bets when checked to.
+    pub bluff: f64,
+}
+
+pub const PERSONALITIES: [(&str, Personality); 6] = [
+    ("nit", Personality::new(0.12, 0.3, 0.0)),
+    ("rock", Personality::new(0.18, 0.15, 0.02)),
+    ("tag", Personality::new(0.22, 0.7, 0.08)),
+    ("station", Personality::new(0.55, 0.1, 0.0)),
+    ("lag", Personality::new(0.4, 0.7, 0.15)),
+    ("maniac", Personality::new(0.7, 0.9, 0.35)),
+];
+
+impl Personality {
+    const fn new(vpip: f64, aggression: f64, bluff: f64) -> Self {
+        Self {
+            vpip,
+            aggression,
+            bluff,
+        }
+    }
+
+    pub fn preset(name: &str) -> Option<Self> {
+        PERSONALITIES
+            .iter()
+            .find(|(n, _)| *n == name)
+            .map(|(_, p)| *p)
+    }
+
+    /// Parses a preset name and/or knobs separated by colons, like `nit`, `lag:bluff=0.3`
+    /// or `vpip=0.3:aggression=0.5:bluff=0.1`. Knobs not given come from the preset, or
+    /// from `tag` when there is none.
+    pub fn parse(spec: &str) -> Option<Self> {
+        let mut parts = spec.split(':').peekable();
+        let mut personality = match parts.peek().and_then(|p| Self::preset(p)) {
+            Some(preset) => {
+                parts.next();
+                preset
+            }
+            None => Self::preset("tag").unwrap(),
+        };
+        for part in parts {
+            let (knob, value) = part.split_once('=')?;
+            let value: f64 = value.parse().ok().filter(|v| (0.0..=1.0).contains(v))?;
+            match knob {
+                "vpip" => personality.vpip = value,
+                "aggression" => personality.aggression = value,
+                "bluff" => personality.bluff = value,
+                _ => return None,
+            }
+        }
+        Some(personality)
+    }
+}
+
+/// Plays the starting hands its personality allows and leans on `RuleBot` after the flop,
+/// raising and bluffing as often as the personality says.
+pub struct PersonalityBot {
+    pub personality: Personality,
+    rng: StdRng,
+}
+
+impl PersonalityBot {
+    /// With a `seed` the bot makes the same choices every time it sees the same spots.
+    pub fn new(personality: Personality, seed: Option<u64>) -> Self {
+        Self {
+            personality,
+            rng: seeded_rng(seed),
+        }
+    }
+}
+
+impl BotPlayer for PersonalityBot {
+    fn decide(&mut self, view: &BotContext) -> PokerAction {
+        self.decide_explained(view).0
+    }
+
+    fn decide_explained(&mut self, view: &BotContext) -> (PokerAction, Option<String>) {
+        let p = self.personality;
+        if view.board.is_empty() {
+            let strength = preflop_strength(view.cards);
+            let percent = 100.0 * strength;
+            if strength < 1.0 - p.vpip {
+                let reason = format!("hand strength {percent:.0}% is outside its range");
+                return (check_or_fold(view), Some(reason));
+            }
+            // Only the top of its range is worth a big bet.
+            let expensive = view.expected_call > 6 * BIG_BLIND_CHIPS;
+            if expensive && strength < 1.0 - p.vpip / 2.0 {
+                let reason = format!("hand strength {percent:.0}% is too weak for a big bet");
+                return (check_or_fold(view), Some(reason));
+            }
+            let reason = Some(format!("hand strength {percent:.0}% is in its range"));
+            return if self.rng.random_bool(p.aggression) && !expensive {
+                (raise_or_call(view, 1), reason)
+            } else {
+                (PokerAction::CallOrCheck, reason)
+            };
+        }
+
+        let (action, reason) = RuleBot.decide_explained(view);
+        match action {
+            PokerAction::Raise(_) if !self.rng.random_bool(p.aggression) => {
+                (PokerAction::CallOrCheck, reason.map(|r| r + ", slowplayed"))
+            }
+            PokerAction::CallOrCheck
+                if view.expected_call == 0 && self.rng.random_bool(p.bluff) =>
+            {
+                (raise_or_call(view, 2), Some("bluff".to_owned()))
+            }
+            PokerAction::CallOrCheck
+                if view.expected_call > 0 && self.rng.random_bool(p.aggression / 2.0) =>
+            {
+                (
+                    raise_or_call(view, 2),
+                    reason.map(|r| r + ", raised for aggression"),
+                )
+            }
+            action => (action, reason),
+        }
+    }
+}
+
+pub(crate) fn seeded_rng(seed: Option<u64>) -> StdRng {
+    match seed {
+        Some(seed) => StdRng::seed_from_u64(seed),
+        None => StdRng::from_os_rng(),
+    }
+}
+
+/// How often a bot swaps its usual action for another, each between 0 and 1, so it can't
+/// be read from repeating the same line in the same spot.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Mix {
+    /// Bet instead of checking.
+    pub bluff: f64,
+    /// Call instead of raising.
+    pub slowplay: f64,
+    /// Call instead of folding.
+    pub hero_call: f64,
+}
+
+impl Mix {
+    /// Parses knobs separated by colons like `bluff=0.3:slowplay=0.1`, the rest stay 0.
+    pub fn parse(spec: &str) -> Option<Self> {
+        let mut mix = Mix::default();
+        for part in spec.split(':') {
+            let (knob, value) = part.split_once('=')?;
+            let value: f64 = value.parse().ok().filter(|v| (0.0..=1.0).contains(v))?;
+            match knob {
+                "bluff" => mix.bluff = value,
+                "slowplay" => mix.slowplay = value,
+                "hero_call" => mix.hero_call = value,
+                _ => return None,
+            }
+        }
+        Some(mix)
+    }
+
+    /// The action to play instead of `action`, or `None` to keep it.
+    pub fn apply(
+        &self,
+        view: &PlayerView,
+        action: PokerAction,
+        rng: &mut impl Rng,
+    ) -> Option<(PokerAction, &'static str)> {
+        match action {
+            PokerAction::CallOrCheck if view.expected_call == 0 && rng.random_bool(self.bluff) => {
+                Some((raise_or_call(view, 2), "mixed in a bluff"))
+            }
+            PokerAction::Raise(_) if rng.random_bool(self.slowplay) => {
+                Some((PokerAction::CallOrCheck, "mixed in a slowplay"))
+            }
+            PokerAction::Fold if rng.random_bool(self.hero_call) => {
+                Some((PokerAction::CallOrCheck, "mixed in a hero call"))
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Plays like another bot, but mixes in other actions as often as its `Mix` says.
+pub struct MixedBot {
+    bot: Box<dyn BotPlayer>,
+    mix: Mix,
+    rng: StdRng,
+}
+
+impl MixedBot {
+    /// With a `seed` the mixing is the same every time the bot sees the same spots.
+    pub fn new(bot: Box<dyn BotPlayer>, mix: Mix, seed: Option<u64>) -> Self {
+        Self {
+            bot,
+            mix,
+            rng: seeded_rng(seed),
+        }
+    }
+}
+
+impl BotPlayer for MixedBot {
+    fn decide(&mut self, view: &BotContext) -> PokerAction {
+        self.decide_explained(view).0
+    }
+
+    fn decide_explained(&mut self, view: &BotContext) -> (PokerAction, Option<String>) {
+        let (action, reason) = self.bot.decide_explained(view);
+        match self.mix.apply(view, action, &mut self.rng) {
+            Some((mixed, why)) => (mixed, Some(why.to_owned())),
+            None => (action, reason),
+        }
+    }
+}
+
+/// Simulations per decision of the `hard` bot.
+pub const HARD_BOT_SAMPLES: usize = 500;
+
+/// A bot that can be picked for a seat by name.
+#[derive(Clone, Debug, PartialEq)]
+pub enum BotKind {
+    /// A `CallingStation`.
+    Easy,
+    /// A `RuleBot`.
+    Normal,
+    /// A `MonteCarloBot` with this many samples.
+    Hard(usize),
+    Personality(Personality),
+    /// An `ExternalBot` running this command line.
+    External(String),
+    /// An `AdaptiveBot`.
+    Adaptive,
+    /// A `PushFoldBot`.
+    PushFold,
+    /// A `CfrBot`.
+    Cfr,
+    /// A `ChartBot` reading its chart from this file, or using the default chart.
+    Chart(Option<String>),
+    /// An `OnnxBot` loading its model from this file, only available with the `onnx`
+    /// feature.
+    Onnx(String),
+    /// Another bot wrapped in a `MixedBot`.
+    Mixed(Box<BotKind>, Mix),
+}
+
+impl BotKind {
+    /// Parses `easy`, `normal`, `hard`, `adaptive`, `pushfold`, `cfr`, `mc=SAMPLES`,
+    /// `exec=COMMAND`, `chart`, `chart=FILE`, `onnx=FILE` or a personality spec, optionally
+    /// followed by a mix like `@bluff=0.3`.
+    pub fn parse(spec: &str) -> Option<Self> {
+        if let Some((kind, mix)) = spec.rsplit_once('@') {
+            let kind = BotKind::parse(kind)?;
+            return Some(BotKind::Mixed(Box::new(kind), Mix::parse(mix)?));
+        }
+        match spec {
+            "easy" => Some(BotKind::Easy),
+            "normal" => Some(BotKind::Normal),
+            "hard" => Some(BotKind::Hard(HARD_BOT_SAMPLES)),
+            "adaptive" => Some(BotKind::Adaptive),
+            "pushfold" => Some(BotKind::PushFold),
+            "cfr" => Some(BotKind::Cfr),
+            "chart" => Some(BotKind::Chart(None)),
+            _ => {
+                if let Some(samples) = spec.strip_prefix("mc=") {
+                    samples.parse().ok().map(BotKind::Hard)
+                } else if let Some(path) = spec.strip_prefix("chart=") {
+                    Some(path.trim())
+                        .filter(|p| !p.is_empty())
+                        .map(|p| BotKind::Chart(Some(p.to_owned())))
+                } else if let Some(path) = spec.strip_prefix("onnx=") {
+                    Some(path.trim())
+                        .filter(|p| !p.is_empty())
+                        .map(|p| BotKind::Onnx(p.to_owned()))
+                } else if let Some(command) = spec.strip_prefix("exec=") {
+                    Some(command.trim())
+                        .filter(|c| !c.is_empty())
+                        .map(|c| BotKind::External(c.to_owned()))
+                } else {
+                    Personality::parse(spec).map(BotKind::Personality)
+                }
+            }
+        }
+    }
+
+    /// Creates the bot, which fails only if an external bot's program can't be started or
+    /// a chart or model file can't be read.
+    pub fn build(&self) -> io::Result<Box<dyn BotPlayer>> {
+        self.build_seeded(None)
+    }
+
+    /// Like `build`, with the random choices of the bot made from `seed`.
+    pub fn build_seeded(&self, seed: Option<u64>) -> io::Result<Box<dyn BotPlayer>> {
+        Ok(match self {
+            BotKind::Easy => Box::new(CallingStation),
+            BotKind::Normal => Box::new(RuleBot),
+            BotKind::Hard(samples) => Box::new(MonteCarloBot::new(*samples, seed)),
+            BotKind::Personality(personality) => Box::new(PersonalityBot::new(*personality, seed)),
+            BotKind::External(command) => {
+                let mut words = command.split_ascii_whitespace();
+                let program = words.next().unwrap_or_default();
+                Box::new(ExternalBot::spawn(program, &words.collect::<Vec<_>>())?)
+            }
+            BotKind::Adaptive => Box::new(AdaptiveBot::default()),
+            BotKind::PushFold => Box::new(PushFoldBot),
+            BotKind::Cfr => Box::new(CfrBot::new(HARD_BOT_SAMPLES, seed)),
+            BotKind::Chart(path) => Box::new(ChartBot {
+                chart: match path {
+                    Some(path) => PreflopChart::load(Path::new(path))?,
+                    None => PreflopChart::default(),
+                },
+            }),
+            #[cfg(feature = "onnx")]
+            BotKind::Onnx(path) => Box::new(crate::onnx::OnnxBot::load(path, seed)?),
+            #[cfg(not(feature = "onnx"))]
+            BotKind::Onnx(_) => {
+                return Err(io::Error::other(
+                    "ONNX models need poker-tui built with the onnx feature",
+                ))
+            }
+            BotKind::Mixed(kind, mix) => {
+                Box::new(MixedBot::new(kind.build_seeded(seed)?, *mix, seed))
+            }
+        })
+    }
+}
+
+/// The player's best hand after the flop and its category, which counts as high card when
+/// the hand only plays the board, as that is no better than anyone else's.
+pub(crate) fn made_hand(view: &PlayerView) -> (Hand, u8) {
+    let mut cards = vec![view.cards.0, view.cards.1];
+    cards.extend(&view.board);
+    let hand = best_hand(&cards).unwrap();
+    let board_only = best_hand(&view.board).map_or(0, |h| h.category());
+    let made = if hand.category() == board_only {
+        0
+    } else {
+        hand.category()
+    };
+    (hand, made)
+}
+
+/// Pairs, aces, two high cards and suited connectors.
+pub(crate) fn playable(cards: (Card, Card)) -> bool {
+    let (high, low) = (
+        cards.0.value.max(cards.1.value),
+        cards.0.value.min(cards.1.value),
+    );
+    high == low || high == 14 || low >= 10 || (cards.0.suit == cards.1.suit && high - low == 1)
+}
+
+/// The pot including all bets once this player called.
+fn pot_after_call(view: &PlayerView) -> u32 {
+    view.pot + view.bets.iter().sum::<u32>() + view.expected_call
+}
+
+pub(crate) fn check_or_fold(view: &PlayerView) -> PokerAction {
+    if view.expected_call == 0 {
+        PokerAction::CallOrCheck
+    } else {
+        PokerAction::Fold
+    }
+}
+
+/// Raises by `1 / fraction` of the pot, or calls if the stack doesn't allow a raise.
+pub(crate) fn raise_or_call(view: &PlayerView, fraction: u32) -> PokerAction {
+    raise_or_call_by(view, pot_after_call(view) / fraction)
+}
+
+/// Calls and puts in `extra` chips on top, at least the smallest raise, as far as the stack
+/// allows.
+pub(crate) fn raise_or_call_by(view: &PlayerView, extra: u32) -> PokerAction {
+    let stack = view.stacks[view.player];
+    let amount = (view.expected_call + extra)
+        .max(view.min_raise)
+        .min(stack)
+        .min(MAX_RAISE);
+    if amount > view.expected_call && (amount >= view.min_raise || amount == stack) {
+        PokerAction::Raise(amount)
+    } else {
+        PokerAction::CallOrCheck
+    }
+}
+
+impl HandState {
+    pub fn player_view(&self, player: usize) -> PlayerView {
+        PlayerView {
+            player,
+            button: self.button(),
+            cards: self.hole_cards(player),
+            board: self.board.clone(),
+            street: self.street(),
+            stacks: self.chips.player_chips.iter().map(|pc| pc.stack).collect(),
+            bets: self.chips.player_chips.iter().map(|pc| pc.bet).collect(),
+            active: self.turn.active_players.clone(),
+            pot: self.chips.pot,
+            expected_call: self.chips.expected_call(player),
+            min_raise: self.chips.min_raise(player),
+        }
+    }
+
+    /// The context for a bot deciding for `player`.
+    pub fn bot_context(&self, player: usize) -> BotContext {
+        let history = self
+            .events
+            .iter()
+            .filter(|e| !matches!(e, GameEvent::HoleCardsDealt { player: p, .. } if *p != player))
+            .cloned()
+            .collect();
+        BotContext {
+            history,
+            ..BotContext::from(self.player_view(player))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core_engine::Deck;
+    use crate::events::GameEvent;
+    use crate::{GameState, TurnResult};
+
+    #[test]
+    fn should_derive_the_bot_context_from_the_hand() {
+        let (mut hs, cur) = GameState::init_with_stack(3, 50)
+            .unwrap()
+            .start_play_hand(Deck::ordered_deck());
+        let ctx = hs.bot_context(cur);
+        assert_eq!(ctx.legal.call, 2);
+        assert_eq!(ctx.legal.raise, Some(4..=50));
+        assert!(ctx.legal.allows(PokerAction::Raise(50)));
+        assert!(!ctx.legal.allows(PokerAction::Raise(51)));
+        assert_eq!(ctx.pot_odds, 0.4);
+        assert_eq!(ctx.effective_stack, 50);
+        assert_eq!(ctx.spr, 50.0 / 3.0);
+        assert_eq!(ctx.position, Position::Late);
+
+        // Only this player's own hole cards are in the history.
+        let dealt = |ctx: &BotContext| {
+            ctx.history
+                .iter()
+                .filter(|e| matches!(e, GameEvent::HoleCardsDealt { .. }))
+                .count()
+        };
+        assert_eq!(dealt(&ctx), 1);
+        hs.play_action(PokerAction::Raise(10)).unwrap();
+        let next = hs.bot_context((cur + 1) % 3);
+        assert_eq!(dealt(&next), 1);
+        assert_eq!(next.history.last(), hs.events().last());
+        assert_eq!(next.effective_stack, 50);
+    }
+
+    #[test]
+    fn should_play_a_hand_to_showdown_with_calling_stations() {
+        let (mut hs, mut cur) = GameState::init(3)
+            .unwrap()
+            .start_play_hand(Deck::ordered_deck());
+        let mut bots = [CallingStation, CallingStation, CallingStation];
+
+        let view = hs.player_view(cur);
+        assert_eq!(view.expected_call, 2);
+        assert_eq!(view.bets, vec![0, 1, 2]);
+        assert_eq!(view.cards, hs.hole_cards(cur));
+
+        loop {
+            let action = bots[cur].decide(&hs.bot_context(cur));
+            match hs.play_action(action).unwrap() {
+                TurnResult::NextPlayer(p) => cur = p,
+                TurnResult::WonHand(_) => break,
+            }
+        }
+        assert!(hs.is_showdown());
+    }
+
+    fn view(cards: &str, board: &str, pot: u32, expected_call: u32) -> BotContext {
+        let cards: Vec<Card> = cards
+            .split_ascii_whitespace()
+            .map(|c| Card::try_from(c).unwrap())
+            .collect();
+        let board: Vec<Card> = board
+            .split_ascii_whitespace()
+            .map(|c| Card::try_from(c).unwrap())
+            .collect();
+        BotContext::from(PlayerView {
+            player: 0,
+            button: 0,
+            cards: (cards[0], cards[1]),
+            street: Street::from_board_size(board.len()),
+            board,
+            stacks: vec![100, 100],
+            bets: vec![0, expected_call],
+            active: vec![true, true],
+            pot,
+            expected_call,
+            min_raise: expected_call + BIG_BLIND_CHIPS,
+        })
+    }
+
+    #[test]
+    fn should_raise_premium_hands_and_fold_trash_preflop() {
+        assert!(matches!(
+            RuleBot.decide(&view("S14 H14", "", 0, 2)),
+            PokerAction::Raise(_)
+        ));
+        assert_eq!(RuleBot.decide(&view("S7 H2", "", 0, 2)), PokerAction::Fold);
+        assert_eq!(
+            RuleBot.decide(&view("S7 H2", "", 0, 0)),
+            PokerAction::CallOrCheck
+        );
+    }
+
+    #[test]
+    fn should_call_draws_only_with_the_right_price() {
+        let flush_draw =
+            |expected_call| RuleBot.decide(&view("H14 H13", "H2 H7 S9 C3", 20, expected_call));
+        assert_eq!(flush_draw(5), PokerAction::CallOrCheck);
+        assert_eq!(flush_draw(40), PokerAction::Fold);
+    }
+
+    #[test]
+    fn should_bet_made_hands() {
+        assert!(matches!(
+            RuleBot.decide(&view("H14 H13", "H2 H7 H9", 20, 0)),
+            PokerAction::Raise(_)
+        ));
+        assert_eq!(
+            RuleBot.decide(&view("S3 D4", "H2 C7 H9 S11 D13", 20, 10)),
+            PokerAction::Fold
+        );
+    }
+
+    #[test]
+    fn should_explain_decisions() {
+        let (action, reason) = RuleBot.decide_explained(&view("H14 H13", "H2 H7 S9 C3", 20, 40));
+        assert_eq!(action, PokerAction::Fold);
+        assert_eq!(reason.unwrap(), "15 outs, about 30% vs 40% pot odds");
+
+        let (_, reason) = RuleBot.decide_explained(&view("H14 H13", "H2 H7 H9", 20, 0));
+        assert_eq!(reason.unwrap(), "strong hand, flush");
+
+        let (_, reason) = MonteCarloBot::new(50, None).decide_explained(&view("S3 D2", "", 0, 2));
+        assert!(reason.unwrap().contains("% equity vs 1 opponent(s)"));
+
+        assert_eq!(
+            CallingStation.decide_explained(&view("S3 D2", "", 0, 2)).1,
+            None
+        );
+    }
+
+    #[test]
+    fn should_play_many_hands_between_rule_bots() {
+        let mut gs = GameState::init(4).unwrap();
+        for _ in 0..100 {
+            let (mut hs, mut cur) = gs.start_play_hand(Deck::shuffled_deck());
+            while let TurnResult::NextPlayer(p) = hs
+                .play_action(RuleBot.decide(&hs.bot_context(cur)))
+                .unwrap()
+            {
+                cur = p;
+            }
+            gs = gs.apply_played_hand(hs);
+        }
+        let chips: u32 = (0..4).map(|p| gs.current_chips(p)).sum();
+        assert_eq!(chips, 400);
+    }
+
+    #[test]
+    fn should_size_bets_by_simulated_equity() {
+        let mut bot = MonteCarloBot::new(200, Some(1));
+        assert!(matches!(
+            bot.decide(&view("H14 H13", "H12 H11 H10", 20, 10)),
+            PokerAction::Raise(_)
+        ));
+        assert_eq!(
+            bot.decide(&view("S3 D2", "H14 C13 H9 S11 D7", 20, 30)),
+            PokerAction::Fold
+        );
+        assert_eq!(
+            bot.decide(&view("S3 D2", "H14 C13 H9 S11 D7", 20, 0)),
+            PokerAction::CallOrCheck
+        );
+    }
+
+    #[test]
+    fn should_parse_personalities() {
+        assert_eq!(Personality::parse("nit"), Personality::preset("nit"));
+        let custom = Personality::parse("lag:bluff=0.5").unwrap();
+        assert_eq!(custom.bluff, 0.5);
+        assert_eq!(custom.vpip, Personality::preset("lag").unwrap().vpip);
+        let knobs = Personality::parse("vpip=0.3:aggression=0.4:bluff=0").unwrap();
+        assert_eq!(knobs, Personality::new(0.3, 0.4, 0.0));
+        assert!(Personality::parse("nit:bluff=2").is_none());
+        assert!(Personality::parse("shark").is_none());
+    }
+
+    #[test]
+    fn should_play_hands_according_to_personality() {
+        let mut nit = PersonalityBot::new(Personality::preset("nit").unwrap(), None);
+        let mut maniac = PersonalityBot::new(Personality::new(0.7, 1.0, 1.0), None);
+        let mediocre = view("S10 H7", "", 0, 2);
+        assert_eq!(nit.decide(&mediocre), PokerAction::Fold);
+        assert!(matches!(maniac.decide(&mediocre), PokerAction::Raise(_)));
+
+        let missed = view("S3 D2", "H14 C13 H9", 10, 0);
+        assert!(matches!(maniac.decide(&missed), PokerAction::Raise(_)));
+        assert_eq!(nit.decide(&missed), PokerAction::CallOrCheck);
+    }
+
+    #[test]
+    fn should_mix_actions_at_the_configured_frequency() {
+        let always = Mix {
+            bluff: 1.0,
+            slowplay: 1.0,
+            hero_call: 1.0,
+        };
+        let mut bot = MixedBot::new(Box::new(RuleBot), always, Some(1));
+        let trash = view("S3 D2", "H14 C13 H9", 20, 0);
+        assert!(matches!(bot.decide(&trash), PokerAction::Raise(_)));
+        let (action, reason) = bot.decide_explained(&view("S3 D2", "H14 C13 H9", 20, 30));
+        assert_eq!(action, PokerAction::CallOrCheck);
+        assert_eq!(reason.unwrap(), "mixed in a hero call");
+
+        // The same seed mixes the same way, and about as often as configured.
+        let sometimes = Mix {
+            bluff: 0.3,
+            ..Mix::default()
+        };
+        let bluffs = |seed| {
+            let mut bot = MixedBot::new(Box::new(CallingStation), sometimes, Some(seed));
+            (0..1000)
+                .map(|_| bot.decide(&trash))
+                .map(|a| matches!(a, PokerAction::Raise(_)))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(bluffs(7), bluffs(7));
+        assert_ne!(bluffs(7), bluffs(8));
+        let count = bluffs(7).into_iter().filter(|&b| b).count();
+        assert!((250..350).contains(&count), "{count} bluffs");
+    }
+
+    #[test]
+    fn should_parse_bot_kinds() {
+        assert_eq!(BotKind::parse("easy"), Some(BotKind::Easy));
+        assert_eq!(
+            BotKind::parse("hard"),
+            Some(BotKind::Hard(HARD_BOT_SAMPLES))
+        );
+        assert_eq!(BotKind::parse("mc=50"), Some(BotKind::Hard(50)));
+        assert_eq!(
+            BotKind::parse("maniac"),
+            Personality::preset("maniac").map(BotKind::Personality)
+        );
+        assert_eq!(BotKind::parse("mc=lots"), None);
+        assert_eq!(
+            BotKind::parse("exec=python3 bot.py"),
+            Some(BotKind::External("python3 bot.py".to_owned()))
+        );
+        assert_eq!(BotKind::parse("exec="), None);
+        assert_eq!(BotKind::parse("chart"), Some(BotKind::Chart(None)));
+        assert_eq!(
+            BotKind::parse("easy@hero_call=0.5"),
+            Some(BotKind::Mixed(
+                Box::new(BotKind::Easy),
+                Mix {
+                    hero_call: 0.5,
+                    ..Mix::default()
+                }
+            ))
+        );
+        assert_eq!(BotKind::parse("easy@bluff=2"), None);
+        assert_eq!(BotKind::parse("nobody@bluff=0.1"), None);
+        assert_eq!(
+            BotKind::parse("chart=my.txt"),
+            Some(BotKind::Chart(Some("my.txt".to_owned())))
+        );
+        assert_eq!(
+            BotKind::parse("onnx=policy.onnx"),
+            Some(BotKind::Onnx("policy.onnx".to_owned()))
+        );
+        assert_eq!(BotKind::parse("onnx="), None);
+    }
+
+    #[test]
+    fn should_call_all_in_when_short_stacked() {
+        let gs = GameState::builder().stacks(vec![20, 10]).build().unwrap();
+        let (mut hs, _) = gs.start_play_hand(Deck::ordered_deck());
+        assert!(hs.play_action(PokerAction::Raise(20)).is_err());
+        hs.play_action(PokerAction::Raise(15)).unwrap();
+
+        let view = hs.bot_context(1);
+        assert_eq!(view.expected_call, 14);
+        let result = hs.play_action(CallingStation.decide(&view));
+        assert!(matches!(result, Ok(TurnResult::WonHand(_))));
+        assert!(hs.events().contains(&GameEvent::Called {
+            player: 1,
+            street: Street::Preflop,
+            amount: 8
+        }));
+    }
+}
@@ -0,0 +1,193 @@
+//! `GameState::builder()`: a table set up seat by seat, with names, stacks and the rest
+//! checked before the first hand is dealt. Every game is no-limit hold'em with blinds of
+//! 1/2 and no antes.
+
+use std::time::Duration;
+
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+
+use crate::core_engine::Deck;
+use crate::{GameState, BIG_BLIND_CHIPS};
+
+/// The most players one deck deals to: two cards each and five for the board.
+pub const MAX_PLAYERS: usize = 23;
+
+/// What a game keeps from the builder besides the stacks.
+#[derive(Clone, Default)]
+pub(crate) struct TableSetup {
+    names: Vec<String>,
+    turn_timeout: Option<Duration>,
+    /// Shuffles the decks when the game was given a seed.
+    rng: Option<StdRng>,
+}
+
+pub struct GameBuilder {
+    players: Option<usize>,
+    names: Option<Vec<String>>,
+    stack: u32,
+    stacks: Option<Vec<u32>>,
+    turn_timeout: Option<Duration>,
+    seed: Option<u64>,
+}
+
+impl GameBuilder {
+    pub(crate) fn new() -> Self {
+        Self {
+            players: None,
+            names: None,
+            stack: 100,
+            stacks: None,
+            turn_timeout: None,
+            seed: None,
+        }
+    }
+
+    /// How many seats the table has. Names or stacks given by seat say it too.
+    pub fn players(mut self, players: usize) -> Self {
+        self.players = Some(players);
+        self
+    }
+
+    pub fn names(mut self, names: impl IntoIterator<Item = impl Into<String>>) -> Self {
+        self.names = Some(names.into_iter().map(Into::into).collect());
+        self
+    }
+
+    /// The same stack for every seat, 100 chips unless set.
+    pub fn stack(mut self, stack: u32) -> Self {
+        self.stack = stack;
+        self.stacks = None;
+        self
+    }
+
+    /// A stack for each seat.
+    pub fn stacks(mut self, stacks: Vec<u32>) -> Self {
+        self.stacks = Some(stacks);
+        self
+    }
+
+    /// How long players have to act. The engine never times anyone out itself, the
+    /// frontend playing the game reads it with `GameState::turn_timeout`.
+    pub fn turn_timeout(mut self, timeout: Duration) -> Self {
+        self.turn_timeout = Some(timeout);
+        self
+    }
+
+    /// Shuffles every deck from `seed`, so the game deals the same cards each time.
+    pub fn seed(mut self, seed: u64) -> Self {
+        self.seed = Some(seed);
+        self
+    }
+
+    /// The game, or what is wrong with the table.
+    pub fn build(self) -> Result<GameState, String> {
+        let players = self
+            .players
+            .or(self.stacks.as_ref().map(Vec::len))
+            .or(self.names.as_ref().map(Vec::len))
+            .ok_or("the number of players is not set")?;
+        if players < 2 {
+            return Err("a game needs at least 2 players".into());
+        }
+        if players > MAX_PLAYERS {
+            return Err(format!("a deck deals to at most {MAX_PLAYERS} players"));
+        }
+        let chips = self.stacks.unwrap_or_else(|| vec![self.stack; players]);
+        if chips.len() != players {
+            return Err(format!("{} stacks for {players} players", chips.len()));
+        }
+        if chips.iter().any(|&stack| stack < BIG_BLIND_CHIPS) {
+            return Err(format!(
+                "every stack needs at least {BIG_BLIND_CHIPS} chips"
+            ));
+        }
+        let names = self
+            .names
+            .unwrap_or_else(|| (0..players).map(|p| format!("Player {p}")).collect());
+        if names.len() != players {
+            return Err(format!("{} names for {players} players", names.len()));
+        }
+        if let Some(name) = names
+            .iter()
+            .find(|n| names.iter().filter(|m| m == n).count() > 1)
+        {
+            return Err(format!("{name} sits at the table twice"));
+        }
+
+        let mut gs = GameState::from_stacks(chips, players - 1);
+        gs.table = TableSetup {
+            names,
+            turn_timeout: self.turn_timeout,
+            rng: self.seed.map(StdRng::seed_from_u64),
+        };
+        Ok(gs)
+    }
+}
+
+impl GameState {
+    /// Sets up a table with more than `init` can say.
+    pub fn builder() -> GameBuilder {
+        GameBuilder::new()
+    }
+
+    /// The name of the player in the seat, "Player N" unless the builder named them.
+    pub fn name(&self, player: usize) -> String {
+        self.table
+            .names
+            .get(player)
+            .cloned()
+            .unwrap_or_else(|| format!("Player {player}"))
+    }
+
+    pub fn turn_timeout(&self) -> Option<Duration> {
+        self.table.turn_timeout
+    }
+
+    /// A deck for the next hand, shuffled from the seed if the game has one.
+    pub fn shuffled_deck(&mut self) -> Deck {
+        match &mut self.table.rng {
+            Some(rng) => Deck::shuffled_with(rng),
+            None => Deck::shuffled_deck(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_build_a_checked_table() {
+        let mut gs = GameState::builder()
+            .names(["ada", "bob", "cy"])
+            .stacks(vec![50, 100, 150])
+            .turn_timeout(Duration::from_secs(30))
+            .seed(7)
+            .build()
+            .unwrap();
+        assert_eq!(gs.players(), 3);
+        assert_eq!(gs.current_chips(2), 150);
+        assert_eq!(gs.name(1), "bob");
+        assert_eq!(gs.turn_timeout(), Some(Duration::from_secs(30)));
+        let mut again = GameState::builder().players(2).seed(7).build().unwrap();
+        assert_eq!(again.shuffled_deck().cards(), gs.shuffled_deck().cards());
+
+        let next = gs.apply_played_hand(gs.start_play_hand(Deck::ordered_deck()).0);
+        assert_eq!(next.name(0), "ada");
+
+        assert!(GameState::builder().build().is_err());
+        assert!(GameState::builder().players(1).build().is_err());
+        assert!(GameState::builder()
+            .players(3)
+            .stacks(vec![100, 100])
+            .build()
+            .is_err());
+        assert!(GameState::builder().names(["ada", "ada"]).build().is_err());
+        assert!(GameState::builder().players(MAX_PLAYERS).build().is_ok());
+        assert!(GameState::builder()
+            .players(MAX_PLAYERS + 1)
+            .build()
+            .is_err());
+    }
+}
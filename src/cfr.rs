@@ -0,0 +1,489 @@
+use rand::rngs::StdRng;
+use rand::Rng;
+
+use crate::bots::{raise_or_call_by, seeded_rng, BotContext, BotPlayer, MonteCarloBot, PlayerView};
+use crate::equity::equity_vs_random;
+use crate::PokerAction;
+
+/// A betting tree between two players, player 0 acting first.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Tree {
+    Act {
+        player: usize,
+        options: Vec<(String, Tree)>,
+    },
+    /// `folder` gave up, with each player having put in `invested` on top of the pot.
+    Fold {
+        folder: usize,
+        invested: [f64; 2],
+    },
+    Showdown {
+        invested: [f64; 2],
+    },
+}
+
+enum Node {
+    Act {
+        player: usize,
+        options: Vec<(String, usize)>,
+        /// Where this node's information sets start, one for each hand of `player`.
+        first_info_set: usize,
+    },
+    Fold {
+        folder: usize,
+        invested: [f64; 2],
+    },
+    Showdown {
+        invested: [f64; 2],
+    },
+}
+
+/// A small abstracted game solved by counterfactual regret minimization. Each player holds
+/// one of a few hands, like hand strength buckets; which pairs get dealt and who wins the
+/// showdown between them are given up front.
+pub struct Subgame {
+    nodes: Vec<Node>,
+    pot: f64,
+    /// Hands dealt to players 0 and 1, and how likely that deal is.
+    deals: Vec<([usize; 2], f64)>,
+    /// `equity[a][b]` is player 0's share of the pot holding `a` against `b`.
+    equity: Vec<Vec<f64>>,
+    regrets: Vec<Vec<f64>>,
+    strategy_sums: Vec<Vec<f64>>,
+}
+
+impl Subgame {
+    pub fn new(tree: Tree, pot: f64, deals: Vec<([usize; 2], f64)>, equity: Vec<Vec<f64>>) -> Self {
+        let hands = [equity.len(), equity.first().map_or(0, Vec::len)];
+        let mut game = Subgame {
+            nodes: vec![],
+            pot,
+            deals,
+            equity,
+            regrets: vec![],
+            strategy_sums: vec![],
+        };
+        game.add(tree, hands);
+        game
+    }
+
+    fn add(&mut self, tree: Tree, hands: [usize; 2]) -> usize {
+        let index = self.nodes.len();
+        match tree {
+            Tree::Act { player, options } => {
+                let first_info_set = self.regrets.len();
+                for _ in 0..hands[player] {
+                    self.regrets.push(vec![0.0; options.len()]);
+                    self.strategy_sums.push(vec![0.0; options.len()]);
+                }
+                self.nodes.push(Node::Act {
+                    player,
+                    options: vec![],
+                    first_info_set,
+                });
+                let children = options
+                    .into_iter()
+                    .map(|(label, child)| (label, self.add(child, hands)))
+                    .collect();
+                if let Node::Act { options, .. } = &mut self.nodes[index] {
+                    *options = children;
+                }
+            }
+            Tree::Fold { folder, invested } => self.nodes.push(Node::Fold { folder, invested }),
+            Tree::Showdown { invested } => self.nodes.push(Node::Showdown { invested }),
+        }
+        index
+    }
+
+    /// Runs `iterations` passes of CFR+ over every deal.
+    pub fn solve(&mut self, iterations: usize) {
+        for _ in 0..iterations {
+            for d in 0..self.deals.len() {
+                let (hands, chance) = self.deals[d];
+                self.cfr(0, hands, [chance, chance]);
+            }
+        }
+    }
+
+    /// Player 0's result at a terminal node. The pot is shared out evenly beforehand, so
+    /// whatever one player wins the other loses.
+    fn payoff(&self, node: usize, hands: [usize; 2]) -> f64 {
+        let half_pot = self.pot / 2.0;
+        match self.nodes[node] {
+            Node::Fold {
+                folder: 0,
+                invested,
+            } => -invested[0] - half_pot,
+            Node::Fold { invested, .. } => invested[1] + half_pot,
+            Node::Showdown { invested } => {
+                let total = self.pot + invested[0] + invested[1];
+                self.equity[hands[0]][hands[1]] * total - invested[0] - half_pot
+            }
+            Node::Act { .. } => unreachable!("not a terminal node"),
+        }
+    }
+
+    fn current_strategy(regrets: &[f64]) -> Vec<f64> {
+        let total: f64 = regrets.iter().map(|r| r.max(0.0)).sum();
+        if total > 0.0 {
+            regrets.iter().map(|r| r.max(0.0) / total).collect()
+        } else {
+            vec![1.0 / regrets.len() as f64; regrets.len()]
+        }
+    }
+
+    /// Walks the tree for one deal and returns player 0's expected result.
+    fn cfr(&mut self, node: usize, hands: [usize; 2], reach: [f64; 2]) -> f64 {
+        let Node::Act {
+            player,
+            ref options,
+            first_info_set,
+        } = self.nodes[node]
+        else {
+            return self.payoff(node, hands);
+        };
+        let children: Vec<usize> = options.iter().map(|&(_, child)| child).collect();
+        let info_set = first_info_set + hands[player];
+        let strategy = Self::current_strategy(&self.regrets[info_set]);
+
+        let mut values = vec![0.0; children.len()];
+        let mut value = 0.0;
+        for (a, &child) in children.iter().enumerate() {
+            let mut child_reach = reach;
+            child_reach[player] *= strategy[a];
+            values[a] = self.cfr(child, hands, child_reach);
+            value += strategy[a] * values[a];
+        }
+
+        let sign = if player == 0 { 1.0 } else { -1.0 };
+        let opponent_reach = reach[1 - player];
+        for a in 0..children.len() {
+            let regret = &mut self.regrets[info_set][a];
+            *regret = (*regret + opponent_reach * sign * (values[a] - value)).max(0.0);
+            self.strategy_sums[info_set][a] += reach[player] * strategy[a];
+        }
+        value
+    }
+
+    fn find(&self, path: &[&str]) -> Option<usize> {
+        let mut node = 0;
+        for step in path {
+            let Node::Act { options, .. } = &self.nodes[node] else {
+                return None;
+            };
+            node = options.iter().find(|(label, _)| label == step)?.1;
+        }
+        Some(node)
+    }
+
+    /// The options after following `path` from the root, for mapping real bets onto them.
+    pub fn options(&self, path: &[&str]) -> Option<Vec<String>> {
+        match &self.nodes[self.find(path)?] {
+            Node::Act { options, .. } => Some(options.iter().map(|(l, _)| l.clone()).collect()),
+            _ => None,
+        }
+    }
+
+    /// How often to take each option after following `path` from the root holding `hand`,
+    /// averaged over all iterations so far.
+    pub fn strategy(&self, path: &[&str], hand: usize) -> Option<Vec<(String, f64)>> {
+        let Node::Act {
+            options,
+            first_info_set,
+            ..
+        } = &self.nodes[self.find(path)?]
+        else {
+            return None;
+        };
+        let sums = self.strategy_sums.get(first_info_set + hand)?;
+        let strategy = Self::current_strategy(sums);
+        Some(
+            options
+                .iter()
+                .zip(strategy)
+                .map(|((label, _), p)| (label.clone(), p))
+                .collect(),
+        )
+    }
+
+    /// Player 0's expected result when both players use their average strategies.
+    pub fn value(&self) -> f64 {
+        self.deals
+            .iter()
+            .map(|&(hands, chance)| chance * self.average_value(0, hands))
+            .sum()
+    }
+
+    fn average_value(&self, node: usize, hands: [usize; 2]) -> f64 {
+        let Node::Act {
+            player,
+            options,
+            first_info_set,
+        } = &self.nodes[node]
+        else {
+            return self.payoff(node, hands);
+        };
+        let strategy = Self::current_strategy(&self.strategy_sums[first_info_set + hands[*player]]);
+        options
+            .iter()
+            .zip(strategy)
+            .map(|(&(_, child), p)| p * self.average_value(child, hands))
+            .sum()
+    }
+}
+
+/// One street of betting with `stack` behind: player 0 checks or bets one of `bet_sizes`
+/// (in chips), and whoever faces a bet folds or calls.
+pub fn one_street_tree(stack: f64, bet_sizes: &[f64]) -> Tree {
+    let bets = |bettor: usize| -> Vec<(String, Tree)> {
+        bet_sizes
+            .iter()
+            .map(|&size| {
+                let size = size.min(stack);
+                let mut invested = [0.0; 2];
+                invested[bettor] = size;
+                let responses = vec![
+                    (
+                        "fold".to_owned(),
+                        Tree::Fold {
+                            folder: 1 - bettor,
+                            invested,
+                        },
+                    ),
+                    (
+                        "call".to_owned(),
+                        Tree::Showdown {
+                            invested: [size, size],
+                        },
+                    ),
+                ];
+                (
+                    format!("bet {size}"),
+                    Tree::Act {
+                        player: 1 - bettor,
+                        options: responses,
+                    },
+                )
+            })
+            .collect()
+    };
+
+    let mut after_check = vec![(
+        "check".to_owned(),
+        Tree::Showdown {
+            invested: [0.0, 0.0],
+        },
+    )];
+    after_check.extend(bets(1));
+    let mut options = vec![(
+        "check".to_owned(),
+        Tree::Act {
+            player: 1,
+            options: after_check,
+        },
+    )];
+    options.extend(bets(0));
+    Tree::Act { player: 0, options }
+}
+
+/// Hand strength buckets the river solver tells apart.
+const RIVER_BUCKETS: usize = 8;
+const RIVER_ITERATIONS: usize = 300;
+
+/// Solves heads-up river spots with CFR over hand strength buckets, both players assumed
+/// to hold any hand, and plays like a `MonteCarloBot` everywhere else.
+pub struct CfrBot {
+    fallback: MonteCarloBot,
+    rng: StdRng,
+}
+
+impl CfrBot {
+    pub fn new(samples: usize, seed: Option<u64>) -> Self {
+        Self {
+            fallback: MonteCarloBot::new(samples, seed),
+            rng: seeded_rng(seed),
+        }
+    }
+
+    fn river_game(pot: f64, stack: f64) -> Subgame {
+        let bet_sizes = [(pot / 2.0).round().max(1.0), pot, stack];
+        let mut sizes: Vec<f64> = bet_sizes.into_iter().map(|s| s.min(stack)).collect();
+        sizes.dedup();
+        let equity = (0..RIVER_BUCKETS)
+            .map(|a| {
+                (0..RIVER_BUCKETS)
+                    .map(|b| match a.cmp(&b) {
+                        std::cmp::Ordering::Greater => 1.0,
+                        std::cmp::Ordering::Equal => 0.5,
+                        std::cmp::Ordering::Less => 0.0,
+                    })
+                    .collect()
+            })
+            .collect();
+        let chance = 1.0 / (RIVER_BUCKETS * RIVER_BUCKETS) as f64;
+        let deals = (0..RIVER_BUCKETS)
+            .flat_map(|a| (0..RIVER_BUCKETS).map(move |b| ([a, b], chance)))
+            .collect();
+        let mut game = Subgame::new(one_street_tree(stack, &sizes), pot, deals, equity);
+        game.solve(RIVER_ITERATIONS);
+        game
+    }
+
+    fn decide_river(&mut self, view: &PlayerView) -> Option<(PokerAction, String)> {
+        let opponents: Vec<usize> = (0..view.active.len())
+            .filter(|&p| p != view.player && view.active[p])
+            .collect();
+        let [opponent] = opponents[..] else {
+            return None;
+        };
+        if view.board.len() != 5 || view.bets[view.player] > 0 {
+            return None;
+        }
+
+        let stack = view.stacks[view.player].min(view.stacks[opponent] + view.bets[opponent]);
+        let game = Self::river_game(view.pot as f64, stack as f64);
+        // The player after the button acts first after the flop.
+        let first = view.player != view.button;
+        let facing = view.bets[opponent];
+        let closest_bet = |path: &[&str]| -> Option<String> {
+            game.options(path)?
+                .into_iter()
+                .filter_map(|o| Some((o.strip_prefix("bet ")?.parse::<f64>().ok()?, o)))
+                .min_by(|a, b| {
+                    (a.0 - facing as f64)
+                        .abs()
+                        .total_cmp(&(b.0 - facing as f64).abs())
+                })
+                .map(|(_, o)| o)
+        };
+        let path: Vec<String> = match (first, facing) {
+            (true, 0) => vec![],
+            (false, 0) => vec!["check".to_owned()],
+            (true, _) => vec!["check".to_owned(), closest_bet(&["check"])?],
+            (false, _) => vec![closest_bet(&[])?],
+        };
+        let path: Vec<&str> = path.iter().map(String::as_str).collect();
+
+        let samples = self.fallback.samples;
+        let strength = equity_vs_random(view.cards, &view.board, 1, samples, &mut self.rng);
+        let bucket = ((strength * RIVER_BUCKETS as f64) as usize).min(RIVER_BUCKETS - 1);
+        let strategy = game.strategy(&path, bucket)?;
+        let mut roll = self.rng.random::<f64>();
+        let (label, _) = strategy
+            .iter()
+            .find(|(_, p)| {
+                roll -= p;
+                roll < 0.0
+            })
+            .unwrap_or(&strategy[strategy.len() - 1]);
+
+        let action = match label.as_str() {
+            "fold" => PokerAction::Fold,
+            "check" | "call" => PokerAction::CallOrCheck,
+            bet => raise_or_call_by(view, bet.strip_prefix("bet ")?.parse::<f64>().ok()? as u32),
+        };
+        let mix = strategy
+            .iter()
+            .filter(|(_, p)| *p >= 0.01)
+            .map(|(l, p)| format!("{l} {:.0}%", 100.0 * p))
+            .collect::<Vec<_>>()
+            .join(", ");
+        let reason = format!(
+            "river solver, strength bucket {} of {RIVER_BUCKETS}: {mix}",
+            bucket + 1
+        );
+        Some((action, reason))
+    }
+}
+
+impl BotPlayer for CfrBot {
+    fn decide(&mut self, view: &BotContext) -> PokerAction {
+        self.decide_explained(view).0
+    }
+
+    fn decide_explained(&mut self, view: &BotContext) -> (PokerAction, Option<String>) {
+        match self.decide_river(view) {
+            Some((action, reason)) => (action, Some(reason)),
+            None => self.fallback.decide_explained(view),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core_engine::Card;
+    use crate::events::Street;
+    use crate::BIG_BLIND_CHIPS;
+
+    /// Kuhn poker: one of three cards each, an ante of 1 and a single bet of 1.
+    fn kuhn() -> Subgame {
+        let deals = (0..3)
+            .flat_map(|a| {
+                (0..3)
+                    .filter(move |&b| b != a)
+                    .map(move |b| ([a, b], 1.0 / 6.0))
+            })
+            .collect();
+        let equity = (0..3)
+            .map(|a| (0..3).map(|b| if a > b { 1.0 } else { 0.0 }).collect())
+            .collect();
+        Subgame::new(one_street_tree(1.0, &[1.0]), 2.0, deals, equity)
+    }
+
+    #[test]
+    fn should_solve_kuhn_poker() {
+        let mut game = kuhn();
+        game.solve(2000);
+        // The first player loses 1/18 of a chip per hand at equilibrium.
+        assert!(
+            (game.value() - -1.0 / 18.0).abs() < 0.005,
+            "{}",
+            game.value()
+        );
+
+        // Holding the best card, always call a bet.
+        let calls = game.strategy(&["bet 1"], 2).unwrap();
+        assert_eq!(calls[1].0, "call");
+        assert!(calls[1].1 > 0.99);
+        // Holding the worst card, never call one.
+        let calls = game.strategy(&["check", "bet 1"], 0).unwrap();
+        assert!(calls[1].1 < 0.01);
+        assert_eq!(game.strategy(&["nowhere"], 0), None);
+    }
+
+    fn river(player: usize, cards: &str, bets: Vec<u32>) -> BotContext {
+        let cards: Vec<Card> = format!("{cards} H14 H13 H12 C2 D7")
+            .split_ascii_whitespace()
+            .map(|c| Card::try_from(c).unwrap())
+            .collect();
+        let expected_call = bets[1 - player].saturating_sub(bets[player]);
+        BotContext::from(PlayerView {
+            player,
+            button: 0,
+            cards: (cards[0], cards[1]),
+            board: cards[2..].to_vec(),
+            street: Street::River,
+            stacks: vec![50, 50],
+            expected_call,
+            min_raise: expected_call + BIG_BLIND_CHIPS,
+            bets,
+            active: vec![true, true],
+            pot: 20,
+        })
+    }
+
+    #[test]
+    fn should_play_river_spots_by_the_solution() {
+        let mut bot = CfrBot::new(100, Some(1));
+        let (action, reason) = bot.decide_explained(&river(1, "H11 H10", vec![0, 0]));
+        assert_ne!(action, PokerAction::Fold);
+        assert!(reason
+            .unwrap()
+            .starts_with("river solver, strength bucket 8 of 8"));
+
+        // Nothing at all facing a pot sized bet.
+        let (action, reason) = bot.decide_explained(&river(0, "S4 D3", vec![0, 20]));
+        assert_eq!(action, PokerAction::Fold, "{reason:?}");
+    }
+}
@@ -0,0 +1,419 @@
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use crate::bots::{check_or_fold, raise_or_call_by, BotContext, BotPlayer, PlayerView, RuleBot};
+use crate::core_engine::Card;
+use crate::{PokerAction, BIG_BLIND_CHIPS};
+
+/// The chart used when no file is given, also a template for writing your own.
+pub const DEFAULT_CHART: &str = include_str!("../charts/default.txt");
+
+/// Where a seat acts preflop relative to the button.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Position {
+    Early,
+    Middle,
+    /// The cutoff and the button.
+    Late,
+    Blinds,
+}
+
+impl Position {
+    pub fn of(view: &PlayerView) -> Self {
+        Self::at(view.player, view.button, view.stacks.len())
+    }
+
+    /// The position of `seat` at a table of `players` with the button at `button`.
+    pub fn at(seat: usize, button: usize, players: usize) -> Self {
+        let after_button = (seat + players - button) % players;
+        if players == 2 {
+            return if after_button == 0 {
+                Position::Late
+            } else {
+                Position::Blinds
+            };
+        }
+        match (after_button, players - after_button) {
+            (0, _) | (_, 1) => Position::Late,
+            (1 | 2, _) => Position::Blinds,
+            (_, 2) => Position::Middle,
+            _ => Position::Early,
+        }
+    }
+
+    fn parse(name: &str) -> Option<Self> {
+        match name {
+            "early" => Some(Position::Early),
+            "middle" => Some(Position::Middle),
+            "late" => Some(Position::Late),
+            "blinds" => Some(Position::Blinds),
+            _ => None,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Position::Early => "early",
+            Position::Middle => "middle",
+            Position::Late => "late",
+            Position::Blinds => "blinds",
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum ChartAction {
+    /// Raise when nobody has raised yet.
+    Open,
+    /// Call a single raise.
+    Call,
+    /// Re-raise a raise, and call when re-raised.
+    ThreeBet,
+}
+
+impl ChartAction {
+    fn parse(name: &str) -> Option<Self> {
+        match name {
+            "open" => Some(ChartAction::Open),
+            "call" => Some(ChartAction::Call),
+            "3bet" => Some(ChartAction::ThreeBet),
+            _ => None,
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            ChartAction::Open => "open",
+            ChartAction::Call => "call",
+            ChartAction::ThreeBet => "3bet",
+        }
+    }
+}
+
+/// A set of starting hands, written like `77+ ATs+ KQo`.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Range(HashSet<(u8, u8, bool)>);
+
+impl Range {
+    pub fn parse(text: &str) -> Option<Self> {
+        let mut range = Range::default();
+        for token in text.split_ascii_whitespace() {
+            range.add(token)?;
+        }
+        Some(range)
+    }
+
+    fn add(&mut self, token: &str) -> Option<()> {
+        let (token, plus) = match token.strip_suffix('+') {
+            Some(token) => (token, true),
+            None => (token, false),
+        };
+        let mut chars = token.chars();
+        let first = rank(chars.next()?)?;
+        let second = rank(chars.next()?)?;
+        let suited = match chars.next() {
+            None => vec![true, false],
+            Some('s') => vec![true],
+            Some('o') => vec![false],
+            Some(_) => return None,
+        };
+        if chars.next().is_some() {
+            return None;
+        }
+
+        let (high, low) = (first.max(second), first.min(second));
+        if high == low {
+            if suited.len() == 1 {
+                return None;
+            }
+            let top = if plus { 14 } else { high };
+            self.0.extend((high..=top).map(|v| (v, v, false)));
+        } else {
+            let top = if plus { high - 1 } else { low };
+            for low in low..=top {
+                self.0.extend(suited.iter().map(|&s| (high, low, s)));
+            }
+        }
+        Some(())
+    }
+
+    pub fn contains(&self, cards: (Card, Card)) -> bool {
+        self.0.contains(&class(cards))
+    }
+}
+
+fn rank(c: char) -> Option<u8> {
+    match c {
+        '2'..='9' => c.to_digit(10).map(|d| d as u8),
+        'T' => Some(10),
+        'J' => Some(11),
+        'Q' => Some(12),
+        'K' => Some(13),
+        'A' => Some(14),
+        _ => None,
+    }
+}
+
+/// The high card, the low card and whether they are suited, pairs never are.
+pub(crate) fn class(cards: (Card, Card)) -> (u8, u8, bool) {
+    let (high, low) = (
+        cards.0.value.max(cards.1.value),
+        cards.0.value.min(cards.1.value),
+    );
+    (high, low, high != low && cards.0.suit == cards.1.suit)
+}
+
+/// Starting hands written the way charts do, like `AKs` or `T9o`.
+fn class_name(cards: (Card, Card)) -> String {
+    label(class(cards))
+}
+
+/// A `class` written the way charts do.
+pub(crate) fn label((high, low, suited): (u8, u8, bool)) -> String {
+    let name = |v: u8| b"23456789TJQKA"[v as usize - 2] as char;
+    let suffix = match (high == low, suited) {
+        (true, _) => "",
+        (false, true) => "s",
+        (false, false) => "o",
+    };
+    format!("{}{}{suffix}", name(high), name(low))
+}
+
+/// The line of a chart file that could not be read.
+#[derive(Debug, PartialEq, Eq)]
+pub struct ChartError {
+    pub line: usize,
+}
+
+impl fmt::Display for ChartError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "invalid chart line {}", self.line)
+    }
+}
+
+/// Which hands to open, call and 3-bet from each position.
+#[derive(Clone, Debug, PartialEq)]
+pub struct PreflopChart {
+    ranges: HashMap<(Position, ChartAction), Range>,
+}
+
+impl Default for PreflopChart {
+    fn default() -> Self {
+        PreflopChart::parse(DEFAULT_CHART).expect("the default chart is valid")
+    }
+}
+
+impl PreflopChart {
+    /// Reads lines of `POSITION ACTION HANDS...`, skipping empty lines and `#` comments.
+    /// Lines for the same position and action add up.
+    pub fn parse(text: &str) -> Result<Self, ChartError> {
+        let mut ranges: HashMap<_, Range> = HashMap::new();
+        for (number, line) in text.lines().enumerate() {
+            let line = line.split('#').next().unwrap_or_default();
+            let mut words = line.split_ascii_whitespace();
+            let Some(position) = words.next() else {
+                continue;
+            };
+            let error = || ChartError { line: number + 1 };
+            let position = Position::parse(position).ok_or_else(error)?;
+            let action = words
+                .next()
+                .and_then(ChartAction::parse)
+                .ok_or_else(error)?;
+            let range = ranges.entry((position, action)).or_default();
+            for token in words {
+                range.add(token).ok_or_else(error)?;
+            }
+        }
+        Ok(PreflopChart { ranges })
+    }
+
+    pub fn load(path: &Path) -> io::Result<Self> {
+        let text = fs::read_to_string(path)?;
+        PreflopChart::parse(&text).map_err(|e| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("{}: {e}", path.display()),
+            )
+        })
+    }
+
+    pub fn contains(&self, position: Position, action: ChartAction, cards: (Card, Card)) -> bool {
+        self.ranges
+            .get(&(position, action))
+            .is_some_and(|range| range.contains(cards))
+    }
+}
+
+/// Plays preflop by a `PreflopChart` and like a `RuleBot` after the flop.
+pub struct ChartBot {
+    pub chart: PreflopChart,
+}
+
+impl BotPlayer for ChartBot {
+    fn decide(&mut self, view: &BotContext) -> PokerAction {
+        self.decide_explained(view).0
+    }
+
+    fn decide_explained(&mut self, view: &BotContext) -> (PokerAction, Option<String>) {
+        if !view.board.is_empty() {
+            return RuleBot.decide_explained(view);
+        }
+
+        let position = view.position;
+        let highest_bet = view.bets.iter().copied().max().unwrap_or(0);
+        let in_range = |action| self.chart.contains(position, action, view.cards);
+        let (action, chart_action) = if highest_bet <= BIG_BLIND_CHIPS {
+            // Open to three big blinds.
+            let open = raise_or_call_by(view, 2 * BIG_BLIND_CHIPS);
+            let action = if in_range(ChartAction::Open) {
+                open
+            } else {
+                check_or_fold(view)
+            };
+            (action, ChartAction::Open)
+        } else if view.bets[view.player] > BIG_BLIND_CHIPS {
+            // This player raised already and was re-raised.
+            let action = if in_range(ChartAction::ThreeBet) {
+                PokerAction::CallOrCheck
+            } else {
+                PokerAction::Fold
+            };
+            (action, ChartAction::ThreeBet)
+        } else if in_range(ChartAction::ThreeBet) {
+            // Re-raise to three times the raise.
+            (
+                raise_or_call_by(view, 2 * highest_bet),
+                ChartAction::ThreeBet,
+            )
+        } else if in_range(ChartAction::Call) {
+            (PokerAction::CallOrCheck, ChartAction::Call)
+        } else {
+            (PokerAction::Fold, ChartAction::Call)
+        };
+
+        let verdict = if in_range(chart_action) {
+            "in"
+        } else {
+            "not in"
+        };
+        let reason = format!(
+            "{} is {verdict} the {} {} range",
+            class_name(view.cards),
+            position.name(),
+            chart_action.name()
+        );
+        (action, Some(reason))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::events::Street;
+
+    fn cards(text: &str) -> (Card, Card) {
+        let mut cards = text
+            .split_ascii_whitespace()
+            .map(|c| Card::try_from(c).unwrap());
+        (cards.next().unwrap(), cards.next().unwrap())
+    }
+
+    fn view(hand: &str, player: usize, bets: Vec<u32>) -> BotContext {
+        let players = bets.len();
+        let expected_call = bets.iter().max().unwrap() - bets[player];
+        BotContext::from(PlayerView {
+            player,
+            button: 0,
+            cards: cards(hand),
+            board: vec![],
+            street: Street::Preflop,
+            stacks: vec![100; players],
+            expected_call,
+            min_raise: expected_call + BIG_BLIND_CHIPS,
+            bets,
+            active: vec![true; players],
+            pot: 0,
+        })
+    }
+
+    #[test]
+    fn should_parse_ranges() {
+        let range = Range::parse("TT+ ATs+ KQ").unwrap();
+        assert!(range.contains(cards("S10 H10")));
+        assert!(range.contains(cards("S14 H14")));
+        assert!(!range.contains(cards("S9 H9")));
+        assert!(range.contains(cards("S14 S10")));
+        assert!(!range.contains(cards("S14 H10")));
+        assert!(range.contains(cards("S13 H12")));
+        assert!(range.contains(cards("S13 S12")));
+
+        assert_eq!(Range::parse("AAs"), None);
+        assert_eq!(Range::parse("AX"), None);
+        assert_eq!(Range::parse("AKss"), None);
+    }
+
+    #[test]
+    fn should_report_the_invalid_chart_line() {
+        assert_eq!(
+            PreflopChart::parse("# comment\nlate open AA\nlate raise KK"),
+            Err(ChartError { line: 3 })
+        );
+        assert_eq!(
+            PreflopChart::parse("late open AA\n\nnowhere open KK"),
+            Err(ChartError { line: 3 })
+        );
+        let chart = PreflopChart::parse("late open AA\nlate open KK # and kings").unwrap();
+        assert!(chart.contains(Position::Late, ChartAction::Open, cards("S13 H13")));
+        assert!(!chart.contains(Position::Early, ChartAction::Open, cards("S13 H13")));
+    }
+
+    #[test]
+    fn should_find_the_position_from_the_button() {
+        let at = |player, players| Position::of(&view("S2 H3", player, vec![0; players]));
+        assert_eq!(at(0, 2), Position::Late);
+        assert_eq!(at(1, 2), Position::Blinds);
+        assert_eq!(at(2, 6), Position::Blinds);
+        assert_eq!(at(3, 6), Position::Early);
+        assert_eq!(at(4, 6), Position::Middle);
+        assert_eq!(at(5, 6), Position::Late);
+    }
+
+    #[test]
+    fn should_play_preflop_by_the_chart() {
+        let mut bot = ChartBot {
+            chart: PreflopChart::default(),
+        };
+        // Six players, first in from early position.
+        let (action, reason) = bot.decide_explained(&view("S14 H13", 3, vec![0, 1, 2, 0, 0, 0]));
+        assert_eq!(action, PokerAction::Raise(6));
+        assert_eq!(reason.unwrap(), "AKo is in the early open range");
+        assert_eq!(
+            bot.decide(&view("S9 H5", 3, vec![0, 1, 2, 0, 0, 0])),
+            PokerAction::Fold
+        );
+
+        // Facing a raise to 6 on the button.
+        let raised = vec![0, 1, 2, 6, 0, 0];
+        assert_eq!(
+            bot.decide(&view("S14 H14", 0, raised.clone())),
+            PokerAction::Raise(18)
+        );
+        assert_eq!(
+            bot.decide(&view("S7 H7", 0, raised.clone())),
+            PokerAction::CallOrCheck
+        );
+        assert_eq!(bot.decide(&view("S14 H2", 0, raised)), PokerAction::Fold);
+
+        // Re-raised after opening.
+        let reraised = vec![18, 1, 2, 6, 0, 0];
+        assert_eq!(
+            bot.decide(&view("S12 H12", 3, reraised.clone())),
+            PokerAction::CallOrCheck
+        );
+        assert_eq!(bot.decide(&view("S7 H7", 3, reraised)), PokerAction::Fold);
+    }
+}
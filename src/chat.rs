@@ -0,0 +1,88 @@
+const MAX_MESSAGE_LENGTH: usize = 200;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ChatMessage {
+    pub player: usize,
+    pub text: String,
+}
+
+pub struct ChatLog {
+    messages: Vec<ChatMessage>,
+    muted: Vec<bool>,
+}
+
+impl ChatLog {
+    pub fn init(players: usize) -> Self {
+        Self {
+            messages: vec![],
+            muted: vec![false; players],
+        }
+    }
+
+    /// Adds a message to the log unless the sender is muted or the message is blank.
+    /// Returns whether the message was accepted.
+    pub fn post(&mut self, player: usize, text: &str) -> bool {
+        let text = text.trim();
+        if text.is_empty() || self.is_muted(player) {
+            return false;
+        }
+        self.messages.push(ChatMessage {
+            player,
+            text: text.chars().take(MAX_MESSAGE_LENGTH).collect(),
+        });
+        true
+    }
+
+    pub fn toggle_mute(&mut self, player: usize) -> Option<bool> {
+        let muted = self.muted.get_mut(player)?;
+        *muted = !*muted;
+        Some(*muted)
+    }
+
+    pub fn is_muted(&self, player: usize) -> bool {
+        self.muted.get(player).copied().unwrap_or(true)
+    }
+
+    /// The most recent messages, oldest first, skipping those from currently muted players.
+    pub fn recent(&self, count: usize) -> Vec<&ChatMessage> {
+        let mut recent: Vec<_> = self
+            .messages
+            .iter()
+            .rev()
+            .filter(|m| !self.is_muted(m.player))
+            .take(count)
+            .collect();
+        recent.reverse();
+        recent
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_keep_recent_messages_in_order() {
+        let mut chat = ChatLog::init(2);
+        assert!(chat.post(0, "hi"));
+        assert!(chat.post(1, " gl hf "));
+        assert!(chat.post(0, "nh"));
+        assert!(!chat.post(1, "   "));
+
+        let texts: Vec<_> = chat.recent(2).iter().map(|m| m.text.as_str()).collect();
+        assert_eq!(texts, vec!["gl hf", "nh"]);
+    }
+
+    #[test]
+    fn should_hide_and_reject_messages_from_muted_players() {
+        let mut chat = ChatLog::init(2);
+        chat.post(1, "before mute");
+        assert_eq!(chat.toggle_mute(1), Some(true));
+        assert!(!chat.post(1, "spam"));
+        assert!(chat.recent(10).is_empty());
+
+        assert_eq!(chat.toggle_mute(1), Some(false));
+        assert_eq!(chat.recent(10).len(), 1);
+        assert_eq!(chat.toggle_mute(5), None);
+    }
+}
@@ -0,0 +1,111 @@
+use std::mem::discriminant;
+
+use crate::bots::{BotContext, BotPlayer, MonteCarloBot};
+use crate::PokerAction;
+
+/// Suggests an action for every human decision and keeps score of how often the player
+/// did the same.
+pub struct Coach {
+    advisor: MonteCarloBot,
+    /// The advice for the decision the player is facing, if it was already worked out.
+    pending: Option<(BotContext, PokerAction, Option<String>)>,
+    matched: u32,
+    decisions: u32,
+}
+
+impl Coach {
+    pub fn init(samples: usize) -> Self {
+        Self {
+            advisor: MonteCarloBot::new(samples, None),
+            pending: None,
+            matched: 0,
+            decisions: 0,
+        }
+    }
+
+    /// The recommended action for `view` and the reason for it. The advice is worked out
+    /// once per decision, asking again for the same view gives the same answer.
+    pub fn advise(&mut self, view: &BotContext) -> (PokerAction, Option<&str>) {
+        if self.pending.as_ref().is_none_or(|(v, ..)| v != view) {
+            let (action, reason) = self.advisor.decide_explained(view);
+            self.pending = Some((view.clone(), action, reason));
+        }
+        let (_, action, reason) = self.pending.as_ref().unwrap();
+        (*action, reason.as_deref())
+    }
+
+    /// Scores the action the player took against the advice for `view`. Raises match any
+    /// advised raise, whatever their size.
+    pub fn record(&mut self, view: &BotContext, action: PokerAction) {
+        let (advice, _) = self.advise(view);
+        self.decisions += 1;
+        if discriminant(&advice) == discriminant(&action) {
+            self.matched += 1;
+        }
+        self.pending = None;
+    }
+
+    /// How many decisions followed the advice, out of how many.
+    pub fn score(&self) -> (u32, u32) {
+        (self.matched, self.decisions)
+    }
+
+    /// A letter grade from the share of decisions that followed the advice, `None` before
+    /// the first decision.
+    pub fn grade(&self) -> Option<char> {
+        if self.decisions == 0 {
+            return None;
+        }
+        let share = self.matched as f64 / self.decisions as f64;
+        Some(match share {
+            0.9.. => 'A',
+            0.75.. => 'B',
+            0.6.. => 'C',
+            0.4.. => 'D',
+            _ => 'F',
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core_engine::Deck;
+    use crate::GameState;
+
+    #[test]
+    fn should_grade_decisions_against_the_advice() {
+        let mut coach = Coach::init(100);
+        assert_eq!(coach.grade(), None);
+
+        let (hs, cur) = GameState::init(2)
+            .unwrap()
+            .start_play_hand(Deck::ordered_deck());
+        let view = hs.bot_context(cur);
+        let (advice, reason) = coach.advise(&view);
+        assert!(reason.is_some());
+        assert_eq!(coach.advise(&view).0, advice);
+
+        coach.record(&view, advice);
+        let other = match advice {
+            PokerAction::Fold => PokerAction::CallOrCheck,
+            _ => PokerAction::Fold,
+        };
+        coach.record(&view, other);
+        assert_eq!(coach.score().1, 2);
+        assert!(coach.score().0 >= 1);
+    }
+
+    #[test]
+    fn should_match_raises_of_any_size() {
+        let mut coach = Coach::init(100);
+        let (hs, cur) = GameState::init(2)
+            .unwrap()
+            .start_play_hand(Deck::ordered_deck());
+        let view = hs.bot_context(cur);
+        coach.pending = Some((view.clone(), PokerAction::Raise(10), None));
+        coach.record(&view, PokerAction::Raise(4));
+        assert_eq!(coach.score(), (1, 1));
+        assert_eq!(coach.grade(), Some('A'));
+    }
+}
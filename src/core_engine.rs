@@ -1,7 +1,8 @@
 use rand::{prelude::*, rng};
-use std::{cmp::Ordering, collections::HashMap};
+use serde::{Deserialize, Serialize};
+use std::{cmp::Ordering, collections::HashMap, fmt};
 
-#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
 pub enum Suit {
     Hearts,
     Spades,
@@ -11,7 +12,7 @@ pub enum Suit {
 
 use Suit::*;
 
-#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
 pub struct Card {
     pub suit: Suit,
     pub value: u8,
@@ -31,11 +32,26 @@ impl TryFrom<&str> for Card {
             _ => Err(())?,
         };
 
-        let value: u8 = value[1..].parse().unwrap();
+        let value: u8 = value[1..].parse().map_err(|_| ())?;
+        if !(2..=14).contains(&value) {
+            return Err(());
+        }
         Ok(Card { suit, value })
     }
 }
 
+impl fmt::Display for Card {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let suit = match self.suit {
+            Hearts => 'H',
+            Spades => 'S',
+            Diamonds => 'D',
+            Clubs => 'C',
+        };
+        write!(f, "{}{}", suit, self.value)
+    }
+}
+
 impl Card {
     pub fn pretty_print(&self) -> String {
         let suit = match self.suit {
@@ -53,7 +69,48 @@ impl Card {
             v => &v.to_string(),
         };
 
-        return suit.to_string() + val;
+        suit.to_string() + val
+    }
+
+    /// The card the way most hand history formats write it, like `Ah` or `Td`.
+    pub fn short_name(&self) -> String {
+        let rank = match self.value {
+            10 => 'T',
+            11 => 'J',
+            12 => 'Q',
+            13 => 'K',
+            14 => 'A',
+            v => char::from_digit(v.into(), 10).unwrap_or('?'),
+        };
+        let suit = match self.suit {
+            Hearts => 'h',
+            Spades => 's',
+            Diamonds => 'd',
+            Clubs => 'c',
+        };
+        format!("{rank}{suit}")
+    }
+
+    /// Reads a card written by `short_name`.
+    pub fn from_short_name(name: &str) -> Option<Card> {
+        let mut chars = name.chars();
+        let value = match chars.next()? {
+            'T' => 10,
+            'J' => 11,
+            'Q' => 12,
+            'K' => 13,
+            'A' => 14,
+            c @ '2'..='9' => c.to_digit(10)? as u8,
+            _ => return None,
+        };
+        let suit = match chars.next()? {
+            'h' => Hearts,
+            's' => Spades,
+            'd' => Diamonds,
+            'c' => Clubs,
+            _ => return None,
+        };
+        chars.next().is_none().then_some(Card { suit, value })
     }
 }
 
@@ -72,20 +129,19 @@ enum Ranking {
 
 use Ranking::*;
 
-#[allow(dead_code)]
-#[derive(Debug)]
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
 pub struct Hand(pub [Card; 5]);
 
 impl Hand {
     fn as_sorted(&self) -> Hand {
-        let mut cards = self.0.clone();
+        let mut cards = self.0;
         cards.sort_by_key(|card| card.value);
         cards.reverse();
         Hand(cards)
     }
 
     fn as_values(&self) -> [u8; 5] {
-        self.as_sorted().0.clone().map(|card| card.value)
+        self.as_sorted().0.map(|card| card.value)
     }
 
     fn get_values_with_ace_as_one(&self) -> [u8; 5] {
@@ -101,12 +157,40 @@ impl Hand {
         values
     }
 
+    pub fn ranking_name(&self) -> &'static str {
+        match self.get_ranking() {
+            HighCard => "High card",
+            Pair(_) => "Pair",
+            TwoPairs(..) => "Two pairs",
+            ThreeOfAKind(_) => "Three of a kind",
+            Straight(_) => "Straight",
+            Flush => "Flush",
+            FullHouse(..) => "Full house",
+            FourOfAKind(_) => "Four of a kind",
+            StraightFlush(_) => "Straight flush",
+        }
+    }
+
+    /// How strong the kind of hand is, from 0 for a high card to 8 for a straight flush.
+    pub fn category(&self) -> u8 {
+        match self.get_ranking() {
+            HighCard => 0,
+            Pair(_) => 1,
+            TwoPairs(..) => 2,
+            ThreeOfAKind(_) => 3,
+            Straight(_) => 4,
+            Flush => 5,
+            FullHouse(..) => 6,
+            FourOfAKind(_) => 7,
+            StraightFlush(_) => 8,
+        }
+    }
+
     fn get_ranking(&self) -> Ranking {
-        return self
-            .try_get_flush()
+        self.try_get_flush()
             .or_else(|| self.try_get_straight())
             .or_else(|| self.try_get_group_based_ranking())
-            .unwrap_or(HighCard);
+            .unwrap_or(HighCard)
     }
 
     fn try_get_flush(&self) -> Option<Ranking> {
@@ -194,9 +278,31 @@ impl PartialEq for Hand {
 
 impl Eq for Hand {}
 
+const RANKING_EXAMPLES: [(&str, [&str; 5]); 9] = [
+    ("High card", ["S14", "D11", "C8", "H6", "S3"]),
+    ("Pair", ["H10", "C10", "S13", "D7", "C2"]),
+    ("Two pairs", ["D12", "S12", "H5", "C5", "S9"]),
+    ("Three of a kind", ["C8", "H8", "D8", "S13", "H3"]),
+    ("Straight", ["H9", "S8", "D7", "C6", "H5"]),
+    ("Flush", ["D13", "D10", "D7", "D4", "D2"]),
+    ("Full house", ["S6", "H6", "C6", "D11", "S11"]),
+    ("Four of a kind", ["C4", "D4", "H4", "S4", "H12"]),
+    ("Straight flush", ["S13", "S12", "S11", "S10", "S9"]),
+];
+
+/// An example hand for every ranking, from the weakest to the strongest.
+pub fn ranking_examples() -> Vec<(&'static str, Hand)> {
+    RANKING_EXAMPLES
+        .iter()
+        .map(|(name, cards)| (*name, Hand(cards.map(|c| Card::try_from(c).unwrap()))))
+        .collect()
+}
+
 pub trait DeckGenerator {
     fn shuffle(&mut self) -> Deck;
 }
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct Deck {
     cards: Vec<Card>,
 }
@@ -206,6 +312,10 @@ impl Deck {
         Self { cards }
     }
 
+    pub fn cards(&self) -> &[Card] {
+        &self.cards
+    }
+
     pub fn draw(&mut self) -> Card {
         self.cards.pop().unwrap()
     }
@@ -216,6 +326,18 @@ impl Deck {
         cards
     }
 
+    /// The next `count` cards, in the order they would be drawn.
+    pub fn peek(&self, count: usize) -> Vec<Card> {
+        self.cards.iter().rev().take(count).copied().collect()
+    }
+
+    /// Takes `card` out of the deck, wherever it is.
+    pub fn remove(&mut self, card: Card) {
+        if let Some(i) = self.cards.iter().rposition(|&c| c == card) {
+            self.cards.remove(i);
+        }
+    }
+
     pub fn ordered_deck() -> Self {
         let mut cards = Vec::with_capacity(52);
 
@@ -229,9 +351,13 @@ impl Deck {
     }
 
     pub fn shuffled_deck() -> Self {
-        let deck = Self::ordered_deck();
-        let mut cards = deck.cards;
-        cards.shuffle(&mut rng());
+        Self::shuffled_with(&mut rng())
+    }
+
+    /// A deck shuffled by `rng`, the same deck every time for a seeded `rng`.
+    pub fn shuffled_with(rng: &mut impl Rng) -> Self {
+        let mut cards = Self::ordered_deck().cards;
+        cards.shuffle(rng);
         Self::init(cards)
     }
 }
@@ -274,6 +400,37 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_card_notation_round_trips() {
+        for card in Deck::ordered_deck().cards() {
+            assert_eq!(Card::try_from(card.to_string().as_str()), Ok(*card));
+        }
+        assert!(Card::try_from("H1").is_err());
+        assert!(Card::try_from("Hx").is_err());
+    }
+
+    #[test]
+    fn test_categories_follow_ranking_examples() {
+        let categories: Vec<u8> = ranking_examples()
+            .iter()
+            .map(|(_, h)| h.category())
+            .collect();
+        assert_eq!(categories, (0..9).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_ranking_examples_are_in_increasing_order() {
+        let examples = ranking_examples();
+        for pair in examples.windows(2) {
+            assert!(
+                pair[1].1 > pair[0].1,
+                "{} should beat {}",
+                pair[1].0,
+                pair[0].0
+            );
+        }
+    }
+
     fn assert_hands_are_equal(first: &str, second: &str) {
         let comp = create_hand(first).cmp(&create_hand(second));
         assert_eq!(comp, Ordering::Equal);
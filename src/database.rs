@@ -0,0 +1,357 @@
+//! Every finished hand kept in a SQLite database, so that statistics survive the session:
+//! the hand itself, each player's result, every action and every hand shown down.
+
+use std::io;
+use std::path::Path;
+
+use rusqlite::{params, Connection};
+
+use crate::core_engine::Hand;
+use crate::events::{GameEvent, Street};
+use crate::stats::{PlayerStats, SessionStats};
+use crate::HandState;
+
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS hands (
+    id INTEGER PRIMARY KEY,
+    played_ms INTEGER NOT NULL,
+    players INTEGER NOT NULL,
+    history TEXT NOT NULL
+);
+CREATE TABLE IF NOT EXISTS results (
+    hand INTEGER NOT NULL REFERENCES hands(id),
+    seat INTEGER NOT NULL,
+    player TEXT NOT NULL,
+    stack INTEGER NOT NULL,
+    net INTEGER NOT NULL,
+    vpip INTEGER NOT NULL,
+    pfr INTEGER NOT NULL,
+    raises INTEGER NOT NULL,
+    calls INTEGER NOT NULL,
+    cbets_faced INTEGER NOT NULL,
+    cbet_folds INTEGER NOT NULL,
+    pot INTEGER NOT NULL,
+    showdowns_won INTEGER NOT NULL,
+    three_bet_chances INTEGER NOT NULL DEFAULT 0,
+    three_bets INTEGER NOT NULL DEFAULT 0,
+    PRIMARY KEY (hand, seat)
+);
+CREATE INDEX IF NOT EXISTS results_by_player ON results(player);
+CREATE TABLE IF NOT EXISTS actions (
+    hand INTEGER NOT NULL REFERENCES hands(id),
+    number INTEGER NOT NULL,
+    seat INTEGER NOT NULL,
+    street TEXT NOT NULL,
+    action TEXT NOT NULL,
+    amount INTEGER NOT NULL,
+    PRIMARY KEY (hand, number)
+);
+CREATE TABLE IF NOT EXISTS showdowns (
+    hand INTEGER NOT NULL REFERENCES hands(id),
+    seat INTEGER NOT NULL,
+    cards TEXT NOT NULL,
+    ranking TEXT NOT NULL,
+    PRIMARY KEY (hand, seat)
+);
+";
+
+/// The version of the tables, kept in SQLite's `user_version`. Databases from before it
+/// was kept have the tables of version 1.
+const DATABASE_VERSION: u32 = 2;
+
+/// The SQL that changes the tables of every version into the next, starting with the one
+/// from 1 to 2.
+const MIGRATIONS: &[&str] = &[
+    // Version 2 counts 3-bets.
+    "ALTER TABLE results ADD COLUMN three_bet_chances INTEGER NOT NULL DEFAULT 0;
+    ALTER TABLE results ADD COLUMN three_bets INTEGER NOT NULL DEFAULT 0;",
+];
+
+pub struct StatsDatabase {
+    conn: Connection,
+}
+
+impl StatsDatabase {
+    /// Opens the database at `path`, creating it and its tables if needed. `:memory:`
+    /// opens one that lives only as long as this value.
+    pub fn open(path: impl AsRef<Path>) -> io::Result<Self> {
+        let mut conn = Connection::open(path).map_err(sql_error)?;
+        let stored: u32 = conn
+            .query_row("PRAGMA user_version", [], |row| row.get(0))
+            .map_err(sql_error)?;
+        let has_tables = conn
+            .query_row(
+                "SELECT COUNT(*) FROM sqlite_master WHERE type = 'table' AND name = 'hands'",
+                [],
+                |row| row.get::<_, u32>(0),
+            )
+            .map_err(sql_error)?
+            > 0;
+        let version = match (stored, has_tables) {
+            (0, false) => {
+                conn.execute_batch(SCHEMA).map_err(sql_error)?;
+                DATABASE_VERSION
+            }
+            (0, true) => 1,
+            (version, _) => version,
+        };
+        if version > DATABASE_VERSION {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("the stats database is version {version}, this poker-tui reads up to version {DATABASE_VERSION}"),
+            ));
+        }
+        let tx = conn.transaction().map_err(sql_error)?;
+        for migration in &MIGRATIONS[version as usize - 1..] {
+            tx.execute_batch(migration).map_err(sql_error)?;
+        }
+        tx.pragma_update(None, "user_version", DATABASE_VERSION)
+            .map_err(sql_error)?;
+        tx.commit().map_err(sql_error)?;
+        Ok(Self { conn })
+    }
+
+    /// Stores a finished hand, `names` naming the player in each seat. Returns the hand's
+    /// id in the database.
+    pub fn record_hand(
+        &mut self,
+        hs: &HandState,
+        names: &[String],
+        played_ms: u64,
+    ) -> io::Result<i64> {
+        let events = hs.events();
+        let history = hs.history();
+        let mut stats = SessionStats::init(history.stacks.len());
+        stats.record_hand(events);
+
+        let tx = self.conn.transaction().map_err(sql_error)?;
+        tx.execute(
+            "INSERT INTO hands (played_ms, players, history) VALUES (?1, ?2, ?3)",
+            params![played_ms as i64, names.len(), history.to_text()],
+        )
+        .map_err(sql_error)?;
+        let hand = tx.last_insert_rowid();
+        for (seat, (s, stack)) in stats.players().iter().zip(&history.stacks).enumerate() {
+            tx.execute(
+                "INSERT INTO results VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13,
+                    ?14, ?15)",
+                params![
+                    hand,
+                    seat,
+                    names[seat],
+                    stack,
+                    s.net_chips,
+                    s.vpip_hands,
+                    s.pfr_hands,
+                    s.raises,
+                    s.calls,
+                    s.cbets_faced,
+                    s.cbet_folds,
+                    s.biggest_pot,
+                    s.showdowns_won,
+                    s.three_bet_chances,
+                    s.three_bets
+                ],
+            )
+            .map_err(sql_error)?;
+        }
+        let mut number = 0;
+        for event in events {
+            let (seat, street, action, amount) = match *event {
+                GameEvent::BlindPosted { player, amount } => {
+                    (player, Street::Preflop, "blind", amount)
+                }
+                GameEvent::Checked { player, street } => (player, street, "check", 0),
+                GameEvent::Called {
+                    player,
+                    street,
+                    amount,
+                } => (player, street, "call", amount),
+                GameEvent::Raised {
+                    player,
+                    street,
+                    amount,
+                } => (player, street, "raise", amount),
+                GameEvent::Folded { player, street } => (player, street, "fold", 0),
+                GameEvent::HandShown {
+                    player,
+                    hand: shown,
+                } => {
+                    let cards = serde_json::to_string(&shown).map_err(io::Error::other)?;
+                    tx.execute(
+                        "INSERT INTO showdowns VALUES (?1, ?2, ?3, ?4)",
+                        params![hand, player, cards, shown.ranking_name()],
+                    )
+                    .map_err(sql_error)?;
+                    continue;
+                }
+                _ => continue,
+            };
+            tx.execute(
+                "INSERT INTO actions VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+                params![hand, number, seat, format!("{street:?}"), action, amount],
+            )
+            .map_err(sql_error)?;
+            number += 1;
+        }
+        tx.commit().map_err(sql_error)?;
+        Ok(hand)
+    }
+
+    /// How many hands are stored.
+    pub fn hands(&self) -> io::Result<u32> {
+        self.conn
+            .query_row("SELECT COUNT(*) FROM hands", [], |row| row.get(0))
+            .map_err(sql_error)
+    }
+
+    /// Everyone who played a stored hand, by name.
+    pub fn players(&self) -> io::Result<Vec<String>> {
+        let mut statement = self
+            .conn
+            .prepare("SELECT DISTINCT player FROM results ORDER BY player")
+            .map_err(sql_error)?;
+        let names = statement
+            .query_map([], |row| row.get(0))
+            .map_err(sql_error)?;
+        names.collect::<Result<_, _>>().map_err(sql_error)
+    }
+
+    /// The totals over every stored hand `player` played.
+    pub fn player_stats(&self, player: &str) -> io::Result<PlayerStats> {
+        let mut stats = self
+            .conn
+            .query_row(
+                "SELECT COUNT(*), IFNULL(SUM(vpip), 0), IFNULL(SUM(pfr), 0),
+                    IFNULL(SUM(showdowns_won), 0), IFNULL(MAX(pot), 0), IFNULL(SUM(net), 0),
+                    IFNULL(SUM(raises), 0), IFNULL(SUM(calls), 0), IFNULL(SUM(cbets_faced), 0),
+                    IFNULL(SUM(cbet_folds), 0), IFNULL(SUM(three_bet_chances), 0),
+                    IFNULL(SUM(three_bets), 0)
+                FROM results WHERE player = ?1",
+                [player],
+                |row| {
+                    Ok(PlayerStats {
+                        hands_played: row.get(0)?,
+                        vpip_hands: row.get(1)?,
+                        pfr_hands: row.get(2)?,
+                        showdowns_won: row.get(3)?,
+                        biggest_pot: row.get(4)?,
+                        net_chips: row.get(5)?,
+                        best_hand: None,
+                        raises: row.get(6)?,
+                        calls: row.get(7)?,
+                        cbets_faced: row.get(8)?,
+                        cbet_folds: row.get(9)?,
+                        three_bet_chances: row.get(10)?,
+                        three_bets: row.get(11)?,
+                    })
+                },
+            )
+            .map_err(sql_error)?;
+
+        let mut statement = self
+            .conn
+            .prepare(
+                "SELECT showdowns.cards FROM showdowns JOIN results
+                ON results.hand = showdowns.hand AND results.seat = showdowns.seat
+                WHERE results.player = ?1",
+            )
+            .map_err(sql_error)?;
+        let shown = statement
+            .query_map([player], |row| row.get::<_, String>(0))
+            .map_err(sql_error)?;
+        for cards in shown {
+            let cards = cards.map_err(sql_error)?;
+            let hand: Hand = serde_json::from_str(&cards).map_err(io::Error::other)?;
+            if stats.best_hand.is_none_or(|best| hand > best) {
+                stats.best_hand = Some(hand);
+            }
+        }
+        Ok(stats)
+    }
+}
+
+fn sql_error(e: rusqlite::Error) -> io::Error {
+    io::Error::other(e)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core_engine::Deck;
+    use crate::GameState;
+    use crate::PokerAction::*;
+    use crate::TurnResult::NextPlayer;
+
+    #[test]
+    fn should_add_up_results_across_stored_hands() {
+        let names = vec!["ada".to_owned(), "bob".to_owned()];
+        let mut db = StatsDatabase::open(":memory:").unwrap();
+        let mut gs = GameState::init(2).unwrap();
+
+        let (mut hs, _) = gs.start_play_hand(Deck::ordered_deck());
+        hs.play_action(Raise(5)).unwrap();
+        hs.play_action(Fold).unwrap();
+        db.record_hand(&hs, &names, 0).unwrap();
+        gs = gs.apply_played_hand(hs);
+
+        let (mut hs, _) = gs.start_play_hand(Deck::ordered_deck());
+        while let NextPlayer(_) = hs.play_action(CallOrCheck).unwrap() {}
+        db.record_hand(&hs, &names, 1).unwrap();
+
+        assert_eq!(db.hands().unwrap(), 2);
+        assert_eq!(db.players().unwrap(), names);
+        let ada = db.player_stats("ada").unwrap();
+        let bob = db.player_stats("bob").unwrap();
+        assert_eq!((ada.hands_played, ada.vpip_hands, ada.pfr_hands), (2, 1, 1));
+        assert_eq!(ada.net_chips + bob.net_chips, 0);
+        // The board plays for both, so they split the pot at showdown.
+        assert_eq!(ada.showdowns_won + bob.showdowns_won, 2);
+        assert!(ada.best_hand.is_some() && bob.best_hand.is_some());
+        assert_eq!(db.player_stats("nobody").unwrap(), PlayerStats::default());
+    }
+
+    #[test]
+    fn should_refuse_databases_from_a_newer_version() {
+        let path = std::env::temp_dir().join(format!("poker-tui-{}.db", rand::random::<u64>()));
+        StatsDatabase::open(&path).unwrap();
+        let conn = Connection::open(&path).unwrap();
+        let version: u32 = conn
+            .query_row("PRAGMA user_version", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(version, DATABASE_VERSION);
+        conn.pragma_update(None, "user_version", DATABASE_VERSION + 1)
+            .unwrap();
+        drop(conn);
+
+        assert!(StatsDatabase::open(&path).is_err());
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn should_migrate_databases_from_version_1() {
+        let path = std::env::temp_dir().join(format!("poker-tui-{}.db", rand::random::<u64>()));
+        let conn = Connection::open(&path).unwrap();
+        conn.execute_batch(SCHEMA).unwrap();
+        conn.execute_batch(
+            "ALTER TABLE results DROP COLUMN three_bet_chances;
+            ALTER TABLE results DROP COLUMN three_bets;
+            PRAGMA user_version = 1;",
+        )
+        .unwrap();
+        drop(conn);
+
+        let mut db = StatsDatabase::open(&path).unwrap();
+        let (mut hs, _) = GameState::init(2)
+            .unwrap()
+            .start_play_hand(Deck::ordered_deck());
+        hs.play_action(Raise(5)).unwrap();
+        hs.play_action(Raise(10)).unwrap();
+        hs.play_action(Fold).unwrap();
+        db.record_hand(&hs, &["ada".to_owned(), "bob".to_owned()], 0)
+            .unwrap();
+        let bob = db.player_stats("bob").unwrap();
+        assert_eq!((bob.three_bet_chances, bob.three_bets), (1, 1));
+        std::fs::remove_file(path).unwrap();
+    }
+}
@@ -0,0 +1,123 @@
+//! Bots that get softer while the people at the keyboard lose and tougher while they win,
+//! so solo play stays a contest.
+
+use crate::bots::{BotKind, HARD_BOT_SAMPLES};
+use crate::BIG_BLIND_CHIPS;
+
+/// Hands the players' results are added up over before the difficulty may change.
+pub const DIFFICULTY_WINDOW: usize = 20;
+
+/// Big blinds won or lost over a window that make the bots tougher or softer.
+const SHIFT_BB: i64 = 10;
+
+/// Follows the results of the human players and picks the bot for the seats that play one
+/// of the difficulty levels: easy, normal and hard.
+#[derive(Clone, Debug, PartialEq)]
+pub struct DifficultyDial {
+    levels: [BotKind; 3],
+    level: usize,
+    seats: Vec<usize>,
+    recent: Vec<i64>,
+}
+
+impl DifficultyDial {
+    /// A dial for the bot seats that play a difficulty level, starting at the level of the
+    /// first of them. `None` if no seat does.
+    pub fn new(seats: &[Option<BotKind>]) -> Option<Self> {
+        let samples = seats
+            .iter()
+            .find_map(|seat| match seat {
+                Some(BotKind::Hard(samples)) => Some(*samples),
+                _ => None,
+            })
+            .unwrap_or(HARD_BOT_SAMPLES);
+        let levels = [BotKind::Easy, BotKind::Normal, BotKind::Hard(samples)];
+        let level_of = |p: usize| levels.iter().position(|l| Some(l) == seats[p].as_ref());
+        let dialed: Vec<usize> = (0..seats.len())
+            .filter(|&p| level_of(p).is_some())
+            .collect();
+        let level = level_of(*dialed.first()?)?;
+        Some(Self {
+            levels,
+            level,
+            seats: dialed,
+            recent: vec![],
+        })
+    }
+
+    /// The seats whose bot follows the dial.
+    pub fn seats(&self) -> &[usize] {
+        &self.seats
+    }
+
+    pub fn bot(&self) -> &BotKind {
+        &self.levels[self.level]
+    }
+
+    /// Records the chips the human players won or lost together in a hand. Once they won
+    /// or lost enough over the last `DIFFICULTY_WINDOW` hands, the dial moves a level and
+    /// returns true, and the dialed seats should play `bot` from now on.
+    pub fn record_hand(&mut self, net_chips: i64) -> bool {
+        self.recent.push(net_chips);
+        if self.recent.len() < DIFFICULTY_WINDOW {
+            return false;
+        }
+        let net_bb = self.recent.iter().sum::<i64>() / BIG_BLIND_CHIPS as i64;
+        self.recent.clear();
+        let level = if net_bb >= SHIFT_BB {
+            (self.level + 1).min(self.levels.len() - 1)
+        } else if net_bb <= -SHIFT_BB {
+            self.level.saturating_sub(1)
+        } else {
+            self.level
+        };
+        let moved = level != self.level;
+        self.level = level;
+        moved
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn dial() -> DifficultyDial {
+        DifficultyDial::new(&[None, Some(BotKind::Adaptive), Some(BotKind::Normal)]).unwrap()
+    }
+
+    #[test]
+    fn should_dial_only_seats_playing_a_level() {
+        let dial = dial();
+        assert_eq!(dial.seats(), [2]);
+        assert_eq!(dial.bot(), &BotKind::Normal);
+        assert_eq!(DifficultyDial::new(&[None, Some(BotKind::Cfr)]), None);
+    }
+
+    #[test]
+    fn should_toughen_the_bots_for_winning_players() {
+        let mut dial = dial();
+        for _ in 1..DIFFICULTY_WINDOW {
+            assert!(!dial.record_hand(2));
+        }
+        assert!(dial.record_hand(2));
+        assert_eq!(dial.bot(), &BotKind::Hard(HARD_BOT_SAMPLES));
+        // Already as tough as it gets.
+        for _ in 0..DIFFICULTY_WINDOW {
+            assert!(!dial.record_hand(2));
+        }
+    }
+
+    #[test]
+    fn should_soften_the_bots_for_losing_players() {
+        let mut dial = dial();
+        for _ in 1..DIFFICULTY_WINDOW {
+            dial.record_hand(-1);
+        }
+        assert!(!dial.record_hand(0), "a small loss keeps the level");
+        let moves = (0..DIFFICULTY_WINDOW)
+            .filter(|_| dial.record_hand(-5))
+            .count();
+        assert_eq!(moves, 1);
+        assert_eq!(dial.bot(), &BotKind::Easy);
+    }
+}
@@ -0,0 +1,108 @@
+//! Finding games on the local network without swapping addresses: a host advertises itself
+//! over mDNS as a `_poker-tui._tcp` service, under a name of its choosing, and clients look
+//! for every such service that answers.
+
+use std::io;
+use std::net::{IpAddr, SocketAddr};
+use std::time::{Duration, Instant};
+
+use mdns_sd::{ServiceDaemon, ServiceEvent, ServiceInfo};
+
+use crate::protocol::PROTOCOL_VERSION;
+
+/// The DNS-SD service type hosts advertise.
+const SERVICE: &str = "_poker-tui._tcp.local.";
+
+/// A host that answered on the local network.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct LanHost {
+    /// The name the host advertises itself under.
+    pub name: String,
+    pub addr: SocketAddr,
+}
+
+/// Keeps a host advertised for as long as it lives.
+pub struct Advertisement {
+    daemon: ServiceDaemon,
+}
+
+impl Drop for Advertisement {
+    fn drop(&mut self) {
+        // Other hosts notice the service is gone once its records run out anyway.
+        let _ = self.daemon.shutdown();
+    }
+}
+
+/// Advertises the host taking players on `port` as `name` on every network interface.
+pub fn advertise(name: &str, port: u16) -> io::Result<Advertisement> {
+    let label: String = name
+        .chars()
+        .map(|c| match c {
+            'a'..='z' | 'A'..='Z' | '0'..='9' => c.to_ascii_lowercase(),
+            _ => '-',
+        })
+        .collect();
+    let host = format!("poker-tui-{label}.local.");
+    let version = PROTOCOL_VERSION.to_string();
+    let properties = [("protocol", version.as_str())];
+    let service = ServiceInfo::new(SERVICE, name, &host, (), port, &properties[..])
+        .map_err(io::Error::other)?
+        .enable_addr_auto();
+    let daemon = ServiceDaemon::new().map_err(io::Error::other)?;
+    daemon.register(service).map_err(io::Error::other)?;
+    Ok(Advertisement { daemon })
+}
+
+/// The hosts that answer within `wait`, each once, in the order they answered.
+pub fn discover(wait: Duration) -> io::Result<Vec<LanHost>> {
+    let daemon = ServiceDaemon::new().map_err(io::Error::other)?;
+    let events = daemon.browse(SERVICE).map_err(io::Error::other)?;
+    let deadline = Instant::now() + wait;
+    let mut hosts: Vec<LanHost> = vec![];
+    while let Ok(event) = events.recv_deadline(deadline) {
+        let ServiceEvent::ServiceResolved(service) = event else {
+            continue;
+        };
+        let name = service
+            .get_fullname()
+            .strip_suffix(&format!(".{SERVICE}"))
+            .unwrap_or(service.get_fullname())
+            .to_owned();
+        // IPv4 first, as it is the most likely to reach the host. Link-local IPv6 addresses
+        // don't say which interface they are on, so they can't be dialed.
+        let Some(ip) = service
+            .get_addresses()
+            .iter()
+            .copied()
+            .filter(|ip| !matches!(ip, IpAddr::V6(v6) if v6.segments()[0] & 0xffc0 == 0xfe80))
+            .min_by_key(|ip| (ip.is_ipv6(), *ip))
+        else {
+            continue;
+        };
+        let addr = SocketAddr::new(ip, service.get_port());
+        // A host's addresses may come in over several answers.
+        match hosts.iter_mut().find(|host| host.name == name) {
+            Some(host) if host.addr.is_ipv6() => host.addr = addr,
+            Some(_) => {}
+            None => hosts.push(LanHost { name, addr }),
+        }
+    }
+    let _ = daemon.shutdown();
+    Ok(hosts)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_find_a_host_advertised_on_the_network() {
+        // Other hosts on the network may answer too.
+        let name = format!("Friday night {}", rand::random::<u32>());
+        let _advertisement = advertise(&name, 7777).unwrap();
+        let hosts = discover(Duration::from_secs(3)).unwrap();
+        assert!(hosts
+            .iter()
+            .any(|h| h.name == name && h.addr.port() == 7777));
+    }
+}
@@ -0,0 +1,115 @@
+//! An async game loop: every seat is an `ActionProvider` that is awaited for its action,
+//! whether it asks someone at a keyboard, runs a bot or waits on a network client. A turn
+//! that takes longer than the game's time limit checks or folds for the player.
+
+use std::future::{self, Future};
+use std::pin::Pin;
+use std::time::Duration;
+
+use crate::bots::{BotContext, BotPlayer, PlayerView};
+use crate::{GameState, HandState, PokerAction, TurnResult};
+
+pub type ActionFuture<'a> = Pin<Box<dyn Future<Output = PokerAction> + 'a>>;
+
+/// Whoever decides for a seat.
+pub trait ActionProvider {
+    fn get_action(&mut self, view: PlayerView) -> ActionFuture<'_>;
+}
+
+/// Bots decide right away, from the view alone.
+impl ActionProvider for Box<dyn BotPlayer> {
+    fn get_action(&mut self, view: PlayerView) -> ActionFuture<'_> {
+        let action = self.decide(&BotContext::from(view));
+        Box::pin(future::ready(action))
+    }
+}
+
+/// Plays the hand to the end, `seats` deciding for each player. Returns who won it. A
+/// raise by too much or too little is refused and the player asked again.
+pub async fn play_hand(
+    hs: &mut HandState,
+    seats: &mut [Box<dyn ActionProvider>],
+    turn_timeout: Option<Duration>,
+) -> usize {
+    let mut player = hs.current_player();
+    loop {
+        let timed_out = if hs.expected_call() == 0 {
+            PokerAction::CallOrCheck
+        } else {
+            PokerAction::Fold
+        };
+        let asked = seats[player].get_action(hs.player_view(player));
+        let action = match turn_timeout {
+            Some(timeout) => tokio::select! {
+                action = asked => action,
+                _ = tokio::time::sleep(timeout) => timed_out,
+            },
+            None => asked.await,
+        };
+        match hs.play_action(action) {
+            Ok(TurnResult::NextPlayer(next)) => player = next,
+            Ok(TurnResult::WonHand(winner)) => return winner,
+            Err(_) => {}
+        }
+    }
+}
+
+/// Plays up to `hands` hands of the game with its decks and time limit, stopping early
+/// once a single player has every chip. Returns the game after the last of them.
+pub async fn play_game(
+    mut gs: GameState,
+    seats: &mut [Box<dyn ActionProvider>],
+    hands: usize,
+) -> GameState {
+    for _ in 0..hands {
+        if (0..gs.players())
+            .filter(|&p| gs.current_chips(p) > 0)
+            .count()
+            < 2
+        {
+            break;
+        }
+        let deck = gs.shuffled_deck();
+        let (mut hs, _) = gs.start_play_hand(deck);
+        play_hand(&mut hs, seats, gs.turn_timeout()).await;
+        gs = gs.apply_played_hand(hs);
+    }
+    gs
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bots::CallingStation;
+    use crate::core_engine::Deck;
+
+    /// Never answers.
+    struct Asleep;
+
+    impl ActionProvider for Asleep {
+        fn get_action(&mut self, _view: PlayerView) -> ActionFuture<'_> {
+            Box::pin(future::pending())
+        }
+    }
+
+    #[tokio::test]
+    async fn should_await_every_seat_and_time_out_slow_ones() {
+        let gs = GameState::builder()
+            .players(2)
+            .seed(3)
+            .turn_timeout(Duration::from_millis(5))
+            .build()
+            .unwrap();
+        let station: Box<dyn BotPlayer> = Box::new(CallingStation);
+        let mut seats: Vec<Box<dyn ActionProvider>> = vec![Box::new(station), Box::new(Asleep)];
+
+        // The calling station never bets, so the sleeping seat checks every hand down.
+        let (mut hs, _) = gs.start_play_hand(Deck::ordered_deck());
+        play_hand(&mut hs, &mut seats, gs.turn_timeout()).await;
+        assert!(hs.is_showdown());
+
+        let gs = play_game(gs, &mut seats, 3).await;
+        assert_eq!(gs.current_chips(0) + gs.current_chips(1), 200);
+        assert_ne!(gs.current_chips(0), 100);
+    }
+}
@@ -0,0 +1,194 @@
+//! A fixed-size numeric encoding of a `PlayerView`, for bots backed by trained models.
+//!
+//! `encode` turns a view into `ENCODED_LEN` floats laid out as follows:
+//!
+//! | Offset | Length | Content                                                           |
+//! |--------|--------|-------------------------------------------------------------------|
+//! | 0      | 52     | Hole cards, one-hot by `card_index`                               |
+//! | 52     | 52     | Board cards, one-hot by `card_index`                              |
+//! | 104    | 4      | Street, one-hot: preflop, flop, turn, river                       |
+//! | 108    | 5      | Pot, amount to call, own stack, own bet and the largest stack of  |
+//! |        |        | an opponent still in the hand, in units of 100 big blinds         |
+//! | 113    | 1      | Opponents still in the hand, divided by 9                         |
+//! | 114    | 1      | Seats after the button, divided by the number of players          |
+//!
+//! A model answers with one weight per entry of `ACTIONS`, which `ActionChoice::action`
+//! turns into a concrete `PokerAction`.
+
+use rand::Rng;
+
+use crate::bots::{check_or_fold, raise_or_call, raise_or_call_by, PlayerView};
+use crate::core_engine::{Card, Suit};
+use crate::events::Street;
+use crate::{PokerAction, BIG_BLIND_CHIPS};
+
+pub const ENCODED_LEN: usize = 115;
+
+/// Chip amounts are divided by this many chips, 100 big blinds.
+const CHIP_SCALE: f32 = 100.0 * BIG_BLIND_CHIPS as f32;
+
+/// The actions a model chooses from.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ActionChoice {
+    /// Folds, or checks when there is nothing to call.
+    Fold,
+    CallOrCheck,
+    /// Raises by half the pot after calling.
+    HalfPot,
+    /// Raises by the pot after calling.
+    Pot,
+    /// Puts in as much as the stack and the raise limit allow.
+    AllIn,
+}
+
+/// Output order of the model weights.
+pub const ACTIONS: [ActionChoice; 5] = [
+    ActionChoice::Fold,
+    ActionChoice::CallOrCheck,
+    ActionChoice::HalfPot,
+    ActionChoice::Pot,
+    ActionChoice::AllIn,
+];
+
+impl ActionChoice {
+    pub fn action(self, view: &PlayerView) -> PokerAction {
+        match self {
+            ActionChoice::Fold => check_or_fold(view),
+            ActionChoice::CallOrCheck => PokerAction::CallOrCheck,
+            ActionChoice::HalfPot => raise_or_call(view, 2),
+            ActionChoice::Pot => raise_or_call(view, 1),
+            ActionChoice::AllIn => raise_or_call_by(view, view.stacks[view.player]),
+        }
+    }
+}
+
+/// Position of the card in the one-hot blocks: hearts, spades, diamonds, clubs, each from
+/// deuce to ace.
+pub fn card_index(card: Card) -> usize {
+    let suit = match card.suit {
+        Suit::Hearts => 0,
+        Suit::Spades => 1,
+        Suit::Diamonds => 2,
+        Suit::Clubs => 3,
+    };
+    13 * suit + card.value as usize - 2
+}
+
+pub fn encode(view: &PlayerView) -> Vec<f32> {
+    let mut encoded = vec![0.0; ENCODED_LEN];
+    for card in [view.cards.0, view.cards.1] {
+        encoded[card_index(card)] = 1.0;
+    }
+    for card in &view.board {
+        encoded[52 + card_index(*card)] = 1.0;
+    }
+    let street = match view.street {
+        Street::Preflop => 0,
+        Street::Flop => 1,
+        Street::Turn => 2,
+        Street::River => 3,
+    };
+    encoded[104 + street] = 1.0;
+
+    let opponents = || (0..view.active.len()).filter(|&p| p != view.player && view.active[p]);
+    let biggest_opponent = opponents().map(|p| view.stacks[p]).max().unwrap_or(0);
+    let chips = [
+        view.pot,
+        view.expected_call,
+        view.stacks[view.player],
+        view.bets[view.player],
+        biggest_opponent,
+    ];
+    for (i, chips) in chips.into_iter().enumerate() {
+        encoded[108 + i] = chips as f32 / CHIP_SCALE;
+    }
+    encoded[113] = opponents().count() as f32 / 9.0;
+    let players = view.stacks.len();
+    encoded[114] = ((view.player + players - view.button) % players) as f32 / players as f32;
+    encoded
+}
+
+/// Picks an action at random in proportion to `weights`, one per entry of `ACTIONS`.
+/// Negative weights count as zero, and weights that are all zero or not numbers mean
+/// checking or calling.
+pub fn sample(weights: &[f32], rng: &mut impl Rng) -> ActionChoice {
+    let weights: Vec<f32> = weights.iter().map(|w| w.max(0.0)).collect();
+    let total: f32 = weights.iter().sum();
+    if !total.is_finite() || total <= 0.0 {
+        return ActionChoice::CallOrCheck;
+    }
+    let mut pick = rng.random_range(0.0..total);
+    for (choice, weight) in ACTIONS.iter().zip(&weights) {
+        if pick < *weight {
+            return *choice;
+        }
+        pick -= weight;
+    }
+    ACTIONS[weights.iter().rposition(|w| *w > 0.0).unwrap()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bots::seeded_rng;
+
+    fn view() -> PlayerView {
+        PlayerView {
+            player: 1,
+            button: 0,
+            cards: (
+                Card::try_from("H2").unwrap(),
+                Card::try_from("C14").unwrap(),
+            ),
+            street: Street::Flop,
+            board: ["S10", "S11", "D5"]
+                .map(|c| Card::try_from(c).unwrap())
+                .to_vec(),
+            stacks: vec![150, 80, 300],
+            bets: vec![10, 0, 0],
+            active: vec![true, true, false],
+            pot: 30,
+            expected_call: 10,
+            min_raise: 10 + BIG_BLIND_CHIPS,
+        }
+    }
+
+    #[test]
+    fn should_encode_cards_street_and_chips() {
+        let encoded = encode(&view());
+        assert_eq!(encoded.len(), ENCODED_LEN);
+        assert_eq!(encoded[..52].iter().sum::<f32>(), 2.0);
+        assert_eq!(encoded[0], 1.0);
+        assert_eq!(encoded[51], 1.0);
+        assert_eq!(encoded[52..104].iter().sum::<f32>(), 3.0);
+        assert_eq!(encoded[52 + 13 + 8], 1.0);
+        assert_eq!(encoded[104..108], [0.0, 1.0, 0.0, 0.0]);
+        assert_eq!(encoded[108], 30.0 / CHIP_SCALE);
+        assert_eq!(encoded[110], 80.0 / CHIP_SCALE);
+        // The folded player's bigger stack doesn't count.
+        assert_eq!(encoded[112], 150.0 / CHIP_SCALE);
+        assert_eq!(encoded[113], 1.0 / 9.0);
+        assert_eq!(encoded[114], 1.0 / 3.0);
+    }
+
+    #[test]
+    fn should_sample_only_actions_with_weight() {
+        let mut rng = seeded_rng(Some(3));
+        for _ in 0..100 {
+            let choice = sample(&[0.0, -1.0, 0.5, 0.0, 0.5], &mut rng);
+            assert!(matches!(
+                choice,
+                ActionChoice::HalfPot | ActionChoice::AllIn
+            ));
+        }
+        assert_eq!(sample(&[0.0; 5], &mut rng), ActionChoice::CallOrCheck);
+    }
+
+    #[test]
+    fn should_turn_choices_into_legal_actions() {
+        let view = view();
+        assert_eq!(ActionChoice::Fold.action(&view), PokerAction::Fold);
+        assert_eq!(ActionChoice::Pot.action(&view), PokerAction::Raise(60));
+        assert_eq!(ActionChoice::AllIn.action(&view), PokerAction::Raise(80));
+    }
+}
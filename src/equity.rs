@@ -0,0 +1,390 @@
+use rand::{prelude::*, rng};
+
+use crate::best_hand_from_cards;
+use crate::core_engine::{Card, Deck};
+use crate::events::{GameEvent, Street};
+use crate::history::HandHistory;
+use crate::HandState;
+
+/// Boards with at most this many unknown cards are enumerated exactly instead of sampled.
+const MAX_EXACT_UNKNOWN_CARDS: usize = 2;
+const MAX_RANGE_REDEALS: usize = 20;
+/// Run-outs worth sampling to find the equity in an all-in before the flop.
+pub const ALL_IN_SAMPLES: usize = 2000;
+
+/// Each player's share of the pot if the board were run out from here, between 0 and 1.
+/// Tied hands split it evenly, the way the engine pays them. Boards with few unknown cards are enumerated exactly; otherwise `samples` random
+/// run-outs drawn from `rng` are played.
+pub fn equity(
+    hands: &[(Card, Card)],
+    board: &[Card],
+    samples: usize,
+    rng: &mut impl Rng,
+) -> Vec<f64> {
+    let known: Vec<Card> = hands
+        .iter()
+        .flat_map(|&(c1, c2)| [c1, c2])
+        .chain(board.iter().copied())
+        .collect();
+    let mut remaining: Vec<Card> = Deck::ordered_deck()
+        .cards()
+        .iter()
+        .filter(|c| !known.contains(c))
+        .copied()
+        .collect();
+    let unknown = 5 - board.len();
+
+    let mut shares = vec![0.0; hands.len()];
+    let mut runs = 0;
+    let mut score = |runout: &[Card]| {
+        let mut full_board = board.to_vec();
+        full_board.extend(runout);
+        for (player, share) in split_pot(hands, &full_board).into_iter().enumerate() {
+            shares[player] += share;
+        }
+        runs += 1;
+    };
+
+    if unknown <= MAX_EXACT_UNKNOWN_CARDS {
+        for_each_combination(&remaining, unknown, &mut vec![], &mut score);
+    } else {
+        for _ in 0..samples {
+            let (runout, _) = remaining.partial_shuffle(rng, unknown);
+            score(runout);
+        }
+    }
+
+    shares.iter().map(|s| s / runs.max(1) as f64).collect()
+}
+
+/// The player's share of the pot against `opponents` random hands, estimated from `samples`
+/// random deals of the opponents' hole cards and the rest of the board drawn from `rng`.
+pub fn equity_vs_random(
+    hand: (Card, Card),
+    board: &[Card],
+    opponents: usize,
+    samples: usize,
+    rng: &mut impl Rng,
+) -> f64 {
+    equity_vs_range(hand, board, opponents, samples, |_| true, rng)
+}
+
+/// Like `equity_vs_random`, but opponents only hold hands `in_range` accepts. Deals that
+/// keep missing the range are given up on and count with whatever was dealt.
+pub fn equity_vs_range(
+    hand: (Card, Card),
+    board: &[Card],
+    opponents: usize,
+    samples: usize,
+    in_range: impl Fn((Card, Card)) -> bool,
+    rng: &mut impl Rng,
+) -> f64 {
+    let mut remaining: Vec<Card> = Deck::ordered_deck()
+        .cards()
+        .iter()
+        .filter(|&&c| c != hand.0 && c != hand.1 && !board.contains(&c))
+        .copied()
+        .collect();
+    let unknown = 5 - board.len();
+
+    let mut share = 0.0;
+    for _ in 0..samples {
+        let mut dealt: &[Card] = &[];
+        for _ in 0..MAX_RANGE_REDEALS {
+            dealt = remaining.partial_shuffle(rng, 2 * opponents + unknown).0;
+            if dealt[..2 * opponents]
+                .chunks(2)
+                .all(|c| in_range((c[0], c[1])))
+            {
+                break;
+            }
+        }
+        let mut hands = vec![hand];
+        hands.extend(dealt[..2 * opponents].chunks(2).map(|c| (c[0], c[1])));
+        let mut full_board = board.to_vec();
+        full_board.extend(&dealt[2 * opponents..]);
+        share += split_pot(&hands, &full_board)[0];
+    }
+    share / samples.max(1) as f64
+}
+
+fn split_pot(hands: &[(Card, Card)], board: &[Card]) -> Vec<f64> {
+    let best: Vec<_> = hands
+        .iter()
+        .map(|&(c1, c2)| {
+            best_hand_from_cards([c1, c2, board[0], board[1], board[2], board[3], board[4]])
+        })
+        .collect();
+    let winning = best.iter().max().unwrap();
+    let winners = best.iter().filter(|h| *h == winning).count() as f64;
+    best.iter()
+        .map(|h| if h == winning { 1.0 / winners } else { 0.0 })
+        .collect()
+}
+
+pub(crate) fn for_each_combination(
+    cards: &[Card],
+    count: usize,
+    chosen: &mut Vec<Card>,
+    f: &mut impl FnMut(&[Card]),
+) {
+    if count == 0 {
+        f(chosen);
+        return;
+    }
+    for i in 0..cards.len() {
+        chosen.push(cards[i]);
+        for_each_combination(&cards[i + 1..], count - 1, chosen, f);
+        chosen.pop();
+    }
+}
+
+pub struct StreetEquity {
+    pub street: Street,
+    /// Equity per player, `None` for players who had already folded.
+    pub equities: Vec<Option<f64>>,
+}
+
+/// Replays a hand and computes every remaining player's equity at the start of each street.
+pub fn review_hand(history: &HandHistory, samples: usize) -> Vec<StreetEquity> {
+    let mut hs = history.start();
+    let mut review = vec![];
+    let mut record = |hs: &HandState| {
+        let players: Vec<usize> = (0..hs.players)
+            .filter(|&p| hs.turn.active_players[p])
+            .collect();
+        let hands: Vec<_> = players.iter().map(|&p| hs.hole_cards(p)).collect();
+        let mut equities = vec![None; hs.players];
+        for (p, e) in players
+            .iter()
+            .zip(equity(&hands, &hs.board, samples, &mut rng()))
+        {
+            equities[*p] = Some(e);
+        }
+        review.push(StreetEquity {
+            street: hs.street(),
+            equities,
+        });
+    };
+
+    record(&hs);
+    for action in &history.actions {
+        let street = hs.street();
+        if hs.play_action(*action).is_err() {
+            break;
+        }
+        if hs.street() != street {
+            record(&hs);
+        }
+    }
+    review
+}
+
+/// How a hand that was all in before the river went compared to the players' equity when
+/// the last chips went in.
+#[derive(Clone, Debug, PartialEq)]
+pub struct AllIn {
+    pub street: Street,
+    /// Equity per player in the main pot, `None` for players who had folded.
+    pub equities: Vec<Option<f64>>,
+    /// Chips won minus what the player's equity in each pot was worth.
+    pub luck: Vec<f64>,
+}
+
+/// The all-in of a finished hand if the players were all in before the river, with every
+/// pot split by equity over `samples` run-outs where the board isn't enumerated.
+pub fn all_in(hs: &HandState, samples: usize) -> Option<AllIn> {
+    let events = hs.events();
+    // Players all in still check through the streets left, so the all-in is the last
+    // action that put chips in.
+    let last = events.iter().rposition(|e| match *e {
+        GameEvent::Called { amount, .. } => amount > 0,
+        GameEvent::Raised { .. } => true,
+        _ => false,
+    })?;
+    let at = HandState::from_events(
+        hs.big_blind,
+        hs.history.stacks.clone(),
+        Deck::init(hs.history.deck.clone()),
+        &events[..=last],
+    );
+    let with_chips = (0..at.players)
+        .filter(|&p| at.turn.active_players[p] && at.chips.player_chips[p].stack > 0)
+        .count();
+    let shown = events[last..]
+        .iter()
+        .any(|e| matches!(e, GameEvent::HandShown { .. }));
+    if at.board.len() == 5 || with_chips > 1 || !shown {
+        return None;
+    }
+
+    let mut chips = at.chips.clone();
+    chips.move_chips_to_pot();
+    let mut equities = vec![None; at.players];
+    let mut luck = vec![0.0; at.players];
+    for (i, pot) in chips
+        .pots(&at.turn.active_players, &at.folds())
+        .iter()
+        .enumerate()
+    {
+        let shares = match &pot.eligible[..] {
+            [_] => vec![1.0],
+            eligible => {
+                let hands: Vec<_> = eligible.iter().map(|&p| at.hole_cards(p)).collect();
+                equity(&hands, &at.board, samples, &mut rng())
+            }
+        };
+        for (&p, share) in pot.eligible.iter().zip(shares) {
+            luck[p] -= share * pot.amount as f64;
+            if i == 0 {
+                equities[p] = Some(share);
+            }
+        }
+    }
+    for event in &events[last..] {
+        if let GameEvent::BetReturned { player, amount }
+        | GameEvent::PotAwarded { player, amount, .. } = *event
+        {
+            luck[player] += amount as f64;
+        }
+    }
+    Some(AllIn {
+        street: at.street(),
+        equities,
+        luck,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cards(s: &str) -> Vec<Card> {
+        s.split_ascii_whitespace()
+            .map(|c| Card::try_from(c).unwrap())
+            .collect()
+    }
+
+    fn hole(s: &str) -> (Card, Card) {
+        let c = cards(s);
+        (c[0], c[1])
+    }
+
+    #[test]
+    fn should_know_the_winner_on_the_river() {
+        let board = cards("C7 H4 C10 H14 H12");
+        let equities = equity(&[hole("H2 D7"), hole("D5 C11")], &board, 0, &mut rng());
+        assert_eq!(equities, vec![1.0, 0.0]);
+    }
+
+    #[test]
+    fn should_split_ties() {
+        let board = cards("C14 C13 C12 C11 C10");
+        let equities = equity(&[hole("H2 D7"), hole("D5 S3")], &board, 0, &mut rng());
+        assert_eq!(equities, vec![0.5, 0.5]);
+    }
+
+    #[test]
+    fn should_enumerate_flop_runouts_exactly() {
+        let board = cards("S14 D7 C2");
+        let equities = equity(&[hole("H14 D14"), hole("H13 D13")], &board, 0, &mut rng());
+        assert!((equities[0] - 989.0 / 990.0).abs() < 1e-9);
+        assert!((equities[0] + equities[1] - 1.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn should_estimate_equity_against_random_hands() {
+        let mut rng = StdRng::seed_from_u64(1);
+        let nuts = equity_vs_random(
+            hole("H14 H13"),
+            &cards("H12 H11 H10 C2 D3"),
+            3,
+            100,
+            &mut rng,
+        );
+        assert_eq!(nuts, 1.0);
+
+        let aces = equity_vs_random(hole("S14 C14"), &[], 1, 2000, &mut rng);
+        assert!((0.8..0.9).contains(&aces), "aces had {aces}");
+    }
+
+    #[test]
+    fn should_estimate_equity_against_a_range() {
+        let mut rng = StdRng::seed_from_u64(1);
+        let vs_random = equity_vs_random(hole("S12 C12"), &[], 1, 1000, &mut rng);
+        let broadway = |(c1, c2): (Card, Card)| c1.value >= 10 && c2.value >= 10;
+        let vs_broadway = equity_vs_range(hole("S12 C12"), &[], 1, 1000, broadway, &mut rng);
+        assert!(
+            vs_broadway < vs_random - 0.05,
+            "queens had {vs_broadway} against broadway cards and {vs_random} against any two"
+        );
+    }
+
+    #[test]
+    fn should_review_each_street_of_a_hand() {
+        let (mut hs, _) = crate::GameState::init(2)
+            .unwrap()
+            .start_play_hand(Deck::ordered_deck());
+        while let Ok(crate::TurnResult::NextPlayer(_)) =
+            hs.play_action(crate::PokerAction::CallOrCheck)
+        {}
+
+        let review = review_hand(hs.history(), 200);
+        let streets: Vec<_> = review.iter().map(|r| r.street).collect();
+        assert_eq!(
+            streets,
+            vec![Street::Preflop, Street::Flop, Street::Turn, Street::River]
+        );
+        let river = &review[3].equities;
+        assert!(river
+            .iter()
+            .all(|e| matches!(e, Some(v) if *v == 0.0 || *v == 0.5 || *v == 1.0)));
+        assert!(all_in(&hs, 200).is_none());
+    }
+
+    #[test]
+    fn should_split_ties_the_way_the_engine_pays_them() {
+        let (mut hs, _) = crate::GameState::init(3)
+            .unwrap()
+            .start_play_hand(Deck::ordered_deck());
+        while let Ok(crate::TurnResult::NextPlayer(_)) =
+            hs.play_action(crate::PokerAction::CallOrCheck)
+        {}
+
+        let river = &review_hand(hs.history(), 0)[3].equities;
+        let mut won = [0; 3];
+        for event in hs.events() {
+            if let GameEvent::PotAwarded { player, amount, .. } = *event {
+                won[player] += amount;
+            }
+        }
+        let pot: u32 = won.iter().sum();
+        for (equity, won) in river.iter().zip(won) {
+            assert_eq!(equity.unwrap() * pot as f64, won as f64);
+        }
+    }
+
+    #[test]
+    fn should_weigh_an_all_in_by_equity() {
+        use crate::PokerAction::*;
+        let (mut hs, _) = crate::GameState::init_with_stack(2, 20)
+            .unwrap()
+            .start_play_hand(Deck::ordered_deck());
+        for action in [CallOrCheck, CallOrCheck, Raise(18)] {
+            hs.play_action(action).unwrap();
+        }
+        while let Ok(crate::TurnResult::NextPlayer(_)) = hs.play_action(CallOrCheck) {}
+
+        let all_in = all_in(&hs, 200).unwrap();
+        assert_eq!(all_in.street, Street::Flop);
+        let equities: Vec<f64> = all_in.equities.iter().flatten().copied().collect();
+        assert_eq!(equities.len(), 2);
+        assert!((equities.iter().sum::<f64>() - 1.0).abs() < 1e-9);
+        assert!(all_in.luck.iter().sum::<f64>().abs() < 1e-9);
+        // The board runs out to a straight flush both players share.
+        for (p, equity) in equities.iter().enumerate() {
+            assert_eq!(hs.chips.player_chips[p].stack, 20);
+            assert!((all_in.luck[p] - 20.0 + 40.0 * equity).abs() < 1e-9);
+        }
+    }
+}
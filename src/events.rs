@@ -0,0 +1,116 @@
+use std::sync::{Arc, Mutex};
+
+use serde::{Deserialize, Serialize};
+
+use crate::core_engine::{Card, Hand};
+use crate::PokerAction;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Street {
+    Preflop,
+    Flop,
+    Turn,
+    River,
+}
+
+impl Street {
+    pub(crate) fn from_board_size(cards: usize) -> Self {
+        match cards {
+            0 => Street::Preflop,
+            3 => Street::Flop,
+            4 => Street::Turn,
+            _ => Street::River,
+        }
+    }
+}
+
+/// Something that happened during a hand, in the order it happened.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum GameEvent {
+    HoleCardsDealt {
+        player: usize,
+        cards: [Card; 2],
+    },
+    BlindPosted {
+        player: usize,
+        amount: u32,
+    },
+    Checked {
+        player: usize,
+        street: Street,
+    },
+    Called {
+        player: usize,
+        street: Street,
+        amount: u32,
+    },
+    Raised {
+        player: usize,
+        street: Street,
+        amount: u32,
+    },
+    Folded {
+        player: usize,
+        street: Street,
+    },
+    StreetDealt {
+        street: Street,
+        cards: Vec<Card>,
+    },
+    /// The best five cards of a player still in the hand at showdown.
+    HandShown {
+        player: usize,
+        hand: Hand,
+    },
+    /// Chips the player bet that nobody called, given back before the pots are awarded.
+    BetReturned {
+        player: usize,
+        amount: u32,
+    },
+    /// A pot, or a player's share of one when the best hands tie. A hand with side pots
+    /// awards each of them.
+    PotAwarded {
+        player: usize,
+        amount: u32,
+        showdown: bool,
+    },
+    PlayerEliminated {
+        player: usize,
+    },
+    /// The player let their turn run out, and checked or folded for it. The engine never
+    /// records this or the sitting out and in below, a networked table does.
+    TimedOut {
+        player: usize,
+    },
+    /// The player ran out of time too often. They stay dealt in and post their blinds, but
+    /// check or fold without being asked until they sit in again.
+    SatOut {
+        player: usize,
+    },
+    SatIn {
+        player: usize,
+    },
+}
+
+impl GameEvent {
+    /// The action a player took, if this event is one.
+    pub fn action(&self) -> Option<PokerAction> {
+        match *self {
+            GameEvent::Checked { .. } | GameEvent::Called { .. } => Some(PokerAction::CallOrCheck),
+            GameEvent::Raised { amount, .. } => Some(PokerAction::Raise(amount)),
+            GameEvent::Folded { .. } => Some(PokerAction::Fold),
+            _ => None,
+        }
+    }
+}
+
+/// Something told about every event as it happens, like a log, a display or a stats
+/// collector. Subscribed to a `GameState` it hears every hand the game deals from then on,
+/// subscribed to a `HandState` only the rest of that hand.
+pub trait EventObserver {
+    fn on_event(&mut self, event: &GameEvent);
+}
+
+/// An observer shared by the game, its hands and whoever reads what it collected. It is
+/// behind a mutex so games with observers can still move between threads.
+pub type Observer = Arc<Mutex<dyn EventObserver + Send>>;
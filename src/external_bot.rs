@@ -0,0 +1,120 @@
+use std::io::{self, BufRead, BufReader, Write};
+use std::process::{Child, ChildStdin, ChildStdout, Command, Stdio};
+
+use serde::Deserialize;
+
+use crate::bots::{BotContext, BotPlayer, PlayerView};
+use crate::PokerAction;
+
+/// One line a bot program answers with.
+#[derive(Debug, Deserialize, PartialEq, Eq)]
+#[serde(tag = "action", rename_all = "lowercase")]
+enum ExternalAction {
+    Call,
+    Check,
+    Fold,
+    Raise { amount: u32 },
+}
+
+/// A seat played by another program. On its turn the bot gets its `PlayerView` as one
+/// line of JSON on stdin and answers with one line like `{"action":"call"}`,
+/// `{"action":"check"}`, `{"action":"fold"}` or `{"action":"raise","amount":10}`.
+///
+/// A program that exits or answers with anything else checks when it can and folds
+/// otherwise, for the rest of the game.
+pub struct ExternalBot {
+    process: Option<(Child, ChildStdin, BufReader<ChildStdout>)>,
+}
+
+impl ExternalBot {
+    pub fn spawn(program: &str, args: &[&str]) -> io::Result<Self> {
+        let mut child = Command::new(program)
+            .args(args)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .spawn()?;
+        let stdin = child.stdin.take().expect("stdin is piped");
+        let stdout = BufReader::new(child.stdout.take().expect("stdout is piped"));
+        Ok(Self {
+            process: Some((child, stdin, stdout)),
+        })
+    }
+
+    fn ask(&mut self, view: &PlayerView) -> Option<ExternalAction> {
+        let (_, stdin, stdout) = self.process.as_mut()?;
+        let request = serde_json::to_string(view).ok()?;
+        writeln!(stdin, "{request}").ok()?;
+        stdin.flush().ok()?;
+        let mut answer = String::new();
+        if stdout.read_line(&mut answer).ok()? == 0 {
+            return None;
+        }
+        serde_json::from_str(&answer).ok()
+    }
+}
+
+impl BotPlayer for ExternalBot {
+    fn decide(&mut self, view: &BotContext) -> PokerAction {
+        match self.ask(view) {
+            Some(ExternalAction::Call | ExternalAction::Check) => PokerAction::CallOrCheck,
+            Some(ExternalAction::Raise { amount }) => PokerAction::Raise(amount),
+            Some(ExternalAction::Fold) => PokerAction::Fold,
+            None => {
+                self.process = None;
+                if view.expected_call == 0 {
+                    PokerAction::CallOrCheck
+                } else {
+                    PokerAction::Fold
+                }
+            }
+        }
+    }
+}
+
+impl Drop for ExternalBot {
+    fn drop(&mut self) {
+        if let Some((mut child, stdin, _)) = self.process.take() {
+            // Closing stdin lets a well behaved bot exit on its own.
+            drop(stdin);
+            let _ = child.kill();
+            let _ = child.wait();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core_engine::Deck;
+    use crate::GameState;
+
+    fn view() -> BotContext {
+        let (hs, cur) = GameState::init(2)
+            .unwrap()
+            .start_play_hand(Deck::ordered_deck());
+        hs.bot_context(cur)
+    }
+
+    #[test]
+    fn should_play_the_action_the_program_answers() {
+        let script = r#"while read line; do echo '{"action":"raise","amount":5}'; done"#;
+        let mut bot = ExternalBot::spawn("sh", &["-c", script]).unwrap();
+        assert_eq!(bot.decide(&view()), PokerAction::Raise(5));
+        assert_eq!(bot.decide(&view()), PokerAction::Raise(5));
+    }
+
+    #[test]
+    fn should_send_the_view_as_json() {
+        let script = r#"read line; case "$line" in *'"expected_call":1'*) echo '{"action":"call"}';; *) echo '{"action":"fold"}';; esac"#;
+        let mut bot = ExternalBot::spawn("sh", &["-c", script]).unwrap();
+        assert_eq!(bot.decide(&view()), PokerAction::CallOrCheck);
+    }
+
+    #[test]
+    fn should_fold_when_the_program_misbehaves() {
+        let mut bot = ExternalBot::spawn("sh", &["-c", "echo nonsense"]).unwrap();
+        assert_eq!(bot.decide(&view()), PokerAction::Fold);
+        assert!(bot.process.is_none());
+        assert!(ExternalBot::spawn("/nonexistent/bot", &[]).is_err());
+    }
+}
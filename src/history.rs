@@ -0,0 +1,251 @@
+use serde::{Deserialize, Serialize};
+
+use crate::core_engine::{Card, Deck};
+use crate::{HandSnapshot, HandState, IllegalRaise, PokerAction, TurnResult};
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HandHistory {
+    pub stacks: Vec<u32>,
+    pub big_blind: usize,
+    pub deck: Vec<Card>,
+    pub actions: Vec<PokerAction>,
+}
+
+impl HandHistory {
+    pub(crate) fn init(stacks: Vec<u32>, big_blind: usize, deck: &Deck) -> Self {
+        Self {
+            stacks,
+            big_blind,
+            deck: deck.cards().to_vec(),
+            actions: vec![],
+        }
+    }
+
+    /// The hand dealt from a deck that gives out `hole_cards` by seat and then `board` the
+    /// way the engine draws, with the cards nobody saw filled in from the rest of the deck.
+    /// It has to play out with `actions`, each by the seat whose turn it is, without going
+    /// on after it is over.
+    pub(crate) fn from_deal(
+        stacks: Vec<u32>,
+        big_blind: usize,
+        hole_cards: &[[Option<Card>; 2]],
+        board: &[Option<Card>],
+        actions: &[(usize, PokerAction)],
+    ) -> Result<Self, String> {
+        if stacks.len() < 2 || big_blind >= stacks.len() || hole_cards.len() != stacks.len() {
+            return Err("a hand needs at least 2 players with cards".into());
+        }
+        if board.len() > 5 {
+            return Err("the board has at most five cards".into());
+        }
+        let mut dealt: Vec<Option<Card>> = hole_cards.iter().flatten().copied().collect();
+        dealt.extend(board);
+        dealt.resize(hole_cards.len() * 2 + 5, None);
+        let mut rest: Vec<Card> = Deck::ordered_deck()
+            .cards()
+            .iter()
+            .filter(|card| !dealt.contains(&Some(**card)))
+            .copied()
+            .collect();
+        if rest.len() + dealt.iter().flatten().count() != 52 {
+            return Err("a card is dealt twice".into());
+        }
+        let missing = dealt.iter().filter(|card| card.is_none()).count();
+        if missing > rest.len() {
+            return Err("there are too many players for one deck".into());
+        }
+        let mut unseen = rest.split_off(rest.len() - missing);
+        let mut deck = rest;
+        // The engine draws from the end of the deck.
+        for card in dealt.into_iter().rev() {
+            deck.extend(card.or_else(|| unseen.pop()));
+        }
+
+        let history = HandHistory {
+            stacks,
+            big_blind,
+            deck,
+            actions: actions.iter().map(|&(_, action)| action).collect(),
+        };
+        let mut hs = history.start();
+        for (i, &(seat, action)) in actions.iter().enumerate() {
+            if seat != hs.current_player() {
+                return Err(format!("seat {seat} acts when it isn't their turn"));
+            }
+            match hs.play_action(action) {
+                Ok(TurnResult::WonHand(_)) if i + 1 < actions.len() => {
+                    return Err("the hand goes on after it is over".into())
+                }
+                Ok(_) => {}
+                Err(IllegalRaise::TooBig) => {
+                    return Err(format!("{action:?} is more than can be raised"))
+                }
+                Err(IllegalRaise::TooSmall) => {
+                    return Err(format!("{action:?} is less than can be raised"))
+                }
+            }
+        }
+        Ok(history)
+    }
+
+    pub fn players(&self) -> usize {
+        self.stacks.len()
+    }
+
+    pub fn start(&self) -> HandState {
+        HandState::init(
+            self.players(),
+            self.big_blind,
+            self.stacks.clone(),
+            Deck::init(self.deck.clone()),
+            vec![],
+        )
+    }
+
+    /// The hand as it ends after the recorded actions.
+    pub fn play(&self) -> Result<HandState, String> {
+        let mut hs = self.start();
+        for action in &self.actions {
+            hs.play_action(*action).map_err(|e| match e {
+                IllegalRaise::TooBig => format!("{action:?} is more than can be raised"),
+                IllegalRaise::TooSmall => format!("{action:?} is less than can be raised"),
+            })?;
+        }
+        Ok(hs)
+    }
+
+    /// Replays the recorded actions and returns the state of the table before the first
+    /// action and after every action that follows it.
+    pub fn replay(&self) -> Vec<HandSnapshot> {
+        let mut hs = self.start();
+        let mut snapshots = vec![hs.spectator_snapshot()];
+        for action in &self.actions {
+            if hs.play_action(*action).is_err() {
+                break;
+            }
+            snapshots.push(hs.spectator_snapshot());
+        }
+        snapshots
+    }
+
+    pub fn to_text(&self) -> String {
+        let mut lines = vec![
+            format!("stacks {}", join(&self.stacks)),
+            format!("big_blind {}", self.big_blind),
+            format!("deck {}", join(&self.deck)),
+        ];
+        lines.extend(self.actions.iter().map(|a| match a {
+            PokerAction::CallOrCheck => "action c".to_owned(),
+            PokerAction::Fold => "action f".to_owned(),
+            PokerAction::Raise(v) => format!("action r {v}"),
+        }));
+        lines.join("\n") + "\n"
+    }
+
+    pub fn from_text(text: &str) -> Option<Self> {
+        let mut stacks = None;
+        let mut big_blind = None;
+        let mut deck = None;
+        let mut actions = vec![];
+
+        for line in text.lines().filter(|l| !l.trim().is_empty()) {
+            let (key, rest) = line.split_once(' ')?;
+            match key {
+                "stacks" => stacks = Some(parse_all(rest, |s| s.parse().ok())?),
+                "big_blind" => big_blind = Some(rest.trim().parse().ok()?),
+                "deck" => deck = Some(parse_all(rest, |s| Card::try_from(s).ok())?),
+                "action" => actions.push(parse_action(rest)?),
+                _ => return None,
+            }
+        }
+
+        let history = HandHistory {
+            stacks: stacks?,
+            big_blind: big_blind?,
+            deck: deck?,
+            actions,
+        };
+        if history.players() < 2 || history.big_blind >= history.players() {
+            return None;
+        }
+        // The deck has to deal every hole card and the whole board, no card twice.
+        let deck = &history.deck;
+        let repeated = (0..deck.len()).any(|i| deck[..i].contains(&deck[i]));
+        if repeated || deck.len() < history.players() * 2 + 5 {
+            return None;
+        }
+        Some(history)
+    }
+}
+
+fn join<T: ToString>(items: &[T]) -> String {
+    items
+        .iter()
+        .map(|i| i.to_string())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn parse_all<T>(s: &str, parse: impl Fn(&str) -> Option<T>) -> Option<Vec<T>> {
+    s.split_ascii_whitespace().map(parse).collect()
+}
+
+fn parse_action(s: &str) -> Option<PokerAction> {
+    let mut parts = s.split_ascii_whitespace();
+    let action = match parts.next()? {
+        "c" => PokerAction::CallOrCheck,
+        "f" => PokerAction::Fold,
+        "r" => PokerAction::Raise(parts.next()?.parse().ok()?),
+        _ => return None,
+    };
+    Some(action)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::GameState;
+    use PokerAction::*;
+
+    #[test]
+    fn should_record_and_replay_a_hand() {
+        let gs = GameState::init(2).unwrap();
+        let (mut hs, _) = gs.start_play_hand(Deck::shuffled_deck());
+        let mut expected = vec![hs.spectator_snapshot()];
+        for action in [Raise(5), CallOrCheck, CallOrCheck, Raise(3), Fold] {
+            hs.play_action(action).unwrap();
+            expected.push(hs.spectator_snapshot());
+        }
+
+        let history = HandHistory::from_text(&hs.history().to_text()).unwrap();
+        assert_eq!(&history, hs.history());
+
+        let replayed = history.replay();
+        assert_eq!(replayed.len(), expected.len());
+        for (actual, expected) in replayed.iter().zip(expected) {
+            assert_eq!(actual.board, expected.board);
+            assert_eq!(actual.pot, expected.pot);
+            assert_eq!(actual.current_player, expected.current_player);
+            assert_eq!(
+                actual.chips.iter().map(|c| c.stack).collect::<Vec<_>>(),
+                expected.chips.iter().map(|c| c.stack).collect::<Vec<_>>()
+            );
+        }
+    }
+
+    #[test]
+    fn should_reject_malformed_history() {
+        assert!(HandHistory::from_text("").is_none());
+        assert!(HandHistory::from_text("stacks 100\nbig_blind 0\ndeck H2").is_none());
+        assert!(HandHistory::from_text("stacks 100 100\nbig_blind 1\ndeck H2\naction x").is_none());
+    }
+
+    #[test]
+    fn should_reject_a_deck_that_cant_deal_the_hand() {
+        let deck = "H2 H3 H4 H5 H6 H7 H8 H9 H10";
+        let text = |deck: &str| format!("stacks 100 100\nbig_blind 1\ndeck {deck}\naction f");
+        assert!(HandHistory::from_text(&text(deck)).is_some());
+        assert!(HandHistory::from_text(&text("H2 H3 H4 H5 H6 H7 H8 H9")).is_none());
+        assert!(HandHistory::from_text(&text("H2 H3 H4 H5 H6 H7 H8 H9 H2")).is_none());
+    }
+}
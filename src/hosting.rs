@@ -0,0 +1,256 @@
+//! How a host is set up: the ports it takes players on, the secret it signs tokens with
+//! and its tables. `poker-tui host` takes it from flags, and `poker-tui serve` from a JSON
+//! file like
+//!
+//! ```json
+//! {
+//!   "version": 1,
+//!   "port": 7777,
+//!   "ws_port": 7778,
+//!   "secret": "something long",
+//!   "status_port": 9090,
+//!   "advertise": "Friday night",
+//!   "archive_dir": "hands",
+//!   "tables": [
+//!     { "name": "main", "players": 6, "stack": 200, "turn_timeout_secs": 60 },
+//!     { "name": "club", "password": "pw", "spectator_delay_secs": 30 },
+//!     { "name": "trustless", "mental_shuffle": true },
+//!     { "name": "heads-up", "queue": true }
+//!   ]
+//! }
+//! ```
+//!
+//! where everything left out is as `TableConfig::new` and `ServerConfig::default` have it,
+//! and `version` is the version of the format, 1 if left out.
+//!
+//! Players can also play over ssh, without installing anything, when the machine's sshd
+//! is set up with `sshd_config` to start a client of the host for everyone who logs in as
+//! a dedicated user. Every login gets a session of its own.
+
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use serde::Deserialize;
+
+use crate::net::TableConfig;
+use crate::schema::Schema;
+use crate::BIG_BLIND_CHIPS;
+
+pub const DEFAULT_PORT: u16 = 7777;
+
+const SERVER_CONFIG: Schema = Schema {
+    name: "server config",
+    version: 1,
+    migrations: &[],
+};
+
+#[derive(Clone, Debug)]
+pub struct ServerConfig {
+    pub port: u16,
+    /// Where players can also join over WebSocket, if anywhere.
+    pub ws_port: Option<u16>,
+    /// What invites and resume tokens are signed with. Without one they are only good
+    /// until the server stops.
+    pub secret: Option<String>,
+    /// Where the read-only HTTP endpoint of `status` answers, if anywhere.
+    pub status_port: Option<u16>,
+    /// The name the host advertises itself under on the local network, if it does.
+    pub advertise: Option<String>,
+    /// Where every hand dealt is archived, if anywhere.
+    pub archive_dir: Option<PathBuf>,
+    pub tables: Vec<TableConfig>,
+}
+
+impl Default for ServerConfig {
+    /// Plain TCP on the default port and a single table called main.
+    fn default() -> Self {
+        Self {
+            port: DEFAULT_PORT,
+            ws_port: None,
+            secret: None,
+            status_port: None,
+            advertise: None,
+            archive_dir: None,
+            tables: vec![TableConfig::new("main")],
+        }
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ServerFile {
+    port: Option<u16>,
+    ws_port: Option<u16>,
+    secret: Option<String>,
+    status_port: Option<u16>,
+    advertise: Option<String>,
+    archive_dir: Option<PathBuf>,
+    #[serde(default)]
+    tables: Vec<TableFile>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct TableFile {
+    name: String,
+    players: Option<usize>,
+    stack: Option<u32>,
+    password: Option<String>,
+    #[serde(default)]
+    invite_only: bool,
+    #[serde(default)]
+    mental_shuffle: bool,
+    /// Pairs players at heads-up tables of their own, so `players` can only be 2.
+    #[serde(default)]
+    queue: bool,
+    spectator_delay_secs: Option<u64>,
+    /// 0 for no limit.
+    turn_timeout_secs: Option<u64>,
+    sit_out_after: Option<u32>,
+    heartbeat_secs: Option<u64>,
+}
+
+impl TableFile {
+    fn config(self) -> Result<TableConfig, String> {
+        let mut table = TableConfig::new(&self.name);
+        table.players = self.players.unwrap_or(table.players);
+        table.stack = self.stack.unwrap_or(table.stack);
+        table.password = self.password;
+        table.invite_only = self.invite_only;
+        table.mental_shuffle = self.mental_shuffle;
+        table.queue = self.queue;
+        if self.queue {
+            match self.players {
+                Some(players) if players != 2 => {
+                    return Err(format!("the queue {} only makes heads-up games", self.name))
+                }
+                _ => table.players = 2,
+            }
+        }
+        if let Some(secs) = self.spectator_delay_secs {
+            table.spectator_delay = Duration::from_secs(secs);
+        }
+        if let Some(secs) = self.turn_timeout_secs {
+            table.turn_timeout = (secs > 0).then(|| Duration::from_secs(secs));
+        }
+        table.sit_out_after = self.sit_out_after.unwrap_or(table.sit_out_after);
+        if let Some(secs) = self.heartbeat_secs {
+            table.heartbeat = Duration::from_secs(secs);
+        }
+        let name = &table.name;
+        if table.players < 2 {
+            return Err(format!("table {name} needs at least 2 players"));
+        }
+        if table.stack < BIG_BLIND_CHIPS {
+            return Err(format!(
+                "table {name} needs a stack of at least {BIG_BLIND_CHIPS}"
+            ));
+        }
+        if table.sit_out_after < 1 {
+            return Err(format!(
+                "table {name} can't sit players out before a timeout"
+            ));
+        }
+        if table.heartbeat.is_zero() {
+            return Err(format!(
+                "table {name} needs a heartbeat of at least a second"
+            ));
+        }
+        Ok(table)
+    }
+}
+
+impl ServerConfig {
+    /// Reads a config from JSON, refusing fields it doesn't know so that typos don't go
+    /// unnoticed.
+    pub fn from_json(json: &str) -> Result<Self, String> {
+        let file: ServerFile = SERVER_CONFIG.read(json)?;
+        let mut config = ServerConfig {
+            port: file.port.unwrap_or(DEFAULT_PORT),
+            ws_port: file.ws_port,
+            secret: file.secret,
+            status_port: file.status_port,
+            advertise: file.advertise,
+            archive_dir: file.archive_dir,
+            tables: file
+                .tables
+                .into_iter()
+                .map(TableFile::config)
+                .collect::<Result<_, _>>()?,
+        };
+        for (i, table) in config.tables.iter().enumerate() {
+            if config.tables[..i].iter().any(|t| t.name == table.name) {
+                return Err(format!("there are two tables called {}", table.name));
+            }
+        }
+        if config.tables.is_empty() {
+            config.tables = ServerConfig::default().tables;
+        }
+        Ok(config)
+    }
+
+    pub fn load(path: &Path) -> io::Result<Self> {
+        let json = std::fs::read_to_string(path)?;
+        Self::from_json(&json).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+    }
+}
+
+/// The `sshd_config` block that turns every ssh login as `user` into a seat at the host at
+/// `addr`: sshd runs `exe ssh-session addr` in place of whatever the player asked to run,
+/// and allows nothing else.
+pub fn sshd_config(exe: &Path, addr: &str, user: &str) -> String {
+    let command = format!("ForceCommand \"{}\" ssh-session {addr}", exe.display());
+    let settings = [
+        command.as_str(),
+        "PermitTTY yes",
+        "AllowTcpForwarding no",
+        "AllowAgentForwarding no",
+        "X11Forwarding no",
+        "PermitTunnel no",
+    ];
+    let mut config = format!("Match User {user}\n");
+    for setting in settings {
+        config += &format!("    {setting}\n");
+    }
+    config
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_fill_in_what_the_file_leaves_out() {
+        let config = ServerConfig::from_json(
+            r#"{"ws_port": 7778, "status_port": 9090, "advertise": "lan", "archive_dir": "hands", "tables": [
+                {"name": "main", "players": 6, "turn_timeout_secs": 0},
+                {"name": "club", "password": "pw", "spectator_delay_secs": 30}
+            ]}"#,
+        )
+        .unwrap();
+        assert_eq!((config.port, config.ws_port), (DEFAULT_PORT, Some(7778)));
+        assert_eq!(config.status_port, Some(9090));
+        assert_eq!(config.advertise.as_deref(), Some("lan"));
+        assert_eq!(config.archive_dir, Some(PathBuf::from("hands")));
+        let [main, club] = &config.tables[..] else {
+            panic!("expected two tables");
+        };
+        assert_eq!(
+            (main.players, main.stack, main.turn_timeout),
+            (6, 100, None)
+        );
+        assert_eq!(club.password.as_deref(), Some("pw"));
+        assert_eq!(club.spectator_delay, Duration::from_secs(30));
+        assert_eq!(club.turn_timeout, TableConfig::new("club").turn_timeout);
+
+        assert_eq!(ServerConfig::from_json("{}").unwrap().tables.len(), 1);
+        assert!(ServerConfig::from_json(r#"{"prot": 1}"#).is_err());
+        let queue = r#"{"tables": [{"name": "heads-up", "queue": true}]}"#;
+        assert_eq!(ServerConfig::from_json(queue).unwrap().tables[0].players, 2);
+        let crowded = r#"{"tables": [{"name": "heads-up", "queue": true, "players": 6}]}"#;
+        assert!(ServerConfig::from_json(crowded).is_err());
+        let twice = r#"{"tables": [{"name": "main"}, {"name": "main"}]}"#;
+        assert!(ServerConfig::from_json(twice).is_err());
+    }
+}
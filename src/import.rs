@@ -0,0 +1,229 @@
+//! Hand histories read back from files into hands that can be replayed, whichever format
+//! they were written in: ours, PHH, a `.phhs` file of several PHH hands, or PokerStars'.
+//!
+//! PokerStars hands only replay if they were played the way the engine plays: blinds of
+//! 1/2 chips, no antes, and the player after the big blind opening every street.
+
+use std::collections::BTreeMap;
+
+use crate::core_engine::Card;
+use crate::history::HandHistory;
+use crate::phh::PhhHand;
+use crate::{PokerAction, BIG_BLIND_CHIPS, SMALL_BLIND_CHIPS};
+
+const POKERSTARS_HEADER: &str = "PokerStars Hand #";
+
+/// Every hand in `text`, in the order they were played.
+pub fn read_hands(text: &str) -> Result<Vec<HandHistory>, String> {
+    if text.trim_start().starts_with(POKERSTARS_HEADER) {
+        let starts: Vec<_> = text
+            .match_indices(POKERSTARS_HEADER)
+            .map(|(i, _)| i)
+            .collect();
+        return starts
+            .iter()
+            .enumerate()
+            .map(|(i, &start)| {
+                let end = starts.get(i + 1).copied().unwrap_or(text.len());
+                pokerstars_hand(&text[start..end]).map_err(|e| format!("hand {}: {e}", i + 1))
+            })
+            .collect();
+    }
+    if let Some(history) = HandHistory::from_text(text) {
+        return Ok(vec![history]);
+    }
+    if let Ok(hand) = PhhHand::from_toml(text) {
+        return Ok(vec![hand.to_history()?]);
+    }
+    let hands: BTreeMap<String, PhhHand> = toml::from_str(text)
+        .map_err(|_| "not a hand history in a format that can be read".to_owned())?;
+    let mut hands: Vec<_> = hands.into_iter().collect();
+    hands.sort_by_key(|(key, _)| key.parse::<u64>().unwrap_or(u64::MAX));
+    hands
+        .into_iter()
+        .map(|(key, hand)| hand.to_history().map_err(|e| format!("hand {key}: {e}")))
+        .collect()
+}
+
+/// Cards like `[Ah Kd]`, the last ones in brackets on the line.
+fn bracketed_cards(line: &str) -> Option<Vec<Option<Card>>> {
+    let start = line.rfind('[')?;
+    let end = start + line[start..].find(']')?;
+    line[start + 1..end]
+        .split_whitespace()
+        .map(|card| Card::from_short_name(card).map(Some))
+        .collect()
+}
+
+/// A hand as `pokerstars::hand_history` writes it, or PokerStars itself.
+fn pokerstars_hand(text: &str) -> Result<HandHistory, String> {
+    let mut lines = text.lines();
+    let header = lines.next().unwrap_or_default();
+    let blinds = header
+        .split_once('(')
+        .and_then(|(_, rest)| rest.split_once(')'))
+        .map(|(blinds, _)| blinds.replace('$', ""));
+    let expected = format!("{SMALL_BLIND_CHIPS}/{BIG_BLIND_CHIPS}");
+    if blinds.as_deref().and_then(|b| b.split_whitespace().next()) != Some(expected.as_str()) {
+        return Err(format!("only blinds of {expected} are played"));
+    }
+
+    let mut names: Vec<String> = vec![];
+    let mut stacks = vec![];
+    let mut hole_cards = vec![];
+    let mut board = vec![];
+    let mut actions = vec![];
+    let mut bets = vec![];
+    let mut blind_seats = vec![];
+    for line in lines {
+        if line.starts_with("*** SUMMARY") {
+            break;
+        }
+        if let Some(seat) = line
+            .strip_prefix("Seat ")
+            .filter(|_| blind_seats.is_empty())
+        {
+            let Some((name, stack)) = seat
+                .split_once(": ")
+                .and_then(|(_, rest)| rest.rsplit_once(" ("))
+                .and_then(|(name, rest)| Some((name, rest.strip_suffix(" in chips)")?)))
+            else {
+                continue;
+            };
+            names.push(name.to_owned());
+            stacks.push(
+                stack
+                    .parse::<u32>()
+                    .map_err(|_| format!("bad stack in {line:?}"))?,
+            );
+            hole_cards.push([None; 2]);
+            bets.push(0);
+            continue;
+        }
+        if line.starts_with("*** FLOP")
+            || line.starts_with("*** TURN")
+            || line.starts_with("*** RIVER")
+        {
+            board.extend(bracketed_cards(line).ok_or_else(|| format!("bad cards in {line:?}"))?);
+            bets.iter_mut().for_each(|b| *b = 0);
+            continue;
+        }
+        let (subject, separator) = match line.strip_prefix("Dealt to ") {
+            Some(rest) => (rest, " ["),
+            None => (line, ": "),
+        };
+        let Some(seat) = names.iter().position(|name| {
+            let rest = subject.strip_prefix(name.as_str());
+            rest.is_some_and(|rest| rest.starts_with(separator))
+        }) else {
+            continue;
+        };
+        let bad = || format!("can't play {line:?}");
+        let action = subject[names[seat].len()..].trim_start_matches(':').trim();
+        if action.starts_with('[') || action.starts_with("shows [") {
+            match bracketed_cards(line).as_deref() {
+                Some(&[first, second]) => hole_cards[seat] = [first, second],
+                _ => return Err(bad()),
+            }
+            continue;
+        }
+        let action = action.strip_suffix(" and is all-in").unwrap_or(action);
+        let words: Vec<_> = action.split_whitespace().collect();
+        let amount = |word: &str| word.replace('$', "").parse::<u32>().map_err(|_| bad());
+        match words[..] {
+            ["posts", "small" | "big", "blind", posted] => {
+                blind_seats.push(seat);
+                bets[seat] += amount(posted)?;
+            }
+            ["folds"] => actions.push((seat, PokerAction::Fold)),
+            ["checks"] => actions.push((seat, PokerAction::CallOrCheck)),
+            ["calls", called] => {
+                bets[seat] += amount(called)?;
+                actions.push((seat, PokerAction::CallOrCheck));
+            }
+            ["bets", bet] => {
+                let bet = amount(bet)?;
+                bets[seat] += bet;
+                actions.push((seat, PokerAction::Raise(bet)));
+            }
+            ["raises", _, "to", total] => {
+                let total = amount(total)?;
+                let raise = total.checked_sub(bets[seat]).ok_or_else(bad)?;
+                bets[seat] = total;
+                actions.push((seat, PokerAction::Raise(raise)));
+            }
+            _ => {}
+        }
+    }
+
+    let players = names.len();
+    let &[small_blind, big_blind] = &blind_seats[..] else {
+        return Err("the hand needs a small and a big blind".into());
+    };
+    if players < 2 || small_blind != (big_blind + players - 1) % players {
+        return Err("the small blind has to sit right before the big blind".into());
+    }
+    HandHistory::from_deal(stacks, big_blind, &hole_cards, &board, &actions)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core_engine::Deck;
+    use crate::events::GameEvent;
+    use crate::pokerstars::hand_history;
+    use crate::PokerAction::*;
+    use crate::{GameState, TurnResult::NextPlayer};
+
+    #[test]
+    fn should_read_back_hands_in_every_format_written() {
+        let gs = GameState::init(3).unwrap();
+        let mut played = vec![];
+        let mut pokerstars = String::new();
+        let mut gs = gs;
+        for actions in [&[Fold, Fold][..], &[Raise(6), Fold, CallOrCheck, Raise(10)]] {
+            let (mut hs, _) = gs.start_play_hand(Deck::shuffled_deck());
+            let mut result = NextPlayer(0);
+            for action in actions {
+                result = hs.play_action(*action).unwrap();
+            }
+            while let NextPlayer(_) = result {
+                result = hs.play_action(CallOrCheck).unwrap();
+            }
+            pokerstars += &hand_history(&hs, played.len() as u64, "main", 0, None);
+            pokerstars += "\n\n";
+            played.push(hs.history().clone());
+            gs = gs.apply_played_hand(hs);
+        }
+
+        let read = read_hands(&pokerstars).unwrap();
+        assert_eq!(read.len(), 2);
+        for (read, played) in read.iter().zip(&played) {
+            assert_eq!(
+                (&read.stacks, read.big_blind, &read.actions),
+                (&played.stacks, played.big_blind, &played.actions)
+            );
+        }
+        // The cards of a player who folds are never shown, but the rest plays out the same.
+        let outcome = |history: &HandHistory| {
+            let mut hs = history.start();
+            for action in &history.actions {
+                hs.play_action(*action).unwrap();
+            }
+            hs.events()
+                .iter()
+                .filter(|e| {
+                    matches!(
+                        e,
+                        GameEvent::StreetDealt { .. } | GameEvent::PotAwarded { .. }
+                    )
+                })
+                .cloned()
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(outcome(&read[1]), outcome(&played[1]));
+
+        assert_eq!(read_hands(&played[0].to_text()).unwrap(), played[..1]);
+        assert!(read_hands("nothing to see").is_err());
+    }
+}
@@ -0,0 +1,172 @@
+//! How the profiles playing on this computer compare across all their games, season by
+//! season. Starting a new season puts the standings so far behind it and starts everyone
+//! from nothing.
+
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use crate::schema::Schema;
+
+const LEADERBOARD: Schema = Schema {
+    name: "leaderboard",
+    version: 1,
+    migrations: &[],
+};
+
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Standing {
+    pub profile: String,
+    pub games: u32,
+    pub profit: i64,
+    /// Games the profile finished with every chip at the table.
+    pub titles: u32,
+    pub biggest_pot: u32,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Season {
+    pub number: u32,
+    /// Best first.
+    pub standings: Vec<Standing>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Leaderboard {
+    pub season: Season,
+    /// Seasons that are over, oldest first.
+    pub past: Vec<Season>,
+}
+
+impl Default for Leaderboard {
+    fn default() -> Self {
+        Self {
+            season: Season {
+                number: 1,
+                standings: vec![],
+            },
+            past: vec![],
+        }
+    }
+}
+
+impl Leaderboard {
+    /// The leaderboard saved at `path`, or an empty one if nothing is saved there yet.
+    pub fn load(path: impl AsRef<Path>) -> io::Result<Self> {
+        match fs::read_to_string(path) {
+            Ok(text) => LEADERBOARD.read(&text).map_err(io::Error::other),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(e) => Err(e),
+        }
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        let json = serde_json::to_string_pretty(&LEADERBOARD.stamp(self))?;
+        fs::write(path, json)
+    }
+
+    /// Adds a game the profile played to this season.
+    pub fn record_game(&mut self, profile: &str, profit: i64, title: bool, biggest_pot: u32) {
+        let standings = &mut self.season.standings;
+        let at = match standings.iter().position(|s| s.profile == profile) {
+            Some(at) => at,
+            None => {
+                standings.push(Standing {
+                    profile: profile.to_owned(),
+                    ..Standing::default()
+                });
+                standings.len() - 1
+            }
+        };
+        let standing = &mut standings[at];
+        standing.games += 1;
+        standing.profit += profit;
+        standing.titles += u32::from(title);
+        standing.biggest_pot = standing.biggest_pot.max(biggest_pot);
+        standings.sort_by(|a, b| b.profit.cmp(&a.profit).then(b.titles.cmp(&a.titles)));
+    }
+
+    /// Ends this season and starts the next one, unless nobody played in it yet. Returns
+    /// whether it did.
+    pub fn new_season(&mut self) -> bool {
+        if self.season.standings.is_empty() {
+            return false;
+        }
+        let next = Season {
+            number: self.season.number + 1,
+            standings: vec![],
+        };
+        self.past.push(std::mem::replace(&mut self.season, next));
+        true
+    }
+
+    /// This season's standings and who led each season before it.
+    pub fn to_lines(&self) -> Vec<String> {
+        let mut lines = vec![
+            format!("LEADERBOARD, season {}", self.season.number),
+            "Rank  Profile               Games  Titles      Profit  Biggest pot".to_owned(),
+        ];
+        lines.extend(self.season.standings.iter().enumerate().map(|(rank, s)| {
+            format!(
+                "{:>4}  {:<20} {:>6} {:>7} {:>+11} {:>12}",
+                rank + 1,
+                s.profile,
+                s.games,
+                s.titles,
+                s.profit,
+                s.biggest_pot
+            )
+        }));
+        if self.season.standings.is_empty() {
+            lines.push("Nobody has played this season yet.".to_owned());
+        }
+        for season in self.past.iter().rev() {
+            if let Some(first) = season.standings.first() {
+                lines.push(format!(
+                    "Season {} went to {} with {:+}",
+                    season.number, first.profile, first.profit
+                ));
+            }
+        }
+        lines
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_rank_profiles_and_start_new_seasons() {
+        let mut leaderboard = Leaderboard::default();
+        leaderboard.record_game("ada", 50, false, 40);
+        leaderboard.record_game("bob", 120, true, 200);
+        leaderboard.record_game("ada", 100, true, 90);
+
+        let standings = &leaderboard.season.standings;
+        let ranked: Vec<_> = standings.iter().map(|s| s.profile.as_str()).collect();
+        assert_eq!(ranked, ["ada", "bob"]);
+        assert_eq!(
+            standings[0],
+            Standing {
+                profile: "ada".to_owned(),
+                games: 2,
+                profit: 150,
+                titles: 1,
+                biggest_pot: 90,
+            }
+        );
+
+        assert!(leaderboard.new_season());
+        assert!(!leaderboard.new_season());
+        assert_eq!(leaderboard.season.number, 2);
+        assert!(leaderboard.season.standings.is_empty());
+        assert_eq!(leaderboard.past.len(), 1);
+        assert_eq!(
+            leaderboard.to_lines().last().unwrap(),
+            "Season 1 went to ada with +150"
+        );
+    }
+}
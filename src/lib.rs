@@ -1,17 +1,129 @@
+//! The poker engine: `GameState` deals hands and `HandState` plays them, with the hand
+//! histories, stats and file formats around them. The bots are behind the `bots` feature,
+//! networked play behind `net` and the terminal frontend behind `tui`, so a server or
+//! another frontend can depend on the engine alone with `default-features = false`.
+
+#[cfg(feature = "bots")]
+pub mod adaptive;
+pub mod analysis;
+pub mod archive;
+#[cfg(feature = "net")]
+pub mod auth;
+pub mod bankroll;
+#[cfg(feature = "bots")]
+pub mod bots;
+pub mod builder;
+#[cfg(feature = "bots")]
+pub mod cfr;
+#[cfg(feature = "bots")]
+pub mod charts;
+pub mod chat;
+#[cfg(feature = "bots")]
+pub mod coach;
 pub mod core_engine;
-
+#[cfg(feature = "sqlite")]
+pub mod database;
+#[cfg(feature = "bots")]
+pub mod difficulty;
+#[cfg(all(feature = "net", not(target_arch = "wasm32")))]
+pub mod discovery;
+#[cfg(feature = "async")]
+pub mod driver;
+#[cfg(feature = "bots")]
+pub mod encoding;
+pub mod equity;
+pub mod events;
+#[cfg(feature = "bots")]
+pub mod external_bot;
+pub mod history;
+#[cfg(feature = "net")]
+pub mod hosting;
+pub mod import;
+pub mod leaderboard;
+pub mod logging;
+#[cfg(feature = "net")]
+pub mod mental;
+#[cfg(feature = "net")]
+pub mod net;
+pub mod notes;
+#[cfg(feature = "onnx")]
+pub mod onnx;
+pub mod phase;
+pub mod phh;
+pub mod pokerstars;
+#[cfg(feature = "net")]
+pub mod protocol;
+#[cfg(feature = "bots")]
+pub mod pushfold;
+#[cfg(feature = "bots")]
+pub mod report;
+pub mod save;
+pub mod schema;
+#[cfg(feature = "bots")]
+pub mod simulation;
+pub mod stats;
+#[cfg(feature = "net")]
+pub mod status;
+#[cfg(any(test, feature = "proptest"))]
+pub mod strategies;
+#[cfg(feature = "bots")]
+pub mod tournament;
+#[cfg(feature = "net")]
+pub mod transport;
+pub mod tutorial;
+#[cfg(feature = "bots")]
+pub mod view;
+#[cfg(feature = "wasm")]
+pub mod wasm;
+
+use std::collections::VecDeque;
+
+use serde::{Deserialize, Serialize};
+
+use builder::TableSetup;
 use core_engine::Card;
 use core_engine::Deck;
 use core_engine::Hand;
+use events::{GameEvent, Observer, Street};
+use history::HandHistory;
 use TurnResult::*;
 
+const SMALL_BLIND_CHIPS: u32 = 1;
+pub(crate) const BIG_BLIND_CHIPS: u32 = 2;
+/// The most chips a single raise can put in.
+pub const MAX_RAISE: u32 = 99;
+/// How many actions back a hand can be rewound.
+pub const CHECKPOINTS: usize = 32;
+
+// Fails to compile if a change makes the game types unsafe to share between threads.
+const _: () = {
+    const fn shared<T: Send + Sync>() {}
+    shared::<GameState>();
+    shared::<HandState>();
+    shared::<HandSnapshot>();
+    shared::<GameEvent>();
+};
+
+/// The game between hands. Like `HandState` and `HandSnapshot` it is `Send + Sync`, so a
+/// server can keep its tables in an `Arc<Mutex<_>>` and play them from any thread.
 pub struct GameState {
     chips: Vec<u32>,
     big_blind: usize,
     players: usize,
+    observers: Vec<Observer>,
+    table: TableSetup,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BetSize {
+    /// The smallest raise, as `HandState::min_raise`.
+    MinRaise,
+    HalfPot,
+    Pot,
+    AllIn,
 }
 
-#[derive(Clone, Copy, PartialEq, Eq)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
 pub enum PokerAction {
     CallOrCheck,
     Fold,
@@ -20,152 +132,551 @@ pub enum PokerAction {
 
 impl GameState {
     pub fn init(players: usize) -> Option<Self> {
-        if players > 1 {
-            Some(GameState {
-                chips: vec![100; players],
-                big_blind: players - 1,
-                players,
-            })
-        } else {
-            None
+        Self::init_with_stack(players, 100)
+    }
+
+    pub fn init_with_stack(players: usize, stack: u32) -> Option<Self> {
+        Self::builder().players(players).stack(stack).build().ok()
+    }
+
+    fn from_stacks(chips: Vec<u32>, big_blind: usize) -> Self {
+        GameState {
+            players: chips.len(),
+            chips,
+            big_blind,
+            observers: vec![],
+            table: TableSetup::default(),
         }
     }
 
     pub fn start_play_hand(&self, deck: Deck) -> (HandState, usize) {
-        let hs = HandState::init(self.players, self.big_blind, self.chips.clone(), deck);
-        let first = hs.turn.first_player;
+        let hs = HandState::init(
+            self.players,
+            self.big_blind,
+            self.chips.clone(),
+            deck,
+            self.observers.clone(),
+        );
+        let first = hs.turn.current_player;
         (hs, first)
     }
 
+    /// The game after `hand`. The big blind moves on to the next player who still has
+    /// chips.
     pub fn apply_played_hand(&self, hand: HandState) -> Self {
+        let chips = hand.chips.get_stacks();
+        let big_blind = (1..=self.players)
+            .map(|i| (self.big_blind + i) % self.players)
+            .find(|&p| chips[p] > 0)
+            .unwrap_or(self.big_blind);
         Self {
-            chips: hand.chips.get_stacks(),
-            big_blind: (self.big_blind + 1) % self.players,
+            chips,
+            big_blind,
             players: self.players,
+            observers: self.observers.clone(),
+            table: self.table.clone(),
         }
     }
 
+    /// Tells `observer` every event of the hands this game starts from now on.
+    pub fn subscribe(&mut self, observer: Observer) {
+        self.observers.push(observer);
+    }
+
     pub fn current_chips(&self, player: usize) -> u32 {
         self.chips[player]
     }
+
+    pub fn players(&self) -> usize {
+        self.players
+    }
 }
 
+/// A hand being played. Everything that changes it is a `GameEvent`: `play_action` decides
+/// what happens next and records it with `apply`, which is the only place the state
+/// changes. The state is a fold over the events, so `from_events` rebuilds any point of
+/// the hand from the events up to it.
+#[derive(Serialize, Deserialize)]
 pub struct HandState {
     chips: ChipsState,
     turn: TurnState,
     deck: Deck,
     players: usize,
-    hands: Vec<(Card, Card)>,
+    big_blind: usize,
+    /// The hole cards by seat, `None` for the seats left out of the deal.
+    hands: Vec<Option<(Card, Card)>>,
     board: Vec<Card>,
+    history: HandHistory,
+    events: Vec<GameEvent>,
+    /// How many events there were before each of the last `CHECKPOINTS` actions.
+    #[serde(default)]
+    checkpoints: VecDeque<usize>,
+    #[serde(skip)]
+    observers: Vec<Observer>,
 }
 
 pub enum TurnResult {
     NextPlayer(usize),
+    /// The hand is over. Holds who won the main pot, or the first of those who split it.
     WonHand(usize),
 }
 
-#[derive(Debug)]
-pub struct RaiseByTooMuch();
+/// Why a `Raise` was refused.
+#[derive(Debug, PartialEq)]
+pub enum IllegalRaise {
+    /// More than the player's stack or `MAX_RAISE`.
+    TooBig,
+    /// Less than `min_raise` without going all in.
+    TooSmall,
+}
 
 impl HandState {
-    fn init(players: usize, big_blind: usize, chips: Vec<u32>, deck: Deck) -> Self {
-        let (deck, hands) = draw_starting_hands(players, deck);
-        let mut slf = HandState {
+    fn init(
+        players: usize,
+        big_blind: usize,
+        chips: Vec<u32>,
+        deck: Deck,
+        observers: Vec<Observer>,
+    ) -> Self {
+        let mut slf = Self::undealt(players, big_blind, chips, deck);
+        slf.observers = observers;
+        let dealt: Vec<_> = (0..players).filter(|&p| slf.dealt_in(p)).collect();
+        for player in dealt {
+            let cards = slf.deck.peek(2);
+            slf.apply(GameEvent::HoleCardsDealt {
+                player,
+                cards: [cards[0], cards[1]],
+            });
+        }
+        slf.bet_blinds();
+        slf
+    }
+
+    /// The hand before anything happened in it, not even the deal. Players without chips
+    /// sit it out.
+    fn undealt(players: usize, big_blind: usize, chips: Vec<u32>, deck: Deck) -> Self {
+        let seated = chips.iter().map(|&c| c > 0).collect();
+        HandState {
+            history: HandHistory::init(chips.clone(), big_blind, &deck),
             chips: ChipsState::init(chips),
-            turn: TurnState::init(players, (big_blind + 1) % players),
+            turn: TurnState::init(seated, (big_blind + 1) % players),
             deck,
             players,
-            hands,
+            big_blind,
+            hands: vec![None; players],
             board: vec![],
-        };
-        slf.bet_blinds(big_blind);
-        slf
+            events: vec![],
+            checkpoints: VecDeque::new(),
+            observers: vec![],
+        }
     }
 
-    fn bet_blinds(&mut self, big_blind: usize) {
-        let small_blind = if big_blind == 0 {
-            self.players - 1
+    /// The hand with these starting stacks and deck after `events`, which start with the
+    /// deal.
+    pub fn from_events(
+        big_blind: usize,
+        chips: Vec<u32>,
+        deck: Deck,
+        events: &[GameEvent],
+    ) -> Self {
+        let mut hs = Self::undealt(chips.len(), big_blind, chips, deck);
+        for event in events {
+            hs.apply(event.clone());
+        }
+        hs
+    }
+
+    /// Changes the hand by what `event` says happened, records it and tells the observers.
+    fn apply(&mut self, event: GameEvent) {
+        match event {
+            GameEvent::HoleCardsDealt { player, cards } => {
+                cards.iter().for_each(|&card| self.deck.remove(card));
+                self.hands[player] = Some((cards[0], cards[1]));
+            }
+            GameEvent::BlindPosted { player, amount } => {
+                self.chips.bet_chips(player, amount);
+                if player == self.big_blind {
+                    self.pass_blind_turns();
+                }
+            }
+            GameEvent::Checked { .. } => self.advance_player(),
+            GameEvent::Called { player, amount, .. } => {
+                self.chips.bet_chips(player, amount);
+                self.advance_player();
+            }
+            GameEvent::Raised { player, amount, .. } => {
+                self.chips.raise_chips(player, amount);
+                self.turn.reset_action_counter();
+                self.advance_player();
+            }
+            GameEvent::Folded { player, .. } => {
+                self.turn.active_players[player] = false;
+                self.advance_player();
+            }
+            GameEvent::StreetDealt { ref cards, .. } => {
+                cards.iter().for_each(|&card| self.deck.remove(card));
+                self.board.extend(cards);
+            }
+            GameEvent::BetReturned { player, amount } => self.chips.return_bet(player, amount),
+            GameEvent::PotAwarded { player, amount, .. } => self.chips.award_pot(player, amount),
+            GameEvent::HandShown { .. }
+            | GameEvent::PlayerEliminated { .. }
+            | GameEvent::TimedOut { .. }
+            | GameEvent::SatOut { .. }
+            | GameEvent::SatIn { .. } => {}
+        }
+        if let Some(action) = event.action() {
+            self.history.actions.push(action);
+            if self.checkpoints.len() == CHECKPOINTS {
+                self.checkpoints.pop_front();
+            }
+            self.checkpoints.push_back(self.events.len());
+        }
+        for observer in &self.observers {
+            observer.lock().unwrap().on_event(&event);
+        }
+        self.events.push(event);
+    }
+
+    /// Whether `player` had chips when the hand started, and so was dealt in.
+    fn dealt_in(&self, player: usize) -> bool {
+        self.history.stacks[player] > 0
+    }
+
+    /// The closest seat dealt in before `seat`.
+    fn seat_before(&self, seat: usize) -> usize {
+        (1..self.players)
+            .map(|i| (seat + self.players - i) % self.players)
+            .find(|&p| self.dealt_in(p))
+            .unwrap_or(seat)
+    }
+
+    fn small_blind(&self) -> usize {
+        self.seat_before(self.big_blind)
+    }
+
+    /// Heads-up the button posts the small blind, otherwise it sits right before it.
+    pub(crate) fn button(&self) -> usize {
+        if (0..self.players).filter(|&p| self.dealt_in(p)).count() == 2 {
+            self.small_blind()
         } else {
-            big_blind - 1
-        };
-        self.chips.bet_chips(big_blind, 2);
-        self.chips.bet_chips(small_blind, 1);
+            self.seat_before(self.small_blind())
+        }
+    }
+
+    /// The hole cards of a seat dealt in.
+    pub(crate) fn hole_cards(&self, player: usize) -> (Card, Card) {
+        self.hands[player].expect("only the seats dealt in have hole cards")
+    }
+
+    fn bet_blinds(&mut self) {
+        for (player, blind) in [
+            (self.small_blind(), SMALL_BLIND_CHIPS),
+            (self.big_blind, BIG_BLIND_CHIPS),
+        ] {
+            let amount = blind.min(self.chips.player_chips[player].stack);
+            self.apply(GameEvent::BlindPosted { player, amount });
+        }
     }
 
     fn get_hand(&self, player: usize) -> HandVisibility {
         if self.turn.active_players[player] {
-            HandVisibility::Visible(self.hands[player].0, self.hands[player].1)
+            let (first, second) = self.hole_cards(player);
+            HandVisibility::Visible(first, second)
         } else {
             HandVisibility::Folded
         }
     }
 
-    pub fn play_action(&mut self, action: PokerAction) -> Result<TurnResult, RaiseByTooMuch> {
-        match action {
-            PokerAction::CallOrCheck => self.chips.call(self.turn.current_player),
-            PokerAction::Fold => self.turn.fold_current_player(),
+    /// Plays the current player's action. Outside the crate, hands are played through
+    /// `phase`.
+    pub(crate) fn play_action(&mut self, action: PokerAction) -> Result<TurnResult, IllegalRaise> {
+        let player = self.turn.current_player;
+        let street = self.street();
+        let event = match action {
+            PokerAction::CallOrCheck => {
+                let amount = self.chips.call_amount(player);
+                if amount == 0 {
+                    GameEvent::Checked { player, street }
+                } else {
+                    GameEvent::Called {
+                        player,
+                        street,
+                        amount,
+                    }
+                }
+            }
+            PokerAction::Fold => GameEvent::Folded { player, street },
             PokerAction::Raise(amount) => {
-                if amount < 1 || amount > 99 {
-                    return Err(RaiseByTooMuch());
+                let all_in = amount == self.chips.player_chips[player].stack;
+                if amount > self.max_raise() {
+                    return Err(IllegalRaise::TooBig);
+                }
+                if amount == 0 || !all_in && self.min_raise().is_none_or(|min| amount < min) {
+                    return Err(IllegalRaise::TooSmall);
+                }
+                GameEvent::Raised {
+                    player,
+                    street,
+                    amount,
                 }
-                self.chips.bet_chips(self.turn.current_player, amount);
-                self.turn.reset_action_counter();
             }
+        };
+        let rounds = self.turn.rounds;
+        self.apply(event);
+        for round in rounds + 1..=self.turn.rounds {
+            self.draw_board(round);
         }
 
-        self.advance_player();
-
         Ok(self.get_turn_result())
     }
 
+    /// Plays the action like `play_action`, and also returns the events it caused: the
+    /// action itself, any street dealt after it and, when it ends the hand, the hands shown
+    /// and the pots awarded.
+    pub(crate) fn play_action_with_events(
+        &mut self,
+        action: PokerAction,
+    ) -> Result<(TurnResult, Vec<GameEvent>), IllegalRaise> {
+        let seen = self.events.len();
+        let result = self.play_action(action)?;
+        Ok((result, self.events[seen..].to_vec()))
+    }
+
+    pub fn history(&self) -> &HandHistory {
+        &self.history
+    }
+
+    pub fn events(&self) -> &[GameEvent] {
+        &self.events
+    }
+
+    /// What the current player has to put in to call, which their stack may not cover.
+    pub fn expected_call(&self) -> u32 {
+        self.chips.expected_call(self.turn.current_player)
+    }
+
+    /// The smallest `Raise` the current player can make short of going all in: the call
+    /// plus the biggest raise on this street, at least a big blind. `None` if their stack
+    /// doesn't cover it.
+    pub fn min_raise(&self) -> Option<u32> {
+        let min = self.chips.min_raise(self.turn.current_player);
+        (min <= self.max_raise()).then_some(min)
+    }
+
+    /// The biggest `Raise` the current player can make: their stack, up to `MAX_RAISE`.
+    pub fn max_raise(&self) -> u32 {
+        self.chips.player_chips[self.turn.current_player]
+            .stack
+            .min(MAX_RAISE)
+    }
+
+    /// The amount to `Raise` by for a typical sizing, capped by the current player's stack and
+    /// the largest allowed raise. Pot sizes count the pot after the current player called.
+    pub fn bet_size(&self, size: BetSize) -> u32 {
+        let to_call = self.expected_call();
+        let pot = self.chips.total_pot() + to_call;
+        let amount = match size {
+            BetSize::MinRaise => self.chips.min_raise(self.turn.current_player),
+            BetSize::HalfPot => to_call + pot / 2,
+            BetSize::Pot => to_call + pot,
+            BetSize::AllIn => self.max_raise(),
+        };
+        amount.min(self.max_raise())
+    }
+
+    /// The hand as it was before the last action.
+    pub fn undo_last_action(&self) -> Option<HandState> {
+        self.rewind(1)
+    }
+
+    /// How many actions `rewind` can take back.
+    pub fn checkpoints(&self) -> usize {
+        self.checkpoints.len()
+    }
+
+    /// The hand as it was `steps` actions ago, rebuilt from the events up to then. Only the
+    /// last `CHECKPOINTS` actions can be taken back. The observers hear what is played from
+    /// there, but not the rebuilding.
+    pub fn rewind(&self, steps: usize) -> Option<HandState> {
+        let kept = self.checkpoints.len().checked_sub(steps.max(1))?;
+        let mut hs = Self::from_events(
+            self.big_blind,
+            self.history.stacks.clone(),
+            Deck::init(self.history.deck.clone()),
+            &self.events[..self.checkpoints[kept]],
+        );
+        hs.observers = self.observers.clone();
+        Some(hs)
+    }
+
+    /// Tells `observer` every event of this hand from now on.
+    pub fn subscribe(&mut self, observer: Observer) {
+        self.observers.push(observer);
+    }
+
+    pub fn current_player(&self) -> usize {
+        self.turn.current_player
+    }
+
+    pub fn street(&self) -> Street {
+        Street::from_board_size(self.board.len())
+    }
+
     fn get_turn_result(&mut self) -> TurnResult {
-        if let Some(w) = self.try_get_winner() {
-            self.chips.win_pot(w);
-            WonHand(w)
+        if self.is_over() {
+            if self.is_showdown() {
+                let shown: Vec<_> = self
+                    .active_players()
+                    .map(|player| GameEvent::HandShown {
+                        player,
+                        hand: self.best_hand_for_user(player),
+                    })
+                    .collect();
+                shown.into_iter().for_each(|event| self.apply(event));
+            }
+            let winner = self.award_pots();
+            for player in 0..self.players {
+                if self.dealt_in(player) && self.chips.player_chips[player].stack == 0 {
+                    self.apply(GameEvent::PlayerEliminated { player });
+                }
+            }
+            WonHand(winner)
         } else {
             NextPlayer(self.turn.current_player)
         }
     }
 
-    fn try_get_winner(&mut self) -> Option<usize> {
-        self.turn
-            .try_get_last_player()
-            .or_else(|| self.try_get_hand_winner())
+    /// Whether everyone but one player folded or the river's betting is done.
+    fn is_over(&self) -> bool {
+        self.turn.all_but_one_folded() || self.is_showdown()
     }
 
-    fn draw_board(&mut self) {
-        if self.turn.rounds == 1 {
-            self.board.extend(self.deck.draw_multiple(3));
-        } else if let 2 | 3 = self.turn.rounds {
-            self.board.push(self.deck.draw());
+    /// Gives back the chips nobody called, then splits each pot between the best hands of
+    /// the players who can win it. Chips that don't split evenly go to the winners sitting
+    /// first after the button. Returns who won the main pot.
+    fn award_pots(&mut self) -> usize {
+        let mut chips = self.chips.clone();
+        chips.move_chips_to_pot();
+        let (top, &most) = chips
+            .contributed
+            .iter()
+            .enumerate()
+            .max_by_key(|&(_, c)| c)
+            .unwrap();
+        let called = (0..self.players)
+            .filter(|&p| p != top)
+            .map(|p| chips.contributed[p])
+            .max()
+            .unwrap_or(0);
+        if most > called {
+            self.apply(GameEvent::BetReturned {
+                player: top,
+                amount: most - called,
+            });
         }
-    }
 
-    fn try_get_hand_winner(&self) -> Option<usize> {
-        if self.turn.rounds > 3 {
-            let win = self.get_winning_player();
-            return Some(win);
+        let showdown = self.is_showdown();
+        let mut main_winner = None;
+        for pot in self.chips.pots(&self.turn.active_players, &self.folds()) {
+            let best = |p: usize| showdown.then(|| self.best_hand_for_user(p));
+            let best_hand = pot.eligible.iter().map(|&p| best(p)).max().flatten();
+            let winners: Vec<usize> = (1..=self.players)
+                .map(|i| (self.button() + i) % self.players)
+                .filter(|&p| pot.eligible.contains(&p) && best(p) == best_hand)
+                .collect();
+            let share = pot.amount / winners.len() as u32;
+            let odd_chips = pot.amount as usize % winners.len();
+            for (i, &player) in winners.iter().enumerate() {
+                let amount = share + u32::from(i < odd_chips);
+                if amount > 0 {
+                    self.apply(GameEvent::PotAwarded {
+                        player,
+                        amount,
+                        showdown,
+                    });
+                }
+            }
+            main_winner.get_or_insert(winners[0]);
         }
-        None
+        main_winner.expect("a hand always has a pot")
     }
 
+    fn draw_board(&mut self, round: usize) {
+        let (street, cards) = match round {
+            1 => (Street::Flop, self.deck.peek(3)),
+            2 => (Street::Turn, self.deck.peek(1)),
+            3 => (Street::River, self.deck.peek(1)),
+            _ => return,
+        };
+        self.apply(GameEvent::StreetDealt { street, cards });
+    }
+
+    /// Moves the turn on, passing over the players who can't act until the hand is over.
     fn advance_player(&mut self) {
+        self.next_turn();
+        while !self.is_over() && !self.can_act(self.turn.current_player) {
+            self.next_turn();
+        }
+    }
+
+    /// Passes over the players who can't act right after the blinds, short of ending the
+    /// first round: someone has to close it, if only by checking.
+    fn pass_blind_turns(&mut self) {
+        while !self.can_act(self.turn.current_player)
+            && self.turn.turns_since_action + 1 < self.players
+        {
+            self.next_turn();
+        }
+    }
+
+    fn next_turn(&mut self) {
         if self.turn.advance_player() {
-            self.draw_board();
             self.chips.move_chips_to_pot();
         }
     }
 
-    fn get_winning_player(&self) -> usize {
-        (0..self.players)
-            .max_by_key(|p| self.best_hand_for_user(*p))
-            .unwrap()
+    /// Whether `player` still has a decision to make: chips left, and a bet to call or
+    /// someone else with chips to bet against.
+    fn can_act(&self, player: usize) -> bool {
+        let has_chips = |p: usize| self.chips.player_chips[p].stack > 0;
+        has_chips(player)
+            && (self.chips.expected_call(player) > 0
+                || self.active_players().any(|p| p != player && has_chips(p)))
+    }
+
+    /// The players who folded, in the order they did.
+    fn folds(&self) -> Vec<usize> {
+        self.events
+            .iter()
+            .filter_map(|e| match *e {
+                GameEvent::Folded { player, .. } => Some(player),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// The players who haven't folded, by seat.
+    pub fn active_players(&self) -> impl Iterator<Item = usize> + '_ {
+        self.turn.active_players()
+    }
+
+    /// The players who haven't folded, in the order they act on each street.
+    pub fn players_in_action_order(&self) -> impl Iterator<Item = usize> + '_ {
+        self.turn.in_action_order()
+    }
+
+    /// The players still to act before the betting on this street is over, the current
+    /// player first. A raise gives everyone else another turn.
+    pub fn players_yet_to_act(&self) -> impl Iterator<Item = usize> + '_ {
+        self.turn.yet_to_act()
     }
 
     fn best_hand_for_user(&self, player: usize) -> Hand {
+        let (first, second) = self.hole_cards(player);
         best_hand_from_cards([
-            self.hands[player].0,
-            self.hands[player].1,
+            first,
+            second,
             self.board[0],
             self.board[1],
             self.board[2],
@@ -180,11 +691,70 @@ impl HandState {
             hands: (0..self.players).map(|i| self.get_hand(i)).collect(),
             chips: self.chips.player_chips.clone(),
             pot: self.chips.pot,
+            pots: self.chips.pots(&self.turn.active_players, &self.folds()),
             current_player: self.turn.current_player,
             expected_call: self.chips.expected_call(self.turn.current_player),
             players: self.players,
+            button: self.button(),
+            small_blind: self.small_blind(),
+            big_blind: self.big_blind,
         }
     }
+
+    pub fn public_snapshot(&self) -> HandSnapshot {
+        let mut snapshot = self.spectator_snapshot();
+        if !self.is_showdown() {
+            for hand in &mut snapshot.hands {
+                if let HandVisibility::Visible(..) = hand {
+                    *hand = HandVisibility::Hidden;
+                }
+            }
+        }
+        snapshot
+    }
+
+    /// The main pot and the side pots, split from the chips of the finished streets, and
+    /// what each player has put in. The bets of the current street join the pots once
+    /// everyone has acted on it.
+    pub fn pots(&self) -> Pots {
+        let mut pots = self.chips.pots(&self.turn.active_players, &self.folds());
+        let main = pots.remove(0);
+        Pots {
+            main,
+            side: pots,
+            committed: (0..self.players)
+                .map(|p| self.chips.contributed[p] + self.chips.player_chips[p].bet)
+                .collect(),
+        }
+    }
+
+    /// The table as `player` sees it: their own cards and what everyone may see, other
+    /// players' cards hidden until the showdown.
+    pub fn player_snapshot(&self, player: usize) -> HandSnapshot {
+        let mut snapshot = self.public_snapshot();
+        snapshot.hands[player] = self.get_hand(player);
+        snapshot
+    }
+
+    pub fn is_showdown(&self) -> bool {
+        self.turn.rounds > 3
+    }
+
+    /// The player who won the hand at showdown and the five cards they won with.
+    pub fn showdown_winner(&self) -> Option<(usize, Hand)> {
+        let winner = self.events.iter().find_map(|e| match e {
+            GameEvent::PotAwarded {
+                player,
+                showdown: true,
+                ..
+            } => Some(*player),
+            _ => None,
+        })?;
+        self.events.iter().find_map(|e| match e {
+            GameEvent::HandShown { player, hand } if *player == winner => Some((winner, *hand)),
+            _ => None,
+        })
+    }
 }
 
 pub struct HandSnapshot {
@@ -192,14 +762,19 @@ pub struct HandSnapshot {
     pub hands: Vec<HandVisibility>,
     pub chips: Vec<PlayerChips>,
     pub pot: u32,
+    pub pots: Vec<Pot>,
     pub current_player: usize,
     pub expected_call: u32,
     pub players: usize,
+    pub button: usize,
+    pub small_blind: usize,
+    pub big_blind: usize,
 }
 
 #[derive(Debug, PartialEq, Eq)]
 pub enum HandVisibility {
     Visible(Card, Card),
+    Hidden,
     Folded,
 }
 
@@ -227,24 +802,40 @@ fn best_hand_from_cards(cards: [Card; 7]) -> Hand {
     collector.into_iter().max().unwrap()
 }
 
-fn draw_starting_hands(players: usize, deck: Deck) -> (Deck, Vec<(Card, Card)>) {
-    let mut deck = deck;
-    let mut hands: Vec<(Card, Card)> = vec![];
-    for _ in 0..players {
-        let card1 = deck.draw();
-        let card2 = deck.draw();
-        hands.push((card1, card2));
-    }
-    (deck, hands)
-}
-
-#[derive(Clone)]
+#[derive(Clone, Serialize, Deserialize)]
 struct ChipsState {
     player_chips: Vec<PlayerChips>,
     pot: u32,
+    contributed: Vec<u32>,
+    /// The biggest raise over the highest bet on this street so far.
+    #[serde(default)]
+    last_raise: u32,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Pot {
+    pub amount: u32,
+    pub eligible: Vec<usize>,
+}
+
+/// The chips in the middle of the table, as `HandState::pots` splits them.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Pots {
+    pub main: Pot,
+    /// A pot for every all-in amount, smallest first, each with the players who covered it.
+    pub side: Vec<Pot>,
+    /// What each player has put in this hand, the bets in front of them included.
+    pub committed: Vec<u32>,
+}
+
+impl Pots {
+    /// Every chip put in this hand.
+    pub fn total_committed(&self) -> u32 {
+        self.committed.iter().sum()
+    }
 }
 
-#[derive(Clone)]
+#[derive(Clone, Serialize, Deserialize)]
 pub struct PlayerChips {
     pub stack: u32,
     pub bet: u32,
@@ -258,6 +849,8 @@ impl ChipsState {
                 .map(|&c| PlayerChips { stack: c, bet: 0 })
                 .collect(),
             pot: 0,
+            contributed: vec![0; chips.len()],
+            last_raise: 0,
         }
     }
 
@@ -270,14 +863,45 @@ impl ChipsState {
         self.player_chips[player].bet += amount;
     }
 
-    fn win_pot(&mut self, player: usize) {
+    /// Bets `amount` as a raise. A raise short of the biggest one so far, only possible all
+    /// in, doesn't make the next one smaller.
+    fn raise_chips(&mut self, player: usize, amount: u32) {
+        let highest = self.highest_bet();
+        self.bet_chips(player, amount);
+        self.last_raise = self.last_raise.max(self.highest_bet() - highest);
+    }
+
+    /// The fewest chips a raise by `player` puts in short of going all in: the call plus
+    /// the biggest raise on this street, and at least a big blind.
+    fn min_raise(&self, player: usize) -> u32 {
+        self.expected_call(player) + self.last_raise.max(BIG_BLIND_CHIPS)
+    }
+
+    /// The pot with the bets still in front of the players.
+    fn total_pot(&self) -> u32 {
+        self.pot + self.player_chips.iter().map(|pc| pc.bet).sum::<u32>()
+    }
+
+    fn return_bet(&mut self, player: usize, amount: u32) {
         self.move_chips_to_pot();
-        self.player_chips[player].stack += self.pot;
-        self.pot = 0;
+        self.contributed[player] -= amount;
+        self.player_chips[player].stack += amount;
+        self.pot -= amount;
     }
 
-    fn call(&mut self, player: usize) {
-        self.bet_chips(player, self.expected_call(player))
+    /// Moves `amount` from the pot to the player's stack, or what is left of the pot if
+    /// that is less.
+    fn award_pot(&mut self, player: usize, amount: u32) {
+        self.move_chips_to_pot();
+        let amount = amount.min(self.pot);
+        self.player_chips[player].stack += amount;
+        self.pot -= amount;
+    }
+
+    /// As much of the call as the player's stack covers.
+    fn call_amount(&self, player: usize) -> u32 {
+        self.expected_call(player)
+            .min(self.player_chips[player].stack)
     }
 
     fn expected_call(&self, player: usize) -> u32 {
@@ -285,10 +909,55 @@ impl ChipsState {
     }
 
     fn move_chips_to_pot(&mut self) {
-        for pc in &mut self.player_chips {
+        for (pc, contributed) in self.player_chips.iter_mut().zip(&mut self.contributed) {
             self.pot += pc.bet;
+            *contributed += pc.bet;
             pc.bet = 0;
         }
+        self.last_raise = 0;
+    }
+
+    /// Splits the chips in the pot into a main pot and a side pot for every all-in amount,
+    /// each with the players still able to win it. A pot everyone who put chips in has
+    /// folded from goes to the last of them to fold, `folds` being the players in the order
+    /// they folded.
+    fn pots(&self, active: &[bool], folds: &[usize]) -> Vec<Pot> {
+        let players = self.contributed.len();
+        let mut caps: Vec<u32> = (0..players)
+            .filter(|&p| active[p] && self.player_chips[p].stack == 0)
+            .map(|p| self.contributed[p])
+            .collect();
+        caps.sort();
+        caps.dedup();
+        caps.push(u32::MAX);
+
+        let mut pots = vec![];
+        let mut floor = 0;
+        for cap in caps {
+            let amount = self
+                .contributed
+                .iter()
+                .map(|&c| c.min(cap) - c.min(floor))
+                .sum();
+            let mut eligible: Vec<usize> = (0..players)
+                .filter(|&p| active[p] && self.contributed[p] > floor)
+                .collect();
+            if eligible.is_empty() {
+                eligible.extend(folds.iter().rev().find(|&&p| self.contributed[p] > floor));
+            }
+            if amount > 0 {
+                pots.push(Pot { amount, eligible });
+            }
+            floor = cap;
+        }
+
+        if pots.is_empty() {
+            pots.push(Pot {
+                amount: 0,
+                eligible: (0..players).filter(|&p| active[p]).collect(),
+            });
+        }
+        pots
     }
 
     fn highest_bet(&self) -> u32 {
@@ -296,7 +965,7 @@ impl ChipsState {
     }
 }
 
-#[derive(Clone)]
+#[derive(Clone, Serialize, Deserialize)]
 struct TurnState {
     current_player: usize,
     first_player: usize,
@@ -307,21 +976,48 @@ struct TurnState {
 }
 
 impl TurnState {
-    fn init(players: usize, first_player: usize) -> Self {
-        Self {
+    /// The first street's turns, `seated` telling the players in the hand from those
+    /// sitting it out, who are passed over like folded players.
+    fn init(seated: Vec<bool>, first_player: usize) -> Self {
+        let mut slf = Self {
             current_player: first_player,
             first_player,
-            players,
-            active_players: vec![true; players],
+            players: seated.len(),
+            active_players: seated,
             turns_since_action: 0,
             rounds: 0,
+        };
+        if !slf.active_players[first_player] {
+            slf.advance_player();
         }
+        slf
     }
 
     fn reset_action_counter(&mut self) {
         self.turns_since_action = 0;
     }
 
+    fn active_players(&self) -> impl Iterator<Item = usize> + '_ {
+        (0..self.players).filter(|&p| self.active_players[p])
+    }
+
+    /// The seats from `from` on around the table, `count` of them, leaving out folded players.
+    fn seats_from(&self, from: usize, count: usize) -> impl Iterator<Item = usize> + '_ {
+        (0..count)
+            .map(move |i| (from + i) % self.players)
+            .filter(|&p| self.active_players[p])
+    }
+
+    fn in_action_order(&self) -> impl Iterator<Item = usize> + '_ {
+        self.seats_from(self.first_player, self.players)
+    }
+
+    /// Every seat is a turn, folded or not, and the street ends after as many turns
+    /// without a raise as there are seats.
+    fn yet_to_act(&self) -> impl Iterator<Item = usize> + '_ {
+        self.seats_from(self.current_player, self.players - self.turns_since_action)
+    }
+
     fn advance_player(&mut self) -> bool {
         let mut new_round = false;
         self.turns_since_action += 1;
@@ -336,7 +1032,7 @@ impl TurnState {
         }
 
         while !self.active_players[self.current_player] {
-            self.advance_player();
+            new_round |= self.advance_player();
         }
 
         new_round
@@ -346,26 +1042,20 @@ impl TurnState {
         self.turns_since_action == self.players
     }
 
-    fn fold_current_player(&mut self) {
-        self.active_players[self.current_player] = false;
-    }
-
-    fn try_get_last_player(&self) -> Option<usize> {
-        if self.all_but_one_folded() {
-            self.active_players.iter().position(|a| *a)
-        } else {
-            None
-        }
-    }
-
     fn all_but_one_folded(&self) -> bool {
-        self.active_players.iter().filter(|&&a| a).count() == 1
+        self.active_players().count() == 1
     }
 }
 
 #[cfg(test)]
 mod tests {
+    use std::sync::{Arc, Mutex};
+    use std::thread;
+
+    use proptest::prelude::*;
+
     use crate::core_engine::*;
+    use crate::strategies::actions;
 
     use super::*;
     use PokerAction::*;
@@ -376,6 +1066,15 @@ mod tests {
         assert!(GameState::init(1).is_none());
     }
 
+    #[test]
+    fn should_start_with_configured_stack() {
+        assert!(GameState::init_with_stack(2, 1).is_none());
+
+        let gs = GameState::init_with_stack(3, 500).unwrap();
+        assert_eq!(gs.current_chips(0), 500);
+        assert_eq!(gs.current_chips(2), 500);
+    }
+
     #[test]
     fn should_fail_on_raise_out_of_bounds() {
         let (mut hs, _) = GameState::init(2)
@@ -475,7 +1174,7 @@ mod tests {
         sut.when_start_round();
 
         sut.when_player_plays(0, CallOrCheck);
-        sut.when_player_plays(1, Raise(2));
+        sut.when_player_plays(1, Raise(3));
         sut.when_player_plays(2, CallOrCheck);
         sut.when_player_plays(0, CallOrCheck);
 
@@ -547,6 +1246,24 @@ mod tests {
         assert_eq!(snap.expected_call, 2);
     }
 
+    #[test]
+    fn should_move_button_and_blinds_each_hand() {
+        let mut sut = GameTestContainer::init(3);
+        let mut positions = vec![];
+        for _ in 0..3 {
+            sut.when_start_round();
+            let snap = sut.take_snapshot();
+            positions.push((snap.button, snap.small_blind, snap.big_blind));
+            sut.when_play_multi(Fold, 2);
+        }
+        assert_eq!(positions, vec![(0, 1, 2), (1, 2, 0), (2, 0, 1)]);
+
+        let mut sut = GameTestContainer::init(2);
+        sut.when_start_round();
+        let snap = sut.take_snapshot();
+        assert_eq!((snap.button, snap.small_blind, snap.big_blind), (0, 0, 1));
+    }
+
     #[test]
     fn should_show_folded_hands_in_snapshot() {
         const DECK: &[&str; 4] = &["H2 D7", "H13 D13", "S4 D2", "C8 C4 H3 S12 S10"];
@@ -589,6 +1306,23 @@ mod tests {
         assert_player_wins_given_cards(3, &["H2 D7", "D5 C11", "H5 H8", "C7 H4 C10 H11 H12"], 2);
     }
 
+    #[test]
+    fn should_report_winning_five_cards_at_showdown() {
+        const P1_WINS_HAND: &[&str; 3] = &["H2 D7", "H13 D13", "C8 C4 H3 S12 S10"];
+        let (mut hs, _) = GameState::init(2)
+            .unwrap()
+            .start_play_hand(deck_from_strings(P1_WINS_HAND));
+        assert!(hs.showdown_winner().is_none());
+
+        while let NextPlayer(_) = hs.play_action(CallOrCheck).unwrap() {}
+
+        let (winner, hand) = hs.showdown_winner().unwrap();
+        assert_eq!(winner, 1);
+        for card in to_cards(&"H13 D13 S12 S10 C8") {
+            assert!(hand.0.contains(&card), "{card} missing from {hand:?}");
+        }
+    }
+
     #[test]
     fn should_compare_hands_multiple_rounds() {
         const P0_WINS_HAND: &[&str; 3] = &["H13 D13", "H2 D7", "C8 C4 H3 S12 S10"];
@@ -622,6 +1356,228 @@ mod tests {
         sut.then_score_is(&[95, 107, 98]);
     }
 
+    #[test]
+    fn should_not_let_folded_player_win_showdown() {
+        const P0_HAS_BEST_HAND: &[&str; 4] = &["H13 D13", "H2 D7", "S4 D2", "C8 C4 H3 S12 S10"];
+        let mut sut = GameTestContainer::init(3);
+        sut.when_start_round_with_deck(deck_from_strings(P0_HAS_BEST_HAND));
+
+        sut.when_player_plays(0, Fold);
+        sut.when_call_until_player_wins(2);
+    }
+
+    #[test]
+    fn should_split_pot_at_all_in_amounts() {
+        let mut chips = ChipsState::init(vec![10, 50, 50, 50]);
+        chips.bet_chips(0, 10);
+        chips.bet_chips(1, 30);
+        chips.bet_chips(2, 30);
+        chips.bet_chips(3, 5);
+        chips.move_chips_to_pot();
+
+        assert_eq!(
+            chips.pots(&[true, true, true, false], &[3]),
+            vec![
+                Pot {
+                    amount: 35,
+                    eligible: vec![0, 1, 2]
+                },
+                Pot {
+                    amount: 40,
+                    eligible: vec![1, 2]
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn should_pass_over_a_player_all_in() {
+        let gs = GameState::builder()
+            .stacks(vec![20, 100, 100])
+            .build()
+            .unwrap();
+        let (mut hs, _) = gs.start_play_hand(Deck::ordered_deck());
+
+        assert!(matches!(hs.play_action(Raise(20)), Ok(NextPlayer(1))));
+        hs.play_action(CallOrCheck).unwrap();
+        assert!(matches!(hs.play_action(CallOrCheck), Ok(NextPlayer(1))));
+        assert_eq!(hs.street(), Street::Flop);
+        hs.play_action(CallOrCheck).unwrap();
+        assert!(matches!(hs.play_action(CallOrCheck), Ok(NextPlayer(1))));
+        assert_eq!(hs.street(), Street::Turn);
+    }
+
+    #[test]
+    fn should_leave_an_eliminated_player_out_of_the_next_hands() {
+        let gs = GameState::builder()
+            .stacks(vec![100, 100, 2])
+            .build()
+            .unwrap();
+        let deck = deck_from_strings(&["H14 D14", "C13 D13", "C2 D7", "H12 S9 C4 H5 D11"]);
+        let (mut hs, _) = gs.start_play_hand(deck);
+        while let Ok(NextPlayer(_)) = hs.play_action(CallOrCheck) {}
+        let eliminated = |hs: &HandState| {
+            hs.events()
+                .iter()
+                .filter(|e| matches!(e, GameEvent::PlayerEliminated { .. }))
+                .count()
+        };
+        assert_eq!(eliminated(&hs), 1);
+        assert!(hs
+            .events()
+            .contains(&GameEvent::PlayerEliminated { player: 2 }));
+
+        let gs = gs.apply_played_hand(hs);
+        let (mut hs, first) = gs.start_play_hand(Deck::ordered_deck());
+        assert_ne!(first, 2);
+        while let Ok(NextPlayer(next)) = hs.play_action(CallOrCheck) {
+            assert_ne!(next, 2);
+        }
+        assert_eq!(eliminated(&hs), 0);
+        assert!(!hs.events().iter().any(|e| matches!(
+            e,
+            GameEvent::HoleCardsDealt { player: 2, .. } | GameEvent::BlindPosted { player: 2, .. }
+        )));
+        assert_eq!(gs.apply_played_hand(hs).current_chips(2), 0);
+    }
+
+    #[test]
+    fn should_run_out_the_board_once_nobody_can_bet() {
+        let gs = GameState::builder().stacks(vec![50, 100]).build().unwrap();
+        let (mut hs, _) = gs.start_play_hand(Deck::ordered_deck());
+
+        assert!(matches!(hs.play_action(Raise(49)), Ok(NextPlayer(1))));
+        assert!(matches!(hs.play_action(CallOrCheck), Ok(WonHand(_))));
+        assert_eq!(hs.board.len(), 5);
+    }
+
+    #[test]
+    fn should_give_a_side_pot_everyone_folded_from_to_the_last_to_fold() {
+        let mut chips = ChipsState::init(vec![10, 50, 50, 50]);
+        chips.bet_chips(0, 10);
+        chips.bet_chips(1, 30);
+        chips.bet_chips(2, 30);
+        chips.bet_chips(3, 5);
+        chips.move_chips_to_pot();
+
+        let pots = chips.pots(&[true, false, false, false], &[3, 2, 1]);
+        assert_eq!(pots[0].eligible, vec![0]);
+        assert_eq!(
+            pots[1],
+            Pot {
+                amount: 40,
+                eligible: vec![1]
+            }
+        );
+    }
+
+    #[test]
+    fn should_tell_the_main_pot_from_the_side_pots() {
+        let (mut hs, _) = GameState::builder()
+            .stacks(vec![10, 50, 50])
+            .build()
+            .unwrap()
+            .start_play_hand(Deck::ordered_deck());
+        hs.play_action(Raise(10)).unwrap();
+        hs.play_action(CallOrCheck).unwrap();
+        hs.play_action(CallOrCheck).unwrap();
+        let main = Pot {
+            amount: 30,
+            eligible: vec![0, 1, 2],
+        };
+        assert_eq!(hs.pots().main, main);
+
+        hs.play_action(Raise(20)).unwrap();
+        let pots = hs.pots();
+        assert!(pots.side.is_empty());
+        assert_eq!(pots.committed, vec![10, 30, 10]);
+        assert_eq!(pots.total_committed(), 50);
+
+        while hs.street() == Street::Flop {
+            hs.play_action(CallOrCheck).unwrap();
+        }
+        assert_eq!(
+            hs.pots(),
+            Pots {
+                main,
+                side: vec![Pot {
+                    amount: 40,
+                    eligible: vec![1, 2]
+                }],
+                committed: vec![10, 30, 30],
+            }
+        );
+    }
+
+    #[test]
+    fn should_pay_each_pot_to_the_best_hand_that_can_win_it() {
+        let gs = GameState::builder()
+            .stacks(vec![10, 50, 80])
+            .build()
+            .unwrap();
+        let deck = deck_from_strings(&["H14 D14", "C2 D7", "C3 D8", "H13 S9 C4 H5 D11"]);
+        // The short stack's aces win the main pot, and the bigger of the others the side pot.
+        let (mut hs, _) = gs.start_play_hand(deck);
+        hs.play_action(Raise(10)).unwrap();
+        hs.play_action(CallOrCheck).unwrap();
+        hs.play_action(CallOrCheck).unwrap();
+        hs.play_action(CallOrCheck).unwrap();
+        hs.play_action(Raise(70)).unwrap();
+        let winner = loop {
+            if let WonHand(winner) = hs.play_action(CallOrCheck).unwrap() {
+                break winner;
+            }
+        };
+
+        assert_eq!(winner, 0);
+        let awarded: Vec<_> = hs
+            .events()
+            .iter()
+            .filter_map(|e| match *e {
+                GameEvent::BetReturned { player, amount } => Some((player, amount, false)),
+                GameEvent::PotAwarded { player, amount, .. } => Some((player, amount, true)),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(awarded, [(2, 30, false), (0, 30, true), (2, 80, true)]);
+        let next = gs.apply_played_hand(hs);
+        assert_eq!(
+            (0..3).map(|p| next.current_chips(p)).collect::<Vec<_>>(),
+            [30, 0, 110]
+        );
+    }
+
+    #[test]
+    fn should_have_single_pot_without_all_ins() {
+        let mut sut = GameTestContainer::init(3);
+        sut.when_start_round();
+        sut.when_play_multi(CallOrCheck, 3);
+
+        assert_eq!(
+            sut.take_snapshot().pots,
+            vec![Pot {
+                amount: 6,
+                eligible: vec![0, 1, 2]
+            }]
+        );
+    }
+
+    #[test]
+    fn should_report_busted_players_as_eliminated() {
+        const P1_WINS_HAND: &[&str; 3] = &["H2 D7", "H13 D13", "C8 C4 H3 S12 S10"];
+        let (mut hs, _) = GameState::init_with_stack(2, 10)
+            .unwrap()
+            .start_play_hand(deck_from_strings(P1_WINS_HAND));
+
+        hs.play_action(Raise(9)).unwrap();
+        while let NextPlayer(_) = hs.play_action(CallOrCheck).unwrap() {}
+
+        assert_eq!(
+            hs.events().last(),
+            Some(&GameEvent::PlayerEliminated { player: 0 })
+        );
+    }
+
     #[test]
     fn should_advance_when_end_on_fold() {
         let mut sut = GameTestContainer::init(3);
@@ -634,6 +1590,432 @@ mod tests {
         assert_eq!(sut.take_snapshot().board.len(), 3);
     }
 
+    #[test]
+    fn should_hide_hole_cards_in_public_snapshot_until_showdown() {
+        const DECK: &[&str; 4] = &["H2 D7", "H13 D13", "S4 D2", "C8 C4 H3 S12 S10"];
+        let mut sut = GameTestContainer::init(3);
+        sut.when_start_round_with_deck(deck_from_strings(DECK));
+
+        sut.when_player_plays(0, Fold);
+        let hs = sut.hs.as_mut().unwrap();
+        assert_eq!(
+            hs.public_snapshot().hands,
+            vec![
+                HandVisibility::Folded,
+                HandVisibility::Hidden,
+                HandVisibility::Hidden
+            ]
+        );
+
+        while let NextPlayer(_) = hs.play_action(CallOrCheck).unwrap() {}
+
+        assert_eq!(hs.public_snapshot().hands, hs.spectator_snapshot().hands);
+    }
+
+    #[test]
+    fn should_show_a_player_only_their_own_hole_cards() {
+        const DECK: &[&str; 4] = &["H2 D7", "H13 D13", "S4 D2", "C8 C4 H3 S12 S10"];
+        let mut sut = GameTestContainer::init(3);
+        sut.when_start_round_with_deck(deck_from_strings(DECK));
+
+        sut.when_player_plays(0, Fold);
+        let hs = sut.hs.as_mut().unwrap();
+        assert_eq!(
+            hs.player_snapshot(1).hands,
+            vec![
+                HandVisibility::Folded,
+                HandVisibility::Visible(
+                    Card::try_from("H13").unwrap(),
+                    Card::try_from("D13").unwrap()
+                ),
+                HandVisibility::Hidden
+            ]
+        );
+        assert_eq!(hs.player_snapshot(0).hands[1], HandVisibility::Hidden);
+
+        while let NextPlayer(_) = hs.play_action(CallOrCheck).unwrap() {}
+
+        assert_eq!(hs.player_snapshot(2).hands, hs.spectator_snapshot().hands);
+    }
+
+    #[test]
+    fn should_size_bets_from_pot_and_stack() {
+        let mut sut = GameTestContainer::init(3);
+        sut.when_start_round();
+        let hs = sut.hs.as_ref().unwrap();
+        assert_eq!(hs.bet_size(BetSize::MinRaise), 4);
+        assert_eq!(hs.bet_size(BetSize::HalfPot), 4);
+        assert_eq!(hs.bet_size(BetSize::Pot), 7);
+        assert_eq!(hs.bet_size(BetSize::AllIn), MAX_RAISE);
+
+        let (hs, _) = GameState::init_with_stack(2, 20)
+            .unwrap()
+            .start_play_hand(Deck::ordered_deck());
+        assert_eq!(hs.bet_size(BetSize::AllIn), 19);
+        assert_eq!(hs.bet_size(BetSize::Pot), 5);
+    }
+
+    #[test]
+    fn should_tell_the_call_and_the_raises_allowed() {
+        let (mut hs, _) = GameState::builder()
+            .stacks(vec![20, 3])
+            .build()
+            .unwrap()
+            .start_play_hand(Deck::ordered_deck());
+        assert_eq!(hs.expected_call(), 1);
+        assert_eq!(hs.min_raise(), Some(3));
+        assert_eq!(hs.max_raise(), 19);
+        assert!(hs.play_action(Raise(hs.max_raise() + 1)).is_err());
+
+        hs.play_action(Raise(hs.max_raise())).unwrap();
+        assert_eq!(hs.expected_call(), 18);
+        assert_eq!(hs.max_raise(), 1);
+        assert_eq!(hs.min_raise(), None);
+    }
+
+    #[test]
+    fn should_raise_at_least_by_the_last_raise() {
+        let (mut hs, _) = GameState::init(2)
+            .unwrap()
+            .start_play_hand(Deck::ordered_deck());
+        assert_eq!(hs.min_raise(), Some(3));
+
+        // Raising to 6 is a raise of 4 over the big blind, so the next one is at least 4.
+        hs.play_action(Raise(5)).unwrap();
+        assert_eq!(hs.expected_call(), 4);
+        assert_eq!(hs.min_raise(), Some(8));
+        assert_eq!(hs.bet_size(BetSize::MinRaise), 8);
+        assert_eq!(hs.play_action(Raise(7)).err(), Some(IllegalRaise::TooSmall));
+
+        hs.play_action(Raise(8)).unwrap();
+        assert_eq!(hs.min_raise(), Some(8));
+        hs.play_action(CallOrCheck).unwrap();
+        assert_eq!(hs.min_raise(), Some(2));
+    }
+
+    #[test]
+    fn should_refuse_raises_smaller_than_the_minimum_unless_all_in() {
+        let (mut hs, _) = GameState::builder()
+            .stacks(vec![20, 3])
+            .build()
+            .unwrap()
+            .start_play_hand(Deck::ordered_deck());
+        assert_eq!(hs.play_action(Raise(1)).err(), Some(IllegalRaise::TooSmall));
+        assert_eq!(hs.play_action(Raise(0)).err(), Some(IllegalRaise::TooSmall));
+        assert_eq!(hs.play_action(Raise(20)).err(), Some(IllegalRaise::TooBig));
+        assert_eq!(hs.current_player(), 0);
+
+        hs.play_action(Raise(10)).unwrap();
+        assert_eq!(hs.play_action(Raise(0)).err(), Some(IllegalRaise::TooSmall));
+        // Short of a call, going all in is the only raise left.
+        assert!(hs.play_action(Raise(1)).is_ok());
+    }
+
+    #[test]
+    fn should_list_players_by_seat_and_by_turn() {
+        let (mut hs, first) = GameState::init(4)
+            .unwrap()
+            .start_play_hand(Deck::ordered_deck());
+        assert_eq!(first, 0);
+        assert_eq!(
+            hs.players_yet_to_act().collect::<Vec<_>>(),
+            vec![0, 1, 2, 3]
+        );
+
+        hs.play_action(Fold).unwrap();
+        hs.play_action(Raise(6)).unwrap();
+        assert_eq!(hs.active_players().collect::<Vec<_>>(), vec![1, 2, 3]);
+        assert_eq!(hs.players_yet_to_act().collect::<Vec<_>>(), vec![2, 3]);
+        assert_eq!(
+            hs.players_in_action_order().collect::<Vec<_>>(),
+            vec![1, 2, 3]
+        );
+
+        hs.play_action(CallOrCheck).unwrap();
+        hs.play_action(CallOrCheck).unwrap();
+        assert_eq!(hs.street(), Street::Flop);
+        assert_eq!(hs.players_yet_to_act().collect::<Vec<_>>(), vec![1, 2, 3]);
+    }
+
+    #[test]
+    fn should_deal_next_street_when_round_ends_on_a_folded_seat() {
+        let mut sut = GameTestContainer::init(3);
+        sut.when_start_round();
+        sut.when_player_plays(0, CallOrCheck);
+        sut.when_player_plays(1, Fold);
+        sut.when_player_plays(2, Raise(4));
+        sut.when_player_plays(0, CallOrCheck);
+
+        assert_eq!(sut.take_snapshot().board.len(), 3);
+        sut.when_call_until_player_wins(2);
+    }
+
+    #[test]
+    fn should_return_the_events_an_action_caused() {
+        let (mut hs, _) = GameState::init(2)
+            .unwrap()
+            .start_play_hand(Deck::ordered_deck());
+        assert!(hs.play_action_with_events(Raise(1000)).is_err());
+        let (_, events) = hs.play_action_with_events(CallOrCheck).unwrap();
+        assert!(matches!(events[..], [GameEvent::Called { player: 0, .. }]));
+
+        let (result, events) = hs.play_action_with_events(CallOrCheck).unwrap();
+        assert!(matches!(result, TurnResult::NextPlayer(0)));
+        assert!(matches!(
+            events[..],
+            [
+                GameEvent::Checked { player: 1, .. },
+                GameEvent::StreetDealt {
+                    street: Street::Flop,
+                    ..
+                }
+            ]
+        ));
+
+        let (result, events) = hs.play_action_with_events(Fold).unwrap();
+        assert!(matches!(result, TurnResult::WonHand(1)));
+        assert!(matches!(events[0], GameEvent::Folded { player: 0, .. }));
+        assert!(matches!(events.last(), Some(GameEvent::PotAwarded { .. })));
+    }
+
+    struct Recorder(Vec<GameEvent>);
+
+    impl events::EventObserver for Recorder {
+        fn on_event(&mut self, event: &GameEvent) {
+            self.0.push(event.clone());
+        }
+    }
+
+    #[test]
+    fn should_tell_observers_every_event_as_it_happens() {
+        let recorder = Arc::new(Mutex::new(Recorder(vec![])));
+        let mut gs = GameState::init(2).unwrap();
+        gs.subscribe(recorder.clone());
+        let (mut hs, _) = gs.start_play_hand(Deck::ordered_deck());
+        hs.play_action(CallOrCheck).unwrap();
+        assert_eq!(recorder.lock().unwrap().0, hs.events());
+
+        let mut hs = hs.undo_last_action().unwrap();
+        let heard = recorder.lock().unwrap().0.len();
+        hs.play_action(Fold).unwrap();
+        assert_eq!(
+            recorder.lock().unwrap().0[heard..],
+            hs.events()[heard - 1..]
+        );
+
+        let (hs, _) = gs
+            .apply_played_hand(hs)
+            .start_play_hand(Deck::ordered_deck());
+        assert!(recorder.lock().unwrap().0.ends_with(hs.events()));
+    }
+
+    #[test]
+    fn should_play_tables_held_in_arc_mutex_on_other_threads() {
+        let recorder = Arc::new(Mutex::new(Recorder(vec![])));
+        let tables: Vec<_> = (0..4)
+            .map(|_| {
+                let mut gs = GameState::init(3).unwrap();
+                gs.subscribe(recorder.clone());
+                Arc::new(Mutex::new(gs))
+            })
+            .collect();
+
+        thread::scope(|scope| {
+            for table in &tables {
+                scope.spawn(|| {
+                    for _ in 0..5 {
+                        let mut gs = table.lock().unwrap();
+                        let (mut hs, _) = gs.start_play_hand(Deck::shuffled_deck());
+                        // A hand started on one thread can be finished on another.
+                        hs = thread::spawn(move || {
+                            while let Ok(NextPlayer(_)) = hs.play_action(CallOrCheck) {}
+                            hs
+                        })
+                        .join()
+                        .unwrap();
+                        *gs = gs.apply_played_hand(hs);
+                    }
+                });
+            }
+        });
+
+        for table in &tables {
+            let gs = table.lock().unwrap();
+            assert_eq!((0..3).map(|p| gs.current_chips(p)).sum::<u32>(), 300);
+        }
+        let dealt = recorder
+            .lock()
+            .unwrap()
+            .0
+            .iter()
+            .filter(|e| matches!(e, GameEvent::HoleCardsDealt { player: 0, .. }))
+            .count();
+        assert_eq!(dealt, 20);
+    }
+
+    /// Every chip on the table, in the stacks, in front of the players and in the pot.
+    fn chips_on_table(hs: &HandState) -> u32 {
+        let snapshot = hs.spectator_snapshot();
+        snapshot.pot
+            + snapshot
+                .chips
+                .iter()
+                .map(|pc| pc.stack + pc.bet)
+                .sum::<u32>()
+    }
+
+    /// Plays a hand with the actions and checks that no chip is made or lost, that the hand
+    /// ends with someone still in it winning, and that nobody wins more from an opponent
+    /// than they put in against them.
+    fn check_hand(
+        stacks: Vec<u32>,
+        deck: Deck,
+        actions: Vec<PokerAction>,
+    ) -> Result<(), TestCaseError> {
+        let total: u32 = stacks.iter().sum();
+        let gs = GameState::builder().stacks(stacks.clone()).build().unwrap();
+        let (mut hs, _) = gs.start_play_hand(deck);
+
+        // Raises are sized into what is legal, and once the actions run out everyone
+        // calls. Each raise costs a chip, so a hand can't last longer than this.
+        let mut actions = actions.into_iter();
+        let mut played = 0;
+        let winner = loop {
+            prop_assert!(
+                played <= total as usize + 4 * stacks.len(),
+                "the hand never ends"
+            );
+            let action = match actions.next() {
+                Some(Raise(amount)) => match hs.min_raise() {
+                    Some(min) => Raise(amount.clamp(min, hs.max_raise())),
+                    None => CallOrCheck,
+                },
+                Some(action) => action,
+                None => CallOrCheck,
+            };
+            let result = hs.play_action(action);
+            played += 1;
+            prop_assert_eq!(chips_on_table(&hs), total);
+            prop_assert!(hs
+                .spectator_snapshot()
+                .chips
+                .iter()
+                .all(|pc| pc.stack <= total));
+            match result {
+                Ok(WonHand(winner)) => break winner,
+                Ok(NextPlayer(next)) => prop_assert!(hs.chips.player_chips[next].stack > 0),
+                Err(e) => prop_assert!(false, "a legal action was refused: {:?}", e),
+            }
+        };
+
+        prop_assert!(hs.active_players().any(|p| p == winner));
+        let committed = hs.pots().committed;
+        let next = gs.apply_played_hand(hs);
+        for (p, &stack) in stacks.iter().enumerate() {
+            let won = next.current_chips(p).saturating_sub(stack);
+            let covered: u32 = (0..stacks.len())
+                .filter(|&q| q != p)
+                .map(|q| committed[q].min(committed[p]))
+                .sum();
+            prop_assert!(won <= covered, "player {} won {} of {}", p, won, covered);
+        }
+        prop_assert_eq!(
+            (0..stacks.len())
+                .map(|p| next.current_chips(p))
+                .sum::<u32>(),
+            total
+        );
+        Ok(())
+    }
+
+    proptest! {
+        #[test]
+        fn should_keep_every_chip_whatever_is_played(
+            stacks in prop::collection::vec(2..200u32, 2..=6),
+            deck in any::<Deck>(),
+            actions in actions(0, 100),
+        ) {
+            check_hand(stacks, deck, actions)?;
+        }
+    }
+
+    #[test]
+    fn should_not_pay_a_short_stack_more_than_it_covered() {
+        // The smallest hand that used to hand the whole pot to a short stack all in.
+        let deck = deck_from_strings(&["H14 D14", "C2 D7", "C3 D8", "H13 S9 C4 H5 D11"]);
+        let actions = vec![CallOrCheck, CallOrCheck, CallOrCheck, CallOrCheck, Raise(2)];
+        check_hand(vec![2, 4, 4], deck, actions).unwrap();
+    }
+
+    #[test]
+    fn should_undo_last_action() {
+        let (mut hs, _) = GameState::init(3)
+            .unwrap()
+            .start_play_hand(Deck::ordered_deck());
+        assert!(hs.undo_last_action().is_none());
+
+        hs.play_action(CallOrCheck).unwrap();
+        hs.play_action(CallOrCheck).unwrap();
+        let before = hs.spectator_snapshot();
+        let events = hs.events().len();
+        hs.play_action(CallOrCheck).unwrap();
+        assert_eq!(hs.street(), Street::Flop);
+
+        let undone = hs.undo_last_action().unwrap();
+        let after = undone.spectator_snapshot();
+        assert_eq!(after.board, before.board);
+        assert_eq!(after.current_player, before.current_player);
+        assert_eq!(after.expected_call, before.expected_call);
+        assert_eq!(undone.events().len(), events);
+        assert_eq!(undone.history().actions.len(), 2);
+    }
+
+    #[test]
+    fn should_rewind_a_bounded_number_of_actions() {
+        let (mut hs, _) = GameState::init(2)
+            .unwrap()
+            .start_play_hand(Deck::ordered_deck());
+        for _ in 0..CHECKPOINTS + 8 {
+            hs.play_action(Raise(hs.min_raise().unwrap())).unwrap();
+        }
+        assert_eq!(hs.checkpoints(), CHECKPOINTS);
+        assert!(hs.rewind(CHECKPOINTS + 1).is_none());
+
+        let rewound = hs.rewind(CHECKPOINTS).unwrap();
+        assert_eq!(rewound.history().actions.len(), 8);
+        assert_eq!(rewound.checkpoints(), 8);
+        let rewound = hs.rewind(3).unwrap();
+        assert_eq!(rewound.history().actions.len(), CHECKPOINTS + 5);
+        assert_eq!(rewound.checkpoints(), CHECKPOINTS);
+        assert_eq!(
+            rewound.undo_last_action().unwrap().events(),
+            hs.rewind(4).unwrap().events()
+        );
+    }
+
+    #[test]
+    fn should_rebuild_the_hand_from_its_events() {
+        let (mut hs, _) = GameState::init(3)
+            .unwrap()
+            .start_play_hand(Deck::shuffled_deck());
+        for action in [Raise(6), Fold, CallOrCheck] {
+            hs.play_action(action).unwrap();
+        }
+        while let NextPlayer(_) = hs.play_action(CallOrCheck).unwrap() {}
+
+        let history = hs.history();
+        let rebuilt = HandState::from_events(
+            history.big_blind,
+            history.stacks.clone(),
+            Deck::init(history.deck.clone()),
+            hs.events(),
+        );
+        assert_eq!(
+            serde_json::to_value(&rebuilt).unwrap(),
+            serde_json::to_value(&hs).unwrap()
+        );
+    }
+
     struct GameTestContainer {
         gs: GameState,
         hs: Option<HandState>,
@@ -668,17 +2050,17 @@ mod tests {
             assert_eq!(self.actual_next_player, Some(expected));
         }
 
-        fn when_start_round(&mut self) -> () {
+        fn when_start_round(&mut self) {
             self.when_start_round_with_deck(Deck::ordered_deck());
         }
 
-        fn when_start_round_with_deck(&mut self, deck: Deck) -> () {
+        fn when_start_round_with_deck(&mut self, deck: Deck) {
             let (hs, first) = self.gs.start_play_hand(deck);
             self.hs = Some(hs);
             self.actual_next_player = Some(first);
         }
 
-        fn when_player_plays(&mut self, player: usize, action: PokerAction) -> () {
+        fn when_player_plays(&mut self, player: usize, action: PokerAction) {
             assert_eq!(player, self.actual_next_player.unwrap());
             match self.hs.as_mut().unwrap().play_action(action).unwrap() {
                 NextPlayer(p) => self.actual_next_player = Some(p),
@@ -690,13 +2072,13 @@ mod tests {
             }
         }
 
-        fn when_play_multi(&mut self, action: PokerAction, count: u32) -> () {
+        fn when_play_multi(&mut self, action: PokerAction, count: u32) {
             for _ in 0..count {
                 self.when_player_plays(self.actual_next_player.unwrap(), action);
             }
         }
 
-        fn when_call_until_player_wins(&mut self, expected_winner: usize) -> () {
+        fn when_call_until_player_wins(&mut self, expected_winner: usize) {
             for _ in 0..100 {
                 if let WonHand(p) = self.hs.as_mut().unwrap().play_action(CallOrCheck).unwrap() {
                     let x = self.hs.take();
@@ -706,7 +2088,7 @@ mod tests {
                     return;
                 }
             }
-            assert!(false, "round didn't finish after 100 checks");
+            panic!("round didn't finish after 100 checks");
         }
 
         fn take_snapshot(&self) -> HandSnapshot {
@@ -715,7 +2097,7 @@ mod tests {
     }
 
     fn deck_from_strings(cards: &[&str]) -> Deck {
-        Deck::init(cards.iter().map(to_cards).flatten().rev().collect())
+        Deck::init(cards.iter().flat_map(to_cards).rev().collect())
     }
 
     fn to_cards(s: &&str) -> Vec<Card> {
@@ -0,0 +1,193 @@
+use std::io::{self, Write};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde::Serialize;
+
+use crate::archive::{date_time, now_ms};
+use crate::core_engine::Card;
+use crate::events::GameEvent;
+
+#[derive(Serialize)]
+struct LogLine<'a> {
+    timestamp_ms: u128,
+    hand: usize,
+    event: &'a GameEvent,
+}
+
+/// Writes every event as one JSON object per line, for debugging reported engine issues.
+pub struct JsonLinesLogger<W: Write> {
+    out: W,
+}
+
+impl<W: Write> JsonLinesLogger<W> {
+    pub fn init(out: W) -> Self {
+        Self { out }
+    }
+
+    pub fn log(&mut self, hand: usize, events: &[GameEvent]) -> io::Result<()> {
+        let timestamp_ms = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_millis())
+            .unwrap_or(0);
+        for event in events {
+            let line = LogLine {
+                timestamp_ms,
+                hand,
+                event,
+            };
+            serde_json::to_writer(&mut self.out, &line)?;
+            self.out.write_all(b"\n")?;
+        }
+        self.out.flush()
+    }
+}
+
+/// Writes what happens at the table as plain sentences, each with the time it happened, to
+/// read back later or to post a hand somewhere.
+pub struct Journal<W: Write> {
+    out: W,
+    hand: Option<usize>,
+}
+
+impl<W: Write> Journal<W> {
+    pub fn init(out: W) -> Self {
+        Self { out, hand: None }
+    }
+
+    pub fn log(&mut self, hand: usize, events: &[GameEvent]) -> io::Result<()> {
+        self.log_at(now_ms(), hand, events)
+    }
+
+    fn log_at(&mut self, ms: u64, hand: usize, events: &[GameEvent]) -> io::Result<()> {
+        let date_time = date_time(ms);
+        let (_, time) = date_time.split_once(' ').unwrap_or_default();
+        if self.hand != Some(hand) {
+            self.hand = Some(hand);
+            writeln!(self.out, "\n=== Hand {hand}, {date_time} UTC ===")?;
+        }
+        for event in events {
+            writeln!(self.out, "{time}  {}", describe(event))?;
+        }
+        self.out.flush()
+    }
+}
+
+fn describe(event: &GameEvent) -> String {
+    let cards = |cards: &[Card]| {
+        cards
+            .iter()
+            .map(Card::short_name)
+            .collect::<Vec<_>>()
+            .join(" ")
+    };
+    match event {
+        GameEvent::HoleCardsDealt {
+            player,
+            cards: dealt,
+        } => {
+            format!("Player {player} is dealt {}", cards(dealt))
+        }
+        GameEvent::BlindPosted { player, amount } => {
+            format!("Player {player} posts a blind of {amount}")
+        }
+        GameEvent::Checked { player, .. } => format!("Player {player} checks"),
+        GameEvent::Called { player, amount, .. } => format!("Player {player} calls {amount}"),
+        GameEvent::Raised { player, amount, .. } => {
+            format!("Player {player} raises, putting in {amount}")
+        }
+        GameEvent::Folded { player, .. } => format!("Player {player} folds"),
+        GameEvent::StreetDealt {
+            street,
+            cards: dealt,
+        } => {
+            format!("{street:?}: {}", cards(dealt))
+        }
+        GameEvent::HandShown { player, hand } => format!(
+            "Player {player} shows {} ({})",
+            hand.ranking_name().to_lowercase(),
+            cards(&hand.0)
+        ),
+        GameEvent::BetReturned { player, amount } => {
+            format!("Player {player} takes back {amount} nobody called")
+        }
+        GameEvent::PotAwarded {
+            player,
+            amount,
+            showdown,
+        } => {
+            let how = if *showdown {
+                "at the showdown"
+            } else {
+                "uncontested"
+            };
+            format!("Player {player} wins {amount} {how}")
+        }
+        GameEvent::PlayerEliminated { player } => format!("Player {player} is out of chips"),
+        GameEvent::TimedOut { player } => format!("Player {player} ran out of time"),
+        GameEvent::SatOut { player } => format!("Player {player} sits out"),
+        GameEvent::SatIn { player } => format!("Player {player} sits back in"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core_engine::Deck;
+    use crate::GameState;
+    use crate::PokerAction::*;
+
+    #[test]
+    fn should_log_one_json_object_per_event() {
+        let (mut hs, _) = GameState::init(2)
+            .unwrap()
+            .start_play_hand(Deck::ordered_deck());
+        hs.play_action(Fold).unwrap();
+
+        let mut logger = JsonLinesLogger::init(vec![]);
+        logger.log(7, hs.events()).unwrap();
+
+        let text = String::from_utf8(logger.out).unwrap();
+        let lines: Vec<serde_json::Value> = text
+            .lines()
+            .map(|l| serde_json::from_str(l).unwrap())
+            .collect();
+        assert_eq!(lines.len(), hs.events().len());
+        assert_eq!(lines[0]["hand"], 7);
+        assert_eq!(lines[0]["event"]["HoleCardsDealt"]["player"], 0);
+        assert_eq!(
+            lines[0]["event"]["HoleCardsDealt"]["cards"][0]["suit"],
+            "Spades"
+        );
+        assert!(lines[0]["timestamp_ms"].as_u64().unwrap() > 0);
+        assert_eq!(lines.last().unwrap()["event"]["PotAwarded"]["amount"], 2);
+    }
+
+    #[test]
+    fn should_journal_each_hand_with_the_time() {
+        let (mut hs, _) = GameState::init(2)
+            .unwrap()
+            .start_play_hand(Deck::ordered_deck());
+        let mut journal = Journal::init(vec![]);
+        journal.log_at(1_700_000_000_000, 1, hs.events()).unwrap();
+        hs.play_action(Fold).unwrap();
+        journal
+            .log_at(1_700_000_005_000, 1, &hs.events()[4..])
+            .unwrap();
+
+        let text = String::from_utf8(journal.out).unwrap();
+        assert_eq!(
+            text.lines().collect::<Vec<_>>(),
+            [
+                "",
+                "=== Hand 1, 2023/11/14 22:13:20 UTC ===",
+                "22:13:20  Player 0 is dealt 2s 3s",
+                "22:13:20  Player 1 is dealt 4s 5s",
+                "22:13:20  Player 0 posts a blind of 1",
+                "22:13:20  Player 1 posts a blind of 2",
+                "22:13:25  Player 0 folds",
+                "22:13:25  Player 1 takes back 1 nobody called",
+                "22:13:25  Player 1 wins 2 uncontested",
+            ]
+        );
+    }
+}
@@ -1,71 +1,3387 @@
+#[cfg(feature = "sqlite")]
+use poker_tui::database::StatsDatabase;
 use poker_tui::{
-    core_engine::Deck,
-    GameState, HandSnapshot, HandVisibility,
+    archive::{date_time, now_ms},
+    auth::Signer,
+    bankroll::Bankroll,
+    bots::{
+        BotKind, BotPlayer, CallingStation, LooseBot, Personality, HARD_BOT_SAMPLES, PERSONALITIES,
+    },
+    chat::ChatLog,
+    coach::Coach,
+    core_engine::{ranking_examples, Card, Deck},
+    difficulty::DifficultyDial,
+    discovery::{self, LanHost},
+    equity::{self, equity_vs_random, review_hand},
+    events::{EventObserver, GameEvent},
+    history::HandHistory,
+    hosting::{sshd_config, ServerConfig, DEFAULT_PORT},
+    import,
+    leaderboard::Leaderboard,
+    logging::{Journal, JsonLinesLogger},
+    net::{Connection, Lobby, TableConfig},
+    notes::{Notebook, OpponentNotes, Tag},
+    phase::AwaitingAction,
+    phh::PhhHand,
+    pokerstars,
+    protocol::{ClientMessage, Encoding, ServerMessage, TableInfo, HISTORY_VERSION},
+    pushfold::{equity_matrix, PushFoldTable},
+    report::HistoryReport,
+    simulation::{simulate_with, write_decision, SimulationResult},
+    stats::{PlayerStats, SessionStats},
+    status::serve_status,
+    tournament::{run_tournament, Format},
+    transport::Listener,
+    tutorial::{self, Highlight, StepKind},
+    BetSize, GameState, HandSnapshot, HandState, HandVisibility, IllegalRaise,
     PokerAction::{self, *},
 };
-use std::io;
+use signal_hook::{consts::SIGINT, iterator::Signals};
+use std::{
+    cell::RefCell,
+    fs::{self, File, OpenOptions},
+    io::{self, BufWriter, Write},
+    net::TcpListener,
+    path::{Path, PathBuf},
+    rc::Rc,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc::{self, Receiver, RecvTimeoutError},
+        Arc, Mutex, OnceLock,
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+struct Settings {
+    bell: bool,
+    spectator: bool,
+    equity_review: bool,
+    training: bool,
+    coach: bool,
+    history_dir: Option<PathBuf>,
+    /// Where every finished hand is saved in the PHH format, one file each.
+    phh_dir: Option<PathBuf>,
+    /// Where every finished hand is appended in PokerStars' hand history format.
+    pokerstars_file: Option<PathBuf>,
+    /// The SQLite database every finished hand is stored in, for statistics across
+    /// sessions. Needs the `sqlite` feature.
+    stats_db: Option<PathBuf>,
+    log_file: Option<PathBuf>,
+    /// Where what happens at the table is written down as it happens, for people to read.
+    journal_file: Option<PathBuf>,
+    replay: Option<PathBuf>,
+    demo: bool,
+    practice: bool,
+    compact: bool,
+    /// Output without colors or the bell.
+    plain: bool,
+    /// Shows nothing of the game, for tables played only for their logs.
+    quiet: bool,
+    bots: usize,
+    /// Simulations per decision for Monte Carlo bots, rule-based bots are used without it.
+    bot_samples: Option<usize>,
+    /// Makes the bots' random choices repeatable.
+    bot_seed: Option<u64>,
+    /// How long bots think on average before acting.
+    bot_delay: Duration,
+    /// How long a human may take to act before the seat checks or folds, no limit if
+    /// `None`.
+    turn_timeout: Option<Duration>,
+    /// Makes easy, normal and hard bots change level with the human players' results.
+    adaptive_difficulty: bool,
+    /// Personalities for the bot seats in order, the rest play the default bot.
+    personalities: Vec<Personality>,
+    /// Who plays each seat from `--seats`, `None` for a human.
+    seats: Option<Vec<Option<BotKind>>>,
+    /// Where the game is saved after every hand, so that it can be restored after a crash.
+    autosave: Option<PathBuf>,
+    /// Also autosaves after every action, not just every hand.
+    autosave_actions: bool,
+    /// Whose bankroll pays for cash games and gets the chips back after them.
+    profile: Option<String>,
+}
+
+impl Settings {
+    fn from_args(renderer: &mut dyn Renderer, mut args: impl Iterator<Item = String>) -> Self {
+        let mut settings = Settings {
+            bell: false,
+            spectator: false,
+            equity_review: false,
+            training: false,
+            coach: false,
+            history_dir: None,
+            phh_dir: None,
+            pokerstars_file: None,
+            stats_db: None,
+            log_file: None,
+            journal_file: None,
+            replay: None,
+            demo: false,
+            practice: false,
+            compact: false,
+            plain: false,
+            quiet: false,
+            bots: 0,
+            bot_samples: None,
+            bot_seed: None,
+            bot_delay: BOT_DELAY,
+            turn_timeout: None,
+            adaptive_difficulty: false,
+            personalities: vec![],
+            seats: None,
+            autosave: Some(PathBuf::from(AUTOSAVE_FILE)),
+            autosave_actions: false,
+            profile: None,
+        };
+        let mut seats = None;
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--bell" => settings.bell = true,
+                "--spectator" => settings.spectator = true,
+                "--equity-review" => settings.equity_review = true,
+                "--training" => settings.training = true,
+                "--coach" => settings.coach = true,
+                "--history-dir" => settings.history_dir = args.next().map(PathBuf::from),
+                "--phh-dir" => settings.phh_dir = args.next().map(PathBuf::from),
+                "--pokerstars" => settings.pokerstars_file = args.next().map(PathBuf::from),
+                "--stats-db" => settings.stats_db = args.next().map(PathBuf::from),
+                "--log-file" => settings.log_file = args.next().map(PathBuf::from),
+                "--journal" => settings.journal_file = args.next().map(PathBuf::from),
+                "--replay" => settings.replay = args.next().map(PathBuf::from),
+                "--demo" => settings.demo = true,
+                "--practice" => settings.practice = true,
+                "--compact" => settings.compact = true,
+                "--plain" => settings.plain = true,
+                "--quiet" => settings.quiet = true,
+                "--adaptive-difficulty" => settings.adaptive_difficulty = true,
+                "--autosave" => match args.next() {
+                    Some(path) => settings.autosave = Some(PathBuf::from(path)),
+                    None => renderer.line("--autosave needs a file to save to"),
+                },
+                "--no-autosave" => settings.autosave = None,
+                "--autosave-actions" => settings.autosave_actions = true,
+                "--profile" => match args.next() {
+                    Some(name) => settings.profile = Some(name),
+                    None => renderer.line("--profile needs the name of a profile"),
+                },
+                "--bots" => match args.next().and_then(|n| n.parse().ok()) {
+                    Some(n) => settings.bots = n,
+                    None => renderer.line("--bots needs a number of bots"),
+                },
+                "--bot-samples" => match args.next().and_then(|n| n.parse().ok()) {
+                    Some(n) => settings.bot_samples = Some(n),
+                    None => renderer.line("--bot-samples needs a number of simulations"),
+                },
+                "--bot-delay" => match args.next().and_then(|n| n.parse().ok()) {
+                    Some(ms) => settings.bot_delay = Duration::from_millis(ms),
+                    None => renderer.line("--bot-delay needs a number of milliseconds"),
+                },
+                "--turn-timeout" => match args.next().and_then(|n| n.parse().ok()) {
+                    Some(secs) => settings.turn_timeout = Some(Duration::from_secs(secs)),
+                    None => renderer.line("--turn-timeout needs a number of seconds"),
+                },
+                "--bot-seed" => match args.next().and_then(|n| n.parse().ok()) {
+                    Some(n) => settings.bot_seed = Some(n),
+                    None => renderer.line("--bot-seed needs a number"),
+                },
+                "--seats" => seats = args.next(),
+                "--bot-personality" => match args.next().as_deref().and_then(Personality::parse) {
+                    Some(p) => settings.personalities.push(p),
+                    None => renderer.line(&format!(
+                        "--bot-personality needs one of {} or vpip=..:aggression=..:bluff=..",
+                        PERSONALITIES.map(|(name, _)| name).join(", ")
+                    )),
+                },
+                other => renderer.line(&format!("Ignoring unknown argument {other}")),
+            }
+        }
+        if let Some(spec) = seats {
+            settings.seats = parse_seats(&spec, settings.default_bot());
+            if settings.seats.is_none() {
+                renderer.line(&format!("Ignoring --seats {spec}, expected something like h,b:easy,b:hard with at least one h"));
+            }
+        }
+        settings
+    }
+
+    fn snapshot(&self, hs: &HandState) -> HandSnapshot {
+        if self.spectator {
+            hs.public_snapshot()
+        } else {
+            hs.spectator_snapshot()
+        }
+    }
+
+    /// Uses the compact layout when asked to, or when `COLUMNS` says the terminal is too
+    /// narrow for one column per seat.
+    fn compact_layout(&self, players: usize) -> bool {
+        let columns = std::env::var("COLUMNS").ok().and_then(|c| c.parse().ok());
+        self.compact || columns.is_some_and(|c: usize| c < players * 12 + 4)
+    }
+
+    fn default_bot(&self) -> BotKind {
+        match self.bot_samples {
+            Some(samples) => BotKind::Hard(samples),
+            None => BotKind::Normal,
+        }
+    }
+
+    /// Who plays each seat unless the lobby says otherwise: `--seats` if it fits the table,
+    /// otherwise `--bots` in the last seats with `--bot-personality` applied in order.
+    fn seats(&self, renderer: &mut dyn Renderer, players: usize) -> Vec<Option<BotKind>> {
+        if let Some(seats) = self.seats.as_ref().filter(|s| s.len() == players) {
+            return seats.clone();
+        }
+        if self.bots >= players {
+            renderer.line(&format!(
+                "Only {} of the {players} seats can be bots",
+                players - 1
+            ));
+        }
+        let humans = players - self.bots.min(players - 1);
+        (0..players)
+            .map(|p| {
+                (p >= humans).then(|| match self.personalities.get(p - humans) {
+                    Some(&personality) => BotKind::Personality(personality),
+                    None => self.default_bot(),
+                })
+            })
+            .collect()
+    }
+
+    fn renderer(&self) -> Box<dyn Renderer> {
+        if self.quiet {
+            Box::new(SilentRenderer)
+        } else if self.plain {
+            Box::new(PlainTextRenderer)
+        } else {
+            Box::new(TerminalRenderer)
+        }
+    }
+}
+
+enum Input {
+    Line(String),
+    Interrupt,
+    Closed,
+}
+
+static INPUT: OnceLock<Mutex<Receiver<Input>>> = OnceLock::new();
+static INPUT_CLOSED: AtomicBool = AtomicBool::new(false);
+
+/// Reads stdin and Ctrl+C on background threads, so an interrupt is noticed while the game
+/// waits for a line instead of killing the process.
+fn start_input() {
+    let (tx, rx) = mpsc::channel();
+    let lines = tx.clone();
+    thread::spawn(move || {
+        for line in io::stdin().lines() {
+            let Ok(line) = line else {
+                break;
+            };
+            if lines.send(Input::Line(line)).is_err() {
+                return;
+            }
+        }
+        let _ = lines.send(Input::Closed);
+    });
+    if let Ok(mut signals) = Signals::new([SIGINT]) {
+        thread::spawn(move || {
+            for _ in signals.forever() {
+                if tx.send(Input::Interrupt).is_err() {
+                    return;
+                }
+            }
+        });
+    }
+    let _ = INPUT.set(Mutex::new(rx));
+}
+
+fn read_input() -> Input {
+    if INPUT_CLOSED.load(Ordering::Relaxed) {
+        return Input::Closed;
+    }
+    let input = INPUT
+        .get()
+        .expect("input is started in main")
+        .lock()
+        .unwrap()
+        .recv()
+        .unwrap_or(Input::Closed);
+    if let Input::Closed = input {
+        INPUT_CLOSED.store(true, Ordering::Relaxed);
+    }
+    input
+}
+
+/// Like `read_input`, but gives up with `None` once `timeout` passed without input.
+fn read_input_timeout(timeout: Duration) -> Option<Input> {
+    if INPUT_CLOSED.load(Ordering::Relaxed) {
+        return Some(Input::Closed);
+    }
+    let input = match INPUT
+        .get()
+        .expect("input is started in main")
+        .lock()
+        .unwrap()
+        .recv_timeout(timeout)
+    {
+        Ok(input) => input,
+        Err(RecvTimeoutError::Timeout) => return None,
+        Err(RecvTimeoutError::Disconnected) => Input::Closed,
+    };
+    if let Input::Closed = input {
+        INPUT_CLOSED.store(true, Ordering::Relaxed);
+    }
+    Some(input)
+}
+
+/// Returns input that already arrived without waiting for more.
+fn poll_input() -> Option<Input> {
+    let input = INPUT.get()?.lock().unwrap().try_recv().ok()?;
+    if let Input::Closed = input {
+        INPUT_CLOSED.store(true, Ordering::Relaxed);
+    }
+    Some(input)
+}
+
+fn main() {
+    let mut args = std::env::args().skip(1).peekable();
+    let command: fn(_) = match args.peek().map(String::as_str) {
+        Some("simulate") => run_simulation,
+        Some("tournament") => run_tournament_mode,
+        Some("export-csv") => run_csv_export,
+        Some("analyze") => run_analysis,
+        Some("pushfold-table") => run_push_fold_table,
+        Some("host") => run_host,
+        Some("join") => run_join,
+        Some("serve") => run_serve,
+        Some("invite") => run_invite,
+        Some("ssh-session") => run_ssh_session,
+        Some("sshd-config") => run_sshd_config,
+        Some("tables") => run_tables,
+        _ => return run_menu(args),
+    };
+    args.next();
+    command(args);
+}
+
+/// Plays at this terminal with the settings given on the command line, from the main menu.
+fn run_menu(args: impl Iterator<Item = String>) {
+    let renderer: &mut dyn Renderer = &mut TerminalRenderer;
+    let mut settings = Settings::from_args(renderer, args);
+    start_input();
+
+    if let Some(path) = &settings.replay {
+        run_replay(renderer, path);
+        return;
+    }
+    if settings.demo {
+        run_demo(&settings);
+        return;
+    }
+
+    renderer.line("Welcome to PokerTUI!");
+    offer_autosave(renderer, &settings);
+
+    loop {
+        renderer.line("");
+        renderer.line("MAIN MENU");
+        renderer.line("  1) New cash game");
+        renderer.line("  2) New freezeout tournament");
+        renderer.line("  3) Join network game");
+        renderer.line("  4) Load game");
+        renderer.line("  5) Replay hand history");
+        renderer.line("  6) Tutorial");
+        renderer.line("  7) Settings");
+        renderer.line("  8) Leaderboard");
+        renderer.line("  9) Quit");
+
+        match prompt(renderer, "Choose an option:")
+            .as_deref()
+            .map(str::trim)
+        {
+            Some("1") => {
+                if let Some((gs, seats)) = cash_game_form(renderer, &settings) {
+                    let hero = seats.iter().position(Option::is_none).unwrap_or_default();
+                    let chips = gs.current_chips(hero);
+                    if settings
+                        .profile
+                        .as_deref()
+                        .is_none_or(|p| buy_in(renderer, p, chips))
+                    {
+                        let gs = play_game(gs, None, &settings, seats);
+                        if let Some(profile) = &settings.profile {
+                            cash_out(renderer, profile, gs.current_chips(hero));
+                        }
+                    }
+                }
+            }
+            Some("2") => {
+                // A freezeout is a cash game without buying in, played until one stack is left.
+                if let Some((gs, seats)) = cash_game_form(renderer, &settings) {
+                    play_game(gs, None, &settings, seats);
+                }
+            }
+            Some("3") => {
+                if let Some(addr) = host_form(renderer) {
+                    if let Some(connected) = join_form(renderer, &addr) {
+                        run_client(renderer, &addr, connected);
+                    }
+                }
+            }
+            Some("4") => {
+                if let Some((gs, hand, seats)) = load_game_form(renderer, &settings) {
+                    play_game(gs, hand, &settings, seats);
+                }
+            }
+            Some("5") => {
+                if let Some(path) = prompt(renderer, "Path to the hand history file:") {
+                    run_replay(renderer, &PathBuf::from(path.trim()));
+                }
+            }
+            Some("6") => run_tutorial(renderer),
+            Some("7") => settings_form(renderer, &mut settings),
+            Some("8") => show_leaderboard(renderer),
+            Some("9") | None => return,
+            Some(other) => renderer.line(&format!("Invalid option {other}")),
+        }
+    }
+}
+
+/// Prints the question and reads one line of input, or `None` once stdin is closed or the
+/// user pressed Ctrl+C.
+fn prompt(renderer: &mut dyn Renderer, question: &str) -> Option<String> {
+    renderer.line(question);
+    match read_input() {
+        Input::Line(answer) => Some(answer),
+        Input::Interrupt | Input::Closed => None,
+    }
+}
+
+fn prompt_number<T: std::str::FromStr>(
+    renderer: &mut dyn Renderer,
+    question: &str,
+    default: T,
+) -> Option<T> {
+    loop {
+        let answer = prompt(renderer, question)?;
+        let answer = answer.trim();
+        if answer.is_empty() {
+            return Some(default);
+        }
+        match answer.parse() {
+            Ok(v) => return Some(v),
+            Err(_) => renderer.line("Please provide a positive number"),
+        }
+    }
+}
+
+fn cash_game_form(
+    renderer: &mut dyn Renderer,
+    settings: &Settings,
+) -> Option<(GameState, Vec<Option<BotKind>>)> {
+    let default_players = settings.seats.as_ref().map_or(2, |s| s.len());
+    let gs = loop {
+        let players = prompt_number(
+            renderer,
+            &format!("How many players will be playing? [{default_players}]"),
+            default_players,
+        )?;
+        let stack = prompt_number(renderer, "Starting stack per player? [100]", 100)?;
+        match GameState::init_with_stack(players, stack) {
+            Some(gs) => break gs,
+            None => renderer.line("A game needs at least 2 players with at least 2 chips each"),
+        }
+    };
+
+    renderer.line("Seats are played by a human (h) or a bot (b, or b:easy, b:normal, b:hard, b:adaptive, b:pushfold, b:cfr, b:mc=SAMPLES, b:chart, b:chart=FILE, b:onnx=FILE, b:PERSONALITY)");
+    renderer
+        .line("Add @bluff=F:slowplay=F:hero_call=F to a bot to mix in other actions that often");
+    let mut seats = settings.seats(renderer, gs.players());
+    loop {
+        for (p, seat) in seats.iter_mut().enumerate() {
+            let default = seat_spec(seat);
+            loop {
+                let answer = prompt(renderer, &format!("Who plays seat {p}? [{default}]"))?;
+                let answer = Some(answer.trim()).filter(|a| !a.is_empty());
+                match parse_seat(answer.unwrap_or(&default), &settings.default_bot()) {
+                    Some(s) => {
+                        *seat = s;
+                        break;
+                    }
+                    None => renderer.line(&format!("Invalid seat {}", answer.unwrap_or_default())),
+                }
+            }
+        }
+        if seats.contains(&None) {
+            return Some((gs, seats));
+        }
+        renderer.line("At least one seat has to be played by a human");
+    }
+}
+
+const DEFAULT_SAVE_FILE: &str = "poker-tui.save";
+
+/// Loads a game, the hand that was in progress when it was saved and who played each seat.
+/// Saves from before hands were kept in them are still read, and deal a fresh hand.
+fn load_game_form(
+    renderer: &mut dyn Renderer,
+    settings: &Settings,
+) -> Option<(GameState, Option<HandState>, Vec<Option<BotKind>>)> {
+    let path = prompt(
+        renderer,
+        &format!("Save file to load? [{DEFAULT_SAVE_FILE}]"),
+    )?;
+    let path = Some(path.trim()).filter(|p| !p.is_empty());
+    let path = path.unwrap_or(DEFAULT_SAVE_FILE);
+    let text = match fs::read_to_string(path) {
+        Ok(text) => text,
+        Err(e) => {
+            renderer.line(&format!("Could not load a game from {path}: {e}"));
+            return None;
+        }
+    };
+    let (gs, hand) = match GameState::from_json(&text) {
+        Ok(loaded) => loaded,
+        Err(e) => match GameState::from_text(&text) {
+            Some(gs) => (gs, None),
+            None => {
+                renderer.line(&format!("Could not load a game from {path}: {e}"));
+                return None;
+            }
+        },
+    };
+    let seats = saved_seats(renderer, settings, &text, gs.players());
+    Some((gs, hand, seats))
+}
+
+/// Who played each seat of a saved game, or who would by the settings for saves that
+/// don't say.
+fn saved_seats(
+    renderer: &mut dyn Renderer,
+    settings: &Settings,
+    json: &str,
+    players: usize,
+) -> Vec<Option<BotKind>> {
+    GameState::seats_from_json(json)
+        .and_then(|spec| parse_seats(&spec, settings.default_bot()))
+        .filter(|seats| seats.len() == players)
+        .unwrap_or_else(|| settings.seats(renderer, players))
+}
+
+const AUTOSAVE_FILE: &str = "poker-tui.autosave";
+
+/// Offers to play on with the game in the autosave, which is only left behind by a game
+/// that was not left from the table.
+fn offer_autosave(renderer: &mut dyn Renderer, settings: &Settings) {
+    let Some(path) = &settings.autosave else {
+        return;
+    };
+    let Ok(text) = fs::read_to_string(path) else {
+        return;
+    };
+    let (gs, hand) = match GameState::from_json(&text) {
+        Ok(loaded) => loaded,
+        Err(e) => {
+            return renderer.line(&format!("Ignoring the autosave in {}: {e}", path.display()))
+        }
+    };
+    let during = if hand.is_some() { "during" } else { "after" };
+    renderer.line(&format!(
+        "A game of {} players was cut short {during} a hand.",
+        gs.players()
+    ));
+    let answer = prompt(renderer, "Restore it? [Y/n]");
+    if answer.is_some_and(|a| !a.trim().eq_ignore_ascii_case("n")) {
+        let seats = saved_seats(renderer, settings, &text, gs.players());
+        play_game(gs, hand, settings, seats);
+    }
+}
+
+/// Writes the game to the autosave file by way of a temporary file, so that a crash while
+/// writing leaves the last autosave whole.
+fn autosave(
+    renderer: &mut dyn Renderer,
+    settings: &Settings,
+    seats: &str,
+    gs: &GameState,
+    hs: Option<&HandState>,
+) {
+    let Some(path) = &settings.autosave else {
+        return;
+    };
+    let temporary = path.with_extension("tmp");
+    let json = gs.to_json_with_seats(hs, seats);
+    if let Err(e) = fs::write(&temporary, json).and_then(|_| fs::rename(&temporary, path)) {
+        renderer.line(&format!("Could not autosave to {}: {e}", path.display()));
+    }
+}
+
+fn save_game_form(renderer: &mut dyn Renderer, seats: &str, gs: &GameState, hs: &HandState) {
+    let Some(path) = prompt(
+        renderer,
+        &format!("Save to which file? [{DEFAULT_SAVE_FILE}]"),
+    ) else {
+        return;
+    };
+    let path = Some(path.trim()).filter(|p| !p.is_empty());
+    let path = path.unwrap_or(DEFAULT_SAVE_FILE);
+    match fs::write(path, gs.to_json_with_seats(Some(hs), seats)) {
+        Ok(()) => renderer.line(&format!("Saved to {path}.")),
+        Err(e) => renderer.line(&format!("Could not save to {path}: {e}")),
+    }
+}
+
+/// Returns whether the players want to keep playing.
+fn pause_menu(renderer: &mut dyn Renderer, seats: &str, gs: &GameState, hs: &HandState) -> bool {
+    loop {
+        renderer.line("");
+        renderer.line("PAUSED");
+        renderer.line("  1) Resume");
+        renderer.line("  2) Save game");
+        renderer.line("  3) Leave table");
+
+        match prompt(renderer, "Choose an option:")
+            .as_deref()
+            .map(str::trim)
+        {
+            Some("1") => return true,
+            Some("2") => save_game_form(renderer, seats, gs, hs),
+            Some("3") | None => return false,
+            Some(other) => renderer.line(&format!("Invalid option {other}")),
+        }
+    }
+}
+
+fn settings_form(renderer: &mut dyn Renderer, settings: &mut Settings) {
+    loop {
+        renderer.line("");
+        renderer.line("SETTINGS");
+        renderer.line(&format!("  1) Bell cues: {}", on_off(settings.bell)));
+        renderer.line(&format!(
+            "  2) Spectator mode: {}",
+            on_off(settings.spectator)
+        ));
+        renderer.line(&format!(
+            "  3) Equity review after showdown: {}",
+            on_off(settings.equity_review)
+        ));
+        renderer.line(&format!(
+            "  4) Training win-probability overlay: {}",
+            on_off(settings.training)
+        ));
+        let dir = settings
+            .history_dir
+            .as_ref()
+            .map(|d| d.display().to_string());
+        renderer.line(&format!(
+            "  5) Hand history folder: {}",
+            dir.as_deref().unwrap_or("off")
+        ));
+        renderer.line(&format!(
+            "  6) Practice mode, (U)ndo actions: {}",
+            on_off(settings.practice)
+        ));
+        renderer.line(&format!(
+            "  7) Compact layout: {}",
+            on_off(settings.compact)
+        ));
+        renderer.line(&format!(
+            "  8) Coach, (A)dvice on request: {}",
+            on_off(settings.coach)
+        ));
+        let timeout = settings.turn_timeout.map(|t| format!("{}s", t.as_secs()));
+        renderer.line(&format!(
+            "  9) Turn time limit: {}",
+            timeout.as_deref().unwrap_or("off")
+        ));
+        renderer.line(&format!(
+            " 10) Adaptive bot difficulty: {}",
+            on_off(settings.adaptive_difficulty)
+        ));
+        renderer.line(&format!(
+            " 11) Autosave after every action: {}",
+            on_off(settings.autosave_actions)
+        ));
+        renderer.line(" 12) Back");
+
+        match prompt(renderer, "Choose an option:")
+            .as_deref()
+            .map(str::trim)
+        {
+            Some("1") => settings.bell = !settings.bell,
+            Some("2") => settings.spectator = !settings.spectator,
+            Some("3") => settings.equity_review = !settings.equity_review,
+            Some("4") => settings.training = !settings.training,
+            Some("5") => {
+                let dir = prompt(renderer, "Folder to save hands to (empty to disable):");
+                settings.history_dir = dir
+                    .map(|d| d.trim().to_owned())
+                    .filter(|d| !d.is_empty())
+                    .map(PathBuf::from);
+            }
+            Some("6") => settings.practice = !settings.practice,
+            Some("7") => settings.compact = !settings.compact,
+            Some("8") => settings.coach = !settings.coach,
+            Some("9") => {
+                let secs = prompt_number(renderer, "Seconds per turn (0 for no limit):", 0);
+                settings.turn_timeout = secs.filter(|&s| s > 0).map(Duration::from_secs);
+            }
+            Some("10") => settings.adaptive_difficulty = !settings.adaptive_difficulty,
+            Some("11") => settings.autosave_actions = !settings.autosave_actions,
+            Some("12") | None => return,
+            Some(other) => renderer.line(&format!("Invalid option {other}")),
+        }
+    }
+}
+
+fn on_off(value: bool) -> &'static str {
+    if value {
+        "on"
+    } else {
+        "off"
+    }
+}
+
+const TOAST_DURATION: Duration = Duration::from_secs(8);
+
+/// Short notifications shown next to the table until they expire.
+struct Toasts {
+    toasts: Vec<(String, Instant)>,
+}
+
+impl Toasts {
+    fn push(&mut self, text: String) {
+        self.toasts.push((text, Instant::now() + TOAST_DURATION));
+    }
+
+    fn push_events(&mut self, events: &[GameEvent]) {
+        for event in events {
+            match event {
+                GameEvent::PotAwarded { player, amount, .. } => {
+                    self.push(format!("Player {player} won {amount} chips"))
+                }
+                GameEvent::PlayerEliminated { player } => {
+                    self.push(format!("Player {player} eliminated"))
+                }
+                GameEvent::TimedOut { player } => {
+                    self.push(format!("Player {player} ran out of time"))
+                }
+                GameEvent::SatOut { player } => self.push(format!("Player {player} sits out")),
+                GameEvent::SatIn { player } => self.push(format!("Player {player} is back")),
+                _ => {}
+            }
+        }
+    }
+
+    fn active(&mut self) -> Vec<&str> {
+        let now = Instant::now();
+        self.toasts.retain(|(_, expires)| *expires > now);
+        self.toasts.iter().map(|(t, _)| t.as_str()).collect()
+    }
+}
+
+struct Session {
+    toasts: Toasts,
+    chat: ChatLog,
+    stats: SessionStats,
+    /// The chips each seat sat down with.
+    buy_ins: Vec<u32>,
+    hand_number: usize,
+    overlay: bool,
+    started: Instant,
+    /// Who decides for each seat.
+    seats: Vec<Box<dyn SeatController>>,
+    renderer: Box<dyn Renderer>,
+    /// Who plays each seat, as the notes on opponents know them.
+    seat_names: Vec<String>,
+    /// Who plays each seat, the way `--seats` takes it, for the saves to keep.
+    saved_seats: String,
+    coach: Option<Coach>,
+    /// How many turns in a row each seat let run out of time.
+    timeouts: Vec<u32>,
+    difficulty: Option<DifficultyDial>,
+    #[cfg(feature = "sqlite")]
+    database: Option<StatsDatabase>,
+}
+
+impl Session {
+    /// A session with nobody coaching, no difficulty dial and no database.
+    fn new(gs: &GameState, seats: Vec<Box<dyn SeatController>>, seat_names: Vec<String>) -> Self {
+        Self {
+            toasts: Toasts { toasts: vec![] },
+            chat: ChatLog::init(gs.players()),
+            stats: SessionStats::init(gs.players()),
+            buy_ins: (0..gs.players()).map(|p| gs.current_chips(p)).collect(),
+            hand_number: 0,
+            overlay: false,
+            started: Instant::now(),
+            seats,
+            renderer: Box::new(TerminalRenderer),
+            seat_names,
+            saved_seats: String::new(),
+            coach: None,
+            timeouts: vec![0; gs.players()],
+            difficulty: None,
+            #[cfg(feature = "sqlite")]
+            database: None,
+        }
+    }
+
+    /// Shows a line of the game's output.
+    fn show(&mut self, line: &str) {
+        self.renderer.line(line);
+    }
+
+    fn ring_bell(&mut self, settings: &Settings) {
+        if settings.bell {
+            self.renderer.bell();
+        }
+    }
+
+    /// Whether a bot plays the seat, either for good or while its player is away.
+    fn is_bot(&self, player: usize) -> bool {
+        self.seats[player].is_bot() || self.is_away(player)
+    }
+
+    /// Whether the player missed so many turns that an easy bot stands in for them.
+    fn is_away(&self, player: usize) -> bool {
+        self.timeouts[player] >= AWAY_AFTER_TIMEOUTS
+    }
+
+    /// The table as the people at the keyboard may see it: bots' cards stay hidden until
+    /// the showdown.
+    fn snapshot(&self, settings: &Settings, hs: &HandState) -> HandSnapshot {
+        let mut snapshot = settings.snapshot(hs);
+        if !hs.is_showdown() {
+            for (player, hand) in snapshot.hands.iter_mut().enumerate() {
+                if self.is_bot(player) && matches!(hand, HandVisibility::Visible(..)) {
+                    *hand = HandVisibility::Hidden;
+                }
+            }
+        }
+        snapshot
+    }
+}
+
+/// What a seat's controller came up with for the player's turn.
+enum SeatInput {
+    /// An action decided away from the keyboard, with a reason if the controller has one.
+    Decided(PokerAction, Option<String>),
+    /// A line typed for the player: an action or one of the table's commands.
+    Typed(String),
+    Interrupted,
+    /// Nothing came in time.
+    TimedOut,
+    /// The player left the table.
+    Left,
+}
+
+/// Who decides for a seat: someone at the keyboard, a bot or a script.
+trait SeatController {
+    /// Whether a bot plays the seat, so its cards stay hidden from the keyboard.
+    fn is_bot(&self) -> bool;
+
+    fn next_input(&mut self, hs: &HandState, player: usize) -> SeatInput;
+
+    /// Called with everything that happened in a hand once it is over.
+    fn hand_finished(&mut self, _events: &[GameEvent]) {}
+}
+
+/// A player typing at the terminal.
+struct KeyboardSeat {
+    turn_timeout: Option<Duration>,
+}
+
+impl SeatController for KeyboardSeat {
+    fn is_bot(&self) -> bool {
+        false
+    }
+
+    fn next_input(&mut self, _hs: &HandState, _player: usize) -> SeatInput {
+        let input = match self.turn_timeout {
+            Some(timeout) => read_input_timeout(timeout),
+            None => Some(read_input()),
+        };
+        match input {
+            Some(Input::Line(line)) => SeatInput::Typed(line),
+            Some(Input::Interrupt) => SeatInput::Interrupted,
+            Some(Input::Closed) => SeatInput::Left,
+            None => SeatInput::TimedOut,
+        }
+    }
+}
+
+/// A bot, taking a moment to think so its moves can be followed.
+struct BotSeat {
+    bot: Box<dyn BotPlayer>,
+    delay: Duration,
+}
+
+impl SeatController for BotSeat {
+    fn is_bot(&self) -> bool {
+        true
+    }
+
+    fn next_input(&mut self, hs: &HandState, player: usize) -> SeatInput {
+        thread::sleep(thinking_time(self.delay));
+        let (action, reason) = self.bot.decide_explained(&hs.bot_context(player));
+        SeatInput::Decided(action, reason)
+    }
+
+    fn hand_finished(&mut self, events: &[GameEvent]) {
+        self.bot.hand_finished(events);
+    }
+}
+
+/// Where a simulation writes down its bots' decisions, one JSON object a line.
+struct DecisionLog {
+    out: Box<dyn Write>,
+    /// The hand being played.
+    hand: u32,
+    /// Why writing failed, after which nothing more is written.
+    error: Option<io::Error>,
+}
+
+/// A bot seat of a simulation, writing each of its bot's decisions to the log.
+struct LoggedSeat {
+    seat: BotSeat,
+    log: Rc<RefCell<DecisionLog>>,
+}
+
+impl SeatController for LoggedSeat {
+    fn is_bot(&self) -> bool {
+        true
+    }
+
+    fn next_input(&mut self, hs: &HandState, player: usize) -> SeatInput {
+        let input = self.seat.next_input(hs, player);
+        let mut log = self.log.borrow_mut();
+        if let (SeatInput::Decided(action, reason), None) = (&input, &log.error) {
+            let hand = log.hand;
+            if let Err(e) = write_decision(
+                &mut log.out,
+                hand,
+                player,
+                hs.street(),
+                *action,
+                reason.as_deref(),
+            ) {
+                log.error = Some(e);
+            }
+        }
+        input
+    }
+
+    fn hand_finished(&mut self, events: &[GameEvent]) {
+        self.seat.hand_finished(events);
+    }
+}
+
+/// Lines given up front, typed one a turn, leaving the table once they run out.
+#[cfg(test)]
+struct ScriptedSeat {
+    lines: std::collections::VecDeque<String>,
+}
+
+#[cfg(test)]
+impl SeatController for ScriptedSeat {
+    fn is_bot(&self) -> bool {
+        false
+    }
+
+    fn next_input(&mut self, _hs: &HandState, _player: usize) -> SeatInput {
+        match self.lines.pop_front() {
+            Some(line) => SeatInput::Typed(line),
+            None => SeatInput::Left,
+        }
+    }
+}
+
+/// Writes every event to the log file and the journal as the hands play it.
+struct EventLog {
+    hand_number: usize,
+    /// Whether the last event was a deal, the first of which starts a hand.
+    dealing: bool,
+    logger: Option<JsonLinesLogger<File>>,
+    journal: Option<Journal<File>>,
+}
+
+impl EventLog {
+    fn open(renderer: &mut dyn Renderer, settings: &Settings) -> Arc<Mutex<Self>> {
+        Arc::new(Mutex::new(Self {
+            hand_number: 0,
+            dealing: false,
+            logger: open_log_file(renderer, settings),
+            journal: open_journal(renderer, settings),
+        }))
+    }
+}
+
+impl EventObserver for EventLog {
+    fn on_event(&mut self, event: &GameEvent) {
+        // Seats out of chips aren't dealt in, so any seat's cards may come first.
+        let dealt = matches!(event, GameEvent::HoleCardsDealt { .. });
+        if dealt && !self.dealing {
+            self.hand_number += 1;
+        }
+        self.dealing = dealt;
+        let events = std::slice::from_ref(event);
+        if let Some(logger) = &mut self.logger {
+            if let Err(e) = logger.log(self.hand_number, events) {
+                println!("Could not write to the log file, logging stopped: {e}");
+                self.logger = None;
+            }
+        }
+        if let Some(journal) = &mut self.journal {
+            if let Err(e) = journal.log(self.hand_number, events) {
+                println!("Could not write to the journal, it stopped: {e}");
+                self.journal = None;
+            }
+        }
+    }
+}
+
+#[cfg(feature = "sqlite")]
+fn open_stats_database(renderer: &mut dyn Renderer, settings: &Settings) -> Option<StatsDatabase> {
+    let path = settings.stats_db.as_ref()?;
+    match StatsDatabase::open(path) {
+        Ok(database) => Some(database),
+        Err(e) => {
+            renderer.line(&format!(
+                "Could not open stats database {}: {e}",
+                path.display()
+            ));
+            None
+        }
+    }
+}
+
+/// Weighs the hand's all-in by the players' equity, if they were all in before the river.
+fn record_all_in(session: &mut Session, hs: &HandState) {
+    if let Some(all_in) = equity::all_in(hs, equity::ALL_IN_SAMPLES) {
+        session.stats.record_all_in(&all_in);
+    }
+}
+
+/// Stores the finished hand in the stats database, if there is one.
+#[cfg(feature = "sqlite")]
+fn record_in_database(session: &mut Session, hs: &HandState) {
+    let Some(database) = &mut session.database else {
+        return;
+    };
+    let names: Vec<_> = (0..hs.history().stacks.len())
+        .map(|p| format!("Player {p}"))
+        .collect();
+    if let Err(e) = database.record_hand(hs, &names, now_ms()) {
+        session.show(&format!(
+            "Could not write to the stats database, no more hands stored: {e}"
+        ));
+        session.database = None;
+    }
+}
+
+#[cfg(not(feature = "sqlite"))]
+fn record_in_database(_session: &mut Session, _hs: &HandState) {}
+
+fn open_log_file(
+    renderer: &mut dyn Renderer,
+    settings: &Settings,
+) -> Option<JsonLinesLogger<File>> {
+    let path = settings.log_file.as_ref()?;
+    match OpenOptions::new().create(true).append(true).open(path) {
+        Ok(file) => Some(JsonLinesLogger::init(file)),
+        Err(e) => {
+            renderer.line(&format!("Could not open log file {}: {e}", path.display()));
+            None
+        }
+    }
+}
+
+fn open_journal(renderer: &mut dyn Renderer, settings: &Settings) -> Option<Journal<File>> {
+    let path = settings.journal_file.as_ref()?;
+    match OpenOptions::new().create(true).append(true).open(path) {
+        Ok(file) => Some(Journal::init(file)),
+        Err(e) => {
+            renderer.line(&format!("Could not open journal {}: {e}", path.display()));
+            None
+        }
+    }
+}
+
+fn bankroll_path(profile: &str) -> PathBuf {
+    PathBuf::from(format!("poker-tui-{profile}.bankroll"))
+}
+
+const LEADERBOARD_PATH: &str = "poker-tui.leaderboard";
+
+/// Adds the game the first human seat just played to the profile's standing.
+fn record_on_leaderboard(profile: &str, session: &mut Session, gs: &GameState) {
+    let Some(hero) = hero(session) else {
+        return;
+    };
+    let chips = gs.current_chips(hero);
+    let profit = chips as i64 - session.buy_ins[hero] as i64;
+    let title = (0..gs.players()).all(|p| p == hero || gs.current_chips(p) == 0);
+    let biggest_pot = session.stats.player(hero).biggest_pot;
+    let recorded = Leaderboard::load(LEADERBOARD_PATH).and_then(|mut leaderboard| {
+        leaderboard.record_game(profile, profit, title, biggest_pot);
+        leaderboard.save(LEADERBOARD_PATH)
+    });
+    if let Err(e) = recorded {
+        session.show(&format!(
+            "Could not update the leaderboard in {LEADERBOARD_PATH}: {e}"
+        ));
+    }
+}
+
+fn show_leaderboard(renderer: &mut dyn Renderer) {
+    let mut leaderboard = match Leaderboard::load(LEADERBOARD_PATH) {
+        Ok(leaderboard) => leaderboard,
+        Err(e) => {
+            return renderer.line(&format!(
+                "Could not read the leaderboard in {LEADERBOARD_PATH}: {e}"
+            ))
+        }
+    };
+    for line in leaderboard.to_lines() {
+        renderer.line(&format!("    {}", line));
+    }
+    let answer = prompt(
+        renderer,
+        "Enter n to end the season and start a new one, or leave empty:",
+    );
+    if answer.as_deref().map(str::trim) == Some("n") && leaderboard.new_season() {
+        match leaderboard.save(LEADERBOARD_PATH) {
+            Ok(()) => renderer.line(&format!("Season {} has started", leaderboard.season.number)),
+            Err(e) => renderer.line(&format!(
+                "Could not save the leaderboard to {LEADERBOARD_PATH}: {e}"
+            )),
+        }
+    }
+}
+
+fn profile_stats_path(profile: &str) -> PathBuf {
+    PathBuf::from(format!("poker-tui-{profile}.stats"))
+}
+
+/// The first human seat, the one a profile plays.
+fn hero(session: &Session) -> Option<usize> {
+    session.seats.iter().position(|seat| !seat.is_bot())
+}
+
+/// The profile's stats from earlier sessions with this session's added.
+fn profile_stats(profile: &str, session: &Session) -> io::Result<PlayerStats> {
+    let mut stats = PlayerStats::load(profile_stats_path(profile))?;
+    if let Some(hero) = hero(session) {
+        stats.merge(session.stats.player(hero));
+    }
+    Ok(stats)
+}
+
+/// Adds how the first human seat played this session to the profile's stats.
+fn save_profile_stats(profile: &str, session: &mut Session) {
+    let path = profile_stats_path(profile);
+    if let Err(e) = profile_stats(profile, session).and_then(|stats| stats.save(&path)) {
+        session.show(&format!(
+            "Could not save the stats to {}: {e}",
+            path.display()
+        ));
+    }
+}
+
+/// Pays for the chips the first human seat sits down with from the profile's bankroll.
+/// Returns whether it could.
+fn buy_in(renderer: &mut dyn Renderer, profile: &str, chips: u32) -> bool {
+    let path = bankroll_path(profile);
+    let mut bankroll = match Bankroll::load(&path) {
+        Ok(bankroll) => bankroll,
+        Err(e) => {
+            renderer.line(&format!(
+                "Could not read the bankroll in {}: {e}",
+                path.display()
+            ));
+            return false;
+        }
+    };
+    if let Err(e) = bankroll.buy_in(chips.into(), now_ms()) {
+        renderer.line(&format!("Can't sit down, {e}"));
+        return false;
+    }
+    match bankroll.save(&path) {
+        Ok(()) => {
+            renderer.line(&format!(
+                "Bought in for {chips}, {} left in the bankroll",
+                bankroll.balance
+            ));
+            true
+        }
+        Err(e) => {
+            renderer.line(&format!(
+                "Could not save the bankroll to {}: {e}",
+                path.display()
+            ));
+            false
+        }
+    }
+}
+
+/// Puts the chips the first human seat left the table with back in the profile's bankroll.
+fn cash_out(renderer: &mut dyn Renderer, profile: &str, chips: u32) {
+    let path = bankroll_path(profile);
+    let cashed_out = Bankroll::load(&path).and_then(|mut bankroll| {
+        bankroll.cash_out(chips.into(), now_ms());
+        bankroll.save(&path).map(|()| bankroll.balance)
+    });
+    match cashed_out {
+        Ok(balance) => renderer.line(&format!(
+            "Cashed out {chips}, the bankroll is now {balance}"
+        )),
+        Err(e) => renderer.line(&format!("Could not cash out to {}: {e}", path.display())),
+    }
+}
+
+/// Plays hands until the players leave, starting with `hand` if one is in progress, and
+/// returns the game as it was after the last hand finished.
+fn play_game(
+    gs: GameState,
+    hand: Option<HandState>,
+    settings: &Settings,
+    seats: Vec<Option<BotKind>>,
+) -> GameState {
+    let mut renderer = settings.renderer();
+    let Some(bots) = build_bots(
+        &mut *renderer,
+        seats.iter().map(Option::as_ref),
+        settings.bot_seed,
+    ) else {
+        return gs;
+    };
+    let controllers = bots
+        .into_iter()
+        .map(|bot| -> Box<dyn SeatController> {
+            match bot {
+                Some(bot) => Box::new(BotSeat {
+                    bot,
+                    delay: settings.bot_delay,
+                }),
+                None => Box::new(KeyboardSeat {
+                    turn_timeout: settings.turn_timeout,
+                }),
+            }
+        })
+        .collect();
+    let names = seats
+        .iter()
+        .enumerate()
+        .map(|(p, seat)| seat_name(p, seat.as_ref()))
+        .collect();
+    let mut gs = gs;
+    let event_log = EventLog::open(&mut *renderer, settings);
+    let mut session = Session {
+        saved_seats: seats.iter().map(seat_spec).collect::<Vec<_>>().join(","),
+        overlay: settings.training,
+        coach: settings
+            .coach
+            .then(|| Coach::init(settings.bot_samples.unwrap_or(HARD_BOT_SAMPLES))),
+        difficulty: settings
+            .adaptive_difficulty
+            .then(|| DifficultyDial::new(&seats))
+            .flatten(),
+        #[cfg(feature = "sqlite")]
+        database: open_stats_database(&mut *renderer, settings),
+        renderer,
+        ..Session::new(&gs, controllers, names)
+    };
+    #[cfg(not(feature = "sqlite"))]
+    if settings.stats_db.is_some() {
+        session.show("A stats database needs poker-tui built with the sqlite feature");
+    }
+
+    if let Some(profile) = &settings.profile {
+        show_table_notes(profile, &mut session);
+    }
+
+    gs.subscribe(event_log.clone());
+    let mut hand = hand;
+    if let Some(hs) = hand.as_mut() {
+        for event in hs.events() {
+            event_log.lock().unwrap().on_event(event);
+        }
+        hs.subscribe(event_log);
+    }
+    loop {
+        session.hand_number += 1;
+        session.show("\n\nNEW HAND\n\n");
+        match play_hand(&gs, hand.take(), settings, &mut session) {
+            Some(next) => gs = next,
+            None => break,
+        }
+        autosave(
+            &mut *session.renderer,
+            settings,
+            &session.saved_seats,
+            &gs,
+            None,
+        );
+        if let Some(winner) = last_standing(&gs) {
+            session.show(&format!("\nPlayer {winner} has won every chip."));
+            break;
+        }
+    }
+    // Players who leave the table are done with the game, but a closed terminal may not be.
+    if let Some(path) = settings
+        .autosave
+        .as_ref()
+        .filter(|_| !INPUT_CLOSED.load(Ordering::Relaxed))
+    {
+        let _ = fs::remove_file(path);
+    }
+
+    show_session_summary(&mut session);
+    if let Some(profile) = &settings.profile {
+        save_profile_stats(profile, &mut session);
+        record_on_leaderboard(profile, &mut session, &gs);
+    }
+    gs
+}
+
+/// The only player with chips left, once everyone else is out.
+fn last_standing(gs: &GameState) -> Option<usize> {
+    let mut standing = (0..gs.players()).filter(|&p| gs.current_chips(p) > 0);
+    match (standing.next(), standing.next()) {
+        (Some(winner), None) => Some(winner),
+        _ => None,
+    }
+}
+
+const BOT_DELAY: Duration = Duration::from_millis(500);
+/// Turns in a row a player may let run out of time before a bot takes over their seat.
+const AWAY_AFTER_TIMEOUTS: u32 = 2;
+
+/// Somewhere between half and one and a half times `delay`, so the bots at a table don't
+/// all act to the same beat.
+fn thinking_time(delay: Duration) -> Duration {
+    delay.mul_f64(rand::random_range(0.5..1.5))
+}
+
+/// Starts the bot for every seat that has one, or reports which one failed to start. Each
+/// seat gets its own seed derived from `seed`.
+fn build_bots<'a>(
+    renderer: &mut dyn Renderer,
+    seats: impl Iterator<Item = Option<&'a BotKind>>,
+    seed: Option<u64>,
+) -> Option<Vec<Option<Box<dyn BotPlayer>>>> {
+    let mut bots = vec![];
+    for (p, seat) in seats.enumerate() {
+        let seed = seed.map(|s| s.wrapping_add(p as u64));
+        match seat.map(|kind| kind.build_seeded(seed)).transpose() {
+            Ok(bot) => bots.push(bot),
+            Err(e) => {
+                renderer.line(&format!("Could not start the bot for seat {p}: {e}"));
+                return None;
+            }
+        }
+    }
+    Some(bots)
+}
+
+/// Parses seats like `h,b,b:hard`, where `b` alone is `default_bot`. Someone has to be at
+/// the keyboard, so at least one seat must be a human.
+fn parse_seats(spec: &str, default_bot: BotKind) -> Option<Vec<Option<BotKind>>> {
+    let seats = spec
+        .split(',')
+        .map(|seat| parse_seat(seat.trim(), &default_bot))
+        .collect::<Option<Vec<_>>>()?;
+    seats.contains(&None).then_some(seats)
+}
+
+fn parse_seat(seat: &str, default_bot: &BotKind) -> Option<Option<BotKind>> {
+    match seat {
+        "h" => Some(None),
+        "b" => Some(Some(default_bot.clone())),
+        _ => BotKind::parse(seat.strip_prefix("b:")?).map(Some),
+    }
+}
+
+fn seat_spec(seat: &Option<BotKind>) -> String {
+    match seat {
+        Some(bot) => format!("b:{}", bot_spec(bot)),
+        None => "h".to_owned(),
+    }
+}
+
+/// The bot written the way `BotKind::parse` reads it.
+fn bot_spec(bot: &BotKind) -> String {
+    match bot {
+        BotKind::Easy => "easy".to_owned(),
+        BotKind::Normal => "normal".to_owned(),
+        BotKind::Hard(HARD_BOT_SAMPLES) => "hard".to_owned(),
+        BotKind::Hard(samples) => format!("mc={samples}"),
+        BotKind::External(command) => format!("exec={command}"),
+        BotKind::Adaptive => "adaptive".to_owned(),
+        BotKind::PushFold => "pushfold".to_owned(),
+        BotKind::Cfr => "cfr".to_owned(),
+        BotKind::Chart(None) => "chart".to_owned(),
+        BotKind::Chart(Some(path)) => format!("chart={path}"),
+        BotKind::Onnx(path) => format!("onnx={path}"),
+        BotKind::Personality(p) => match PERSONALITIES.iter().find(|(_, preset)| preset == p) {
+            Some((name, _)) => name.to_string(),
+            None => format!(
+                "vpip={}:aggression={}:bluff={}",
+                p.vpip, p.aggression, p.bluff
+            ),
+        },
+        BotKind::Mixed(kind, mix) => format!(
+            "{}@bluff={}:slowplay={}:hero_call={}",
+            bot_spec(kind),
+            mix.bluff,
+            mix.slowplay,
+            mix.hero_call
+        ),
+    }
+}
+
+/// Plays one hand, or returns `None` if the players leave the table before it is over. A
+/// hand already in progress is played on from where it stands.
+fn play_hand(
+    gs: &GameState,
+    hand: Option<HandState>,
+    settings: &Settings,
+    session: &mut Session,
+) -> Option<GameState> {
+    let (mut hs, mut cur) = match hand {
+        Some(hs) => {
+            let cur = hs.current_player();
+            (hs, cur)
+        }
+        None => gs.start_play_hand(Deck::shuffled_deck()),
+    };
+    loop {
+        session.show("");
+        let snapshot = session.snapshot(settings, &hs);
+        draw_table(
+            &mut *session.renderer,
+            &snapshot,
+            &session.chat,
+            &session.toasts.active(),
+            settings.compact_layout(snapshot.players),
+        );
+        if !session.is_bot(cur) {
+            // The overlay would reveal the acting player's cards on a shared screen.
+            if session.overlay && !settings.spectator {
+                session.show(&format!(
+                    "    {}",
+                    pretty_print_overlay(&hs.spectator_snapshot())
+                ));
+            }
+            session.show(&format!("    {}", pretty_print_bet_presets(&hs)));
+            if settings.practice {
+                session.show("    Practice mode: (U)ndo the last action");
+            }
+            if session.coach.is_some() {
+                session.show("    Coach: (A)dvice for this decision");
+            }
+            session.ring_bell(settings);
+        }
+
+        let input = (!session.is_away(cur)).then(|| session.seats[cur].next_input(&hs, cur));
+        let decided_at_keyboard = matches!(input, Some(SeatInput::Typed(_)));
+        let (action, reason) = match input {
+            Some(SeatInput::Decided(action, reason)) => (action, reason),
+            None => {
+                // Anything typed while the bot stands in brings the player back.
+                match poll_input() {
+                    Some(Input::Closed) => return None,
+                    Some(_) => {
+                        session.timeouts[cur] = 0;
+                        session.show(&format!("\nWelcome back, player {cur}."));
+                        continue;
+                    }
+                    None => {}
+                }
+                thread::sleep(thinking_time(settings.bot_delay));
+                let action = CallingStation.decide(&hs.bot_context(cur));
+                (action, Some("standing in for an absent player".to_owned()))
+            }
+            Some(SeatInput::TimedOut) => {
+                session.timeouts[cur] += 1;
+                if session.is_away(cur) {
+                    session.show(&format!(
+                        "\nPlayer {cur} ran out of time {AWAY_AFTER_TIMEOUTS} times in a row, \
+                         an easy bot plays for them until they press enter."
+                    ));
+                } else {
+                    session.show(&format!("\nPlayer {cur} ran out of time."));
+                }
+                let action = match hs.expected_call() {
+                    0 => CallOrCheck,
+                    _ => Fold,
+                };
+                (action, None)
+            }
+            Some(SeatInput::Left) => return None,
+            Some(SeatInput::Interrupted) => {
+                session.timeouts[cur] = 0;
+                session.show("\nInterrupted.");
+                if pause_menu(&mut *session.renderer, &session.saved_seats, gs, &hs) {
+                    continue;
+                }
+                return None;
+            }
+            Some(SeatInput::Typed(action_str)) => {
+                session.timeouts[cur] = 0;
+                if action_str.trim().eq_ignore_ascii_case("q") {
+                    return None;
+                }
+                if action_str.trim().eq_ignore_ascii_case("p") {
+                    if pause_menu(&mut *session.renderer, &session.saved_seats, gs, &hs) {
+                        continue;
+                    }
+                    return None;
+                }
+                if handle_chat_command(&mut session.chat, &mut *session.renderer, cur, &action_str)
+                {
+                    continue;
+                }
+                if handle_notes_command(settings, session, &action_str) {
+                    continue;
+                }
+                if action_str.trim().eq_ignore_ascii_case("o") {
+                    session.overlay = !session.overlay;
+                    continue;
+                }
+                if settings.practice && action_str.trim().eq_ignore_ascii_case("u") {
+                    // Bots would just replay their move, so go back to the last human decision.
+                    let mut previous = hs.undo_last_action();
+                    while let Some(p) = previous
+                        .as_ref()
+                        .filter(|p| session.is_bot(p.current_player()))
+                    {
+                        previous = p.undo_last_action();
+                    }
+                    match previous {
+                        Some(previous) => {
+                            hs = previous;
+                            cur = hs.current_player();
+                            session.show("\nUndid the last action.");
+                        }
+                        None => session.show("\nNothing to undo."),
+                    }
+                    continue;
+                }
+                if let Some((advice, reason)) = session
+                    .coach
+                    .as_mut()
+                    .filter(|_| action_str.trim().eq_ignore_ascii_case("a"))
+                    .map(|coach| {
+                        let (advice, reason) = coach.advise(&hs.bot_context(cur));
+                        (advice, reason.map(str::to_owned))
+                    })
+                {
+                    session.show(&format!(
+                        "    Coach suggests: {}",
+                        pretty_print_advice(&advice)
+                    ));
+                    if let Some(reason) = reason {
+                        session.show(&format!("    ({reason})"));
+                    }
+                    continue;
+                }
+                if action_str.trim().eq_ignore_ascii_case("h") {
+                    for line in pretty_print_ranking_reference() {
+                        session.show(&format!("    {line}"));
+                    }
+                    continue;
+                }
+                if action_str.trim().eq_ignore_ascii_case("s") {
+                    for line in pretty_print_stats(&session.stats) {
+                        session.show(&format!("    {line}"));
+                    }
+                    if let Some(profile) = &settings.profile {
+                        for line in pretty_print_profile_stats(profile, session) {
+                            session.show(&format!("    {line}"));
+                        }
+                        for line in pretty_print_bankroll(profile) {
+                            session.show(&format!("    {line}"));
+                        }
+                    }
+                    #[cfg(feature = "sqlite")]
+                    if let Some(database) = &session.database {
+                        for line in
+                            pretty_print_all_time_stats(database, session.stats.players().len())
+                        {
+                            session.show(&format!("    {line}"));
+                        }
+                    }
+                    continue;
+                }
+                let action = parse_bet_preset(&action_str)
+                    .map(|size| Raise(hs.bet_size(size)))
+                    .or_else(|| parse_action(&action_str));
+                let Some(action) = action else {
+                    session.show(&format!("Invalid action {}", action_str));
+                    continue;
+                };
+                (action, None)
+            }
+        };
+
+        session.show(&pretty_print_action(&action, cur));
+        if let Some(reason) = reason.filter(|_| settings.training) {
+            session.show(&format!("    ({reason})"));
+        }
+        let view = hs.bot_context(cur);
+        let (played, result) = act(hs, action);
+        hs = played;
+        let result = result.map(|(result, events)| {
+            session.toasts.push_events(&events);
+            result
+        });
+        if let Some(coach) = session.coach.as_mut() {
+            if result.is_ok() && decided_at_keyboard {
+                coach.record(&view, action);
+            }
+        }
+        if result.is_ok() && went_all_in(&hs.spectator_snapshot(), cur) {
+            session.toasts.push(format!("Player {cur} is all-in!"));
+            session.ring_bell(settings);
+        }
+        match result {
+            Ok(poker_tui::TurnResult::NextPlayer(p)) => {
+                cur = p;
+                if settings.autosave_actions {
+                    autosave(
+                        &mut *session.renderer,
+                        settings,
+                        &session.saved_seats,
+                        gs,
+                        Some(&hs),
+                    );
+                }
+            }
+            Ok(poker_tui::TurnResult::WonHand(p)) => {
+                if hs.is_showdown() {
+                    session.ring_bell(settings);
+                    for line in pretty_print_showdown(&hs) {
+                        session.show(&format!("    {line}"));
+                    }
+                    if settings.equity_review {
+                        for line in pretty_print_equity_review(hs.history()) {
+                            session.show(&format!("    {line}"));
+                        }
+                    }
+                }
+                for seat in &mut session.seats {
+                    seat.hand_finished(hs.events());
+                }
+                session.show("");
+                session.show("###########################");
+                session.show(&format!("# Player {} won the round #", p));
+                session.show("###########################");
+                session.show("");
+                session.stats.record_hand(hs.events());
+                record_all_in(session, &hs);
+                record_in_database(session, &hs);
+                save_history(
+                    &mut *session.renderer,
+                    settings,
+                    session.hand_number,
+                    hs.history(),
+                );
+                save_pokerstars_history(settings, session, &hs);
+                save_phh_history(&mut *session.renderer, settings, session.hand_number, &hs);
+                let next = gs.apply_played_hand(hs);
+                adjust_difficulty(gs, &next, settings, session);
+                return Some(next);
+            }
+            Err(IllegalRaise::TooBig) => session.show(&format!(
+                "Raised by too much, raise at most {}",
+                hs.max_raise()
+            )),
+            Err(IllegalRaise::TooSmall) => session.show(&format!(
+                "Raised by too little, raise at least {} or go all in",
+                hs.min_raise().unwrap_or(hs.max_raise())
+            )),
+        }
+    }
+}
+
+/// Plays `action` in a hand still being played, and hands the hand back with who acts next
+/// or won and the events the action caused.
+fn act(
+    hs: HandState,
+    action: PokerAction,
+) -> (
+    HandState,
+    Result<(poker_tui::TurnResult, Vec<GameEvent>), IllegalRaise>,
+) {
+    let hand = AwaitingAction::resume(hs)
+        .ok()
+        .expect("only a hand still being played waits for an action");
+    match hand.act(action) {
+        Ok((phase, events)) => {
+            let result = phase.result();
+            (phase.into_hand(), Ok((result, events)))
+        }
+        Err((hand, e)) => (hand.into_hand(), Err(e)),
+    }
+}
+
+/// Moves the dialed bots a level when the human players won or lost enough lately.
+fn adjust_difficulty(
+    before: &GameState,
+    after: &GameState,
+    settings: &Settings,
+    session: &mut Session,
+) {
+    let Some(dial) = session.difficulty.as_mut() else {
+        return;
+    };
+    let net_chips = (0..before.players())
+        .filter(|&p| !session.seats[p].is_bot())
+        .map(|p| after.current_chips(p) as i64 - before.current_chips(p) as i64)
+        .sum();
+    if !dial.record_hand(net_chips) {
+        return;
+    }
+    for &p in dial.seats() {
+        let seed = settings.bot_seed.map(|s| s.wrapping_add(p as u64));
+        if let Ok(bot) = dial.bot().build_seeded(seed) {
+            session.seats[p] = Box::new(BotSeat {
+                bot,
+                delay: settings.bot_delay,
+            });
+        }
+    }
+    session.toasts.push(format!(
+        "The bots now play at {} level",
+        bot_spec(dial.bot())
+    ));
+}
+
+fn show_session_summary(session: &mut Session) {
+    let summary = pretty_print_session_summary(session);
+    for line in &summary {
+        session.show(&format!("    {line}"));
+    }
+
+    let Some(path) = prompt(
+        &mut *session.renderer,
+        "Save the summary to a file? Enter a path or leave empty:",
+    ) else {
+        return;
+    };
+    let path = path.trim();
+    if !path.is_empty() {
+        if let Err(e) = fs::write(path, summary.join("\n") + "\n") {
+            session.show(&format!("Could not save the summary to {path}: {e}"));
+        }
+    }
+}
+
+fn save_history(
+    renderer: &mut dyn Renderer,
+    settings: &Settings,
+    hand_number: usize,
+    history: &HandHistory,
+) {
+    if let Some(dir) = &settings.history_dir {
+        let path = dir.join(format!("hand-{hand_number}.txt"));
+        if let Err(e) = fs::create_dir_all(dir).and_then(|_| fs::write(&path, history.to_text())) {
+            renderer.line(&format!(
+                "Could not save hand history to {}: {e}",
+                path.display()
+            ));
+        }
+    }
+}
+
+/// Appends the hand to the PokerStars hand history file, as seen by the only player at the
+/// keyboard if there is just one.
+fn save_pokerstars_history(settings: &Settings, session: &mut Session, hs: &HandState) {
+    let Some(path) = &settings.pokerstars_file else {
+        return;
+    };
+    let mut humans = (0..session.seats.len()).filter(|&p| !session.seats[p].is_bot());
+    let hero = humans.next().filter(|_| humans.next().is_none());
+    let finished_ms = now_ms();
+    let id = finished_ms * 1000 + session.hand_number as u64 % 1000;
+    let text = pokerstars::hand_history(hs, id, "poker-tui", finished_ms, hero);
+    let written = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .and_then(|mut file| file.write_all((text + "\n\n").as_bytes()));
+    if let Err(e) = written {
+        session.show(&format!(
+            "Could not save the hand to {}: {e}",
+            path.display()
+        ));
+    }
+}
+
+fn save_phh_history(
+    renderer: &mut dyn Renderer,
+    settings: &Settings,
+    hand_number: usize,
+    hs: &HandState,
+) {
+    if let Some(dir) = &settings.phh_dir {
+        let path = dir.join(format!("hand-{hand_number}.phh"));
+        let text = PhhHand::from_hand(hs, Some(hand_number as u64), None).to_toml();
+        if let Err(e) = fs::create_dir_all(dir).and_then(|_| fs::write(&path, text)) {
+            renderer.line(&format!(
+                "Could not save hand history to {}: {e}",
+                path.display()
+            ));
+        }
+    }
+}
+
+/// Replays the hands in a hand history file, in any format `import` reads.
+fn run_replay(renderer: &mut dyn Renderer, path: &PathBuf) {
+    let text = match fs::read_to_string(path) {
+        Ok(text) => text,
+        Err(e) => return renderer.line(&format!("Can't read {}: {e}", path.display())),
+    };
+    let hands = match import::read_hands(&text) {
+        Ok(hands) if !hands.is_empty() => hands,
+        Ok(_) => return renderer.line(&format!("There are no hands in {}", path.display())),
+        Err(e) => return renderer.line(&format!("Can't replay {}: {e}", path.display())),
+    };
+    let mut hand = 0;
+    let mut history = &hands[hand];
+    let mut snapshots = history.replay();
+    let mut last = snapshots.len() - 1;
+    let mut step = 0;
+
+    loop {
+        let description = if step == 0 {
+            "Blinds posted".to_owned()
+        } else {
+            pretty_print_action(
+                &history.actions[step - 1],
+                snapshots[step - 1].current_player,
+            )
+        };
+        let of_hands = if hands.len() > 1 {
+            format!("hand {}/{} ", hand + 1, hands.len())
+        } else {
+            String::new()
+        };
+        renderer.line(&format!(
+            "\n\nREPLAY {of_hands}step {step}/{last}: {}\n",
+            description.trim()
+        ));
+        for line in pretty_print_table(&snapshots[step]) {
+            renderer.line(&format!("    {}", line));
+        }
+        if hands.len() > 1 {
+            renderer.line("(N)ext (P)revious next (S)treet (B)ack a street (]) next hand ([) previous hand (Q)uit");
+        } else {
+            renderer.line("(N)ext (P)revious next (S)treet (B)ack a street (Q)uit");
+        }
+
+        let Input::Line(input) = read_input() else {
+            return;
+        };
+        let street = |i: usize| snapshots[i].board.len();
+        match input.trim().to_ascii_lowercase().as_str() {
+            "n" => step = (step + 1).min(last),
+            "p" => step = step.saturating_sub(1),
+            "s" => {
+                let current = street(step);
+                while step < last && street(step) == current {
+                    step += 1;
+                }
+            }
+            "b" => {
+                step = step.saturating_sub(1);
+                let current = street(step);
+                while step > 0 && street(step - 1) == current {
+                    step -= 1;
+                }
+            }
+            "]" | "[" => {
+                hand = match input.trim() {
+                    "]" => (hand + 1).min(hands.len() - 1),
+                    _ => hand.saturating_sub(1),
+                };
+                history = &hands[hand];
+                snapshots = history.replay();
+                last = snapshots.len() - 1;
+                step = 0;
+            }
+            "q" => return,
+            other => renderer.line(&format!("Invalid command {other}")),
+        }
+    }
+}
+
+/// `poker-tui simulate [--hands N] [--stack S] [--seed N] [--log FILE] [--series FILE] BOTS`
+/// plays bots against each other without any UI, BOTS being a comma separated list like
+/// `easy,normal,hard`. With `--log` every decision and its reason is written to FILE as
+/// JSON lines. `--series` writes every bot's bankroll after each hand, as JSON when FILE
+/// ends in `.json` and as CSV otherwise. `--seed` makes the deal and the bots' own random
+/// choices repeatable, so a run can be reproduced from a single number.
+fn run_simulation(mut args: impl Iterator<Item = String>) {
+    let renderer: &mut dyn Renderer = &mut TerminalRenderer;
+    let mut hands = 1000;
+    let mut stack = 100;
+    let mut bots = None;
+    let mut log_path = None;
+    let mut series_path = None;
+    let mut seed = None;
+    while let Some(arg) = args.next() {
+        let number = |value: Option<String>| value.and_then(|v| v.parse().ok());
+        match arg.as_str() {
+            "--hands" => match number(args.next()) {
+                Some(n) => hands = n,
+                None => return renderer.line("--hands needs a number of hands"),
+            },
+            "--stack" => match number(args.next()) {
+                Some(n) => stack = n,
+                None => return renderer.line("--stack needs a number of chips"),
+            },
+            "--seed" => match args.next().and_then(|v| v.parse().ok()) {
+                Some(n) => seed = Some(n),
+                None => return renderer.line("--seed needs a number"),
+            },
+            "--log" => match args.next() {
+                Some(path) => log_path = Some(path),
+                None => return renderer.line("--log needs a file for the bot decisions"),
+            },
+            "--series" => match args.next() {
+                Some(path) => series_path = Some(path),
+                None => return renderer.line("--series needs a file for the bankrolls"),
+            },
+            spec => match spec
+                .split(',')
+                .map(BotKind::parse)
+                .collect::<Option<Vec<_>>>()
+            {
+                Some(kinds) if kinds.len() >= 2 => bots = Some(kinds),
+                _ => {
+                    return renderer.line(&format!(
+                        "Expected at least two bots like easy,normal,hard, got {spec}"
+                    ))
+                }
+            },
+        }
+    }
+    let kinds = bots.unwrap_or(vec![BotKind::Normal, BotKind::Normal]);
+    if stack < 2 {
+        return renderer.line("Bots need a stack of at least 2 chips");
+    }
+
+    let Some(bots) = build_bots(renderer, kinds.iter().map(Some), seed) else {
+        return;
+    };
+    let out: Box<dyn Write> = match &log_path {
+        Some(path) => match File::create(path) {
+            Ok(file) => Box::new(BufWriter::new(file)),
+            Err(e) => {
+                return renderer.line(&format!("Could not write the decision log to {path}: {e}"))
+            }
+        },
+        None => Box::new(io::sink()),
+    };
+    let names = kinds
+        .iter()
+        .enumerate()
+        .map(|(p, kind)| seat_name(p, Some(kind)))
+        .collect();
+    let bots = bots.into_iter().flatten().collect();
+    let started = Instant::now();
+    let result = match simulate_at_table(bots, names, hands, stack, seed, out) {
+        Ok(result) => result,
+        Err(e) => {
+            let path = log_path.expect("only the decision log can fail");
+            return renderer.line(&format!("Could not write the decision log to {path}: {e}"));
+        }
+    };
+
+    renderer.line(&format!(
+        "{hands} hands in {:.1}s",
+        started.elapsed().as_secs_f64()
+    ));
+    renderer.line(&format!(
+        "{:<6} {:<12} {:>9} {:>10} {:>10} {:>14} {:>12}",
+        "Seat", "Bot", "Win rate", "Net chips", "bb/100", "SD (bb/hand)", "EV bb/100"
+    ));
+    for (p, (kind, seat)) in kinds.iter().zip(&result.seats).enumerate() {
+        renderer.line(&format!(
+            "{:<6} {:<12} {:>8.1}% {:>10} {:>10.1} {:>14.2} {:>12.1}",
+            p,
+            bot_spec(kind),
+            100.0 * seat.win_rate(hands),
+            seat.net_chips,
+            seat.bb_per_100(hands),
+            seat.std_dev_bb(hands),
+            seat.all_in_ev_bb_per_100(hands)
+        ));
+    }
+
+    if let Some(path) = series_path {
+        let names: Vec<String> = kinds.iter().map(bot_spec).collect();
+        let written = File::create(&path).and_then(|file| {
+            let mut out = BufWriter::new(file);
+            if path.ends_with(".json") {
+                result.write_json(&names, &mut out)?;
+            } else {
+                result.write_csv(&names, &mut out)?;
+            }
+            out.flush()
+        });
+        match written {
+            Ok(()) => renderer.line(&format!("Wrote the bankrolls to {path}")),
+            Err(e) => renderer.line(&format!("Could not write the bankrolls to {path}: {e}")),
+        }
+    }
+}
+
+/// Plays a simulation's hands the way the interactive game plays them, with nothing shown
+/// and every decision written to `out`.
+fn simulate_at_table(
+    bots: Vec<Box<dyn BotPlayer>>,
+    names: Vec<String>,
+    hands: u32,
+    stack: u32,
+    seed: Option<u64>,
+    out: Box<dyn Write>,
+) -> io::Result<SimulationResult> {
+    let players = bots.len();
+    let log = Rc::new(RefCell::new(DecisionLog {
+        out,
+        hand: 0,
+        error: None,
+    }));
+    let seats = bots
+        .into_iter()
+        .map(|bot| -> Box<dyn SeatController> {
+            Box::new(LoggedSeat {
+                seat: BotSeat {
+                    bot,
+                    delay: Duration::ZERO,
+                },
+                log: log.clone(),
+            })
+        })
+        .collect();
+    let mut settings = Settings::from_args(&mut SilentRenderer, std::iter::empty());
+    settings.quiet = true;
+    settings.autosave = None;
+    let table = GameState::init_with_stack(players, stack).expect("at least two bots");
+    let mut session = Session {
+        renderer: settings.renderer(),
+        ..Session::new(&table, seats, names)
+    };
+    let result = simulate_with(players, hands, stack, seed, |hand, gs, hs| {
+        log.borrow_mut().hand = hand;
+        session.hand_number += 1;
+        let next =
+            play_hand(gs, Some(hs), &settings, &mut session).expect("bots never leave the table");
+        match log.borrow_mut().error.take() {
+            Some(e) => Err(e),
+            None => Ok(next),
+        }
+    })?;
+    log.borrow_mut().out.flush()?;
+    Ok(result)
+}
+
+/// `poker-tui tournament [--format round-robin|bracket] [--hands N] [--stack S] [--seed N]
+/// BOTS` plays heads-up matches of N hands between the bots, BOTS being a comma separated
+/// list like `simulate` takes, and prints a leaderboard.
+fn run_tournament_mode(mut args: impl Iterator<Item = String>) {
+    let renderer: &mut dyn Renderer = &mut TerminalRenderer;
+    let mut format = Format::RoundRobin;
+    let mut hands = 1000;
+    let mut stack = 100;
+    let mut seed = None;
+    let mut entrants = None;
+    while let Some(arg) = args.next() {
+        let number = |value: Option<String>| value.and_then(|v| v.parse().ok());
+        match arg.as_str() {
+            "--format" => match args.next().as_deref().and_then(Format::parse) {
+                Some(f) => format = f,
+                None => return renderer.line("--format needs round-robin or bracket"),
+            },
+            "--hands" => match number(args.next()) {
+                Some(n) => hands = n,
+                None => return renderer.line("--hands needs a number of hands per match"),
+            },
+            "--stack" => match number(args.next()) {
+                Some(n) => stack = n,
+                None => return renderer.line("--stack needs a number of chips"),
+            },
+            "--seed" => match args.next().and_then(|v| v.parse().ok()) {
+                Some(n) => seed = Some(n),
+                None => return renderer.line("--seed needs a number"),
+            },
+            spec => match spec
+                .split(',')
+                .map(BotKind::parse)
+                .collect::<Option<Vec<_>>>()
+            {
+                Some(kinds) if kinds.len() >= 2 => entrants = Some(kinds),
+                _ => {
+                    return renderer.line(&format!(
+                        "Expected at least two bots like easy,normal,hard, got {spec}"
+                    ))
+                }
+            },
+        }
+    }
+    let Some(entrants) = entrants else {
+        return renderer.line("Expected at least two bots like easy,normal,hard");
+    };
+    if stack < 2 {
+        return renderer.line("Bots need a stack of at least 2 chips");
+    }
+
+    let started = Instant::now();
+    let standings = match run_tournament(&entrants, format, hands, stack, seed) {
+        Ok(standings) => standings,
+        Err(e) => return renderer.line(&format!("Could not start a bot: {e}")),
+    };
+    renderer.line(&format!(
+        "Tournament done in {:.1}s",
+        started.elapsed().as_secs_f64()
+    ));
+    renderer.line(&format!(
+        "{:<5} {:<20} {:>8} {:>8} {:>10} {:>16}",
+        "Rank", "Bot", "Matches", "Hands", "bb/100", "95% interval"
+    ));
+    for (rank, standing) in standings.iter().enumerate() {
+        renderer.line(&format!(
+            "{:<5} {:<20} {:>8} {:>8} {:>10.1} {:>16}",
+            rank + 1,
+            format!(
+                "{} #{}",
+                bot_spec(&entrants[standing.entrant]),
+                standing.entrant
+            ),
+            format!("{}/{}", standing.matches_won, standing.matches_played),
+            standing.hands,
+            standing.bb_per_100(),
+            format!("+/- {:.1}", standing.confidence_95())
+        ));
+    }
+}
+
+const PUSH_FOLD_SAMPLES: usize = 1000;
+
+/// `poker-tui pushfold-table [--samples N]` solves the push/fold table the `pushfold` bot
+/// plays by and prints it, N being the boards dealt per pair of starting hands.
+fn run_push_fold_table(mut args: impl Iterator<Item = String>) {
+    let mut samples = PUSH_FOLD_SAMPLES;
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--samples" => match args.next().and_then(|v| v.parse().ok()) {
+                Some(n) => samples = n,
+                None => return eprintln!("--samples needs a number of boards"),
+            },
+            other => return eprintln!("Unknown argument {other}"),
+        }
+    }
+    eprintln!("Dealing {samples} boards for each pair of starting hands...");
+    let table = PushFoldTable::solve(&equity_matrix(samples));
+    print!("{}", table.to_text());
+}
+
+/// `poker-tui export-csv [--hands FILE] [--players FILE] HISTORIES` reads the hands in
+/// the hand history files, in any format `import` reads, and writes every player's result
+/// in each hand to the `--hands` CSV file and their totals to the `--players` one,
+/// `hands.csv` and `players.csv` unless given.
+fn run_csv_export(mut args: impl Iterator<Item = String>) {
+    let renderer: &mut dyn Renderer = &mut TerminalRenderer;
+    let mut hands_path = PathBuf::from("hands.csv");
+    let mut players_path = PathBuf::from("players.csv");
+    let mut files = vec![];
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--hands" => match args.next() {
+                Some(path) => hands_path = path.into(),
+                None => return renderer.line("--hands needs a file for the results of every hand"),
+            },
+            "--players" => match args.next() {
+                Some(path) => players_path = path.into(),
+                None => return renderer.line("--players needs a file for the players' totals"),
+            },
+            _ => files.push(PathBuf::from(arg)),
+        }
+    }
+    if files.is_empty() {
+        return renderer.line("export-csv needs the hand history files to export");
+    }
+
+    let mut stats: Option<SessionStats> = None;
+    for path in &files {
+        let hands = fs::read_to_string(path)
+            .map_err(|e| e.to_string())
+            .and_then(|text| import::read_hands(&text));
+        let hands = match hands {
+            Ok(hands) => hands,
+            Err(e) => return renderer.line(&format!("Could not read {}: {e}", path.display())),
+        };
+        for history in hands {
+            let stats = stats.get_or_insert_with(|| SessionStats::init(history.players()));
+            if history.players() != stats.players().len() {
+                return renderer.line(&format!(
+                    "{} has hands with {} players, the others have {}",
+                    path.display(),
+                    history.players(),
+                    stats.players().len()
+                ));
+            }
+            match history.play() {
+                Ok(hs) => stats.record_hand(hs.events()),
+                Err(e) => {
+                    return renderer
+                        .line(&format!("Could not play a hand in {}: {e}", path.display()))
+                }
+            }
+        }
+    }
+    let Some(stats) = stats else {
+        return renderer.line("There are no hands to export");
+    };
+
+    let names: Vec<_> = (0..stats.players().len())
+        .map(|p| format!("Player {p}"))
+        .collect();
+    let mut write = |path: &Path, csv: &dyn Fn(&mut dyn Write) -> io::Result<()>| {
+        let written = File::create(path).and_then(|file| {
+            let mut out = BufWriter::new(file);
+            csv(&mut out)?;
+            out.flush()
+        });
+        if let Err(e) = &written {
+            renderer.line(&format!("Could not write {}: {e}", path.display()));
+        }
+        written.is_ok()
+    };
+    if write(&hands_path, &|out| stats.write_hands_csv(&names, out))
+        && write(&players_path, &|out| stats.write_players_csv(&names, out))
+    {
+        renderer.line(&format!(
+            "Exported {} hands to {} and {}",
+            stats.hands(),
+            hands_path.display(),
+            players_path.display()
+        ));
+    }
+}
+
+/// `poker-tui analyze HISTORIES` reads every hand in the files of the `HISTORIES`
+/// directory, in any format `import` reads, and prints how each position and player did
+/// over all of them. Files that aren't hand histories are skipped.
+fn run_analysis(mut args: impl Iterator<Item = String>) {
+    let renderer: &mut dyn Renderer = &mut TerminalRenderer;
+    let Some(path) = args.next() else {
+        return renderer
+            .line("analyze needs a directory of hand histories, like the --history-dir");
+    };
+    let path = Path::new(&path);
+    let files = if path.is_dir() {
+        let entries = fs::read_dir(path).and_then(|entries| {
+            entries
+                .map(|entry| entry.map(|e| e.path()))
+                .collect::<io::Result<Vec<_>>>()
+        });
+        match entries {
+            Ok(mut files) => {
+                files.retain(|f| f.is_file());
+                files.sort();
+                files
+            }
+            Err(e) => return renderer.line(&format!("Could not read {}: {e}", path.display())),
+        }
+    } else {
+        vec![path.to_path_buf()]
+    };
+
+    let mut report = HistoryReport::default();
+    for file in &files {
+        let hands = fs::read_to_string(file)
+            .map_err(|e| e.to_string())
+            .and_then(|text| import::read_hands(&text));
+        let hands = match hands {
+            Ok(hands) => hands,
+            Err(e) => {
+                eprintln!("Skipping {}: {e}", file.display());
+                continue;
+            }
+        };
+        let name = file.file_name().unwrap_or_default().to_string_lossy();
+        let numbered = hands.len() > 1;
+        for (i, history) in hands.iter().enumerate() {
+            let hand = if numbered {
+                format!("{name} #{}", i + 1)
+            } else {
+                name.to_string()
+            };
+            match history.play() {
+                Ok(hs) => report.record_hand(hand, &hs),
+                Err(e) => eprintln!("Skipping {hand}: {e}"),
+            }
+        }
+    }
+    if report.hands() == 0 {
+        return renderer.line(&format!(
+            "There are no hands to analyze in {}",
+            path.display()
+        ));
+    }
+    print!("{}", report.to_text());
+}
+
+const DEMO_PLAYERS: usize = 4;
+const DEMO_ACTION_DELAY: Duration = Duration::from_millis(300);
+
+/// How long the invites the host prints when it starts are good for.
+const INVITE_HOURS: u64 = 24;
+
+/// `poker-tui host [--port P] [--ws-port W] [--players N] [--stack S]` waits for N players
+/// to join with `poker-tui join` and then runs their game, sending each of them only what
+/// their seat may see. With `--ws-port` players can also join over WebSocket on port W.
+///
+/// Every `--table NAME` opens another table, and the `--players`, `--stack`, `--password`,
+/// `--invite-only`, `--mental-shuffle`, `--spectator-delay`, `--turn-timeout` and
+/// `--sit-out-after` after it set it up. Those before the first `--table` set up the table called main.
+/// `--queue` makes the table a queue, which pairs players as they join and sits every pair
+/// at a heads-up table of its own.
+///
+/// Invites and resume tokens are signed with `--secret S`, or a secret nobody knows, and
+/// the host prints an invite for every table that is by invite only. With `--status-port P`
+/// it reports on its tables over HTTP on port P, and with `--advertise NAME` it can be
+/// found on the local network as NAME. With `--archive DIR` every hand dealt is kept in
+/// DIR.
+fn run_host(mut args: impl Iterator<Item = String>) {
+    let renderer: &mut dyn Renderer = &mut TerminalRenderer;
+    let mut config = ServerConfig {
+        tables: vec![],
+        ..ServerConfig::default()
+    };
+    fn table(tables: &mut Vec<TableConfig>) -> &mut TableConfig {
+        if tables.is_empty() {
+            tables.push(TableConfig::new("main"));
+        }
+        tables.last_mut().unwrap()
+    }
+    let tables = &mut config.tables;
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--port" => match args.next().and_then(|v| v.parse().ok()) {
+                Some(n) => config.port = n,
+                None => return renderer.line("--port needs a port number"),
+            },
+            "--ws-port" => match args.next().and_then(|v| v.parse().ok()) {
+                Some(n) => config.ws_port = Some(n),
+                None => return renderer.line("--ws-port needs a port number"),
+            },
+            "--players" => match args.next().and_then(|v| v.parse().ok()) {
+                Some(n) if n >= 2 => table(tables).players = n,
+                _ => return renderer.line("--players needs a number of at least 2"),
+            },
+            "--stack" => match args.next().and_then(|v| v.parse().ok()) {
+                Some(n) => table(tables).stack = n,
+                None => return renderer.line("--stack needs a number of chips"),
+            },
+            "--table" => match args.next() {
+                Some(name) if tables.iter().any(|t| t.name == name) => {
+                    return renderer.line(&format!("There already is a table called {name}"))
+                }
+                Some(name) => tables.push(TableConfig::new(&name)),
+                None => return renderer.line("--table needs a name"),
+            },
+            "--password" => match args.next() {
+                Some(password) => table(tables).password = Some(password),
+                None => return renderer.line("--password needs a password"),
+            },
+            "--spectator-delay" => match args.next().and_then(|v| v.parse().ok()) {
+                Some(secs) => table(tables).spectator_delay = Duration::from_secs(secs),
+                None => return renderer.line("--spectator-delay needs a number of seconds"),
+            },
+            "--turn-timeout" => match args.next().and_then(|v| v.parse().ok()) {
+                Some(0) => table(tables).turn_timeout = None,
+                Some(secs) => table(tables).turn_timeout = Some(Duration::from_secs(secs)),
+                None => {
+                    return renderer.line("--turn-timeout needs a number of seconds, 0 for none")
+                }
+            },
+            "--sit-out-after" => match args.next().and_then(|v| v.parse().ok()) {
+                Some(n) if n >= 1 => table(tables).sit_out_after = n,
+                _ => return renderer.line("--sit-out-after needs a number of at least 1"),
+            },
+            "--invite-only" => table(tables).invite_only = true,
+            "--mental-shuffle" => table(tables).mental_shuffle = true,
+            "--queue" => table(tables).queue = true,
+            "--status-port" => match args.next().and_then(|v| v.parse().ok()) {
+                Some(n) => config.status_port = Some(n),
+                None => return renderer.line("--status-port needs a port number"),
+            },
+            "--advertise" => match args.next() {
+                Some(name) => config.advertise = Some(name),
+                None => return renderer.line("--advertise needs a name to be found by"),
+            },
+            "--archive" => match args.next() {
+                Some(dir) => config.archive_dir = Some(PathBuf::from(dir)),
+                None => return renderer.line("--archive needs a directory to keep hands in"),
+            },
+            "--secret" => match args.next() {
+                Some(s) => config.secret = Some(s),
+                None => return renderer.line("--secret needs a secret to sign invites with"),
+            },
+            other => return renderer.line(&format!("Unknown argument {other}")),
+        }
+    }
+    table(tables);
+    for table in tables.iter_mut().filter(|t| t.queue) {
+        table.players = 2;
+    }
+    serve(renderer, config);
+}
+
+/// `poker-tui serve CONFIG` runs a host set up by the JSON file CONFIG, as described in
+/// `hosting`, without reading anything from the terminal, so that it can run unattended.
+fn run_serve(mut args: impl Iterator<Item = String>) {
+    let renderer: &mut dyn Renderer = &mut TerminalRenderer;
+    let Some(path) = args.next() else {
+        return renderer.line("serve needs the path of a config file");
+    };
+    match ServerConfig::load(Path::new(&path)) {
+        Ok(config) => serve(renderer, config),
+        Err(e) => renderer.line(&format!("Could not read {path}: {e}")),
+    }
+}
+
+/// Takes players on the ports of `config` and plays a game at each of its tables.
+fn serve(renderer: &mut dyn Renderer, config: ServerConfig) {
+    let ServerConfig {
+        port,
+        ws_port,
+        secret,
+        status_port,
+        advertise,
+        archive_dir,
+        tables,
+    } = config;
+    let mut listeners = vec![];
+    for (port, websocket) in [(Some(port), false), (ws_port, true)] {
+        let Some(port) = port else {
+            continue;
+        };
+        match TcpListener::bind(("0.0.0.0", port)) {
+            Ok(listener) if websocket => listeners.push(Listener::WebSocket(listener)),
+            Ok(listener) => listeners.push(Listener::Tcp(listener)),
+            Err(e) => return renderer.line(&format!("Could not listen on port {port}: {e}")),
+        }
+    }
+    match ws_port {
+        Some(ws_port) => renderer.line(&format!(
+            "Waiting for players to join on port {port}, or over WebSocket on port {ws_port}"
+        )),
+        None => renderer.line(&format!("Waiting for players to join on port {port}")),
+    }
+    for table in &tables {
+        let locked = match (table.invite_only, table.password.is_some()) {
+            (true, _) => ", by invite only",
+            (false, true) => ", with a password",
+            (false, false) => "",
+        };
+        let delay = table.spectator_delay.as_secs();
+        let shuffle = if table.mental_shuffle {
+            ", shuffled by the players"
+        } else {
+            ""
+        };
+        if table.queue {
+            renderer.line(&format!(
+                "    Table {}: a queue for heads-up games with {} chips each{locked}{shuffle}",
+                table.name, table.stack
+            ));
+            continue;
+        }
+        renderer.line(&format!("    Table {}: {} players with {} chips each{locked}{shuffle}, spectators {delay}s behind",
+            table.name, table.players, table.stack));
+    }
+    let mut lobby = Lobby::open(listeners, tables.clone());
+    if let Some(secret) = &secret {
+        lobby = lobby.sign_with(secret.as_bytes());
+    }
+    if let Some(dir) = &archive_dir {
+        lobby = match lobby.archive_to(dir) {
+            Ok(lobby) => lobby,
+            Err(e) => {
+                return renderer.line(&format!(
+                    "Could not archive hands in {}: {e}",
+                    dir.display()
+                ))
+            }
+        };
+        renderer.line(&format!("Archiving every hand in {}", dir.display()));
+    }
+    let valid_for = Duration::from_secs(INVITE_HOURS * 60 * 60);
+    for table in tables.iter().filter(|t| t.invite_only) {
+        renderer.line(&format!(
+            "    Invite to {}: poker-tui join ADDRESS --invite {}",
+            table.name,
+            lobby.invite(&table.name, valid_for)
+        ));
+    }
+    if let Some(status_port) = status_port {
+        match TcpListener::bind(("0.0.0.0", status_port)) {
+            Ok(listener) => {
+                let monitor = lobby.monitor();
+                serve_status(listener, move || monitor.report());
+                renderer.line(&format!(
+                    "Status at http://localhost:{status_port}/status and /metrics"
+                ));
+            }
+            Err(e) => {
+                return renderer.line(&format!("Could not listen on port {status_port}: {e}"))
+            }
+        }
+    }
+    // Stays advertised until the lobby closes.
+    let _advertisement = match advertise.map(|name| (discovery::advertise(&name, port), name)) {
+        Some((Ok(advertisement), name)) => {
+            renderer.line(&format!(
+                "Players on the local network find this host as {name}"
+            ));
+            Some(advertisement)
+        }
+        Some((Err(e), _)) => {
+            return renderer.line(&format!("Could not advertise on the local network: {e}"))
+        }
+        None => None,
+    };
+    // The tables call back from threads of their own, so each call renders to the terminal.
+    match lobby.run(|table, seat, addr| {
+        TerminalRenderer.line(&format!("Player {seat} joined table {table} from {addr}"))
+    }) {
+        Ok(()) => renderer.line("Game over"),
+        Err(e) => renderer.line(&format!("The game stopped: {e}")),
+    }
+}
+
+/// `poker-tui sshd-config [--user USER] [--addr ADDR]` prints what to add to the machine's
+/// sshd_config so that `ssh USER@machine` seats players at the host at ADDR, by default
+/// the one on this machine's default port. USER is poker unless given, and has to exist.
+fn run_sshd_config(mut args: impl Iterator<Item = String>) {
+    let renderer: &mut dyn Renderer = &mut TerminalRenderer;
+    let (mut user, mut addr) = ("poker".to_owned(), format!("localhost:{DEFAULT_PORT}"));
+    while let Some(arg) = args.next() {
+        match (arg.as_str(), args.next()) {
+            ("--user", Some(name)) => user = name,
+            ("--addr", Some(a)) => addr = a,
+            _ => return renderer.line("sshd-config takes --user USER and --addr ADDR"),
+        }
+    }
+    let exe = match std::env::current_exe() {
+        Ok(exe) => exe,
+        Err(e) => return renderer.line(&format!("Could not tell where poker-tui is: {e}")),
+    };
+    print!("{}", sshd_config(&exe, &addr, &user));
+}
+
+/// `poker-tui ssh-session [ADDR]` is what `sshd-config` has sshd run for every player who
+/// logs in. It seats them at the host at ADDR, or on this machine's default port, at the
+/// table named by the command they sent, like `ssh -t poker@host club`, or asks which one.
+fn run_ssh_session(mut args: impl Iterator<Item = String>) {
+    let renderer: &mut dyn Renderer = &mut TerminalRenderer;
+    let addr = args
+        .next()
+        .unwrap_or_else(|| format!("localhost:{DEFAULT_PORT}"));
+    // Whatever the player sent is only ever taken for a table name.
+    let table = std::env::var("SSH_ORIGINAL_COMMAND").unwrap_or_default();
+    let table = table.trim();
+    start_input();
+    renderer.line("Welcome to PokerTUI!");
+    if !table.is_empty() {
+        return run_client(renderer, &addr, Connection::join(&addr, table, None));
+    }
+    if let Some(connected) = join_form(renderer, &addr) {
+        run_client(renderer, &addr, connected);
+    }
+}
+
+/// `poker-tui invite --secret S --table NAME [--hours H]` prints an invite to the table
+/// called NAME of a host started with the same `--secret`, good for H hours.
+fn run_invite(mut args: impl Iterator<Item = String>) {
+    let renderer: &mut dyn Renderer = &mut TerminalRenderer;
+    let (mut secret, mut table, mut hours) = (None, None, INVITE_HOURS);
+    while let Some(arg) = args.next() {
+        match (arg.as_str(), args.next()) {
+            ("--secret", Some(s)) => secret = Some(s),
+            ("--table", Some(name)) => table = Some(name),
+            ("--hours", Some(h)) => match h.parse() {
+                Ok(h) => hours = h,
+                Err(_) => return renderer.line("--hours needs a number of hours"),
+            },
+            _ => {
+                return renderer
+                    .line("invite takes --secret S, --table NAME and optionally --hours H")
+            }
+        }
+    }
+    let (Some(secret), Some(table)) = (secret, table) else {
+        return renderer.line("invite needs the host's --secret and a --table");
+    };
+    let signer = Signer::new(secret.as_bytes());
+    renderer.line(&signer.invite(&table, Duration::from_secs(hours * 60 * 60)));
+}
+
+const RESUME_ATTEMPTS: usize = 10;
+const RESUME_DELAY: Duration = Duration::from_secs(1);
+
+/// Tries for a while to take back the seat `token` belongs to.
+fn reconnect(
+    renderer: &mut dyn Renderer,
+    addr: &str,
+    token: &str,
+    encoding: Encoding,
+) -> Option<Connection> {
+    renderer.line("Lost the connection to the host, trying to get back in");
+    let hello = ClientMessage::resume(token).with_encoding(encoding);
+    (0..RESUME_ATTEMPTS).find_map(|_| {
+        thread::sleep(RESUME_DELAY);
+        Connection::open(addr, &hello).ok()
+    })
+}
+
+fn print_remote_table(
+    renderer: &mut dyn Renderer,
+    snapshot: &HandSnapshot,
+    toasts: &mut Toasts,
+    rtt: Option<Duration>,
+) {
+    renderer.line("");
+    let table = pretty_print_table(snapshot);
+    for line in with_toasts(table, &toasts.active(), snapshot.players) {
+        renderer.line(&format!("    {line}"));
+    }
+    if let Some(rtt) = rtt {
+        renderer.line(&format!("    Ping: {} ms", rtt.as_millis()));
+    }
+}
+
+/// How often a client waiting for its player checks on the connection.
+const KEEP_ALIVE_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Waits for input until `deadline`, if there is one, answering the host's pings
+/// meanwhile. `None` once the deadline passed.
+fn read_input_online(connection: &mut Connection, deadline: Option<Instant>) -> Option<Input> {
+    loop {
+        let wait = match deadline {
+            Some(deadline) => deadline.saturating_duration_since(Instant::now()),
+            None => KEEP_ALIVE_INTERVAL,
+        };
+        if let Some(input) = read_input_timeout(wait.min(KEEP_ALIVE_INTERVAL)) {
+            return Some(input);
+        }
+        if deadline.is_some_and(|deadline| Instant::now() >= deadline) {
+            return None;
+        }
+        // A lost connection shows with the next read.
+        let _ = connection.keep_alive();
+    }
+}
+
+/// How long to listen for hosts on the local network.
+const LAN_WAIT: Duration = Duration::from_secs(2);
+
+fn find_lan_hosts(renderer: &mut dyn Renderer) -> Vec<LanHost> {
+    renderer.line("Looking for games on the local network...");
+    discovery::discover(LAN_WAIT).unwrap_or_else(|e| {
+        renderer.line(&format!("Could not look on the local network: {e}"));
+        vec![]
+    })
+}
+
+/// `poker-tui tables [ADDRESS]` lists the tables of the host at `ADDRESS`, or of every
+/// host on the local network.
+fn run_tables(mut args: impl Iterator<Item = String>) {
+    let renderer: &mut dyn Renderer = &mut TerminalRenderer;
+    match args.next().map(|addr| Connection::list_tables(&addr)) {
+        Some(Ok(tables)) => print_tables(renderer, &tables),
+        Some(Err(e)) => renderer.line(&format!("Could not list the tables: {e}")),
+        None => print_lan_tables(renderer),
+    }
+}
+
+/// The tables of every host on the local network.
+fn print_lan_tables(renderer: &mut dyn Renderer) {
+    let hosts = find_lan_hosts(renderer);
+    if hosts.is_empty() {
+        renderer.line("No games found. List a host's tables with: poker-tui tables ADDRESS");
+    }
+    for host in hosts {
+        renderer.line(&format!("{} at {}", host.name, host.addr));
+        match Connection::list_tables(&host.addr.to_string()) {
+            Ok(tables) => print_tables(renderer, &tables),
+            Err(e) => renderer.line(&format!("  Could not list the tables: {e}")),
+        }
+    }
+}
+
+/// Asks which host to join, offering the ones found on the local network.
+fn host_form(renderer: &mut dyn Renderer) -> Option<String> {
+    let hosts = find_lan_hosts(renderer);
+    if hosts.is_empty() {
+        let addr = prompt(renderer, "Address of the host, like localhost:7777:")?;
+        return Some(addr.trim().to_owned());
+    }
+    for (i, host) in hosts.iter().enumerate() {
+        renderer.line(&format!("  {}) {} at {}", i + 1, host.name, host.addr));
+    }
+    let answer = prompt(
+        renderer,
+        "Which game? (a number, or the address of another host)",
+    )?;
+    let answer = answer.trim();
+    match answer
+        .parse::<usize>()
+        .ok()
+        .and_then(|i| hosts.get(i.wrapping_sub(1)))
+    {
+        Some(host) => Some(host.addr.to_string()),
+        None => Some(answer.to_owned()),
+    }
+}
+
+fn print_tables(renderer: &mut dyn Renderer, tables: &[TableInfo]) {
+    for table in tables {
+        let locked = match (table.invite_only, table.password) {
+            (true, _) => ", invite only",
+            (false, true) => ", password",
+            (false, false) => "",
+        };
+        let shuffle = if table.mental_shuffle {
+            ", mental poker"
+        } else {
+            ""
+        };
+        let queue = if table.queue { ", queue" } else { "" };
+        renderer.line(&format!(
+            "  {}: {}/{} players, {} chips{locked}{shuffle}{queue}",
+            table.name, table.seated, table.players, table.stack
+        ));
+    }
+}
+
+/// Asks which of the host's tables to join, and for its password or an invite if it takes one.
+fn join_form(renderer: &mut dyn Renderer, addr: &str) -> Option<io::Result<Connection>> {
+    let tables = match Connection::list_tables(addr) {
+        Ok(tables) => tables,
+        Err(e) => return Some(Err(e)),
+    };
+    let table = match tables.as_slice() {
+        [only] => only,
+        _ => {
+            print_tables(renderer, &tables);
+            loop {
+                let name = prompt(renderer, "Which table? (blank for the first)")?;
+                let name = name.trim();
+                match tables.iter().find(|t| t.name == name) {
+                    Some(table) => break table,
+                    None if name.is_empty() => break tables.first()?,
+                    None => renderer.line(&format!("There is no table called {name}")),
+                }
+            }
+        }
+    };
+    let play = loop {
+        match prompt(renderer, "(P)lay or (W)atch?")?.trim() {
+            "p" | "P" => break true,
+            "w" | "W" => break false,
+            _ => renderer.line("Invalid option"),
+        }
+    };
+    if play && table.invite_only {
+        let invite = prompt(renderer, "Invite:")?;
+        return Some(Connection::join_invited(addr, &table.name, invite.trim()));
+    }
+    let password = if table.password {
+        Some(prompt(renderer, "Password:")?.trim().to_owned())
+    } else {
+        None
+    };
+    let password = password.as_deref();
+    Some(match play {
+        true => Connection::join(addr, &table.name, password),
+        false => Connection::watch(addr, &table.name, password),
+    })
+}
+
+/// `poker-tui join ADDRESS [--table NAME] [--password PASSWORD] [--invite TOKEN] [--watch]
+/// [--resume TOKEN] [--compact]` takes a seat at a table of the host at `ADDRESS`, or
+/// only watches it.
+fn run_join(mut args: impl Iterator<Item = String>) {
+    let renderer: &mut dyn Renderer = &mut TerminalRenderer;
+    let Some(addr) = args.next() else {
+        return renderer.line(
+            "join needs the address of the host, like localhost:7777 or ws://localhost:7778",
+        );
+    };
+    let (mut table, mut password, mut resume) = (String::new(), None, None);
+    let (mut watch, mut invite, mut encoding) = (false, None, Encoding::Json);
+    while let Some(arg) = args.next() {
+        if arg == "--watch" {
+            watch = true;
+            continue;
+        }
+        if arg == "--compact" {
+            encoding = Encoding::MessagePack;
+            continue;
+        }
+        match (arg.as_str(), args.next()) {
+            ("--table", Some(name)) => table = name,
+            ("--password", Some(secret)) => password = Some(secret),
+            ("--resume", Some(token)) => resume = Some(token),
+            ("--invite", Some(token)) => invite = Some(token),
+            _ => return renderer.line("join takes an address and optionally --table NAME, --password PASSWORD, --invite TOKEN, --watch, --resume TOKEN or --compact"),
+        }
+    }
+    start_input();
+    let hello = match (resume, invite) {
+        (Some(token), _) => ClientMessage::resume(&token),
+        _ if watch => ClientMessage::watch(&table, password.as_deref()),
+        (None, Some(invite)) => {
+            if table.is_empty() {
+                table = Signer::invite_table(&invite).unwrap_or_default().to_owned();
+            }
+            ClientMessage::join_invited(&table, &invite)
+        }
+        (None, None) => ClientMessage::join(&table, password.as_deref()),
+    };
+    let hello = hello.with_encoding(encoding);
+    run_client(renderer, &addr, Connection::open(&addr, &hello));
+}
+
+/// Plays at a table run by `poker-tui host`, drawing it whenever the host sends it, and
+/// takes the seat back on its own if the connection drops.
+fn run_client(renderer: &mut dyn Renderer, addr: &str, connected: io::Result<Connection>) {
+    let mut connection = match connected {
+        Ok(connection) => connection,
+        Err(e) => return renderer.line(&format!("Could not connect to {addr}: {e}")),
+    };
+    let seat = connection.seat;
+    match connection.delay {
+        Some(delay) => renderer.line(&format!(
+            "Watching a table of {} players, {} seconds behind",
+            connection.players,
+            delay.as_secs()
+        )),
+        None => renderer.line(&format!(
+            "Joined as player {seat} of {}, waiting for the others",
+            connection.players
+        )),
+    }
+    if !connection.token.is_empty() {
+        renderer.line(&format!(
+            "If you lose the connection, rejoin with: poker-tui join {addr} --resume {}",
+            connection.token
+        ));
+    }
+    let mut toasts = Toasts { toasts: vec![] };
+    let mut chat = ChatLog::init(connection.players);
+    let mut sitting_out = false;
+    // The player whose turn it is and when the host checks or folds for them, as the host
+    // last said.
+    let mut turn: Option<(usize, Instant)> = None;
+    loop {
+        let message = match connection.recv() {
+            Ok(Some(message)) => message,
+            _ if !connection.token.is_empty() => {
+                match reconnect(renderer, addr, &connection.token, connection.encoding) {
+                    Some(mut resumed) => {
+                        resumed.carry_on_from(connection);
+                        connection = resumed;
+                        continue;
+                    }
+                    None => return renderer.line("Could not get back to the table"),
+                }
+            }
+            Ok(None) => return renderer.line("The host ended the game"),
+            Err(e) => return renderer.line(&format!("Lost the connection to the host: {e}")),
+        };
+        if let Some(event) = message.event() {
+            toasts.push_events(&[event]);
+        }
+        match message {
+            ServerMessage::Chat { player, text } if chat.post(player, &text) => {
+                for line in pretty_print_chat(&chat, 1) {
+                    renderer.line(&format!("    {line}"));
+                }
+            }
+            ServerMessage::Table { view } => print_remote_table(
+                renderer,
+                &HandSnapshot::from(&view),
+                &mut toasts,
+                connection.rtt,
+            ),
+            ServerMessage::PublicTable { view } => print_remote_table(
+                renderer,
+                &HandSnapshot::from(&view),
+                &mut toasts,
+                connection.rtt,
+            ),
+            ServerMessage::Sync { hand, view } => {
+                let events: Vec<_> = hand.iter().filter_map(ServerMessage::event).collect();
+                toasts.push_events(&events);
+                print_remote_table(
+                    renderer,
+                    &HandSnapshot::from(&view),
+                    &mut toasts,
+                    connection.rtt,
+                );
+            }
+            ServerMessage::ActionRequest { legal, seconds } => {
+                let call = match legal.call {
+                    0 => "(C)heck".to_owned(),
+                    call => format!("(C)all {call}"),
+                };
+                let raise = legal
+                    .raise
+                    .map(|r| format!(" (R)aise {}-{}", r.start(), r.end()))
+                    .unwrap_or_default();
+                let question = format!(
+                    "Player {seat}, what do you do? {call}{raise} (F)old (T)alk (M)ute (H)istory"
+                );
+                let deadline = match turn {
+                    Some((player, deadline)) if player == seat => Some(deadline),
+                    // Hosts before deadlines were shared only tell the player.
+                    _ => seconds.map(|s| Instant::now() + Duration::from_secs(s)),
+                };
+                loop {
+                    match deadline {
+                        Some(deadline) => {
+                            let left = deadline.saturating_duration_since(Instant::now());
+                            renderer.line(&format!("{question} ({}s left)", left.as_secs()))
+                        }
+                        None => renderer.line(&question),
+                    }
+                    let input = match read_input_online(&mut connection, deadline) {
+                        Some(Input::Line(line)) => line,
+                        Some(Input::Interrupt | Input::Closed) => return,
+                        // The host checks or folds for us and tells everyone.
+                        None => break,
+                    };
+                    let input = input.trim();
+                    // The host sends chat back to everyone, so it shows once the turn is over.
+                    let message = match input.strip_prefix("t ") {
+                        Some(text) => ClientMessage::Chat {
+                            text: text.to_owned(),
+                        },
+                        None if input.eq_ignore_ascii_case("h") => {
+                            if connection.version < HISTORY_VERSION {
+                                renderer.line("The host doesn't hand out the hands played");
+                                continue;
+                            }
+                            renderer.line("Asked the host for the hands so far");
+                            ClientMessage::History
+                        }
+                        None if handle_chat_command(
+                            &mut chat,
+                            &mut TerminalRenderer,
+                            seat,
+                            input,
+                        ) =>
+                        {
+                            continue
+                        }
+                        None => match parse_action(input) {
+                            Some(action) => ClientMessage::Action { action },
+                            None => {
+                                renderer.line("Invalid action");
+                                continue;
+                            }
+                        },
+                    };
+                    let acted = matches!(message, ClientMessage::Action { .. });
+                    // Without a connection the next read notices, and the host asks again
+                    // once we are back.
+                    if connection.send(&message).is_err() || acted {
+                        break;
+                    }
+                }
+            }
+            ServerMessage::SatOut { player } if player == seat && connection.delay.is_none() => {
+                sitting_out = true;
+                renderer.line(
+                    "    You are sitting out and check or fold every turn. Press enter to sit in.",
+                );
+            }
+            ServerMessage::SatIn { player } if player == seat => sitting_out = false,
+            ServerMessage::ShuffleAudit { .. } => match connection.shuffle_verdict() {
+                Some(Ok(())) => renderer.line("    Checked the shuffle: the hand was dealt fairly"),
+                Some(Err(e)) => renderer.line(&format!("    The shuffle doesn't check out: {e}")),
+                None => {}
+            },
+            ServerMessage::CalledOff => {
+                renderer.line("    The hand is called off: a player didn't hand over their keys")
+            }
+            ServerMessage::Turn {
+                player,
+                remaining_ms,
+                ..
+            } => {
+                let remaining = Duration::from_millis(remaining_ms);
+                turn = Some((player, Instant::now() + remaining));
+                if player != seat {
+                    renderer.line(&format!(
+                        "    Player {player} has {}s to act",
+                        remaining.as_secs()
+                    ));
+                }
+            }
+            ServerMessage::History { hands } => {
+                let path = format!("hands-seat-{seat}.jsonl");
+                let lines: Vec<_> = hands
+                    .iter()
+                    .map(|hand| serde_json::to_string(hand).expect("hands serialize"))
+                    .collect();
+                match fs::write(&path, lines.join("\n") + "\n") {
+                    Ok(()) => renderer.line(&format!("    Saved {} hands to {path}", hands.len())),
+                    Err(e) => {
+                        renderer.line(&format!("    Could not save the hands to {path}: {e}"))
+                    }
+                }
+            }
+            ServerMessage::Error { message } => {
+                renderer.line(&format!("    The host refused: {message}"))
+            }
+            ServerMessage::GameOver => return renderer.line("Game over"),
+            _ => {}
+        }
+        if sitting_out {
+            match poll_input() {
+                Some(Input::Line(_)) => {
+                    // A lost connection shows with the next read.
+                    let _ = connection.send(&ClientMessage::SitIn);
+                }
+                Some(Input::Interrupt | Input::Closed) => return,
+                None => {}
+            }
+        }
+    }
+}
+
+const DEMO_HAND_DELAY: Duration = Duration::from_millis(1500);
+
+/// Bots play each other until the user presses enter or Ctrl+C. A new table is set up
+/// whenever a bot can no longer cover the big blind.
+fn run_demo(settings: &Settings) {
+    let mut renderer = settings.renderer();
+    let event_log = EventLog::open(&mut *renderer, settings);
+    let mut gs = GameState::init(DEMO_PLAYERS).unwrap();
+    gs.subscribe(event_log.clone());
+    let bots = (0..DEMO_PLAYERS)
+        .map(|_| -> Box<dyn SeatController> {
+            Box::new(BotSeat {
+                bot: Box::new(LooseBot::new(None)),
+                delay: Duration::ZERO,
+            })
+        })
+        .collect();
+    let names = (0..DEMO_PLAYERS).map(|p| seat_name(p, None)).collect();
+    let mut session = Session {
+        renderer,
+        ..Session::new(&gs, bots, names)
+    };
+    session.show("DEMO MODE - press enter or Ctrl+C to stop");
+
+    loop {
+        if (0..gs.players()).any(|p| gs.current_chips(p) < 2) {
+            session.show("\n\nNEW TABLE\n\n");
+            gs = GameState::init(DEMO_PLAYERS).unwrap();
+            gs.subscribe(event_log.clone());
+        }
+        session.hand_number += 1;
+        session.show(&format!("\n\nNEW HAND {}\n\n", session.hand_number));
+
+        let (mut hs, mut cur) = gs.start_play_hand(Deck::shuffled_deck());
+        let winner = loop {
+            let snapshot = settings.snapshot(&hs);
+            session.show("");
+            let table = pretty_print_table(&snapshot);
+            for line in with_toasts(table, &session.toasts.active(), snapshot.players) {
+                session.show(&format!("    {line}"));
+            }
+            if demo_should_stop(thinking_time(DEMO_ACTION_DELAY)) {
+                show_session_summary(&mut session);
+                return;
+            }
+
+            let SeatInput::Decided(action, _) = session.seats[cur].next_input(&hs, cur) else {
+                unreachable!("every demo seat is a bot");
+            };
+            session.show(&pretty_print_action(&action, cur));
+            let (played, result) = act(hs, action);
+            hs = played;
+            let (result, events) = result.expect("bots only raise within limits");
+            session.toasts.push_events(&events);
+            match result {
+                poker_tui::TurnResult::NextPlayer(p) => cur = p,
+                poker_tui::TurnResult::WonHand(p) => break p,
+            }
+        };
+
+        if hs.is_showdown() {
+            for line in pretty_print_showdown(&hs) {
+                session.show(&format!("    {line}"));
+            }
+        }
+        session.show(&format!("\nPlayer {winner} won the round"));
+        session.stats.record_hand(hs.events());
+        record_all_in(&mut session, &hs);
+        gs = gs.apply_played_hand(hs);
+        if demo_should_stop(DEMO_HAND_DELAY) {
+            show_session_summary(&mut session);
+            return;
+        }
+    }
+}
 
-fn main() {
-    println!("Welcome to PokerTUI!");
+/// Waits for `delay` and reports whether the user asked to stop the demo meanwhile.
+fn demo_should_stop(delay: Duration) -> bool {
+    thread::sleep(delay);
+    while let Some(input) = poll_input() {
+        if let Input::Line(_) | Input::Interrupt = input {
+            return true;
+        }
+    }
+    false
+}
 
-    println!("How many players will be playing?");
+fn run_tutorial(renderer: &mut dyn Renderer) {
+    let (mut hs, _) = GameState::init(2)
+        .unwrap()
+        .start_play_hand(tutorial::deck());
 
-    let mut players = String::new();
+    for step in tutorial::steps() {
+        if let StepKind::Opponent(action) = step.kind {
+            let (played, result) = act(hs, action);
+            result.unwrap();
+            hs = played;
+            renderer.line(&pretty_print_action(&action, 1));
+        }
 
-    io::stdin()
-        .read_line(&mut players)
-        .expect("Expected an input");
+        renderer.line("");
+        let highlighted = highlighted_line(step.highlight);
+        for (i, line) in pretty_print_table(&hs.spectator_snapshot())
+            .iter()
+            .enumerate()
+        {
+            let marker = if Some(i) == highlighted {
+                "==> "
+            } else {
+                "    "
+            };
+            renderer.line(&format!("{marker}{line}"));
+        }
+        renderer.line("");
+        renderer.line(&format!("TUTORIAL: {}", step.text));
 
-    let players: usize = players
-        .trim()
-        .parse()
-        .expect("Please provide a positive number");
+        match step.kind {
+            StepKind::Play(expected) => loop {
+                let Some(input) = prompt(renderer, "What do you do?") else {
+                    return;
+                };
+                if parse_action(&input) == Some(expected) {
+                    let (played, result) = act(hs, expected);
+                    result.unwrap();
+                    hs = played;
+                    renderer.line(&pretty_print_action(&expected, 0));
+                    break;
+                }
+                renderer.line("Not quite, follow the instructions above.");
+            },
+            StepKind::Read | StepKind::Opponent(_) => {
+                if prompt(renderer, "Press enter to continue").is_none() {
+                    return;
+                }
+            }
+        }
+    }
 
-    let mut gs = GameState::init(players).unwrap();
+    renderer.line("That's it! Start a cash game from the menu to play for real.");
+}
 
-    loop {
-        println!("\n\nNEW HAND\n\n");
-        gs = play_hand(gs);
+/// The line of `pretty_print_table` showing the highlighted part of the table.
+fn highlighted_line(highlight: Highlight) -> Option<usize> {
+    match highlight {
+        Highlight::Nothing => None,
+        Highlight::Pot => Some(2),
+        Highlight::Board => Some(4),
+        Highlight::Hands => Some(6),
+        Highlight::Bets => Some(7),
+        Highlight::Stacks => Some(8),
     }
 }
 
-fn play_hand(gs: GameState) -> GameState {
-    let (mut hs, mut cur) = gs.start_play_hand(Deck::shuffled_deck());
-    loop {
-        println!("");
-        for line in pretty_print_hand_snapshot(hs.spectator_snapshot()) {
-            println!("    {}", line);
+fn went_all_in(snapshot: &HandSnapshot, player: usize) -> bool {
+    snapshot.chips[player].stack == 0 && snapshot.hands[player] != HandVisibility::Folded
+}
+
+fn handle_chat_command(
+    chat: &mut ChatLog,
+    renderer: &mut dyn Renderer,
+    player: usize,
+    input: &str,
+) -> bool {
+    if let Some(text) = input.strip_prefix("t ") {
+        if !chat.post(player, text) {
+            renderer.line("Message not sent");
         }
+        true
+    } else if let Some(target) = input.strip_prefix("m ") {
+        match target.trim().parse().ok().and_then(|p| chat.toggle_mute(p)) {
+            Some(true) => renderer.line(&format!("Muted player {}", target.trim())),
+            Some(false) => renderer.line(&format!("Unmuted player {}", target.trim())),
+            None => renderer.line(&format!("No such player {}", target.trim())),
+        }
+        true
+    } else {
+        false
+    }
+}
 
-        let mut action_str = String::new();
-        io::stdin()
-            .read_line(&mut action_str)
-            .expect("Expected an input");
-        let action = parse_action(&action_str);
+fn notes_path(profile: &str) -> PathBuf {
+    PathBuf::from(format!("poker-tui-{profile}.notes"))
+}
 
-        match action {
-            Some(a) => {
-                println!("{}", pretty_print_action(&a, cur));
-                match hs.play_action(a) {
-                    Ok(poker_tui::TurnResult::NextPlayer(p)) => cur = p,
-                    Ok(poker_tui::TurnResult::WonHand(p)) => {
-                        println!();
-                        println!("###########################");
-                        println!("# Player {} won the round #", p);
-                        println!("###########################");
-                        println!();
-                        return gs.apply_played_hand(hs);
-                    }
-                    Err(_) => println!("Raised by too much"),
-                }
-            }
+/// Who plays the seat as notes know them: bots by what kind they are, so notes on one
+/// carry over to the next game, and people at the keyboard by their seat.
+fn seat_name(player: usize, seat: Option<&BotKind>) -> String {
+    match seat {
+        Some(bot) => format!("{} bot", bot_spec(bot)),
+        None => format!("Player {player}"),
+    }
+}
+
+/// `n SEAT` shows the profile's notes on whoever plays the seat, `n SEAT TEXT` adds a note,
+/// `n SEAT #COLOR` tags them and `n SEAT #` takes the tag off.
+fn handle_notes_command(settings: &Settings, session: &mut Session, input: &str) -> bool {
+    let Some(rest) = input.strip_prefix("n ") else {
+        return false;
+    };
+    let Some(profile) = &settings.profile else {
+        session.show("Notes are kept with a profile, start with --profile NAME");
+        return true;
+    };
+    let (seat, text) = rest.trim().split_once(' ').unwrap_or((rest.trim(), ""));
+    let Some(name) = seat
+        .parse::<usize>()
+        .ok()
+        .and_then(|seat| session.seat_names.get(seat).cloned())
+    else {
+        session.show(&format!("No such player {seat}"));
+        return true;
+    };
+    let path = notes_path(profile);
+    let mut notebook = match Notebook::load(&path) {
+        Ok(notebook) => notebook,
+        Err(e) => {
+            session.show(&format!(
+                "Could not read the notes in {}: {e}",
+                path.display()
+            ));
+            return true;
+        }
+    };
+
+    let text = text.trim();
+    if text.is_empty() {
+        for line in pretty_print_notes(&name, notebook.get(&name)) {
+            session.show(&format!("    {line}"));
+        }
+        return true;
+    }
+    match text.strip_prefix('#') {
+        Some("") => notebook.set_tag(&name, None),
+        Some(tag) => match Tag::parse(tag) {
+            Some(tag) => notebook.set_tag(&name, Some(tag)),
             None => {
-                println!("Invalid action {}", action_str);
+                let tags: Vec<_> = Tag::ALL.iter().map(|t| t.name()).collect();
+                session.show(&format!("Tags are {}", tags.join(", ")));
+                return true;
             }
+        },
+        None => notebook.add_note(&name, text, now_ms()),
+    }
+    match notebook.save(&path) {
+        Ok(()) => session.show(&format!("Noted on {name}")),
+        Err(e) => session.show(&format!(
+            "Could not save the notes to {}: {e}",
+            path.display()
+        )),
+    }
+    true
+}
+
+/// Reminds the player of the opponents at the table they have notes on.
+fn show_table_notes(profile: &str, session: &mut Session) {
+    let path = notes_path(profile);
+    let notebook = match Notebook::load(&path) {
+        Ok(notebook) => notebook,
+        Err(e) => {
+            return session.show(&format!(
+                "Could not read the notes in {}: {e}",
+                path.display()
+            ))
+        }
+    };
+    for seat in 0..session.seat_names.len() {
+        if !session.seats[seat].is_bot() {
+            continue;
+        }
+        if let Some(notes) = notebook.get(&session.seat_names[seat]) {
+            let line = format!(
+                "    Seat {seat}: {} with {} notes, (N)otes {seat} to read them",
+                pretty_print_tagged(&session.seat_names[seat], notes.tag),
+                notes.notes.len()
+            );
+            session.show(&line);
         }
     }
 }
 
+fn pretty_print_notes(name: &str, notes: Option<&OpponentNotes>) -> Vec<String> {
+    let Some(notes) = notes else {
+        return vec![format!("No notes on {name} yet")];
+    };
+    let mut lines = vec![pretty_print_tagged(name, notes.tag)];
+    lines.extend(
+        notes
+            .notes
+            .iter()
+            .map(|note| format!("  {}  {}", date_time(note.at_ms), note.text)),
+    );
+    lines
+}
+
+/// The name in the color of its tag.
+fn pretty_print_tagged(name: &str, tag: Option<Tag>) -> String {
+    let Some(tag) = tag else {
+        return name.to_owned();
+    };
+    let color = match tag {
+        Tag::Red => "\x1b[31m",
+        Tag::Yellow => "\x1b[33m",
+        Tag::Green => "\x1b[32m",
+        Tag::Blue => "\x1b[34m",
+        Tag::Purple => "\x1b[35m",
+    };
+    format!("{color}{name} [{}]{RESET}", tag.name())
+}
+
 fn parse_action(as_str: &str) -> Option<PokerAction> {
     let action = as_str.chars().next()?;
     match action.to_ascii_lowercase() {
@@ -76,6 +3392,29 @@ fn parse_action(as_str: &str) -> Option<PokerAction> {
     }
 }
 
+const BET_PRESETS: [(&str, BetSize, &str); 4] = [
+    ("1", BetSize::MinRaise, "min"),
+    ("2", BetSize::HalfPot, "1/2 pot"),
+    ("3", BetSize::Pot, "pot"),
+    ("4", BetSize::AllIn, "all-in"),
+];
+
+fn parse_bet_preset(as_str: &str) -> Option<BetSize> {
+    BET_PRESETS
+        .iter()
+        .find(|(key, _, _)| *key == as_str.trim())
+        .map(|(_, size, _)| *size)
+}
+
+fn pretty_print_bet_presets(hs: &HandState) -> String {
+    let presets = BET_PRESETS
+        .iter()
+        .map(|(key, size, name)| format!("({key}) {name} {}", hs.bet_size(*size)))
+        .collect::<Vec<_>>()
+        .join("  ");
+    format!("Raise presets: {presets}")
+}
+
 fn pretty_print_action(action: &PokerAction, player: usize) -> String {
     match action {
         CallOrCheck => format!("\nPlayer {player} called or checked."),
@@ -84,9 +3423,142 @@ fn pretty_print_action(action: &PokerAction, player: usize) -> String {
     }
 }
 
-fn pretty_print_hand_snapshot(snapshot: HandSnapshot) -> Vec<String> {
+fn pretty_print_advice(action: &PokerAction) -> String {
+    match action {
+        CallOrCheck => "call or check".to_owned(),
+        Fold => "fold".to_owned(),
+        Raise(v) => format!("raise by {v} chips"),
+    }
+}
+
+/// Where the game's output goes: a terminal, plain text or nowhere, so the same game loop
+/// runs interactively, headless and in tests.
+trait Renderer {
+    fn line(&mut self, line: &str);
+
+    /// A cue that it is someone's turn, for renderers that can make one.
+    fn bell(&mut self) {}
+}
+
+struct TerminalRenderer;
+
+impl Renderer for TerminalRenderer {
+    fn line(&mut self, line: &str) {
+        println!("{line}");
+    }
+
+    fn bell(&mut self) {
+        print!("\x07");
+        io::stdout().flush().unwrap();
+    }
+}
+
+/// Text without colors or bells, for output read by a screen reader or saved to a file.
+struct PlainTextRenderer;
+
+impl Renderer for PlainTextRenderer {
+    fn line(&mut self, line: &str) {
+        println!("{}", strip_ansi(line));
+    }
+}
+
+/// Shows nothing, for tables nobody watches.
+struct SilentRenderer;
+
+impl Renderer for SilentRenderer {
+    fn line(&mut self, _line: &str) {}
+}
+
+/// The text without the ANSI escape sequences that color and style it.
+fn strip_ansi(line: &str) -> String {
+    let mut text = String::with_capacity(line.len());
+    let mut chars = line.chars();
+    while let Some(c) = chars.next() {
+        if c == '\x1b' {
+            // Sequences end with a letter, like the m of colors.
+            chars.by_ref().find(char::is_ascii_alphabetic);
+        } else {
+            text.push(c);
+        }
+    }
+    text
+}
+
+#[cfg(test)]
+#[derive(Default)]
+struct StringRenderer {
+    buffer: String,
+}
+
+#[cfg(test)]
+impl Renderer for StringRenderer {
+    fn line(&mut self, line: &str) {
+        self.buffer.push_str(line);
+        self.buffer.push('\n');
+    }
+}
+
+fn draw_table(
+    renderer: &mut dyn Renderer,
+    snapshot: &HandSnapshot,
+    chat: &ChatLog,
+    toasts: &[&str],
+    compact: bool,
+) {
+    if compact {
+        for line in pretty_print_chat(chat, 2) {
+            renderer.line(&line);
+        }
+        for line in pretty_print_compact_hand_snapshot(snapshot, toasts) {
+            renderer.line(&line);
+        }
+    } else {
+        for line in pretty_print_chat(chat, 5) {
+            renderer.line(&format!("    {line}"));
+        }
+        let table = pretty_print_hand_snapshot(snapshot);
+        for line in with_toasts(table, toasts, snapshot.players) {
+            renderer.line(&format!("    {line}"));
+        }
+    }
+}
+
+fn pretty_print_hand_snapshot(snapshot: &HandSnapshot) -> Vec<String> {
+    let call_to_action = format!("Player {}, what do you do?", snapshot.current_player);
+    let call_or_check = if snapshot.expected_call == 0 {
+        "(C)heck".to_owned()
+    } else {
+        format!("(C)all {}", snapshot.expected_call)
+    };
+    let actions = format!(
+        "{}  (R)aise (F)old  (T)alk (M)ute (N)otes (S)tats (O)verlay (H)and rankings (P)ause (Q)uit",
+        call_or_check
+    );
+
+    let mut lines = pretty_print_table(snapshot);
+    lines.extend([String::new(), call_to_action, actions]);
+    lines
+}
+
+/// Places the toasts in the top right corner, next to the table.
+fn with_toasts(lines: Vec<String>, toasts: &[&str], players: usize) -> Vec<String> {
+    let width = players * 12 - 3;
+    lines
+        .into_iter()
+        .enumerate()
+        .map(|(i, line)| match toasts.get(i) {
+            Some(toast) => {
+                let padding = width.saturating_sub(line.chars().count());
+                format!("{line}{}   ** {toast} **", " ".repeat(padding))
+            }
+            None => line,
+        })
+        .collect()
+}
+
+fn pretty_print_table(snapshot: &HandSnapshot) -> Vec<String> {
     let divider = "-".repeat(snapshot.players * 12 - 3);
-    let pot = format!("Current pot: {} chips", snapshot.pot);
+    let pot = pretty_print_pots(snapshot);
 
     let board = (0..5)
         .map(|i| {
@@ -106,12 +3578,7 @@ fn pretty_print_hand_snapshot(snapshot: HandSnapshot) -> Vec<String> {
         .collect::<Vec<_>>()
         .join(" | ");
 
-    let hands = snapshot
-        .hands
-        .iter()
-        .map(pretty_print_hand)
-        .collect::<Vec<_>>()
-        .join(" | ");
+    let hands = pretty_print_hands(snapshot);
 
     let stacks = snapshot
         .chips
@@ -120,15 +3587,15 @@ fn pretty_print_hand_snapshot(snapshot: HandSnapshot) -> Vec<String> {
         .collect::<Vec<_>>()
         .join(" | ");
 
-    let player_pointer = "            ".repeat(snapshot.current_player) + "    🔼";
+    let markers = (0..snapshot.players)
+        .map(|p| match seat_marker(snapshot, p) {
+            Some(marker) => format!("{:>9}", format!("({marker})")),
+            None => " ".repeat(9),
+        })
+        .collect::<Vec<_>>()
+        .join("   ");
 
-    let call_to_action = format!("Player {}, what do you do?", snapshot.current_player);
-    let call_or_check = if snapshot.expected_call == 0 {
-        "(C)heck".to_owned()
-    } else {
-        format!("(C)all {}", snapshot.expected_call)
-    };
-    let actions = format!("{}  (R)aise (F)old", call_or_check);
+    let player_pointer = "            ".repeat(snapshot.current_player) + "    🔼";
 
     vec![
         divider.clone(),
@@ -140,20 +3607,575 @@ fn pretty_print_hand_snapshot(snapshot: HandSnapshot) -> Vec<String> {
         hands,
         bets,
         stacks,
+        markers,
         player_pointer,
         String::new(),
         divider,
+    ]
+}
+
+const COMPACT_WIDTH: usize = 50;
+
+fn seat_marker(snapshot: &HandSnapshot, player: usize) -> Option<String> {
+    let mut marker = vec![];
+    if player == snapshot.button {
+        marker.push("D");
+    }
+    if player == snapshot.small_blind {
+        marker.push("SB");
+    }
+    if player == snapshot.big_blind {
+        marker.push("BB");
+    }
+    (!marker.is_empty()).then(|| marker.join("/"))
+}
+
+/// The table for 80x24 terminals: one line per seat instead of one column per seat, and
+/// the toasts below the table instead of next to it.
+fn pretty_print_compact_hand_snapshot(snapshot: &HandSnapshot, toasts: &[&str]) -> Vec<String> {
+    let board = (0..5)
+        .map(|i| {
+            snapshot
+                .board
+                .get(i)
+                .map(|c| format!("{:<4}", c.pretty_print()))
+                .unwrap_or("??? ".to_owned())
+        })
+        .collect::<Vec<_>>()
+        .join("");
+    let divider = "-".repeat(COMPACT_WIDTH);
+
+    let mut lines = vec![
+        divider.clone(),
+        format!("{}  {board}", pretty_print_pots(snapshot)),
+    ];
+    lines.extend(snapshot.chips.iter().enumerate().map(|(p, pc)| {
+        let pointer = if p == snapshot.current_player {
+            ">"
+        } else {
+            " "
+        };
+        format!(
+            "{pointer} P{p:<2} {:<8} {}  bet {:>4}  stack {:>5}",
+            seat_marker(snapshot, p).unwrap_or_default(),
+            pretty_print_hand(&snapshot.hands[p]),
+            pc.bet,
+            pc.stack
+        )
+    }));
+    lines.push(divider);
+    if !toasts.is_empty() {
+        lines.push(format!("** {} **", toasts.join(" | ")));
+    }
+
+    let call_or_check = if snapshot.expected_call == 0 {
+        "(C)heck".to_owned()
+    } else {
+        format!("(C)all {}", snapshot.expected_call)
+    };
+    lines.push(format!(
+        "P{} to act: {call_or_check} (R)aise (F)old  T/M/S/O/H/P/Q",
+        snapshot.current_player
+    ));
+    lines
+}
+
+fn pretty_print_ranking_reference() -> Vec<String> {
+    let mut lines = vec![String::new(), "HAND RANKINGS (strongest first)".to_owned()];
+    lines.extend(ranking_examples().iter().rev().map(|(name, hand)| {
+        let cards = hand
+            .0
+            .iter()
+            .map(|c| format!("{:<4}", c.pretty_print()))
+            .collect::<Vec<_>>()
+            .join(" ");
+        format!("{:<16} {}", name, cards)
+    }));
+    lines
+}
+
+fn pretty_print_overlay(snapshot: &HandSnapshot) -> String {
+    let HandVisibility::Visible(c1, c2) = snapshot.hands[snapshot.current_player] else {
+        return String::new();
+    };
+    let opponents = snapshot
+        .hands
+        .iter()
+        .filter(|h| **h != HandVisibility::Folded)
+        .count()
+        - 1;
+    let equity = equity_vs_random((c1, c2), &snapshot.board, opponents, 1000, &mut rand::rng());
+    format!(
+        "[TRAINING AID] Player {} wins {:.0}% against {} random hand(s)",
+        snapshot.current_player,
+        equity * 100.0,
+        opponents
+    )
+}
+
+fn pretty_print_equity_review(history: &HandHistory) -> Vec<String> {
+    let mut lines = vec![String::new(), "EQUITY BY STREET".to_owned()];
+    lines.extend(review_hand(history, 2000).iter().map(|street| {
+        let equities = street
+            .equities
+            .iter()
+            .enumerate()
+            .filter_map(|(p, e)| e.map(|e| format!("P{p} {:>3.0}%", e * 100.0)))
+            .collect::<Vec<_>>()
+            .join(" | ");
+        format!("{:<8} {}", format!("{:?}:", street.street), equities)
+    }));
+    lines
+}
+
+fn pretty_print_session_summary(session: &Session) -> Vec<String> {
+    let minutes = session.started.elapsed().as_secs() / 60;
+    let stats = &session.stats;
+    let mut lines = vec![
+        String::new(),
+        "SESSION SUMMARY".to_owned(),
+        format!("Duration: {}h {:02}m", minutes / 60, minutes % 60),
+        format!("Hands played: {}", stats.hands()),
+        format!("Biggest pot: {} chips", stats.biggest_pot()),
+    ];
+    if let Some((player, hand)) = stats.best_hand() {
+        let cards = hand
+            .0
+            .iter()
+            .map(|c| c.pretty_print())
+            .collect::<Vec<_>>()
+            .join(" ");
+        lines.push(format!(
+            "Best hand: {} ({cards}) by player {player}",
+            hand.ranking_name()
+        ));
+    }
+    lines.push("Net result:".to_owned());
+    lines.extend(
+        stats
+            .players()
+            .iter()
+            .enumerate()
+            .map(|(p, s)| format!("  Player {p}: {:+} chips", s.net_chips)),
+    );
+    if stats.all_ins() > 0 {
+        lines.push(format!(
+            "All-in luck over {} all-ins, against the chips equity would have won:",
+            stats.all_ins()
+        ));
+        lines.extend((0..stats.players().len()).map(|p| {
+            let luck = stats.all_in_luck(p);
+            let buy_ins = luck.abs() / session.buy_ins[p].max(1) as f64;
+            let ran = if luck < 0.0 { "below" } else { "above" };
+            format!(
+                "  Player {p}: ran {buy_ins:.1} buy-ins {ran} EV, {:+.0} chips adjusted for luck",
+                stats.expected_net_chips(p)
+            )
+        }));
+    }
+    if let Some(coach) = &session.coach {
+        let (matched, decisions) = coach.score();
+        if let Some(grade) = coach.grade() {
+            lines.push(format!(
+                "Coach: followed the advice in {matched} of {decisions} decisions, grade {grade}"
+            ));
+        }
+    }
+    lines
+}
+
+fn pretty_print_stats(stats: &SessionStats) -> Vec<String> {
+    let mut lines = vec![String::new(), "SESSION STATS".to_owned()];
+    lines.extend(pretty_print_stats_table(stats.players()));
+    lines
+}
+
+/// How the profile has played over every session, this one included.
+fn pretty_print_profile_stats(profile: &str, session: &Session) -> Vec<String> {
+    match profile_stats(profile, session) {
+        Ok(stats) => vec![
+            String::new(),
+            format!("STATS of {profile}"),
+            STATS_TABLE_HEADER.to_owned(),
+            pretty_print_stats_row("you", &stats),
+        ],
+        Err(e) => vec![format!("Could not read the stats of {profile}: {e}")],
+    }
+}
+
+const BANKROLL_GRAPH_WIDTH: usize = 40;
+const BANKROLL_GRAPH_HEIGHT: usize = 6;
+
+/// The profile's bankroll off the table, and how it went over the last sessions.
+fn pretty_print_bankroll(profile: &str) -> Vec<String> {
+    let path = bankroll_path(profile);
+    match Bankroll::load(&path) {
+        Ok(bankroll) => {
+            let mut lines = vec![
+                String::new(),
+                format!("BANKROLL of {profile}: {} off the table", bankroll.balance),
+            ];
+            lines.extend(bankroll.graph(BANKROLL_GRAPH_WIDTH, BANKROLL_GRAPH_HEIGHT));
+            lines
+        }
+        Err(e) => vec![format!(
+            "Could not read the bankroll in {}: {e}",
+            path.display()
+        )],
+    }
+}
+
+/// The stats of the players at the table over every hand in the database.
+#[cfg(feature = "sqlite")]
+fn pretty_print_all_time_stats(database: &StatsDatabase, players: usize) -> Vec<String> {
+    let stats: io::Result<Vec<_>> = (0..players)
+        .map(|p| database.player_stats(&format!("Player {p}")))
+        .collect();
+    match stats {
+        Ok(stats) => {
+            let mut lines = vec![String::new(), "ALL TIME STATS".to_owned()];
+            lines.extend(pretty_print_stats_table(&stats));
+            lines
+        }
+        Err(e) => vec![format!("Could not read the stats database: {e}")],
+    }
+}
+
+const STATS_TABLE_HEADER: &str =
+    "Player  Hands  VPIP   PFR  3-bet   AF  Showdowns won  Biggest pot  Net chips";
+
+fn pretty_print_stats_table(stats: &[PlayerStats]) -> Vec<String> {
+    let mut lines = vec![STATS_TABLE_HEADER.to_owned()];
+    lines.extend(
+        stats
+            .iter()
+            .enumerate()
+            .map(|(p, s)| pretty_print_stats_row(&p.to_string(), s)),
+    );
+    lines
+}
+
+fn pretty_print_stats_row(player: &str, s: &PlayerStats) -> String {
+    format!(
+        "{:>6} {:>6} {:>4.0}% {:>4.0}% {:>5.0}% {:>4.1} {:>14} {:>12} {:>+10}",
+        player,
+        s.hands_played,
+        s.vpip(),
+        s.pfr(),
+        s.three_bet(),
+        s.aggression(),
+        s.showdowns_won,
+        s.biggest_pot,
+        s.net_chips
+    )
+}
+
+fn pretty_print_chat(chat: &ChatLog, messages: usize) -> Vec<String> {
+    chat.recent(messages)
+        .iter()
+        .map(|m| format!("[Player {}] {}", m.player, m.text))
+        .collect()
+}
+
+const BOLD: &str = "\x1b[1m";
+const DIM: &str = "\x1b[2m";
+const RESET: &str = "\x1b[0m";
+
+/// The board and the hands still in at showdown, with the five winning cards in bold and
+/// every other card dimmed.
+fn pretty_print_showdown(hs: &HandState) -> Vec<String> {
+    let Some((winner, hand)) = hs.showdown_winner() else {
+        return vec![];
+    };
+    let snapshot = hs.public_snapshot();
+    let card = |c: &Card| {
+        let style = if hand.0.contains(c) { BOLD } else { DIM };
+        format!("{style}{:<4}{RESET}", c.pretty_print())
+    };
+
+    let board = snapshot
+        .board
+        .iter()
+        .map(card)
+        .collect::<Vec<_>>()
+        .join(" ");
+    let hands = snapshot
+        .hands
+        .iter()
+        .enumerate()
+        .map(|(p, h)| match h {
+            HandVisibility::Visible(c1, c2) if p == winner => format!("{} {}", card(c1), card(c2)),
+            HandVisibility::Visible(c1, c2) => format!(
+                "{DIM}{:<4} {:<4}{RESET}",
+                c1.pretty_print(),
+                c2.pretty_print()
+            ),
+            other => pretty_print_hand(other),
+        })
+        .collect::<Vec<_>>()
+        .join(" | ");
+
+    vec![
         String::new(),
-        call_to_action,
-        actions,
+        "SHOWDOWN".to_owned(),
+        board,
+        hands,
+        format!("Player {winner} wins with {}", hand.ranking_name()),
     ]
 }
 
+fn pretty_print_pots(snapshot: &HandSnapshot) -> String {
+    if snapshot.pots.len() < 2 {
+        return format!("Current pot: {} chips", snapshot.pot);
+    }
+    snapshot
+        .pots
+        .iter()
+        .enumerate()
+        .map(|(i, pot)| {
+            let name = if i == 0 {
+                "Main pot".to_owned()
+            } else {
+                format!("Side pot {i}:")
+            };
+            let eligible = pot
+                .eligible
+                .iter()
+                .map(|p| format!("P{p}"))
+                .collect::<Vec<_>>()
+                .join(", ");
+            format!("{name} {} ({eligible})", pot.amount)
+        })
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+fn pretty_print_hands(snapshot: &HandSnapshot) -> String {
+    snapshot
+        .hands
+        .iter()
+        .map(pretty_print_hand)
+        .collect::<Vec<_>>()
+        .join(" | ")
+}
+
 fn pretty_print_hand(h: &HandVisibility) -> String {
     match *h {
         HandVisibility::Visible(c1, c2) => {
             format!("{:<4} {:<4}", c1.pretty_print(), c2.pretty_print())
         }
+        HandVisibility::Hidden => " ??   ?? ".to_owned(),
         HandVisibility::Folded => "  FOLD   ".to_owned(),
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Compares against `src/snapshots/<name>.txt`, or rewrites it when `UPDATE_SNAPSHOTS`
+    /// is set.
+    fn assert_snapshot(name: &str, actual: &str) {
+        let path = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+            .join("src/snapshots")
+            .join(format!("{name}.txt"));
+        if std::env::var_os("UPDATE_SNAPSHOTS").is_some() {
+            fs::write(&path, actual).unwrap();
+            return;
+        }
+        let expected = fs::read_to_string(&path).unwrap_or_default();
+        assert_eq!(actual, expected, "layout changed, see {}", path.display());
+    }
+
+    fn render(compact: bool, spectator: bool) -> String {
+        let (mut hs, _) = GameState::init(3)
+            .unwrap()
+            .start_play_hand(Deck::ordered_deck());
+        let (played, result) = act(hs, Raise(6));
+        result.unwrap();
+        hs = played;
+        let (played, result) = act(hs, CallOrCheck);
+        result.unwrap();
+        hs = played;
+        let snapshot = if spectator {
+            hs.public_snapshot()
+        } else {
+            hs.spectator_snapshot()
+        };
+        let mut chat = ChatLog::init(3);
+        chat.post(0, "nice hand");
+
+        let mut renderer = StringRenderer::default();
+        draw_table(
+            &mut renderer,
+            &snapshot,
+            &chat,
+            &["Player 0 raised"],
+            compact,
+        );
+        renderer.buffer
+    }
+
+    #[test]
+    fn should_think_around_the_configured_delay() {
+        let delay = Duration::from_millis(400);
+        for _ in 0..100 {
+            let time = thinking_time(delay);
+            assert!(time >= delay / 2 && time <= delay * 3 / 2);
+        }
+        assert_eq!(thinking_time(Duration::ZERO), Duration::ZERO);
+    }
+
+    #[test]
+    fn should_end_a_game_once_one_stack_is_left() {
+        let gs = GameState::builder().stacks(vec![10, 10]).build().unwrap();
+        assert_eq!(last_standing(&gs), None);
+
+        let (hs, _) = gs.start_play_hand(Deck::ordered_deck());
+        let (hs, result) = act(hs, Raise(9));
+        result.unwrap();
+        let (hs, result) = act(hs, CallOrCheck);
+        assert!(matches!(result, Ok((poker_tui::TurnResult::WonHand(_), _))));
+        let gs = gs.apply_played_hand(hs);
+        let winner = (0..2).find(|&p| gs.current_chips(p) == 20);
+        assert_eq!(last_standing(&gs), winner);
+    }
+
+    #[test]
+    fn should_go_all_in_with_the_all_in_preset() {
+        let gs = GameState::builder().stacks(vec![40, 150]).build().unwrap();
+        let (hs, cur) = gs.start_play_hand(Deck::ordered_deck());
+        let size = parse_bet_preset("4").unwrap();
+        let amount = hs.bet_size(size);
+        let (hs, result) = act(hs, Raise(amount));
+        result.unwrap();
+        assert_eq!(hs.spectator_snapshot().chips[cur].stack, 0);
+    }
+
+    #[test]
+    fn should_simulate_at_the_table_like_the_simulator() {
+        let bots = || -> Vec<Box<dyn BotPlayer>> {
+            ["station", "normal", "maniac"]
+                .iter()
+                .map(|spec| BotKind::parse(spec).unwrap().build_seeded(Some(3)).unwrap())
+                .collect()
+        };
+        let mut expected_log = vec![];
+        let expected = poker_tui::simulation::simulate_with_log(
+            &mut bots(),
+            30,
+            100,
+            Some(5),
+            &mut expected_log,
+        )
+        .unwrap();
+
+        let path = std::env::temp_dir().join(format!("poker-tui-{}.jsonl", rand::random::<u64>()));
+        let out = Box::new(File::create(&path).unwrap());
+        let names = vec![String::new(); 3];
+        let result = simulate_at_table(bots(), names, 30, 100, Some(5), out).unwrap();
+        assert_eq!(result, expected);
+        assert_eq!(fs::read(&path).unwrap(), expected_log);
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn should_parse_seats() {
+        let seats = parse_seats("h,b,b:easy,b:mc=50", BotKind::Normal).unwrap();
+        assert_eq!(
+            seats,
+            vec![
+                None,
+                Some(BotKind::Normal),
+                Some(BotKind::Easy),
+                Some(BotKind::Hard(50))
+            ]
+        );
+        assert_eq!(
+            seats.iter().map(seat_spec).collect::<Vec<_>>(),
+            vec!["h", "b:normal", "b:easy", "b:mc=50"]
+        );
+        assert!(parse_seats("b,b:hard", BotKind::Normal).is_none());
+        assert!(parse_seats("h,x", BotKind::Normal).is_none());
+
+        let mixed = parse_seat("b:normal@bluff=0.3", &BotKind::Normal).unwrap();
+        assert_eq!(
+            seat_spec(&mixed),
+            "b:normal@bluff=0.3:slowplay=0:hero_call=0"
+        );
+        assert_eq!(
+            parse_seat(&seat_spec(&mixed), &BotKind::Normal),
+            Some(mixed)
+        );
+    }
+
+    #[test]
+    fn should_autosave_a_game_that_can_be_restored() {
+        let path =
+            std::env::temp_dir().join(format!("poker-tui-{}.autosave", rand::random::<u64>()));
+        let args = ["--autosave".to_owned(), path.display().to_string()];
+        let settings = Settings::from_args(&mut SilentRenderer, args.into_iter());
+        let gs = GameState::init(3).unwrap();
+        let (mut hs, _) = gs.start_play_hand(Deck::shuffled_deck());
+        let (played, result) = act(hs, CallOrCheck);
+        result.unwrap();
+        hs = played;
+
+        autosave(
+            &mut SilentRenderer,
+            &settings,
+            "h,b:easy,b:chart",
+            &gs,
+            Some(&hs),
+        );
+        let json = fs::read_to_string(&path).unwrap();
+        let (restored, hand) = GameState::from_json(&json).unwrap();
+        assert_eq!(restored.players(), 3);
+        assert_eq!(
+            saved_seats(&mut SilentRenderer, &settings, &json, 3),
+            [None, Some(BotKind::Easy), Some(BotKind::Chart(None))]
+        );
+        assert_eq!(hand.unwrap().history().actions, [CallOrCheck]);
+        assert!(!path.with_extension("tmp").exists());
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn should_play_a_hand_from_scripted_seats() {
+        let script = |lines: &[&str]| -> Box<dyn SeatController> {
+            Box::new(ScriptedSeat {
+                lines: lines.iter().map(|&line| line.to_owned()).collect(),
+            })
+        };
+        let gs = GameState::init(2).unwrap();
+        let seats = vec![script(&["o", "c", "c", "c", "c"]), script(&["c"; 4])];
+        let names = vec!["Player 0".to_owned(), "Player 1".to_owned()];
+        let mut session = Session {
+            renderer: Box::new(SilentRenderer),
+            ..Session::new(&gs, seats, names)
+        };
+        let settings = Settings::from_args(&mut SilentRenderer, std::iter::empty());
+        let (hs, _) = gs.start_play_hand(Deck::ordered_deck());
+
+        let next = play_hand(&gs, Some(hs), &settings, &mut session).unwrap();
+        assert!(session.overlay);
+        assert_eq!(session.stats.player(0).hands_played, 1);
+        assert_eq!(next.current_chips(0) + next.current_chips(1), 200);
+        assert!(play_hand(&next, None, &settings, &mut session).is_none());
+    }
+
+    #[test]
+    fn should_render_wide_table() {
+        assert_snapshot("wide_table", &render(false, false));
+    }
+
+    #[test]
+    fn should_render_compact_table() {
+        assert_snapshot("compact_table", &render(true, false));
+    }
+
+    #[test]
+    fn should_render_table_for_spectators() {
+        assert_snapshot("spectator_table", &render(false, true));
+    }
+}
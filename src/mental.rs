@@ -0,0 +1,383 @@
+//! Mental poker: a deck shuffled by everyone at the table together, so that no single
+//! party picks its order or knows it until they all hand over their keys.
+//!
+//! Cards are numbers modulo a large safe prime, and every party encrypts them by raising
+//! them to a secret power. Encryption that way commutes, so the layers of different
+//! parties can be added and taken off in any order (the SRA scheme). The cards are
+//! all squares, and stay squares when encrypted, so a ciphertext doesn't give away
+//! anything about the card it hides.
+//!
+//! Dealing takes two passes around the table. In the first, every party encrypts all of
+//! the cards with one key and shuffles them. In the second, every party takes its first
+//! layer off again and puts on a key of its own for every card, so that any one card can
+//! be opened with the keys for it alone. A player handed everyone else's keys to a card is
+//! the only one who can open it. Once the hand is over, every party's keys show
+//! whether anyone cheated: `Audit::check` redoes every step and finds the deck.
+
+use std::collections::HashMap;
+
+use num_bigint::BigUint;
+use rand::seq::SliceRandom;
+use serde::{Deserialize, Serialize};
+
+use crate::core_engine::{Card, Deck};
+
+/// The 1024-bit safe prime of RFC 2409, group 2. Bigger ones make a deck take seconds to
+/// go around the table, and the deck only has to stay secret until the hand is over.
+const PRIME: &str = "FFFFFFFFFFFFFFFFC90FDAA22168C234C4C6628B80DC1CD129024E088A67CC74020BBEA63B139B22514A08798E3404DDEF9519B3CD3A431B302B0A6DF25F14374FE1356D6D51C245E485B576625E7EC6F44C42E9A637ED6B0BFF5CB6F406B7EDEE386BFB5A899FA5AE9F24117C4B1FE649286651ECE65381FFFFFFFFFFFFFFFF";
+
+fn prime() -> BigUint {
+    BigUint::parse_bytes(PRIME.as_bytes(), 16).expect("the prime is hex")
+}
+
+fn to_hex(n: &BigUint) -> String {
+    n.to_str_radix(16)
+}
+
+fn from_hex(hex: &str) -> Result<BigUint, String> {
+    BigUint::parse_bytes(hex.as_bytes(), 16)
+        .filter(|n| *n < prime())
+        .ok_or_else(|| format!("{hex:.16}... is not a number below the prime"))
+}
+
+fn from_hexes(hexes: &[String]) -> Result<Vec<BigUint>, String> {
+    hexes.iter().map(|hex| from_hex(hex)).collect()
+}
+
+/// A secret power and the power that undoes it.
+struct Key {
+    encrypt: BigUint,
+    decrypt: BigUint,
+}
+
+impl Key {
+    fn random() -> Self {
+        let p = prime();
+        let order = &p - 1u32;
+        loop {
+            let bytes = rand::random::<[u8; 128]>();
+            let encrypt = BigUint::from_bytes_be(&bytes) % &order;
+            if let Some(decrypt) = encrypt.modinv(&order) {
+                return Self { encrypt, decrypt };
+            }
+        }
+    }
+
+    /// The key whose encrypting power is `encrypt`, if it has an inverse.
+    fn from_power(encrypt: BigUint) -> Option<Self> {
+        let decrypt = encrypt.modinv(&(prime() - 1u32))?;
+        Some(Self { encrypt, decrypt })
+    }
+
+    fn lock(&self, card: &BigUint) -> BigUint {
+        card.modpow(&self.encrypt, &prime())
+    }
+
+    fn unlock(&self, card: &BigUint) -> BigUint {
+        card.modpow(&self.decrypt, &prime())
+    }
+}
+
+/// The deck before anyone encrypted it, in the order of `Deck::ordered_deck`.
+pub fn plain_deck() -> Vec<String> {
+    (0..52u32)
+        .map(|i| to_hex(&(BigUint::from(i + 2).pow(2))))
+        .collect()
+}
+
+/// A card of `plain_deck` as a card.
+fn card(plain: &BigUint) -> Option<Card> {
+    let deck = Deck::ordered_deck();
+    let index: HashMap<BigUint, usize> = (0..52u32)
+        .map(|i| (BigUint::from(i + 2).pow(2), i as usize))
+        .collect();
+    index.get(plain).map(|&i| deck.cards()[i])
+}
+
+/// Where in the deck the two cards dealt to `seat` are. The game draws from the end of the
+/// deck, two cards a player and then the board.
+pub fn hole_positions(seat: usize) -> [usize; 2] {
+    [51 - 2 * seat, 50 - 2 * seat]
+}
+
+/// Where in the deck the `index`th card of the board is at a table of `players`.
+pub fn board_position(players: usize, index: usize) -> usize {
+    51 - 2 * players - index
+}
+
+/// Opens a card of the locked deck with every party's key to it.
+pub fn open(locked: &str, keys: &[String]) -> Result<Card, String> {
+    let keys = from_hexes(keys)?
+        .into_iter()
+        .map(Key::from_power)
+        .collect::<Option<Vec<_>>>()
+        .ok_or("a party handed over a key that doesn't work")?;
+    let open = keys
+        .iter()
+        .fold(from_hex(locked)?, |card, key| key.unlock(&card));
+    card(&open).ok_or_else(|| "the keys don't open the card".to_owned())
+}
+
+/// The keys a party used, handed over so that the deck can be opened and checked. Each
+/// is the encrypting power in hex.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PartyKeys {
+    pub shuffle: String,
+    /// By position in the deck.
+    pub cards: Vec<String>,
+}
+
+/// One party's part in shuffling a deck, remembered so that it can check the audit.
+pub struct Party {
+    shuffle: Key,
+    cards: Vec<Key>,
+    shuffled: Vec<String>,
+    locked: Vec<String>,
+}
+
+impl Party {
+    pub fn new() -> Self {
+        Self {
+            shuffle: Key::random(),
+            cards: (0..52).map(|_| Key::random()).collect(),
+            shuffled: vec![],
+            locked: vec![],
+        }
+    }
+
+    /// The first pass: encrypts every card with the same key and shuffles them.
+    pub fn shuffle(&mut self, deck: &[String]) -> Result<Vec<String>, String> {
+        let mut deck: Vec<_> = from_hexes(deck)?
+            .iter()
+            .map(|card| to_hex(&self.shuffle.lock(card)))
+            .collect();
+        deck.shuffle(&mut rand::rng());
+        self.shuffled = deck.clone();
+        Ok(deck)
+    }
+
+    /// The second pass: swaps the key of the first for one key per card.
+    pub fn lock(&mut self, deck: &[String]) -> Result<Vec<String>, String> {
+        if deck.len() != self.cards.len() {
+            return Err(format!("a deck of {} cards can't be locked", deck.len()));
+        }
+        self.locked = from_hexes(deck)?
+            .iter()
+            .zip(&self.cards)
+            .map(|(card, key)| to_hex(&key.lock(&self.shuffle.unlock(card))))
+            .collect();
+        Ok(self.locked.clone())
+    }
+
+    /// Checks that `audit` has this party's part as `seat` played it, and that `seen`, the
+    /// cards dealt to the seat followed by the board, came from the deck it opens to in the
+    /// order the game deals them.
+    pub fn check(
+        &self,
+        audit: &Audit,
+        seat: usize,
+        players: usize,
+        seen: &[Card],
+    ) -> Result<(), String> {
+        let deck = audit.check()?;
+        let index = audit
+            .seats
+            .iter()
+            .position(|&s| s == seat)
+            .ok_or("the audit leaves this seat out")?;
+        if audit.shuffled[index] != self.shuffled
+            || audit.locked[index] != self.locked
+            || audit.keys[index] != self.keys()
+        {
+            return Err("the audit doesn't have this seat's part as it was played".to_owned());
+        }
+        // The game draws from the end of the deck, two cards a player and then the board.
+        let order: Vec<_> = deck.cards().iter().rev().copied().collect();
+        let (hole, board) = seen.split_at(seen.len().min(2));
+        let dealt = order
+            .get(2 * seat..2 * seat + hole.len())
+            .zip(order.get(2 * players..2 * players + board.len()));
+        if !dealt.is_some_and(|(hole, board)| hole.iter().chain(board).eq(seen)) {
+            return Err("the cards dealt aren't the ones the deck opens to".to_owned());
+        }
+        Ok(())
+    }
+
+    /// This party's key to the card at `position` of the deck, for whoever is dealt it.
+    pub fn card_key(&self, position: usize) -> Option<String> {
+        self.cards.get(position).map(|key| to_hex(&key.encrypt))
+    }
+
+    /// Opens the card dealt to this party at `position` of the locked deck, given every
+    /// other party's key to it.
+    pub fn open(&self, position: usize, locked: &str, others: &[String]) -> Result<Card, String> {
+        let own = self.card_key(position).ok_or("there is no card there")?;
+        open(locked, &[others, &[own]].concat())
+    }
+
+    pub fn keys(&self) -> PartyKeys {
+        PartyKeys {
+            shuffle: to_hex(&self.shuffle.encrypt),
+            cards: self.cards.iter().map(|key| to_hex(&key.encrypt)).collect(),
+        }
+    }
+}
+
+impl Default for Party {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Everything that happened to a deck on its way around the table, and the keys of
+/// everyone who took part, in the order they did.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Audit {
+    /// The seat of every party.
+    pub seats: Vec<usize>,
+    /// The deck after every party's first pass.
+    pub shuffled: Vec<Vec<String>>,
+    /// The deck after every party's second pass.
+    pub locked: Vec<Vec<String>>,
+    pub keys: Vec<PartyKeys>,
+}
+
+impl Audit {
+    /// Redoes every party's steps with their keys and opens the deck, or tells which
+    /// step wasn't done the way it should have been.
+    pub fn check(&self) -> Result<Deck, String> {
+        let parties = self.seats.len();
+        if [self.shuffled.len(), self.locked.len(), self.keys.len()] != [parties; 3] {
+            return Err("the audit doesn't have every step of every party".to_owned());
+        }
+        let keys = self
+            .keys
+            .iter()
+            .map(|keys| {
+                let key = |hex: &String| from_hex(hex).ok().and_then(Key::from_power);
+                let shuffle = key(&keys.shuffle);
+                let cards: Option<Vec<_>> = keys.cards.iter().map(key).collect();
+                shuffle.zip(cards).filter(|(_, cards)| cards.len() == 52)
+            })
+            .collect::<Option<Vec<_>>>()
+            .ok_or("a party handed over keys that don't work")?;
+
+        let mut deck = from_hexes(&plain_deck())?;
+        for (party, (shuffle, _)) in keys.iter().enumerate() {
+            let seat = self.seats[party];
+            let shuffled = from_hexes(&self.shuffled[party])?;
+            let mut expected: Vec<_> = deck.iter().map(|card| shuffle.lock(card)).collect();
+            let mut got = shuffled.clone();
+            expected.sort();
+            got.sort();
+            if expected != got {
+                return Err(format!("seat {seat} didn't shuffle the deck it was given"));
+            }
+            deck = shuffled;
+        }
+        for (party, (shuffle, cards)) in keys.iter().enumerate() {
+            let seat = self.seats[party];
+            let locked = from_hexes(&self.locked[party])?;
+            let relocked = deck
+                .iter()
+                .zip(cards)
+                .map(|(card, key)| key.lock(&shuffle.unlock(card)));
+            if locked.len() != deck.len() || !relocked.eq(locked.iter().cloned()) {
+                return Err(format!("seat {seat} didn't lock the deck it was given"));
+            }
+            deck = locked;
+        }
+        let cards = deck
+            .iter()
+            .enumerate()
+            .map(|(position, card)| {
+                let open = keys.iter().fold(card.clone(), |card, (_, cards)| {
+                    cards[position].unlock(&card)
+                });
+                self::card(&open)
+            })
+            .collect::<Option<Vec<_>>>()
+            .ok_or("the deck opens to something that isn't a card")?;
+        Ok(Deck::init(cards))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{GameState, PokerAction};
+
+    fn deal(parties: &mut [Party]) -> Audit {
+        let mut deck = plain_deck();
+        let mut audit = Audit {
+            seats: (0..parties.len()).collect(),
+            shuffled: vec![],
+            locked: vec![],
+            keys: parties.iter().map(Party::keys).collect(),
+        };
+        for party in parties.iter_mut() {
+            deck = party.shuffle(&deck).unwrap();
+            audit.shuffled.push(deck.clone());
+        }
+        for party in parties.iter_mut() {
+            deck = party.lock(&deck).unwrap();
+            audit.locked.push(deck.clone());
+        }
+        audit
+    }
+
+    #[test]
+    fn should_open_a_jointly_shuffled_deck_and_catch_a_party_that_cheats() {
+        let mut parties = [Party::new(), Party::new()];
+        let audit = deal(&mut parties);
+        let deck = audit.check().unwrap();
+        let (mut hs, _) = GameState::init(2).unwrap().start_play_hand(deck.clone());
+        hs.play_action(PokerAction::CallOrCheck).unwrap();
+        hs.play_action(PokerAction::CallOrCheck).unwrap();
+        let view = hs.player_view(1);
+        assert_eq!(view.board.len(), 3);
+        let seen = [&[view.cards.0, view.cards.1][..], &view.board].concat();
+        parties[1].check(&audit, 1, 2, &seen).unwrap();
+        assert!(parties[0].check(&audit, 0, 2, &seen).is_err());
+
+        let mut cards = deck.cards().to_vec();
+        cards.sort_by_key(|c| (c.value, c.suit as u8));
+        let mut all = Deck::ordered_deck().cards().to_vec();
+        all.sort_by_key(|c| (c.value, c.suit as u8));
+        assert_eq!(cards, all);
+        assert_ne!(deck.cards(), Deck::ordered_deck().cards());
+
+        // Swapping two cards after the shuffle is caught even though both are still there.
+        let mut stacked = audit.clone();
+        stacked.locked[1].swap(0, 1);
+        assert_eq!(
+            stacked.check().err().unwrap(),
+            "seat 1 didn't lock the deck it was given"
+        );
+        let mut marked = audit;
+        marked.shuffled[0][0] = plain_deck()[0].clone();
+        assert!(marked.check().is_err());
+    }
+
+    #[test]
+    fn should_open_a_card_only_with_every_partys_key_to_it() {
+        let mut parties = [Party::new(), Party::new(), Party::new()];
+        let audit = deal(&mut parties);
+        let deck = audit.check().unwrap();
+        let locked = audit.locked.last().unwrap();
+        let position = hole_positions(1)[1];
+        let others: Vec<_> = [&parties[0], &parties[2]]
+            .iter()
+            .map(|party| party.card_key(position).unwrap())
+            .collect();
+        assert!(open(&locked[position], &others).is_err());
+        assert_eq!(
+            parties[1].open(position, &locked[position], &others),
+            Ok(deck.cards()[position])
+        );
+
+        let (hs, _) = GameState::init(3).unwrap().start_play_hand(deck.clone());
+        let cards = hole_positions(1).map(|position| deck.cards()[position]);
+        assert_eq!(hs.player_view(1).cards, (cards[0], cards[1]));
+    }
+}
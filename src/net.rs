@@ -0,0 +1,2556 @@
+//! Networked multiplayer. The host runs the only copy of the game, and each client gets
+//! the messages of `protocol` over any `Transport`, with nothing a player at a real table
+//! couldn't see: their own hole cards, but nobody else's until the showdown. A client that
+//! loses its connection can come back to its seat with the token it was given. One that
+//! stops answering pings is treated like one that hung up.
+//!
+//! At tables set up for it, the players shuffle each deck together as `mental` describes,
+//! and the host deals from it. It can't choose the order of the cards, and it only opens
+//! the board as it is dealt: each player's cards go to them still locked with their own
+//! keys, so nobody else sees them before the showdown, the host included. The host plays
+//! the hand on stand-ins for the cards it hasn't seen, which the betting doesn't depend on,
+//! and opens the whole deck once the hand is over to find who won. Every player can tell
+//! afterwards whether the cards they saw came from the deck they helped shuffle.
+//!
+//! A `Lobby` hosts any number of named tables, each playing its own game on a thread of
+//! its own, and sends every client to the table it asks for. Players can ask for the hands
+//! of their game so far, and the host can keep every hand on disk with `archive`.
+
+use std::collections::VecDeque;
+use std::fs;
+use std::io::{self, ErrorKind};
+use std::net::{SocketAddr, TcpStream};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, SendError, Sender, TryRecvError};
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+use serde_json::Value;
+
+use crate::archive::{self, ArchivedHand, HandArchive};
+use crate::auth::Signer;
+use crate::bots::check_or_fold;
+use crate::chat::ChatLog;
+use crate::core_engine::{Card, Deck};
+use crate::events::GameEvent;
+use crate::mental::{self, Audit, Party};
+use crate::protocol::{
+    apply_merge_patch, decode, merge_patch, negotiate, ClientMessage, Encoding, ServerMessage,
+    TableInfo, COMPACT_VERSION, DEADLINE_VERSION, HEARTBEAT_VERSION, HOLE_KEYS_VERSION,
+    RESUME_VERSION, SIT_OUT_VERSION, SUPPORTED_VERSIONS,
+};
+use crate::status::{StatusReport, TableStatus};
+use crate::transport::{connect_ws, Frame, LineTransport, Listener, Transport};
+use crate::view::SeatView;
+use crate::{GameState, HandState, IllegalRaise, PokerAction, TurnResult, BIG_BLIND_CHIPS};
+
+/// How long a new client has to say which protocol versions it speaks.
+const JOIN_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// How often the server looks for chat while it waits for a player to act.
+const CHAT_INTERVAL: Duration = Duration::from_millis(50);
+
+/// How many heartbeats a client has to answer a ping before it is taken for gone.
+const MISSED_PINGS: u32 = 3;
+
+/// How many messages a client can send at once before it has to slow down to
+/// `MESSAGES_PER_SECOND`. Pongs don't count.
+const MESSAGE_BURST: f64 = 20.0;
+const MESSAGES_PER_SECOND: f64 = 10.0;
+
+/// How long a client has to do its part in a mental shuffle.
+const SHUFFLE_TIMEOUT: Duration = Duration::from_secs(10);
+
+const UNKNOWN_TOKEN: &str = "That token doesn't belong to any seat";
+
+struct Seat {
+    /// Messages waiting to be sent to the client.
+    outgoing: Sender<Frame>,
+    /// Messages read from the client, or the reason one could not be read. Disconnects
+    /// when the client does.
+    messages: Receiver<Result<ClientMessage, String>>,
+    /// The thread talking to the client, which ends once `outgoing` is dropped and every
+    /// message in it has been sent.
+    pump: JoinHandle<()>,
+    /// The protocol version agreed on, 0 until then.
+    version: u32,
+    /// How the client is sent messages.
+    encoding: Encoding,
+    /// How often the pump pings the client, once it is known to answer.
+    heartbeat: Arc<Mutex<Option<Duration>>>,
+}
+
+impl Seat {
+    /// Moves messages between the channels and the client on a thread of its own.
+    fn connect(transport: Box<dyn Transport>) -> Self {
+        let (outgoing, to_send) = mpsc::channel::<Frame>();
+        let (received, messages) = mpsc::channel();
+        let heartbeat = Arc::new(Mutex::new(None));
+        let every = heartbeat.clone();
+        let pump = thread::spawn(move || Self::pump(transport, to_send, received, &every));
+        Self {
+            outgoing,
+            messages,
+            pump,
+            version: 0,
+            encoding: Encoding::Json,
+            heartbeat,
+        }
+    }
+
+    /// Sends what there is to send and passes on what the client says, taking its pongs
+    /// itself. Hangs up on a client that doesn't answer a ping within `MISSED_PINGS`
+    /// heartbeats, and once there is nothing left to send and no ping left unanswered: a
+    /// pong arriving after that would reset the connection and lose what the client
+    /// hadn't read yet. Drops what a client sends faster than it is allowed to, telling it
+    /// once for every run of messages dropped.
+    fn pump(
+        mut transport: Box<dyn Transport>,
+        to_send: Receiver<Frame>,
+        received: Sender<Result<ClientMessage, String>>,
+        heartbeat: &Mutex<Option<Duration>>,
+    ) {
+        let mut last_ping = Instant::now();
+        let mut unanswered: Option<Instant> = None;
+        let mut rtt: Option<Duration> = None;
+        let mut closing = false;
+        let mut allowance = MESSAGE_BURST;
+        let mut last_message = Instant::now();
+        let mut dropping = false;
+        loop {
+            while !closing {
+                match to_send.try_recv() {
+                    Ok(frame) => {
+                        if transport.send(&frame).is_err() {
+                            return;
+                        }
+                    }
+                    Err(TryRecvError::Empty) => break,
+                    Err(TryRecvError::Disconnected) => closing = true,
+                }
+            }
+            if closing && unanswered.is_none() {
+                return;
+            }
+            if let Some(every) = *heartbeat.lock().unwrap() {
+                match unanswered {
+                    Some(sent) if sent.elapsed() >= every * MISSED_PINGS => return,
+                    Some(_) => {}
+                    None if !closing && last_ping.elapsed() >= every => {
+                        let ping = ServerMessage::Ping {
+                            rtt_ms: rtt.map(|rtt| rtt.as_millis() as u64),
+                        };
+                        if transport.send(&Encoding::Json.encode(&ping)).is_err() {
+                            return;
+                        }
+                        last_ping = Instant::now();
+                        unanswered = Some(last_ping);
+                    }
+                    None => {}
+                }
+            }
+            match transport.recv() {
+                Ok(Some(frame)) => {
+                    let message = decode(&frame);
+                    if let Ok(ClientMessage::Pong) = message {
+                        rtt = unanswered.take().map(|sent| sent.elapsed());
+                        continue;
+                    }
+                    let elapsed = last_message.elapsed().as_secs_f64();
+                    last_message = Instant::now();
+                    allowance = (allowance + elapsed * MESSAGES_PER_SECOND).min(MESSAGE_BURST);
+                    if allowance < 1.0 {
+                        if !dropping {
+                            let slow_down = ServerMessage::Error {
+                                message: "Too many messages, slow down".to_owned(),
+                            };
+                            if transport.send(&Encoding::Json.encode(&slow_down)).is_err() {
+                                return;
+                            }
+                        }
+                        dropping = true;
+                        continue;
+                    }
+                    allowance -= 1.0;
+                    dropping = false;
+                    if received.send(message).is_err() {
+                        return;
+                    }
+                }
+                Ok(None) => {}
+                Err(_) => return,
+            }
+        }
+    }
+
+    /// Starts pinging the client every `every`.
+    fn start_heartbeat(&self, every: Duration) {
+        *self.heartbeat.lock().unwrap() = Some(every);
+    }
+
+    /// Waits for the client's first message and agrees on a protocol version, or tells the
+    /// client why not.
+    fn greet(&self) -> Option<(u32, Hello)> {
+        let version = |min, max| {
+            negotiate(min, max).ok_or(format!(
+                "This server speaks protocol versions {} to {}",
+                SUPPORTED_VERSIONS.start(),
+                SUPPORTED_VERSIONS.end()
+            ))
+        };
+        let answer = match self.messages.recv_timeout(JOIN_TIMEOUT) {
+            Ok(Ok(ClientMessage::Join {
+                min_version,
+                max_version,
+                table,
+                password,
+                invite,
+                encoding,
+            })) => version(min_version, max_version).map(|v| {
+                let hello = Hello::Join {
+                    table,
+                    password,
+                    invite,
+                    encoding,
+                };
+                (v, hello)
+            }),
+            Ok(Ok(ClientMessage::Resume {
+                token,
+                min_version,
+                max_version,
+                encoding,
+            })) => {
+                version(min_version, max_version).map(|v| (v, Hello::Resume { token, encoding }))
+            }
+            Ok(Ok(ClientMessage::Watch {
+                min_version,
+                max_version,
+                table,
+                password,
+            })) => version(min_version, max_version).map(|v| (v, Hello::Watch { table, password })),
+            Ok(Ok(ClientMessage::ListTables)) => Ok((*SUPPORTED_VERSIONS.end(), Hello::ListTables)),
+            Ok(Ok(_)) => Err("Expected a join message first".to_owned()),
+            Ok(Err(message)) => Err(message),
+            Err(_) => return None,
+        };
+        answer.map_err(|message| self.refuse(message)).ok()
+    }
+
+    fn tell(&self, message: &ServerMessage) {
+        // A client that left just misses out.
+        let message = message.for_version(self.version);
+        let _ = self.outgoing.send(self.encoding.encode(&message));
+    }
+
+    fn refuse(&self, message: String) {
+        self.tell(&ServerMessage::Error { message });
+    }
+}
+
+/// What a client opened with.
+enum Hello {
+    Join {
+        table: String,
+        password: Option<String>,
+        invite: Option<String>,
+        encoding: Encoding,
+    },
+    Resume {
+        token: String,
+        encoding: Encoding,
+    },
+    ListTables,
+    Watch {
+        table: String,
+        password: Option<String>,
+    },
+}
+
+/// A client that connected and said hello.
+struct Arrival {
+    seat: Seat,
+    version: u32,
+    hello: Hello,
+    addr: SocketAddr,
+}
+
+/// Greets every client that connects on a thread of its own, so a slow one doesn't hold up
+/// the game, and hands over those that said hello.
+fn greet_arrivals(listeners: Vec<Listener>) -> Receiver<Arrival> {
+    let (tx, connected) = mpsc::channel();
+    for listener in listeners {
+        listener.spawn(tx.clone());
+    }
+    let (arrived, arrivals) = mpsc::channel();
+    thread::spawn(move || {
+        for (transport, addr) in connected {
+            let arrived = arrived.clone();
+            thread::spawn(move || {
+                let seat = Seat::connect(transport);
+                if let Some((version, hello)) = seat.greet() {
+                    let _ = arrived.send(Arrival {
+                        seat,
+                        version,
+                        hello,
+                        addr,
+                    });
+                }
+            });
+        }
+    });
+    arrivals
+}
+
+/// A token nobody can guess, for resuming a seat at the lobby's `table`th table, or at the
+/// `game`th game its queue started.
+fn new_token(signer: &Signer, table: usize, game: Option<usize>) -> String {
+    let place = match game {
+        Some(game) => format!("{table}.{game}"),
+        None => table.to_string(),
+    };
+    signer.sign(&format!("{place}-{:032x}", rand::random::<u128>()))
+}
+
+/// The table a token was handed out at, and the game if the table is a queue, if it was
+/// handed out at all.
+fn token_place(signer: &Signer, token: &str) -> Option<(usize, Option<usize>)> {
+    let verified = signer.verify(token)?;
+    let (place, _) = verified.split_once('-')?;
+    match place.split_once('.') {
+        Some((table, game)) => Some((table.parse().ok()?, Some(game.parse().ok()?))),
+        None => Some((place.parse().ok()?, None)),
+    }
+}
+
+/// Whether `seat` may see `event`: everything but the other players' hole cards.
+fn shown_to(event: &GameEvent, seat: usize) -> bool {
+    !matches!(event, GameEvent::HoleCardsDealt { player, .. } if *player != seat)
+}
+
+/// Whether spectators may see `event`: everything but hole cards.
+fn public(event: &GameEvent) -> bool {
+    !matches!(event, GameEvent::HoleCardsDealt { .. })
+}
+
+/// A deck the players shuffled together, opened only as far as the hand has gone.
+struct SharedDeck {
+    /// Every party's steps, and their keys once the hand is over.
+    audit: Audit,
+    /// The cards opened so far, by position in the deck.
+    opened: Vec<Option<Card>>,
+}
+
+impl SharedDeck {
+    /// The card at `position` as the deck is locked once every party did its part.
+    fn locked(&self, position: usize) -> &str {
+        &self.audit.locked.last().expect("a shared deck is locked")[position]
+    }
+
+    /// Opens the card at `position` with every party's key to it.
+    fn open(&mut self, position: usize, keys: &[String]) -> Option<()> {
+        let card = mental::open(self.locked(position), keys).ok()?;
+        self.opened[position] = Some(card);
+        Some(())
+    }
+
+    /// The deck to play the hand on: the cards opened so far, and stand-ins for the rest.
+    fn deck(&self) -> Deck {
+        let stand_ins = Deck::ordered_deck();
+        let cards = self.opened.iter().zip(stand_ins.cards());
+        Deck::init(
+            cards
+                .map(|(opened, stand_in)| opened.unwrap_or(*stand_in))
+                .collect(),
+        )
+    }
+}
+
+/// Plays the actions of `hs` again from the start of the hand, on `deck`.
+fn replay(gs: &GameState, hs: &HandState, deck: Deck) -> HandState {
+    let (mut again, _) = gs.start_play_hand(deck);
+    for &action in &hs.history().actions {
+        again
+            .play_action(action)
+            .expect("the cards don't change which actions are legal");
+    }
+    again
+}
+
+/// A client watching the table.
+struct Spectator {
+    client: Seat,
+    /// Messages held back until they are due.
+    queue: VecDeque<(Instant, Frame)>,
+}
+
+/// The authoritative side of the game at one table, with a client connected to every seat.
+struct Server {
+    /// Where the table is in the lobby.
+    table: usize,
+    /// Which game of the table's queue this is, if the table is one.
+    game: Option<usize>,
+    signer: Arc<Signer>,
+    counters: Arc<Counters>,
+    players: usize,
+    seats: Vec<Seat>,
+    /// The token each seat is resumed with.
+    tokens: Vec<String>,
+    spectators: Vec<Spectator>,
+    /// How long spectators wait to see what happened.
+    spectator_delay: Duration,
+    turn_timeout: Option<Duration>,
+    sit_out_after: u32,
+    heartbeat: Duration,
+    mental_shuffle: bool,
+    /// The seats dealt cards the server hasn't seen in the hand being played.
+    unseen: Vec<usize>,
+    /// How many turns in a row each seat let run out of time or missed by being away.
+    timeouts: Vec<u32>,
+    sitting_out: Vec<bool>,
+    /// The JSON of the table each seat was sent last, for seats sent only what changed.
+    views: Vec<Option<Value>>,
+    arrivals: Receiver<Arrival>,
+    chat: ChatLog,
+    /// Where every hand is kept once it is over, if anywhere.
+    archive: Option<HandArchive>,
+    /// The hands of the game finished so far.
+    hands: Vec<ArchivedHand>,
+}
+
+impl Server {
+    /// Waits until the `table`th table of the lobby, or the `game`th game of its queue, is
+    /// full, calling `joined` with the seat and address of each player as they take a seat.
+    fn accept(
+        table: usize,
+        game: Option<usize>,
+        config: &TableConfig,
+        signer: Arc<Signer>,
+        counters: Arc<Counters>,
+        arrivals: Receiver<Arrival>,
+        mut joined: impl FnMut(usize, SocketAddr),
+    ) -> io::Result<Self> {
+        let players = config.players;
+        let mut server = Server {
+            table,
+            game,
+            signer,
+            counters,
+            players,
+            seats: vec![],
+            tokens: vec![],
+            spectators: vec![],
+            spectator_delay: config.spectator_delay,
+            turn_timeout: config.turn_timeout,
+            sit_out_after: config.sit_out_after,
+            heartbeat: config.heartbeat,
+            mental_shuffle: config.mental_shuffle,
+            unseen: vec![],
+            timeouts: vec![],
+            sitting_out: vec![],
+            views: vec![],
+            arrivals,
+            chat: ChatLog::init(players),
+            archive: None,
+            hands: vec![],
+        };
+        while server.seats.len() < players {
+            let arrival = server
+                .arrivals
+                .recv()
+                .map_err(|_| io::Error::other("stopped taking players"))?;
+            let addr = arrival.addr;
+            if let Some(seat) = server.admit(arrival, None) {
+                server
+                    .counters
+                    .seated
+                    .store(server.seats.len(), Ordering::Relaxed);
+                joined(seat, addr);
+            }
+        }
+        Ok(server)
+    }
+
+    /// Seats a client that joined, or gives a resuming one its seat back along with what
+    /// it missed of `hand`. Returns the seat. Spectators get no seat, and `hand` as it is
+    /// now once they have waited out the delay.
+    fn admit(&mut self, arrival: Arrival, hand: Option<&HandState>) -> Option<usize> {
+        let Arrival {
+            seat: mut client,
+            version,
+            hello,
+            ..
+        } = arrival;
+        client.version = version;
+        if version >= HEARTBEAT_VERSION {
+            client.start_heartbeat(self.heartbeat);
+        }
+        if let Hello::Join { encoding, .. } | Hello::Resume { encoding, .. } = hello {
+            if version >= COMPACT_VERSION {
+                client.encoding = encoding;
+            }
+        }
+        let seat = match hello {
+            Hello::Join { .. } if self.seats.len() < self.players => {
+                self.seats.push(client);
+                self.tokens
+                    .push(new_token(&self.signer, self.table, self.game));
+                self.timeouts.push(0);
+                self.sitting_out.push(false);
+                self.views.push(None);
+                self.seats.len() - 1
+            }
+            Hello::Join { .. } => {
+                client.refuse("The table is full".to_owned());
+                return None;
+            }
+            Hello::Resume { token, .. } => match self.tokens.iter().position(|t| *t == token) {
+                // The old connection's thread ends once it notices it is gone.
+                Some(seat) => {
+                    self.seats[seat] = client;
+                    self.views[seat] = None;
+                    seat
+                }
+                None => {
+                    client.refuse(UNKNOWN_TOKEN.to_owned());
+                    return None;
+                }
+            },
+            Hello::Watch { .. } => {
+                let message = ServerMessage::Watching {
+                    players: self.players,
+                    version,
+                    delay_secs: self.spectator_delay.as_secs(),
+                };
+                client.tell(&message);
+                self.spectators.push(Spectator {
+                    client,
+                    queue: VecDeque::new(),
+                });
+                if let Some(hs) = hand {
+                    let view = hs.public_view();
+                    let spectator = self.spectators.len() - 1;
+                    self.watch(Some(spectator), &ServerMessage::PublicTable { view });
+                }
+                return None;
+            }
+            // The lobby answers these itself.
+            Hello::ListTables => return None,
+        };
+        let message = ServerMessage::Seat {
+            seat,
+            players: self.players,
+            version,
+            token: self.tokens[seat].clone(),
+            encoding: self.seats[seat].encoding,
+        };
+        self.send(seat, &message);
+        if let Some(hs) = hand.filter(|_| version >= RESUME_VERSION) {
+            let hand = hs
+                .events()
+                .iter()
+                .filter(|event| self.sent_to(event, seat))
+                .map(ServerMessage::from_event)
+                .collect();
+            let view = hs.seat_view(seat);
+            self.send(seat, &ServerMessage::Sync { hand, view });
+        }
+        // Coming back to the table is all it takes to sit in again.
+        self.sit_in(seat);
+        Some(seat)
+    }
+
+    /// Whether `seat` is sent `event`: what `shown_to` allows, but not the stand-ins for
+    /// cards the server hasn't seen.
+    fn sent_to(&self, event: &GameEvent, seat: usize) -> bool {
+        shown_to(event, seat)
+            && !matches!(event, GameEvent::HoleCardsDealt { player, .. } if self.unseen.contains(player))
+    }
+
+    /// Plays hands with `stack` chips for everyone until a player can no longer cover the
+    /// big blind, then says goodbye to every client. A client that disconnects checks or
+    /// folds for the rest of the game. Every hand is archived once it is over. A hand on a
+    /// shared deck is called off, as if it never happened, if a player doesn't hand over
+    /// their keys to it.
+    fn play(&mut self, stack: u32) -> io::Result<()> {
+        let players = self.seats.len();
+        let mut gs = GameState::init_with_stack(players, stack).ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "a game needs two players and a stack of at least 2 chips",
+            )
+        })?;
+        let game_ms = archive::now_ms();
+        while (0..players).all(|p| gs.current_chips(p) >= BIG_BLIND_CHIPS) {
+            let stacks = (0..players).map(|p| gs.current_chips(p)).collect();
+            let mut shared = self
+                .mental_shuffle
+                .then(|| self.shuffle_together())
+                .flatten();
+            self.unseen = shared.as_ref().map_or(vec![], |s| s.audit.seats.clone());
+            let deck = shared
+                .as_ref()
+                .map_or_else(Deck::shuffled_deck, SharedDeck::deck);
+            let (mut hs, mut cur) = gs.start_play_hand(deck);
+            self.counters.hands.fetch_add(1, Ordering::Relaxed);
+            let mut sent = 0;
+            let over = loop {
+                self.broadcast(&hs, &mut sent);
+                let result = loop {
+                    let action = self.request_action(&hs, cur);
+                    match hs.play_action(action) {
+                        Ok(result) => break result,
+                        Err(IllegalRaise::TooBig) => self.error(cur, "Raised by too much"),
+                        Err(IllegalRaise::TooSmall) => self.error(cur, "Raised by too little"),
+                    }
+                };
+                let won = matches!(result, TurnResult::WonHand(_));
+                if let Some(shared) = &mut shared {
+                    if self.open_shared(shared, &gs, &mut hs, won).is_none() {
+                        break false;
+                    }
+                }
+                match result {
+                    TurnResult::NextPlayer(next) => cur = next,
+                    TurnResult::WonHand(_) => break true,
+                }
+            };
+            self.unseen.clear();
+            if !over {
+                for seat in 0..players {
+                    self.send(seat, &ServerMessage::CalledOff);
+                }
+                continue;
+            }
+            self.broadcast(&hs, &mut sent);
+            let hand = ArchivedHand {
+                game_ms,
+                hand: self.hands.len() + 1,
+                finished_ms: archive::now_ms(),
+                stacks,
+                events: hs.events().to_vec(),
+            };
+            if let Some(archive) = &self.archive {
+                // A full disk is no reason to stop the game.
+                let _ = archive.record(&hand);
+            }
+            self.hands.push(hand);
+            if self.mental_shuffle {
+                let audit = shared.map(|shared| shared.audit);
+                for seat in 0..players {
+                    self.send(
+                        seat,
+                        &ServerMessage::ShuffleAudit {
+                            audit: audit.clone(),
+                        },
+                    );
+                }
+            }
+            gs = gs.apply_played_hand(hs);
+        }
+        for seat in 0..players {
+            self.send(seat, &ServerMessage::GameOver);
+        }
+        self.counters.over.store(true, Ordering::Relaxed);
+        self.watch(None, &ServerMessage::GameOver);
+        while self.spectators.iter().any(|s| !s.queue.is_empty()) {
+            thread::sleep(CHAT_INTERVAL);
+            self.flush_spectators();
+        }
+        let spectators = self.spectators.drain(..).map(|s| s.client);
+        for seat in self.seats.drain(..).chain(spectators) {
+            drop(seat.outgoing);
+            let _ = seat.pump.join();
+        }
+        Ok(())
+    }
+
+    /// Has every seat that does its part shuffle the deck, then deals every seat its cards
+    /// from it. Those of the seats that took part go to them still locked with their own
+    /// keys, and only the server opens the others. Returns `None` when fewer than two
+    /// seats shuffled, or one of them didn't finish its part, so that the server has to
+    /// shuffle alone.
+    fn shuffle_together(&mut self) -> Option<SharedDeck> {
+        let mut audit = Audit {
+            seats: vec![],
+            shuffled: vec![],
+            locked: vec![],
+            keys: vec![],
+        };
+        let mut deck = mental::plain_deck();
+        for seat in 0..self.seats.len() {
+            let request = ServerMessage::Shuffle { deck: deck.clone() };
+            if let Some(ClientMessage::Shuffled { deck: shuffled }) =
+                self.ask_for_part(seat, &request)
+            {
+                audit.seats.push(seat);
+                audit.shuffled.push(shuffled.clone());
+                deck = shuffled;
+            }
+        }
+        if audit.seats.len() < 2 {
+            return None;
+        }
+        for seat in audit.seats.clone() {
+            let request = ServerMessage::Lock { deck };
+            let Some(ClientMessage::Locked { deck: locked }) = self.ask_for_part(seat, &request)
+            else {
+                return None;
+            };
+            audit.locked.push(locked.clone());
+            deck = locked;
+        }
+        let mut shared = SharedDeck {
+            audit,
+            opened: vec![None; 52],
+        };
+        let players = self.seats.len();
+        // Every party's key to each seat's cards but its own.
+        let mut keys = vec![[vec![], vec![]]; players];
+        for party in shared.audit.seats.clone() {
+            let seats: Vec<_> = (0..players).filter(|&seat| seat != party).collect();
+            let positions = seats.iter().flat_map(|&s| mental::hole_positions(s));
+            let given = self.card_keys(party, positions.collect())?;
+            for (&seat, pair) in seats.iter().zip(given.chunks(2)) {
+                keys[seat][0].push(pair[0].clone());
+                keys[seat][1].push(pair[1].clone());
+            }
+        }
+        let mut dealt = vec![];
+        for (seat, keys) in keys.into_iter().enumerate() {
+            let positions = mental::hole_positions(seat);
+            if shared.audit.seats.contains(&seat) {
+                let locked = positions.map(|p| shared.locked(p).to_owned()).to_vec();
+                let keys = keys.to_vec();
+                dealt.push((seat, ServerMessage::HoleCards { locked, keys }));
+            } else {
+                for (position, keys) in positions.into_iter().zip(keys) {
+                    shared.open(position, &keys)?;
+                }
+            }
+        }
+        for (seat, message) in dealt {
+            self.send(seat, &message);
+        }
+        Some(shared)
+    }
+
+    /// Opens the cards of `shared` dealt to the board since the last call, or the whole
+    /// deck once the hand is `over`, and plays `hs` again on them. Returns `None` if a
+    /// party didn't hand over its keys, or they open to other cards than before.
+    fn open_shared(
+        &mut self,
+        shared: &mut SharedDeck,
+        gs: &GameState,
+        hs: &mut HandState,
+        over: bool,
+    ) -> Option<()> {
+        let parties = shared.audit.seats.clone();
+        if over {
+            for &party in &parties {
+                let Some(ClientMessage::Keys { keys }) =
+                    self.ask_for_part(party, &ServerMessage::RevealKeys)
+                else {
+                    return None;
+                };
+                shared.audit.keys.push(keys);
+            }
+            let deck = shared.audit.check().ok()?;
+            let opened = shared.opened.iter().zip(deck.cards());
+            if opened
+                .into_iter()
+                .any(|(opened, card)| opened.is_some_and(|c| c != *card))
+            {
+                return None;
+            }
+            *hs = replay(gs, hs, deck);
+            return Some(());
+        }
+        let board = hs.public_view().board.len();
+        let positions: Vec<_> = (0..board)
+            .map(|index| mental::board_position(self.seats.len(), index))
+            .filter(|&position| shared.opened[position].is_none())
+            .collect();
+        if positions.is_empty() {
+            return Some(());
+        }
+        let mut keys = vec![vec![]; positions.len()];
+        for party in parties {
+            let given = self.card_keys(party, positions.clone())?;
+            for (keys, key) in keys.iter_mut().zip(given) {
+                keys.push(key);
+            }
+        }
+        for (&position, keys) in positions.iter().zip(&keys) {
+            shared.open(position, keys)?;
+        }
+        *hs = replay(gs, hs, shared.deck());
+        Some(())
+    }
+
+    /// Asks `party` for its keys to the cards at `positions` of a shared deck.
+    fn card_keys(&mut self, party: usize, positions: Vec<usize>) -> Option<Vec<String>> {
+        let count = positions.len();
+        match self.ask_for_part(party, &ServerMessage::RevealCards { positions }) {
+            Some(ClientMessage::CardKeys { keys }) if keys.len() == count => Some(keys),
+            _ => None,
+        }
+    }
+
+    /// Asks the seat for its part in a mental shuffle, passing on chat and seating
+    /// resuming clients meanwhile. Returns the answer, or `None` if the seat didn't give
+    /// one in time or came back on a connection that wasn't asked.
+    fn ask_for_part(&mut self, seat: usize, request: &ServerMessage) -> Option<ClientMessage> {
+        self.send(seat, request);
+        let deadline = Instant::now() + SHUFFLE_TIMEOUT;
+        while Instant::now() < deadline {
+            self.flush_spectators();
+            while let Ok(arrival) = self.arrivals.try_recv() {
+                if self.admit(arrival, None) == Some(seat) {
+                    return None;
+                }
+            }
+            for other in (0..self.seats.len()).filter(|&other| other != seat) {
+                while let Ok(message) = self.seats[other].messages.try_recv() {
+                    self.handle_out_of_turn(other, message);
+                }
+            }
+            match self.seats[seat].messages.recv_timeout(CHAT_INTERVAL) {
+                Ok(Ok(
+                    part @ (ClientMessage::Shuffled { .. }
+                    | ClientMessage::Locked { .. }
+                    | ClientMessage::CardKeys { .. }
+                    | ClientMessage::Keys { .. }),
+                )) => return Some(part),
+                Ok(message) => self.handle_out_of_turn(seat, message),
+                Err(RecvTimeoutError::Timeout) => {}
+                Err(RecvTimeoutError::Disconnected) => return None,
+            }
+        }
+        None
+    }
+
+    /// Sends every seat the events after the first `sent` that it may see, and the table,
+    /// and queues up what spectators may see of them.
+    fn broadcast(&mut self, hs: &HandState, sent: &mut usize) {
+        for seat in 0..self.seats.len() {
+            let events: Vec<_> = hs.events()[*sent..]
+                .iter()
+                .filter(|e| self.sent_to(e, seat))
+                .map(ServerMessage::from_event)
+                .collect();
+            for message in events {
+                self.send(seat, &message);
+            }
+            self.send_table(seat, hs.seat_view(seat));
+        }
+        for event in hs.events()[*sent..].iter().filter(|e| public(e)) {
+            self.watch(None, &ServerMessage::from_event(event));
+        }
+        let view = hs.public_view();
+        self.watch(None, &ServerMessage::PublicTable { view });
+        *sent = hs.events().len();
+        self.flush_spectators();
+    }
+
+    /// Sends the seat the table, or only what changed about it since the last one to a
+    /// client on a compact encoding.
+    fn send_table(&mut self, seat: usize, view: SeatView) {
+        if self.seats[seat].encoding == Encoding::Json {
+            return self.send(seat, &ServerMessage::Table { view });
+        }
+        let json = serde_json::to_value(&view).expect("views serialize");
+        let message = match &self.views[seat] {
+            Some(last) => ServerMessage::TableChanges {
+                changes: merge_patch(last, &json),
+            },
+            None => ServerMessage::Table { view },
+        };
+        self.views[seat] = Some(json);
+        self.send(seat, &message);
+    }
+
+    /// Queues up `message` for the spectator `to`, or for all of them.
+    fn watch(&mut self, to: Option<usize>, message: &ServerMessage) {
+        // Spectators all get the same messages, so they all get them as JSON.
+        let due = Instant::now() + self.spectator_delay;
+        for (index, spectator) in self.spectators.iter_mut().enumerate() {
+            if to.is_none_or(|to| to == index) {
+                let message = message.for_version(spectator.client.version);
+                spectator
+                    .queue
+                    .push_back((due, Encoding::Json.encode(&message)));
+            }
+        }
+    }
+
+    /// Sends spectators the messages that are due, and lets go of those who left.
+    fn flush_spectators(&mut self) {
+        let now = Instant::now();
+        for spectator in &mut self.spectators {
+            // Spectators have no say.
+            while spectator.client.messages.try_recv().is_ok() {}
+            while spectator.queue.front().is_some_and(|(due, _)| *due <= now) {
+                let (_, frame) = spectator.queue.pop_front().unwrap();
+                let _ = spectator.client.outgoing.send(frame);
+            }
+        }
+        self.spectators.retain(|s| !s.client.pump.is_finished());
+        let watching = self.spectators.len();
+        self.counters.spectators.store(watching, Ordering::Relaxed);
+    }
+
+    /// Asks the player to act until they answer with a legal action, passing on the chat
+    /// of everyone at the table and seating resuming clients meanwhile. A player who is
+    /// sitting out, away or out of time checks or folds.
+    fn request_action(&mut self, hs: &HandState, player: usize) -> PokerAction {
+        if self.sitting_out[player] {
+            return check_or_fold(&hs.player_view(player));
+        }
+        let legal = hs.bot_context(player).legal;
+        let request = ServerMessage::ActionRequest {
+            legal: legal.clone(),
+            seconds: self.turn_timeout.map(|t| t.as_secs()),
+        };
+        let mut deadline = self.start_clock(player);
+        self.send(player, &request);
+        loop {
+            self.flush_spectators();
+            while let Ok(arrival) = self.arrivals.try_recv() {
+                if self.admit(arrival, Some(hs)) == Some(player) {
+                    deadline = self.start_clock(player);
+                    self.send(player, &request);
+                }
+            }
+            for seat in (0..self.seats.len()).filter(|&seat| seat != player) {
+                while let Ok(message) = self.seats[seat].messages.try_recv() {
+                    self.handle_out_of_turn(seat, message);
+                }
+            }
+            match self.seats[player].messages.recv_timeout(CHAT_INTERVAL) {
+                Ok(Ok(ClientMessage::Action { action })) if legal.allows(action) => {
+                    self.timeouts[player] = 0;
+                    return action;
+                }
+                Ok(Ok(ClientMessage::Action { action })) => {
+                    self.error(player, &format!("{action:?} is not allowed now"))
+                }
+                Ok(message) => self.handle_out_of_turn(player, message),
+                Err(RecvTimeoutError::Timeout)
+                    if deadline.is_some_and(|deadline| Instant::now() >= deadline) =>
+                {
+                    break
+                }
+                Err(RecvTimeoutError::Timeout) => {}
+                Err(RecvTimeoutError::Disconnected) => break,
+            }
+        }
+        self.time_out(player);
+        check_or_fold(&hs.player_view(player))
+    }
+
+    /// Sets the player's deadline to act, if the table has one, and tells every player
+    /// whose client understands it. The server alone decides when it has passed.
+    fn start_clock(&mut self, player: usize) -> Option<Instant> {
+        let timeout = self.turn_timeout?;
+        let message = ServerMessage::Turn {
+            player,
+            deadline_ms: archive::now_ms() + timeout.as_millis() as u64,
+            remaining_ms: timeout.as_millis() as u64,
+        };
+        for seat in 0..self.seats.len() {
+            if self.seats[seat].version >= DEADLINE_VERSION {
+                self.send(seat, &message);
+            }
+        }
+        Some(Instant::now() + timeout)
+    }
+
+    /// Counts a turn the player let run out, and sits them out once too many did in a row.
+    fn time_out(&mut self, player: usize) {
+        self.announce(GameEvent::TimedOut { player });
+        self.timeouts[player] += 1;
+        if self.timeouts[player] >= self.sit_out_after {
+            self.sitting_out[player] = true;
+            self.announce(GameEvent::SatOut { player });
+        }
+    }
+
+    fn sit_in(&mut self, seat: usize) {
+        self.timeouts[seat] = 0;
+        if std::mem::replace(&mut self.sitting_out[seat], false) {
+            self.announce(GameEvent::SatIn { player: seat });
+        }
+    }
+
+    /// Tells everyone whose client understands it about an event at the table that isn't
+    /// part of the hand.
+    fn announce(&mut self, event: GameEvent) {
+        let message = ServerMessage::from_event(&event);
+        for seat in 0..self.seats.len() {
+            if self.seats[seat].version >= SIT_OUT_VERSION {
+                self.send(seat, &message);
+            }
+        }
+        for spectator in 0..self.spectators.len() {
+            if self.spectators[spectator].client.version >= SIT_OUT_VERSION {
+                self.watch(Some(spectator), &message);
+            }
+        }
+    }
+
+    /// Passes chat on to everyone, and tells the client anything else isn't expected.
+    fn handle_out_of_turn(&mut self, seat: usize, message: Result<ClientMessage, String>) {
+        match message {
+            Ok(ClientMessage::Chat { text }) => {
+                if !self.chat.post(seat, &text) {
+                    return;
+                }
+                let text = self.chat.recent(1)[0].text.clone();
+                let message = ServerMessage::Chat { player: seat, text };
+                self.watch(None, &message);
+                for to in 0..self.seats.len() {
+                    self.send(to, &message);
+                }
+            }
+            Ok(ClientMessage::Action { .. }) => self.error(seat, "It is not your turn"),
+            Ok(ClientMessage::SitIn) => self.sit_in(seat),
+            Ok(ClientMessage::History) => {
+                let hands = self.hands.iter().map(|hand| hand.seen_by(seat)).collect();
+                self.send(seat, &ServerMessage::History { hands });
+            }
+            // The pump takes these.
+            Ok(ClientMessage::Pong) => {}
+            Ok(
+                ClientMessage::Shuffled { .. }
+                | ClientMessage::Locked { .. }
+                | ClientMessage::CardKeys { .. }
+                | ClientMessage::Keys { .. },
+            ) => self.error(seat, "Nobody is shuffling now"),
+            Ok(
+                ClientMessage::Join { .. }
+                | ClientMessage::Resume { .. }
+                | ClientMessage::ListTables
+                | ClientMessage::Watch { .. },
+            ) => self.error(seat, "Already joined"),
+            Err(message) => self.error(seat, &message),
+        }
+    }
+
+    fn error(&mut self, seat: usize, message: &str) {
+        let message = message.to_owned();
+        self.send(seat, &ServerMessage::Error { message });
+    }
+
+    fn send(&mut self, seat: usize, message: &ServerMessage) {
+        self.seats[seat].tell(message);
+    }
+}
+
+/// How long players have to act unless the table says otherwise.
+pub const TURN_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// How often clients are pinged unless the table says otherwise.
+pub const HEARTBEAT: Duration = Duration::from_secs(5);
+
+/// A table a `Lobby` hosts.
+#[derive(Clone, Debug)]
+pub struct TableConfig {
+    pub name: String,
+    pub players: usize,
+    pub stack: u32,
+    /// What players and spectators have to bring to join, if anything. Players with an
+    /// invite get in without it.
+    pub password: Option<String>,
+    /// Whether only players with an invite may sit down.
+    pub invite_only: bool,
+    /// How long after it happened spectators get to see everything, so that they can't
+    /// pass on what they see to a player in time to matter.
+    pub spectator_delay: Duration,
+    /// How long players have to act before the table checks or folds for them, if there
+    /// is a limit.
+    pub turn_timeout: Option<Duration>,
+    /// After how many turns in a row run out of time, or missed by a dropped client, a
+    /// player sits out.
+    pub sit_out_after: u32,
+    /// How often players and spectators are pinged, to notice those who vanished without
+    /// hanging up.
+    pub heartbeat: Duration,
+    /// Whether the players shuffle every deck together, so that the host can't stack it.
+    pub mental_shuffle: bool,
+    /// Whether the table is a queue that seats every two players who join it at a heads-up
+    /// table of their own, rather than a single table for `players`.
+    pub queue: bool,
+}
+
+impl TableConfig {
+    /// A table of two with 100 chips each that anyone may join and watch as it happens,
+    /// sitting players out after two turns in a row they let run out. The host shuffles.
+    pub fn new(name: &str) -> Self {
+        Self {
+            name: name.to_owned(),
+            players: 2,
+            stack: 100,
+            password: None,
+            invite_only: false,
+            spectator_delay: Duration::ZERO,
+            turn_timeout: Some(TURN_TIMEOUT),
+            sit_out_after: 2,
+            heartbeat: HEARTBEAT,
+            mental_shuffle: false,
+            queue: false,
+        }
+    }
+}
+
+/// Seats every two players who join the queue at the `table`th table of the lobby at a
+/// heads-up table of their own, where they play a game on a thread of its own, and sends
+/// players resuming a seat back to their game. Hands are archived per game, in `archive`
+/// if anywhere. Runs for as long as the lobby sends players.
+fn matchmake(
+    table: usize,
+    config: &TableConfig,
+    signer: Arc<Signer>,
+    counters: Arc<Counters>,
+    arrivals: Receiver<Arrival>,
+    archive: Option<PathBuf>,
+    joined: impl Fn(usize, SocketAddr) + Clone + Send + 'static,
+) {
+    let mut waiting: Option<Arrival> = None;
+    let mut games: Vec<Sender<Arrival>> = vec![];
+    for arrival in arrivals {
+        match &arrival.hello {
+            Hello::Join { .. } => {}
+            Hello::Resume { token, .. } => {
+                let game = token_place(&signer, token).and_then(|(_, game)| game);
+                match game.and_then(|game| games.get(game)) {
+                    Some(door) => {
+                        if let Err(SendError(arrival)) = door.send(arrival) {
+                            arrival.seat.refuse("That game is over".to_owned());
+                        }
+                    }
+                    None => arrival.seat.refuse(UNKNOWN_TOKEN.to_owned()),
+                }
+                continue;
+            }
+            Hello::Watch { .. } => {
+                let name = &config.name;
+                arrival
+                    .seat
+                    .refuse(format!("Games of the queue {name} can't be watched"));
+                continue;
+            }
+            // The lobby answers these itself.
+            Hello::ListTables => continue,
+        }
+        // A player who gave up waiting makes way for the next one.
+        let Some(first) = waiting
+            .take()
+            .filter(|first| !first.seat.pump.is_finished())
+        else {
+            waiting = Some(arrival);
+            counters.seated.store(1, Ordering::Relaxed);
+            continue;
+        };
+        counters.seated.store(0, Ordering::Relaxed);
+        let (door, arrivals) = mpsc::channel();
+        for arrival in [first, arrival] {
+            door.send(arrival).expect("the game hasn't started yet");
+        }
+        let game = games.len();
+        games.push(door);
+        let config = TableConfig {
+            players: 2,
+            ..config.clone()
+        };
+        let (signer, joined) = (signer.clone(), joined.clone());
+        let archive = archive
+            .as_ref()
+            .map(|dir| HandArchive::new(dir, &format!("{}-{game}", config.name)));
+        // The queue only counts who is waiting.
+        let counters = Arc::default();
+        thread::spawn(move || {
+            let mut server = Server::accept(
+                table,
+                Some(game),
+                &config,
+                signer,
+                counters,
+                arrivals,
+                joined,
+            )?;
+            server.archive = archive;
+            server.play(config.stack)
+        });
+    }
+}
+
+/// What the game at a table keeps count of, for the lobby to tell.
+#[derive(Default)]
+struct Counters {
+    seated: AtomicUsize,
+    spectators: AtomicUsize,
+    hands: AtomicUsize,
+    over: AtomicBool,
+}
+
+/// A table whose game is running.
+struct OpenTable {
+    config: TableConfig,
+    /// Where the lobby sends the table's players.
+    door: Sender<Arrival>,
+    counters: Arc<Counters>,
+    game: JoinHandle<io::Result<()>>,
+}
+
+/// Tells how a lobby's tables are doing, from any thread, while the lobby runs.
+#[derive(Clone)]
+pub struct Monitor {
+    opened: Instant,
+    tables: Vec<(TableConfig, Arc<Counters>)>,
+}
+
+impl Monitor {
+    pub fn report(&self) -> StatusReport {
+        let tables = self
+            .tables
+            .iter()
+            .map(|(config, counters)| TableStatus {
+                name: config.name.clone(),
+                players: config.players,
+                seated: counters.seated.load(Ordering::Relaxed),
+                spectators: counters.spectators.load(Ordering::Relaxed),
+                hands: counters.hands.load(Ordering::Relaxed),
+                over: counters.over.load(Ordering::Relaxed),
+            })
+            .collect();
+        StatusReport {
+            uptime_secs: self.opened.elapsed().as_secs(),
+            tables,
+        }
+    }
+}
+
+/// Takes players on for a number of named tables.
+pub struct Lobby {
+    tables: Vec<TableConfig>,
+    counters: Vec<Arc<Counters>>,
+    opened: Instant,
+    arrivals: Receiver<Arrival>,
+    /// Signs invites and the tokens seats are resumed with.
+    signer: Arc<Signer>,
+    /// The directory hands are archived in, if any.
+    archive: Option<PathBuf>,
+}
+
+impl Lobby {
+    /// Starts taking players on any of the `listeners`.
+    pub fn open(listeners: Vec<Listener>, tables: Vec<TableConfig>) -> Self {
+        Self {
+            counters: tables.iter().map(|_| Arc::default()).collect(),
+            opened: Instant::now(),
+            tables,
+            arrivals: greet_arrivals(listeners),
+            signer: Arc::new(Signer::random()),
+            archive: None,
+        }
+    }
+
+    /// Signs tokens with `secret` instead of one only this lobby knows, so that invites
+    /// can be handed out before it opens or after it closed.
+    pub fn sign_with(mut self, secret: &[u8]) -> Self {
+        self.signer = Arc::new(Signer::new(secret));
+        self
+    }
+
+    /// Archives every hand dealt at each table in `dir`, which is made if it doesn't exist,
+    /// as `archive` describes.
+    pub fn archive_to(mut self, dir: &Path) -> io::Result<Self> {
+        fs::create_dir_all(dir)?;
+        self.archive = Some(dir.to_owned());
+        Ok(self)
+    }
+
+    /// An invite to the table called `table` that is good for `valid_for`.
+    pub fn invite(&self, table: &str, valid_for: Duration) -> String {
+        self.signer.invite(table, valid_for)
+    }
+
+    pub fn monitor(&self) -> Monitor {
+        Monitor {
+            opened: self.opened,
+            tables: self
+                .tables
+                .iter()
+                .cloned()
+                .zip(self.counters.clone())
+                .collect(),
+        }
+    }
+
+    /// Sends every client to the table it asks for and plays one game at each table, or
+    /// any number at a queue, calling `joined` with the table, seat and address of each
+    /// player as they take a seat. Returns once the game at every table is over, which
+    /// with a queue is never.
+    pub fn run(
+        self,
+        joined: impl Fn(&str, usize, SocketAddr) + Send + Sync + 'static,
+    ) -> io::Result<()> {
+        let joined = Arc::new(joined);
+        let tables: Vec<_> = self
+            .tables
+            .iter()
+            .enumerate()
+            .map(|(index, config)| {
+                let (door, arrivals) = mpsc::channel();
+                let counters = self.counters[index].clone();
+                let (table, joined) = (config.clone(), joined.clone());
+                let (signer, count) = (self.signer.clone(), counters.clone());
+                let archive = self.archive.clone();
+                let game = thread::spawn(move || {
+                    let name = table.name.clone();
+                    let joined = move |seat, addr| joined(&name, seat, addr);
+                    if table.queue {
+                        matchmake(index, &table, signer, count, arrivals, archive, joined);
+                        return Ok(());
+                    }
+                    let mut server =
+                        Server::accept(index, None, &table, signer, count, arrivals, joined)?;
+                    server.archive = archive.map(|dir| HandArchive::new(&dir, &table.name));
+                    server.play(table.stack)
+                });
+                OpenTable {
+                    config: config.clone(),
+                    door,
+                    counters,
+                    game,
+                }
+            })
+            .collect();
+        while !tables.iter().all(|table| table.game.is_finished()) {
+            if let Ok(arrival) = self.arrivals.recv_timeout(CHAT_INTERVAL) {
+                Self::route(arrival, &tables, &self.signer);
+            }
+        }
+        for table in tables {
+            table
+                .game
+                .join()
+                .map_err(|_| io::Error::other("a table's game crashed"))??;
+        }
+        Ok(())
+    }
+
+    /// Sends a client to its table, or tells it why not.
+    fn route(arrival: Arrival, tables: &[OpenTable], signer: &Signer) {
+        let index = match &arrival.hello {
+            Hello::ListTables => {
+                let tables = tables
+                    .iter()
+                    .map(|table| TableInfo {
+                        name: table.config.name.clone(),
+                        players: table.config.players,
+                        seated: table.counters.seated.load(Ordering::Relaxed),
+                        stack: table.config.stack,
+                        password: table.config.password.is_some(),
+                        invite_only: table.config.invite_only,
+                        mental_shuffle: table.config.mental_shuffle,
+                        queue: table.config.queue,
+                    })
+                    .collect();
+                return arrival.seat.tell(&ServerMessage::Tables { tables });
+            }
+            Hello::Join { table, .. } | Hello::Watch { table, .. } if table.is_empty() => Some(0),
+            Hello::Join { table, .. } | Hello::Watch { table, .. } => {
+                tables.iter().position(|t| t.config.name == *table)
+            }
+            Hello::Resume { token, .. } => token_place(signer, token).map(|(table, _)| table),
+        };
+        let Some(table) = index.and_then(|index| tables.get(index)) else {
+            let message = match &arrival.hello {
+                Hello::Join { table, .. } | Hello::Watch { table, .. } => {
+                    format!("There is no table called {table}")
+                }
+                _ => UNKNOWN_TOKEN.to_owned(),
+            };
+            return arrival.seat.refuse(message);
+        };
+        let (name, config) = (&table.config.name, &table.config);
+        let refusal = match &arrival.hello {
+            Hello::Join { .. } | Hello::Resume { .. }
+                if config.mental_shuffle && arrival.version < HOLE_KEYS_VERSION =>
+            {
+                Some(format!(
+                    "Table {name} needs a client that can shuffle for mental poker"
+                ))
+            }
+            Hello::Join {
+                invite: Some(invite),
+                ..
+            } if signer.is_invited(invite, name) => None,
+            Hello::Join { .. } if config.invite_only => {
+                Some(format!("Table {name} is by invite only"))
+            }
+            Hello::Join { password, .. } | Hello::Watch { password, .. }
+                if config.password.is_some() && *password != config.password =>
+            {
+                Some(format!("Wrong password for table {name}"))
+            }
+            _ => None,
+        };
+        if let Some(message) = refusal {
+            return arrival.seat.refuse(message);
+        }
+        if let Err(SendError(arrival)) = table.door.send(arrival) {
+            let message = format!("The game at table {} is over", table.config.name);
+            arrival.seat.refuse(message);
+        }
+    }
+}
+
+/// A client's connection to a table of a `Lobby`.
+pub struct Connection {
+    transport: Box<dyn Transport>,
+    /// The seat played. Means nothing for spectators.
+    pub seat: usize,
+    pub players: usize,
+    /// The protocol version agreed on with the server.
+    pub version: u32,
+    /// What `resume` takes the seat back with. Empty if the server doesn't resume seats.
+    pub token: String,
+    /// How messages are sent both ways.
+    pub encoding: Encoding,
+    /// How long after it happened a spectator gets to see everything, `None` for players.
+    pub delay: Option<Duration>,
+    /// How long the server's last ping took to be answered, once it said.
+    pub rtt: Option<Duration>,
+    /// Messages `keep_alive` read before anyone asked for them.
+    pending: VecDeque<ServerMessage>,
+    /// The JSON of the last table, which `TableChanges` apply to.
+    view: Option<Value>,
+    /// The seat's part in the mental shuffle of the hand, if it had one.
+    party: Option<Party>,
+    /// The seat's cards in the hand followed by the board so far.
+    seen: Vec<Card>,
+    /// The seat's cards in a hand dealt from the mental shuffle, which the server only has
+    /// stand-ins for in the tables it sends.
+    hole: Option<[Card; 2]>,
+    /// What checking each `ShuffleAudit` found, until `shuffle_verdict` asks.
+    verdicts: VecDeque<Result<(), String>>,
+}
+
+impl Connection {
+    /// Connects over WebSocket to addresses like `ws://host:port`, and over plain TCP to
+    /// addresses like `host:port`, and takes a seat at the server's first table.
+    pub fn connect(addr: &str) -> io::Result<Self> {
+        Self::join(addr, "", None)
+    }
+
+    /// Connects like `connect`, but takes a seat at the table called `table`.
+    pub fn join(addr: &str, table: &str, password: Option<&str>) -> io::Result<Self> {
+        Self::open(addr, &ClientMessage::join(table, password))
+    }
+
+    /// Connects like `join`, but with an invite to the table instead of its password.
+    pub fn join_invited(addr: &str, table: &str, invite: &str) -> io::Result<Self> {
+        Self::open(addr, &ClientMessage::join_invited(table, invite))
+    }
+
+    /// Connects like `connect`, but takes back the seat `token` belongs to. The server
+    /// follows up with a `Sync` if a hand is in progress.
+    pub fn resume(addr: &str, token: &str) -> io::Result<Self> {
+        Self::open(addr, &ClientMessage::resume(token))
+    }
+
+    /// Connects like `join`, but only to watch the table.
+    pub fn watch(addr: &str, table: &str, password: Option<&str>) -> io::Result<Self> {
+        Self::open(addr, &ClientMessage::watch(table, password))
+    }
+
+    /// The tables the server at `addr` hosts.
+    pub fn list_tables(addr: &str) -> io::Result<Vec<TableInfo>> {
+        let mut connection = Self::dial(addr)?;
+        connection.send(&ClientMessage::ListTables)?;
+        match connection.recv()? {
+            Some(ServerMessage::Tables { tables }) => Ok(tables),
+            Some(ServerMessage::Error { message }) => Err(io::Error::other(message)),
+            _ => Err(io::Error::other("the server didn't list its tables")),
+        }
+    }
+
+    fn dial(addr: &str) -> io::Result<Self> {
+        // Reads time out so that `keep_alive` doesn't wait for messages.
+        let transport: Box<dyn Transport> = if addr.starts_with("ws://") {
+            let socket = connect_ws(addr)?;
+            socket.get_ref().set_read_timeout(Some(CHAT_INTERVAL))?;
+            Box::new(socket)
+        } else {
+            let stream = TcpStream::connect(addr)?;
+            stream.set_read_timeout(Some(CHAT_INTERVAL))?;
+            Box::new(LineTransport::new(stream)?)
+        };
+        Ok(Self {
+            transport,
+            seat: 0,
+            players: 0,
+            version: 0,
+            token: String::new(),
+            delay: None,
+            rtt: None,
+            encoding: Encoding::Json,
+            pending: VecDeque::new(),
+            view: None,
+            party: None,
+            seen: vec![],
+            hole: None,
+            verdicts: VecDeque::new(),
+        })
+    }
+
+    /// Connects like `connect`, but opens with `hello`, such as one that asks for another
+    /// encoding.
+    pub fn open(addr: &str, hello: &ClientMessage) -> io::Result<Self> {
+        let mut connection = Self::dial(addr)?;
+        connection.send(hello)?;
+        match connection.recv()? {
+            Some(ServerMessage::Seat {
+                seat,
+                players,
+                version,
+                token,
+                encoding,
+            }) => {
+                connection.seat = seat;
+                connection.players = players;
+                connection.version = version;
+                connection.token = token;
+                connection.encoding = encoding;
+                Ok(connection)
+            }
+            Some(ServerMessage::Watching {
+                players,
+                version,
+                delay_secs,
+            }) => {
+                connection.players = players;
+                connection.version = version;
+                connection.delay = Some(Duration::from_secs(delay_secs));
+                Ok(connection)
+            }
+            Some(ServerMessage::Error { message }) => Err(io::Error::other(message)),
+            _ => Err(io::Error::other("the server didn't give us a seat")),
+        }
+    }
+
+    /// The next message from the server, `None` once it hung up.
+    pub fn recv(&mut self) -> io::Result<Option<ServerMessage>> {
+        if let Some(message) = self.pending.pop_front() {
+            return Ok(Some(message));
+        }
+        loop {
+            match self.read() {
+                Ok(Some(message)) => return Ok(Some(message)),
+                Ok(None) => {}
+                Err(e) if e.kind() == ErrorKind::UnexpectedEof => return Ok(None),
+                Err(e) => return Err(e),
+            }
+        }
+    }
+
+    /// Answers the server's pings without waiting for anything else, keeping the messages
+    /// that came with them for `recv`. The server hangs up on a client that doesn't read
+    /// for too long, so one that waits for something else calls this every now and then.
+    pub fn keep_alive(&mut self) -> io::Result<()> {
+        while let Some(message) = self.read()? {
+            self.pending.push_back(message);
+        }
+        Ok(())
+    }
+
+    /// Takes over the seat's part in the hand being played from `old`, the connection
+    /// this one resumed the seat of, so that it can still check the shuffle and see its
+    /// cards.
+    pub fn carry_on_from(&mut self, old: Connection) {
+        self.party = old.party;
+        self.seen = old.seen;
+        self.hole = old.hole;
+        self.verdicts = old.verdicts;
+    }
+
+    /// What checking the `ShuffleAudit`s received so far found, one a call in the order
+    /// they arrived: whether the seat's part is in it as played, and the cards the seat
+    /// saw dealt came from the deck it opens to.
+    pub fn shuffle_verdict(&mut self) -> Option<Result<(), String>> {
+        self.verdicts.pop_front()
+    }
+
+    /// The next message other than a ping or a request for the seat's part in a mental
+    /// shuffle, or `None` when none arrived in time. `TableChanges` arrive as the `Table`
+    /// they make.
+    fn read(&mut self) -> io::Result<Option<ServerMessage>> {
+        while let Some(frame) = self.transport.recv()? {
+            let message = decode(&frame).map_err(|e| io::Error::new(ErrorKind::InvalidData, e))?;
+            match message {
+                ServerMessage::Ping { rtt_ms } => {
+                    self.rtt = rtt_ms.map(Duration::from_millis).or(self.rtt);
+                    // The server may have said goodbye already, and a lost connection shows
+                    // with the next read anyway.
+                    let _ = self.send(&ClientMessage::Pong);
+                }
+                // A deck that can't be shuffled goes unanswered, and the server goes on
+                // without this seat.
+                ServerMessage::Shuffle { deck } => {
+                    let mut party = Party::new();
+                    if let Ok(deck) = party.shuffle(&deck) {
+                        self.party = Some(party);
+                        self.send(&ClientMessage::Shuffled { deck })?;
+                    }
+                }
+                ServerMessage::Lock { deck } => {
+                    if let Some(Ok(deck)) = self.party.as_mut().map(|party| party.lock(&deck)) {
+                        self.send(&ClientMessage::Locked { deck })?;
+                    }
+                }
+                // The keys to the seat's own cards stay with it until the hand is over, so
+                // that nobody else sees them.
+                ServerMessage::RevealCards { positions } => {
+                    let own = mental::hole_positions(self.seat);
+                    let keys: Option<Vec<_>> = match &self.party {
+                        Some(party) if !positions.iter().any(|p| own.contains(p)) => {
+                            positions.iter().map(|&p| party.card_key(p)).collect()
+                        }
+                        _ => None,
+                    };
+                    if let Some(keys) = keys {
+                        self.send(&ClientMessage::CardKeys { keys })?;
+                    }
+                }
+                ServerMessage::RevealKeys => {
+                    if let Some(keys) = self.party.as_ref().map(Party::keys) {
+                        self.send(&ClientMessage::Keys { keys })?;
+                    }
+                }
+                ServerMessage::HoleCards { locked, keys } => {
+                    let message = match self.open_hole_cards(&locked, &keys) {
+                        Ok(cards) => ServerMessage::Deal {
+                            player: self.seat,
+                            cards,
+                        },
+                        Err(e) => ServerMessage::Error {
+                            message: format!("The cards dealt don't open: {e}"),
+                        },
+                    };
+                    self.witness(&message);
+                    return Ok(Some(message));
+                }
+                ServerMessage::TableChanges { changes } => {
+                    let view = self.view.as_mut().ok_or_else(|| {
+                        io::Error::new(ErrorKind::InvalidData, "changes to a table never sent")
+                    })?;
+                    apply_merge_patch(view, &changes);
+                    let view = serde_json::from_value(view.clone())?;
+                    return Ok(Some(self.with_hole_cards(ServerMessage::Table { view })));
+                }
+                message => {
+                    self.witness(&message);
+                    return Ok(Some(self.with_hole_cards(message)));
+                }
+            }
+        }
+        Ok(None)
+    }
+
+    /// Keeps track of the last table and the cards the seat sees dealt, and checks the
+    /// shuffle they came from once the hand is over.
+    fn witness(&mut self, message: &ServerMessage) {
+        match message {
+            ServerMessage::Table { view } | ServerMessage::Sync { view, .. } => {
+                self.view = serde_json::to_value(view).ok();
+            }
+            ServerMessage::Deal { player, cards } if *player == self.seat => {
+                self.seen = cards.to_vec();
+                self.hole = None;
+            }
+            ServerMessage::Street { cards, .. } => self.seen.extend(cards),
+            ServerMessage::CalledOff => {
+                self.party = None;
+                self.hole = None;
+            }
+            ServerMessage::ShuffleAudit { audit } => {
+                self.hole = None;
+                let verdict = match (audit, self.party.take()) {
+                    (Some(audit), Some(party)) => {
+                        party.check(audit, self.seat, self.players, &self.seen)
+                    }
+                    (Some(_), None) => Err("this seat had no part in the shuffle".to_owned()),
+                    (None, _) => Err("the host shuffled alone".to_owned()),
+                };
+                self.verdicts.push_back(verdict);
+            }
+            _ => {}
+        }
+    }
+
+    /// Opens the seat's cards of a mental hand, `locked` as the deck is, with the other
+    /// parties' `keys` to each and its own.
+    fn open_hole_cards(
+        &mut self,
+        locked: &[String],
+        keys: &[Vec<String>],
+    ) -> Result<[Card; 2], String> {
+        let party = self
+            .party
+            .as_ref()
+            .ok_or("this seat had no part in the shuffle")?;
+        let [first, second] = mental::hole_positions(self.seat);
+        let ([locked_first, locked_second], [keys_first, keys_second]) = (locked, keys) else {
+            return Err("that isn't two cards".to_owned());
+        };
+        let cards = [
+            party.open(first, locked_first, keys_first)?,
+            party.open(second, locked_second, keys_second)?,
+        ];
+        self.hole = Some(cards);
+        Ok(cards)
+    }
+
+    /// `message` with the seat's own cards in the table when the server only has
+    /// stand-ins for them.
+    fn with_hole_cards(&self, mut message: ServerMessage) -> ServerMessage {
+        if let (
+            Some([first, second]),
+            ServerMessage::Table { view } | ServerMessage::Sync { view, .. },
+        ) = (self.hole, &mut message)
+        {
+            view.view.cards = (first, second);
+        }
+        message
+    }
+
+    pub fn send(&mut self, message: &ClientMessage) -> io::Result<()> {
+        self.transport.send(&self.encoding.encode(message))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::protocol::{HISTORY_VERSION, MENTAL_VERSION, PROTOCOL_VERSION, RETURN_VERSION};
+    use crate::transport::MAX_CLIENT_MESSAGE;
+    use std::net::TcpListener;
+
+    type Client = thread::JoinHandle<(usize, Vec<ServerMessage>)>;
+
+    /// Connects a client that answers every request with `action` and returns everything
+    /// the server sent it.
+    fn client(addr: &str, action: PokerAction) -> Client {
+        client_at(addr, "", None, action)
+    }
+
+    fn client_at(
+        addr: &str,
+        table: &'static str,
+        password: Option<&'static str>,
+        action: PokerAction,
+    ) -> Client {
+        let addr = addr.to_owned();
+        thread::spawn(move || play_out(Connection::join(&addr, table, password).unwrap(), action))
+    }
+
+    /// Answers every request with `action` until the game is over, and returns the seat
+    /// and everything the server sent.
+    fn play_out(mut connection: Connection, action: PokerAction) -> (usize, Vec<ServerMessage>) {
+        assert_eq!(connection.version, PROTOCOL_VERSION);
+        let mut received = vec![];
+        while let Some(message) = connection.recv().unwrap() {
+            if let ServerMessage::ActionRequest { .. } = message {
+                connection.send(&ClientMessage::Action { action }).unwrap();
+            }
+            let over = matches!(message, ServerMessage::GameOver);
+            received.push(message);
+            if over {
+                break;
+            }
+        }
+        (connection.seat, received)
+    }
+
+    fn table(name: &str, players: usize, stack: u32) -> TableConfig {
+        TableConfig {
+            players,
+            stack,
+            turn_timeout: None,
+            ..TableConfig::new(name)
+        }
+    }
+
+    /// Hosts a game and returns its TCP and WebSocket addresses.
+    fn host(players: usize, stack: u32) -> (String, String, thread::JoinHandle<()>) {
+        host_tables(vec![table("main", players, stack)])
+    }
+
+    fn host_tables(tables: Vec<TableConfig>) -> (String, String, thread::JoinHandle<()>) {
+        let tcp = TcpListener::bind("127.0.0.1:0").unwrap();
+        let ws = TcpListener::bind("127.0.0.1:0").unwrap();
+        let tcp_addr = tcp.local_addr().unwrap().to_string();
+        let ws_addr = format!("ws://{}", ws.local_addr().unwrap());
+        let listeners = vec![Listener::Tcp(tcp), Listener::WebSocket(ws)];
+        let lobby = Lobby::open(listeners, tables);
+        let server = thread::spawn(move || lobby.run(|_, _, _| {}).unwrap());
+        (tcp_addr, ws_addr, server)
+    }
+
+    /// The protocol version that each kind of message the server sends first appeared in,
+    /// for those that came after version 2.
+    const INTRODUCED: [(&str, u32); 19] = [
+        ("sync", RESUME_VERSION),
+        ("tables", 4),
+        ("watching", 5),
+        ("public_table", 5),
+        ("timed_out", SIT_OUT_VERSION),
+        ("sat_out", SIT_OUT_VERSION),
+        ("sat_in", SIT_OUT_VERSION),
+        ("ping", HEARTBEAT_VERSION),
+        ("shuffle", MENTAL_VERSION),
+        ("lock", MENTAL_VERSION),
+        ("reveal_keys", MENTAL_VERSION),
+        ("shuffle_audit", MENTAL_VERSION),
+        ("table_changes", COMPACT_VERSION),
+        ("history", HISTORY_VERSION),
+        ("turn", DEADLINE_VERSION),
+        ("returned", RETURN_VERSION),
+        ("reveal_cards", HOLE_KEYS_VERSION),
+        ("hole_cards", HOLE_KEYS_VERSION),
+        ("called_off", HOLE_KEYS_VERSION),
+    ];
+
+    /// Plays a session recorded from a client of an older version against a host of this
+    /// build. Sends what the client sent and checks the answers have what it read from
+    /// them, then plays out the game the way that client would, checking that the server
+    /// never sends it anything newer than the version it speaks.
+    fn replay(session: &str) {
+        let mut lines = session
+            .lines()
+            .filter(|line| !line.starts_with('#') && !line.trim().is_empty());
+        let version: u32 = lines
+            .next()
+            .and_then(|line| line.strip_prefix("version "))
+            .and_then(|version| version.parse().ok())
+            .expect("a session starts with its version");
+        // Everything newer clients are told about, for the old one not to be.
+        let table = TableConfig {
+            turn_timeout: Some(Duration::from_secs(10)),
+            heartbeat: Duration::from_millis(100),
+            ..table("main", 2, 4)
+        };
+        let (tcp, _, server) = host_tables(vec![table]);
+        let mut old = LineTransport::new(TcpStream::connect(&tcp).unwrap()).unwrap();
+        let next = |old: &mut LineTransport| {
+            let frame = old.recv().unwrap().expect("no read timeout");
+            assert!(matches!(frame, Frame::Text(_)), "sent {frame:?}");
+            decode::<ServerMessage>(&frame).unwrap();
+            decode::<Value>(&frame).unwrap()
+        };
+        let mut seated = false;
+        for line in lines {
+            let (direction, json) = line.split_at(2);
+            if direction == "> " {
+                seated |= json.contains(r#""type":"join""#);
+                old.send(&Frame::Text(json.to_owned())).unwrap();
+                continue;
+            }
+            let got = next(&mut old);
+            let expected: Value = serde_json::from_str(json).unwrap();
+            for (key, value) in expected.as_object().unwrap() {
+                assert_eq!(&got[key], value, "{key} of {got}");
+            }
+        }
+        let others: Vec<_> = (0..2 - seated as usize)
+            .map(|_| client(&tcp, PokerAction::CallOrCheck))
+            .collect();
+        loop {
+            let message = next(&mut old);
+            let kind = message["type"].as_str().unwrap();
+            let since = INTRODUCED
+                .iter()
+                .find(|(k, _)| *k == kind)
+                .map_or(2, |k| k.1);
+            assert!(
+                since <= version,
+                "a version {version} client was sent {message}"
+            );
+            let answer = match kind {
+                "action_request" => r#"{"type":"action","action":"CallOrCheck"}"#,
+                "ping" => r#"{"type":"pong"}"#,
+                "game_over" => break,
+                _ => continue,
+            };
+            old.send(&Frame::Text(answer.to_owned())).unwrap();
+        }
+        for other in others {
+            other.join().unwrap();
+        }
+        server.join().unwrap();
+    }
+
+    #[test]
+    fn should_keep_playing_sessions_recorded_from_older_clients() {
+        for session in [
+            include_str!("sessions/v2_join.txt"),
+            include_str!("sessions/v4_named_table.txt"),
+            include_str!("sessions/v5_watch.txt"),
+            include_str!("sessions/v7_heartbeat.txt"),
+        ] {
+            replay(session);
+        }
+    }
+
+    #[test]
+    fn should_turn_away_clients_without_a_shared_protocol_version() {
+        let (tcp, _, server) = host(2, 4);
+        let mut old = LineTransport::new(TcpStream::connect(&tcp).unwrap()).unwrap();
+        let join = ClientMessage::Join {
+            min_version: PROTOCOL_VERSION + 1,
+            max_version: PROTOCOL_VERSION + 1,
+            table: String::new(),
+            password: None,
+            invite: None,
+            encoding: Encoding::Json,
+        };
+        old.send(&Encoding::Json.encode(&join)).unwrap();
+        let answer = old.recv().unwrap().unwrap();
+        assert!(matches!(
+            decode(&answer).unwrap(),
+            ServerMessage::Error { .. }
+        ));
+
+        let first = client(&tcp, PokerAction::CallOrCheck);
+        let second = client(&tcp, PokerAction::CallOrCheck);
+        let seats = [first.join().unwrap().0, second.join().unwrap().0];
+        assert!(seats.contains(&0) && seats.contains(&1));
+        server.join().unwrap();
+    }
+
+    #[test]
+    fn should_only_show_clients_their_own_cards_over_tcp_and_websocket() {
+        let (tcp, ws, server) = host(2, 4);
+        let first = client(&tcp, PokerAction::CallOrCheck);
+        let second = client(&ws, PokerAction::CallOrCheck);
+        for (seat, received) in [first.join().unwrap(), second.join().unwrap()] {
+            assert!(matches!(received.last(), Some(ServerMessage::GameOver)));
+            for message in &received {
+                match message {
+                    ServerMessage::Deal { player, .. } => assert_eq!(*player, seat),
+                    ServerMessage::Table { view } => {
+                        assert_eq!(view.view.player, seat);
+                        if view.view.board.len() < 5 {
+                            assert_eq!(view.shown, vec![None, None]);
+                        }
+                    }
+                    _ => {}
+                }
+            }
+        }
+        server.join().unwrap();
+    }
+
+    #[test]
+    fn should_send_compact_clients_message_pack_and_the_changes_to_the_table() {
+        let (tcp, ws, server) = host(2, 4);
+        let compact = ClientMessage::join("", None).with_encoding(Encoding::MessagePack);
+        let clients: Vec<_> = [tcp, ws]
+            .into_iter()
+            .map(|addr| {
+                let hello = compact.clone();
+                thread::spawn(move || {
+                    let connection = Connection::open(&addr, &hello).unwrap();
+                    assert_eq!(connection.encoding, Encoding::MessagePack);
+                    play_out(connection, PokerAction::CallOrCheck)
+                })
+            })
+            .collect();
+        for client in clients {
+            let (seat, received) = client.join().unwrap();
+            assert!(matches!(received.last(), Some(ServerMessage::GameOver)));
+            let tables: Vec<_> = received
+                .iter()
+                .filter_map(|message| match message {
+                    ServerMessage::Table { view } => Some(view),
+                    _ => None,
+                })
+                .collect();
+            assert!(tables.len() > 2);
+            assert!(tables.iter().all(|view| view.view.player == seat));
+            // The board only ever grows within a hand, so the changes were applied in order.
+            assert!(tables.windows(2).all(|pair| {
+                let (before, after) = (&pair[0].view.board, &pair[1].view.board);
+                after.starts_with(before) || after.is_empty()
+            }));
+        }
+        server.join().unwrap();
+    }
+
+    #[test]
+    fn should_deal_mental_tables_from_a_deck_the_players_shuffled_and_can_check() {
+        let mental = TableConfig {
+            mental_shuffle: true,
+            ..table("main", 2, 4)
+        };
+        let (tcp, _, server) = host_tables(vec![mental]);
+        let old = ClientMessage::Join {
+            min_version: 2,
+            max_version: HOLE_KEYS_VERSION - 1,
+            table: String::new(),
+            password: None,
+            invite: None,
+            encoding: Encoding::Json,
+        };
+        let refused = Connection::open(&tcp, &old).err().unwrap();
+        assert!(refused.to_string().contains("mental poker"));
+
+        let players: Vec<_> = (0..2)
+            .map(|_| {
+                let addr = tcp.clone();
+                thread::spawn(move || {
+                    let mut connection = Connection::connect(&addr).unwrap();
+                    let mut verdicts = vec![];
+                    while let Some(message) = connection.recv().unwrap() {
+                        match message {
+                            ServerMessage::ActionRequest { .. } => {
+                                let action = PokerAction::CallOrCheck;
+                                connection.send(&ClientMessage::Action { action }).unwrap();
+                            }
+                            ServerMessage::ShuffleAudit { .. } => {
+                                verdicts.push(connection.shuffle_verdict().unwrap());
+                            }
+                            ServerMessage::GameOver => break,
+                            _ => {}
+                        }
+                    }
+                    verdicts
+                })
+            })
+            .collect();
+        for player in players {
+            let verdicts = player.join().unwrap();
+            assert!(!verdicts.is_empty());
+            assert!(verdicts.iter().all(Result::is_ok), "{verdicts:?}");
+        }
+        server.join().unwrap();
+    }
+
+    #[test]
+    fn should_keep_a_players_cards_from_the_host_until_the_hand_is_over() {
+        let mental = TableConfig {
+            mental_shuffle: true,
+            ..table("main", 2, 4)
+        };
+        let (tcp, _, server) = host_tables(vec![mental]);
+        let mut spy = LineTransport::new(TcpStream::connect(&tcp).unwrap()).unwrap();
+        let send = |spy: &mut LineTransport, message: &ClientMessage| {
+            spy.send(&Encoding::Json.encode(message)).unwrap();
+        };
+        send(&mut spy, &ClientMessage::join("", None));
+        let other = client(&tcp, PokerAction::CallOrCheck);
+
+        let (mut seat, mut party) = (0, Party::new());
+        let (mut dealt, mut shown, mut hands) = (None, None, 0);
+        while let Some(frame) = spy.recv().unwrap() {
+            match decode::<ServerMessage>(&frame).unwrap() {
+                ServerMessage::Seat { seat: s, .. } => seat = s,
+                ServerMessage::Ping { .. } => send(&mut spy, &ClientMessage::Pong),
+                ServerMessage::Shuffle { deck } => {
+                    party = Party::new();
+                    let deck = party.shuffle(&deck).unwrap();
+                    send(&mut spy, &ClientMessage::Shuffled { deck });
+                }
+                ServerMessage::Lock { deck } => {
+                    let deck = party.lock(&deck).unwrap();
+                    send(&mut spy, &ClientMessage::Locked { deck });
+                }
+                ServerMessage::RevealCards { positions } => {
+                    let own = mental::hole_positions(seat);
+                    assert!(!positions.iter().any(|p| own.contains(p)));
+                    let keys = positions.iter().map(|&p| party.card_key(p).unwrap());
+                    let keys = keys.collect();
+                    send(&mut spy, &ClientMessage::CardKeys { keys });
+                }
+                ServerMessage::HoleCards { locked, keys } => {
+                    // Everything the host has of the cards doesn't open them.
+                    assert!(mental::open(&locked[0], &keys[0]).is_err());
+                    let [first, second] = mental::hole_positions(seat);
+                    dealt = Some((
+                        party.open(first, &locked[0], &keys[0]).unwrap(),
+                        party.open(second, &locked[1], &keys[1]).unwrap(),
+                    ));
+                }
+                ServerMessage::Deal { .. } => panic!("the host dealt cards it saw"),
+                ServerMessage::Table { view } => shown = Some(view.view.cards),
+                ServerMessage::ActionRequest { .. } => {
+                    let action = PokerAction::CallOrCheck;
+                    send(&mut spy, &ClientMessage::Action { action });
+                }
+                ServerMessage::RevealKeys => {
+                    let keys = party.keys();
+                    send(&mut spy, &ClientMessage::Keys { keys });
+                }
+                ServerMessage::ShuffleAudit { audit } => {
+                    let deck = audit.unwrap().check().unwrap();
+                    let [first, second] = mental::hole_positions(seat);
+                    let cards = (deck.cards()[first], deck.cards()[second]);
+                    assert_eq!(dealt.take(), Some(cards));
+                    // The host played the hand out on the cards once it could open them.
+                    assert_eq!(shown, Some(cards));
+                    hands += 1;
+                }
+                ServerMessage::GameOver => break,
+                _ => {}
+            }
+        }
+        assert!(hands > 0);
+        let (_, received) = other.join().unwrap();
+        assert!(matches!(received.last(), Some(ServerMessage::GameOver)));
+        server.join().unwrap();
+    }
+
+    #[test]
+    fn should_pass_on_chat_reject_illegal_actions_and_fold_for_disconnected_clients() {
+        let (tcp, _, server) = host(2, 10);
+        let mut cheat = Connection::connect(&tcp).unwrap();
+        let cheat_seat = cheat.seat;
+        let other = client(&tcp, PokerAction::CallOrCheck);
+        loop {
+            match cheat.recv().unwrap() {
+                Some(ServerMessage::ActionRequest { .. }) => break,
+                Some(_) => {}
+                None => panic!("the server hung up"),
+            }
+        }
+        let text = "nice hand".to_owned();
+        cheat.send(&ClientMessage::Chat { text }).unwrap();
+        let action = PokerAction::Raise(50);
+        cheat.send(&ClientMessage::Action { action }).unwrap();
+        loop {
+            match cheat.recv().unwrap() {
+                Some(ServerMessage::Error { .. }) => break,
+                Some(_) => {}
+                None => panic!("the server hung up"),
+            }
+        }
+        drop(cheat);
+
+        let (_, received) = other.join().unwrap();
+        assert!(matches!(received.last(), Some(ServerMessage::GameOver)));
+        assert!(received.iter().any(|m| matches!(
+            m,
+            ServerMessage::Chat { player, text } if *player == cheat_seat && text == "nice hand"
+        )));
+        server.join().unwrap();
+    }
+
+    #[test]
+    fn should_survive_clients_that_send_garbage_spam_and_oversized_messages() {
+        let (tcp, _, server) = host(2, 10);
+        let mut attacker = LineTransport::new(TcpStream::connect(&tcp).unwrap()).unwrap();
+        let send = |attacker: &mut LineTransport, message: &ClientMessage| {
+            attacker.send(&Encoding::Json.encode(message)).unwrap();
+        };
+        send(&mut attacker, &ClientMessage::join("", None));
+        let until = |attacker: &mut LineTransport, wanted: fn(&ServerMessage) -> bool| loop {
+            let frame = attacker.recv().unwrap().expect("no read timeout");
+            if wanted(&decode(&frame).unwrap()) {
+                break;
+            }
+        };
+        until(&mut attacker, |m| matches!(m, ServerMessage::Seat { .. }));
+        let honest = client(&tcp, PokerAction::CallOrCheck);
+
+        attacker
+            .send(&Frame::Text("{\"type\": ".to_owned()))
+            .unwrap();
+        attacker.send(&Frame::Binary(vec![0xc1])).unwrap();
+        until(&mut attacker, |m| {
+            matches!(m, ServerMessage::ActionRequest { .. })
+        });
+        let action = PokerAction::Raise(u32::MAX);
+        send(&mut attacker, &ClientMessage::Action { action });
+        until(&mut attacker, |m| matches!(m, ServerMessage::Error { .. }));
+        for _ in 0..100 {
+            let text = "spam".to_owned();
+            send(&mut attacker, &ClientMessage::Chat { text });
+        }
+        until(
+            &mut attacker,
+            |m| matches!(m, ServerMessage::Error { message } if message.contains("slow down")),
+        );
+        // The server hangs up partway through, so the write may fail.
+        let _ = attacker.send(&Frame::Text("x".repeat(2 * MAX_CLIENT_MESSAGE)));
+        while attacker.recv().is_ok() {}
+
+        let (_, received) = honest.join().unwrap();
+        assert!(matches!(received.last(), Some(ServerMessage::GameOver)));
+        let chats = received
+            .iter()
+            .filter(|m| matches!(m, ServerMessage::Chat { .. }))
+            .count();
+        assert!(chats > 0 && chats <= MESSAGE_BURST as usize);
+        server.join().unwrap();
+    }
+
+    #[test]
+    fn should_archive_every_hand_and_hand_players_the_ones_they_played() {
+        let dir = std::env::temp_dir().join(format!("poker-tui-hands-{}", rand::random::<u64>()));
+        let tcp = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = tcp.local_addr().unwrap().to_string();
+        let lobby = Lobby::open(vec![Listener::Tcp(tcp)], vec![table("main", 2, 10)])
+            .archive_to(&dir)
+            .unwrap();
+        let server = thread::spawn(move || lobby.run(|_, _, _| {}).unwrap());
+        let other = client(&addr, PokerAction::CallOrCheck);
+
+        let mut connection = Connection::connect(&addr).unwrap();
+        let seat = connection.seat;
+        let (mut asked, mut hands) = (false, None);
+        while let Some(message) = connection.recv().unwrap() {
+            match message {
+                ServerMessage::Pot { .. } if !asked => {
+                    connection.send(&ClientMessage::History).unwrap();
+                    asked = true;
+                }
+                ServerMessage::History { hands: sent } => hands = Some(sent),
+                ServerMessage::ActionRequest { .. } => {
+                    let action = PokerAction::CallOrCheck;
+                    connection.send(&ClientMessage::Action { action }).unwrap();
+                }
+                ServerMessage::GameOver => break,
+                _ => {}
+            }
+        }
+        other.join().unwrap();
+        server.join().unwrap();
+
+        let hands = hands.expect("the server sent the hands");
+        assert!(!hands.is_empty());
+        for hand in &hands {
+            assert!(hand.events.iter().all(|e| !matches!(
+                e,
+                GameEvent::HoleCardsDealt { player, .. } if *player != seat
+            )));
+        }
+        let archived = HandArchive::new(&dir, "main").hands().unwrap();
+        assert!(archived.len() >= hands.len());
+        let numbers: Vec<_> = archived.iter().map(|h| h.hand).collect();
+        assert_eq!(numbers, (1..=archived.len()).collect::<Vec<_>>());
+        assert_eq!(hands[0], archived[0].seen_by(seat));
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn should_give_a_resuming_client_its_seat_and_the_hand_so_far() {
+        let (tcp, ws, server) = host(2, 4);
+        let mut lost = Connection::connect(&ws).unwrap();
+        let other = client(&tcp, PokerAction::CallOrCheck);
+        while !matches!(
+            lost.recv().unwrap(),
+            Some(ServerMessage::ActionRequest { .. })
+        ) {}
+
+        let wrong = Connection::resume(&tcp, "not a token").err().unwrap();
+        assert_eq!(wrong.to_string(), "That token doesn't belong to any seat");
+        let mut resumed = Connection::resume(&tcp, &lost.token).unwrap();
+        assert_eq!(resumed.seat, lost.seat);
+        assert_eq!(resumed.token, lost.token);
+        drop(lost);
+        match resumed.recv().unwrap() {
+            Some(ServerMessage::Sync { hand, view }) => {
+                assert_eq!(view.view.player, resumed.seat);
+                assert!(hand.iter().any(|m| matches!(
+                    m,
+                    ServerMessage::Deal { player, .. } if *player == resumed.seat
+                )));
+            }
+            other => panic!("expected a sync, got {other:?}"),
+        }
+        assert!(matches!(
+            resumed.recv().unwrap(),
+            Some(ServerMessage::ActionRequest { .. })
+        ));
+        let action = PokerAction::CallOrCheck;
+        resumed.send(&ClientMessage::Action { action }).unwrap();
+        loop {
+            match resumed.recv().unwrap() {
+                Some(ServerMessage::ActionRequest { .. }) => {
+                    resumed.send(&ClientMessage::Action { action }).unwrap()
+                }
+                Some(ServerMessage::GameOver) => break,
+                Some(_) => {}
+                None => panic!("the server hung up"),
+            }
+        }
+        other.join().unwrap();
+        server.join().unwrap();
+    }
+
+    #[test]
+    fn should_list_tables_and_seat_players_at_the_one_they_name() {
+        let club = TableConfig {
+            password: Some("pw".to_owned()),
+            ..table("club", 2, 4)
+        };
+        let (tcp, ws, server) = host_tables(vec![table("open", 2, 4), club]);
+        let early = client_at(&tcp, "club", Some("pw"), PokerAction::CallOrCheck);
+        // The client above may not have taken its seat yet.
+        let tables = Connection::list_tables(&ws).unwrap();
+        assert_eq!(tables.len(), 2);
+        assert_eq!((tables[0].seated, tables[0].password), (0, false));
+        assert!(tables[1].password && tables[1].name == "club");
+
+        let refusal = |joined: io::Result<Connection>| joined.err().unwrap().to_string();
+        let wrong = Connection::join(&tcp, "club", Some("guess"));
+        assert_eq!(refusal(wrong), "Wrong password for table club");
+        let missing = Connection::join(&tcp, "casino", None);
+        assert_eq!(refusal(missing), "There is no table called casino");
+
+        let late = client_at(&ws, "club", Some("pw"), PokerAction::CallOrCheck);
+        let open = [
+            client_at(&tcp, "open", None, PokerAction::CallOrCheck),
+            client(&ws, PokerAction::CallOrCheck),
+        ];
+        for client in [early, late].into_iter().chain(open) {
+            let (_, received) = client.join().unwrap();
+            assert!(matches!(received.last(), Some(ServerMessage::GameOver)));
+        }
+        server.join().unwrap();
+    }
+
+    #[test]
+    fn should_pair_players_in_the_queue_at_tables_of_their_own() {
+        let queue = TableConfig {
+            queue: true,
+            ..table("heads-up", 2, 4)
+        };
+        // A lobby with a queue never closes, so its thread is left to run.
+        let (tcp, _, _) = host_tables(vec![queue]);
+        assert!(Connection::list_tables(&tcp).unwrap()[0].queue);
+        let waiting = {
+            let tcp = tcp.clone();
+            thread::spawn(move || Connection::join(&tcp, "heads-up", None).unwrap())
+        };
+        let first = client_at(&tcp, "heads-up", None, PokerAction::CallOrCheck);
+        // Only seated once there is another player.
+        let lost = waiting.join().unwrap();
+        let second = [
+            client_at(&tcp, "heads-up", None, PokerAction::CallOrCheck),
+            client_at(&tcp, "heads-up", None, PokerAction::CallOrCheck),
+        ];
+
+        let resumed = Connection::resume(&tcp, &lost.token).unwrap();
+        assert_eq!(resumed.seat, lost.seat);
+        let resumed_seat = resumed.seat;
+        drop(lost);
+        let (_, received) = play_out(resumed, PokerAction::CallOrCheck);
+        assert!(matches!(received.last(), Some(ServerMessage::GameOver)));
+        let (seat, received) = first.join().unwrap();
+        assert_eq!(seat, 1 - resumed_seat);
+        assert!(matches!(received.last(), Some(ServerMessage::GameOver)));
+        let mut seats = vec![];
+        for client in second {
+            let (seat, received) = client.join().unwrap();
+            assert!(matches!(received.last(), Some(ServerMessage::GameOver)));
+            seats.push(seat);
+        }
+        seats.sort();
+        assert_eq!(seats, [0, 1]);
+    }
+
+    #[test]
+    fn should_only_seat_players_with_a_signed_invite_at_invite_only_tables() {
+        let vip = TableConfig {
+            password: Some("pw".to_owned()),
+            invite_only: true,
+            ..table("vip", 2, 4)
+        };
+        let tcp = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = tcp.local_addr().unwrap().to_string();
+        let lobby = Lobby::open(vec![Listener::Tcp(tcp)], vec![vip]).sign_with(b"secret");
+        let invite = lobby.invite("vip", Duration::from_secs(60));
+        let monitor = lobby.monitor();
+        let server = thread::spawn(move || lobby.run(|_, _, _| {}).unwrap());
+
+        let tables = Connection::list_tables(&addr).unwrap();
+        assert!(tables[0].invite_only);
+        let refusal = |joined: io::Result<Connection>| joined.err().unwrap().to_string();
+        let uninvited = "Table vip is by invite only";
+        assert_eq!(
+            refusal(Connection::join(&addr, "vip", Some("pw"))),
+            uninvited
+        );
+        let forged = Signer::new(b"guess").invite("vip", Duration::from_secs(60));
+        assert_eq!(
+            refusal(Connection::join_invited(&addr, "vip", &forged)),
+            uninvited
+        );
+        let expired = Signer::new(b"secret").invite("vip", Duration::ZERO);
+        assert_eq!(
+            refusal(Connection::join_invited(&addr, "vip", &expired)),
+            uninvited
+        );
+
+        let guests: Vec<_> = (0..2)
+            .map(|_| {
+                let connection = Connection::join_invited(&addr, "vip", &invite).unwrap();
+                thread::spawn(move || play_out(connection, PokerAction::CallOrCheck))
+            })
+            .collect();
+        for guest in guests {
+            let (_, received) = guest.join().unwrap();
+            assert!(matches!(received.last(), Some(ServerMessage::GameOver)));
+        }
+        server.join().unwrap();
+        let status = &monitor.report().tables[0];
+        assert_eq!((status.seated, status.over), (2, true));
+        assert!(status.hands >= 1);
+    }
+
+    #[test]
+    fn should_sit_out_players_who_keep_running_out_of_time_until_they_sit_in() {
+        let impatient = TableConfig {
+            turn_timeout: Some(Duration::from_millis(500)),
+            ..table("main", 2, 10)
+        };
+        let (tcp, _, server) = host_tables(vec![impatient]);
+        let mut slow = Connection::connect(&tcp).unwrap();
+        let seat = slow.seat;
+        let other = client(&tcp, PokerAction::CallOrCheck);
+
+        let (mut requests, mut timeouts, mut deadlines) = (0, 0, vec![]);
+        loop {
+            match slow.recv().unwrap().unwrap() {
+                ServerMessage::Turn {
+                    player,
+                    deadline_ms,
+                    remaining_ms,
+                } if player == seat => {
+                    assert_eq!(remaining_ms, 500);
+                    deadlines.push(deadline_ms);
+                }
+                ServerMessage::ActionRequest { .. } => requests += 1,
+                ServerMessage::TimedOut { player } if player == seat => timeouts += 1,
+                ServerMessage::SatOut { player } if player == seat => break,
+                _ => {}
+            }
+        }
+        assert_eq!((requests, timeouts), (2, 2));
+        slow.send(&ClientMessage::SitIn).unwrap();
+        let mut sat_in = false;
+        loop {
+            match slow.recv().unwrap().unwrap() {
+                ServerMessage::SatIn { player } if player == seat => sat_in = true,
+                ServerMessage::ActionRequest { .. } => {
+                    assert!(sat_in);
+                    let action = PokerAction::Fold;
+                    slow.send(&ClientMessage::Action { action }).unwrap();
+                }
+                ServerMessage::GameOver => break,
+                _ => {}
+            }
+        }
+
+        let (_, received) = other.join().unwrap();
+        // The other player counted down to the same moments.
+        let seen: Vec<_> = received
+            .iter()
+            .filter_map(|m| match m {
+                ServerMessage::Turn {
+                    player,
+                    deadline_ms,
+                    ..
+                } if *player == seat => Some(*deadline_ms),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(seen[..2], deadlines[..]);
+        for event in [
+            ServerMessage::SatOut { player: seat },
+            ServerMessage::SatIn { player: seat },
+        ] {
+            let json = serde_json::to_string(&event).unwrap();
+            assert!(received
+                .iter()
+                .any(|m| serde_json::to_string(m).unwrap() == json));
+        }
+        server.join().unwrap();
+    }
+
+    #[test]
+    fn should_hang_up_on_clients_that_stop_answering_pings() {
+        let pinged = TableConfig {
+            heartbeat: Duration::from_millis(50),
+            ..table("main", 2, 4)
+        };
+        let (tcp, _, server) = host_tables(vec![pinged]);
+        // Joins and then never reads again, like a client whose network went away.
+        let mut gone = LineTransport::new(TcpStream::connect(&tcp).unwrap()).unwrap();
+        gone.send(&Encoding::Json.encode(&ClientMessage::join("", None)))
+            .unwrap();
+
+        let mut connection = Connection::connect(&tcp).unwrap();
+        // Without a turn timeout only the heartbeat can tell the other client is gone.
+        while let Some(message) = connection.recv().unwrap() {
+            match message {
+                ServerMessage::ActionRequest { .. } => {
+                    let action = PokerAction::CallOrCheck;
+                    connection.send(&ClientMessage::Action { action }).unwrap();
+                }
+                ServerMessage::GameOver => break,
+                _ => {}
+            }
+        }
+        assert!(connection.rtt.is_some());
+        server.join().unwrap();
+    }
+
+    #[test]
+    fn should_show_spectators_the_public_table_after_the_delay() {
+        let delay = Duration::from_secs(1);
+        let watched = TableConfig {
+            spectator_delay: delay,
+            ..table("main", 2, 4)
+        };
+        let (tcp, ws, server) = host_tables(vec![watched]);
+        let mut spectator = Connection::watch(&ws, "", None).unwrap();
+        assert_eq!(spectator.delay, Some(delay));
+        let spectator = thread::spawn(move || {
+            let mut received = vec![];
+            while let Some(message) = spectator.recv().unwrap() {
+                let over = matches!(message, ServerMessage::GameOver);
+                received.push(message);
+                if over {
+                    break;
+                }
+            }
+            (Instant::now(), received)
+        });
+        let players = [
+            client(&tcp, PokerAction::CallOrCheck),
+            client(&tcp, PokerAction::CallOrCheck),
+        ];
+        for player in players {
+            player.join().unwrap();
+        }
+        let played_to_the_end = Instant::now();
+
+        let (watched_to_the_end, received) = spectator.join().unwrap();
+        assert!(watched_to_the_end > played_to_the_end + delay / 2);
+        assert!(received
+            .iter()
+            .all(|m| !matches!(m, ServerMessage::Deal { .. } | ServerMessage::Table { .. })));
+        assert!(received.iter().any(|m| matches!(
+            m,
+            ServerMessage::PublicTable { view } if view.shown.iter().all(Option::is_none)
+        )));
+        server.join().unwrap();
+    }
+}
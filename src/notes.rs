@@ -0,0 +1,131 @@
+//! Notes and colored tags a player keeps on their opponents, stored with their profile and
+//! brought back whenever the same opponent sits at the table again.
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use crate::schema::Schema;
+
+const NOTEBOOK: Schema = Schema {
+    name: "notes file",
+    version: 1,
+    migrations: &[],
+};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Tag {
+    Red,
+    Yellow,
+    Green,
+    Blue,
+    Purple,
+}
+
+impl Tag {
+    pub const ALL: [Tag; 5] = [Tag::Red, Tag::Yellow, Tag::Green, Tag::Blue, Tag::Purple];
+
+    pub fn parse(name: &str) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|tag| tag.name().eq_ignore_ascii_case(name))
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Tag::Red => "red",
+            Tag::Yellow => "yellow",
+            Tag::Green => "green",
+            Tag::Blue => "blue",
+            Tag::Purple => "purple",
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Note {
+    /// Milliseconds since the Unix epoch.
+    pub at_ms: u64,
+    pub text: String,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct OpponentNotes {
+    pub tag: Option<Tag>,
+    /// Oldest first.
+    pub notes: Vec<Note>,
+}
+
+/// Notes by the name the opponent plays under.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Notebook {
+    opponents: BTreeMap<String, OpponentNotes>,
+}
+
+impl Notebook {
+    /// The notebook saved at `path`, or an empty one if nothing is saved there yet.
+    pub fn load(path: impl AsRef<Path>) -> io::Result<Self> {
+        match fs::read_to_string(path) {
+            Ok(text) => NOTEBOOK.read(&text).map_err(io::Error::other),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(e) => Err(e),
+        }
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        let json = serde_json::to_string_pretty(&NOTEBOOK.stamp(self))?;
+        fs::write(path, json)
+    }
+
+    pub fn get(&self, opponent: &str) -> Option<&OpponentNotes> {
+        self.opponents.get(opponent)
+    }
+
+    pub fn add_note(&mut self, opponent: &str, text: &str, at_ms: u64) {
+        self.opponents
+            .entry(opponent.to_owned())
+            .or_default()
+            .notes
+            .push(Note {
+                at_ms,
+                text: text.to_owned(),
+            });
+    }
+
+    /// Tags the opponent, or takes the tag off with `None`.
+    pub fn set_tag(&mut self, opponent: &str, tag: Option<Tag>) {
+        let notes = self.opponents.entry(opponent.to_owned()).or_default();
+        notes.tag = tag;
+        if *notes == OpponentNotes::default() {
+            self.opponents.remove(opponent);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_keep_notes_and_tags_by_opponent() {
+        let mut notebook = Notebook::default();
+        notebook.add_note("lag bot", "3-bets light from the button", 1);
+        notebook.set_tag("lag bot", Tag::parse("Red"));
+        notebook.set_tag("Player 2", Some(Tag::Blue));
+        notebook.set_tag("Player 2", None);
+
+        let lag = notebook.get("lag bot").unwrap();
+        assert_eq!(lag.tag, Some(Tag::Red));
+        assert_eq!(lag.notes[0].text, "3-bets light from the button");
+        assert!(notebook.get("Player 2").is_none());
+
+        let path = std::env::temp_dir().join(format!("poker-tui-{}.notes", rand::random::<u64>()));
+        notebook.save(&path).unwrap();
+        assert_eq!(Notebook::load(&path).unwrap(), notebook);
+        std::fs::remove_file(path).unwrap();
+        assert_eq!(Tag::parse("orange"), None);
+    }
+}
@@ -0,0 +1,78 @@
+use std::io;
+use std::path::Path;
+
+use rand::rngs::StdRng;
+use tract_onnx::prelude::*;
+use tract_onnx::tract_core::anyhow::bail;
+
+use crate::bots::{seeded_rng, BotContext, BotPlayer, PlayerView};
+use crate::encoding::{encode, sample, ACTIONS, ENCODED_LEN};
+use crate::PokerAction;
+
+type Model = SimplePlan<TypedFact, Box<dyn TypedOp>, Graph<TypedFact, Box<dyn TypedOp>>>;
+
+/// Plays by an ONNX policy network. The network takes a `[1, ENCODED_LEN]` float input
+/// laid out as described in `encoding`, and answers with `[1, ACTIONS.len()]` logits for
+/// the actions in `ACTIONS`. An optional second output holding a single value, the
+/// expected result of the hand in big blinds, is shown as the reason for each action.
+pub struct OnnxBot {
+    model: Model,
+    rng: StdRng,
+}
+
+impl OnnxBot {
+    pub fn load(path: impl AsRef<Path>, seed: Option<u64>) -> io::Result<Self> {
+        let model = tract_onnx::onnx()
+            .model_for_path(path)
+            .and_then(|model| model.with_input_fact(0, f32::fact([1, ENCODED_LEN]).into()))
+            .and_then(|model| model.into_optimized())
+            .and_then(|model| model.into_runnable())
+            .map_err(|e| io::Error::other(format!("{e:#}")))?;
+        Ok(Self {
+            model,
+            rng: seeded_rng(seed),
+        })
+    }
+
+    /// The action weights and the value estimate, if the model has one, for `view`.
+    fn evaluate(&self, view: &PlayerView) -> TractResult<(Vec<f32>, Option<f32>)> {
+        let input = Tensor::from_shape(&[1, ENCODED_LEN], &encode(view))?;
+        let outputs = self.model.run(tvec!(input.into()))?;
+        let logits: Vec<f32> = outputs[0].as_slice::<f32>()?.to_vec();
+        if logits.len() != ACTIONS.len() {
+            bail!(
+                "expected {} action logits, got {}",
+                ACTIONS.len(),
+                logits.len()
+            );
+        }
+        let max = logits.iter().copied().fold(f32::NEG_INFINITY, f32::max);
+        let weights = logits.iter().map(|l| (l - max).exp()).collect();
+        let value = match outputs.get(1) {
+            Some(value) => value.as_slice::<f32>()?.first().copied(),
+            None => None,
+        };
+        Ok((weights, value))
+    }
+}
+
+impl BotPlayer for OnnxBot {
+    fn decide(&mut self, view: &BotContext) -> PokerAction {
+        self.decide_explained(view).0
+    }
+
+    fn decide_explained(&mut self, view: &BotContext) -> (PokerAction, Option<String>) {
+        match self.evaluate(view) {
+            Ok((weights, value)) => {
+                let choice = sample(&weights, &mut self.rng);
+                let reason = value.map(|v| format!("model expects {v:+.1} bb"));
+                (choice.action(view), reason)
+            }
+            // A broken model shouldn't stall the table.
+            Err(e) => (
+                PokerAction::CallOrCheck,
+                Some(format!("model failed, checking or calling: {e}")),
+            ),
+        }
+    }
+}
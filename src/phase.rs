@@ -0,0 +1,214 @@
+//! The hand as a typed state machine: each phase is its own type with only the calls that
+//! make sense in it, so acting on a finished hand or asking a hand still being played who
+//! won doesn't compile. The phases wrap a `HandState`, which stays available to read.
+//! Outside the crate they are the only way to act in a hand.
+
+use crate::core_engine::{Card, Deck, Hand};
+use crate::events::{GameEvent, Street};
+use crate::{GameState, HandState, IllegalRaise, PokerAction, TurnResult};
+
+/// What a hand turned into after an action.
+pub enum Phase {
+    AwaitingAction(AwaitingAction),
+    StreetComplete(StreetComplete),
+    HandComplete(HandComplete),
+}
+
+/// Waiting for the current player to act.
+pub struct AwaitingAction {
+    hs: Box<HandState>,
+}
+
+/// The betting on a street is over and the next street is dealt.
+pub struct StreetComplete {
+    hs: Box<HandState>,
+    /// How many cards the board had before the street was dealt.
+    board: usize,
+}
+
+/// The hand is over and the pots are awarded.
+pub struct HandComplete {
+    hs: Box<HandState>,
+    winner: usize,
+}
+
+impl Phase {
+    pub fn hand(&self) -> &HandState {
+        match self {
+            Phase::AwaitingAction(hand) => hand.hand(),
+            Phase::StreetComplete(street) => street.hand(),
+            Phase::HandComplete(complete) => complete.hand(),
+        }
+    }
+
+    /// Who acts next, or who won once the hand is over.
+    pub fn result(&self) -> TurnResult {
+        match self {
+            Phase::AwaitingAction(hand) => TurnResult::NextPlayer(hand.player()),
+            Phase::StreetComplete(street) => TurnResult::NextPlayer(street.hand().current_player()),
+            Phase::HandComplete(complete) => TurnResult::WonHand(complete.winner()),
+        }
+    }
+
+    pub fn into_hand(self) -> HandState {
+        match self {
+            Phase::AwaitingAction(AwaitingAction { hs })
+            | Phase::StreetComplete(StreetComplete { hs, .. })
+            | Phase::HandComplete(HandComplete { hs, .. }) => *hs,
+        }
+    }
+}
+
+impl AwaitingAction {
+    /// Deals the next hand of the game.
+    pub fn start(gs: &GameState, deck: Deck) -> Self {
+        let (hs, _) = gs.start_play_hand(deck);
+        Self { hs: Box::new(hs) }
+    }
+
+    /// Picks up a hand played so far some other way, such as one loaded or rewound, or
+    /// hands it back if it is already over.
+    pub fn resume(hs: HandState) -> Result<Self, Box<HandState>> {
+        let hs = Box::new(hs);
+        match hs.is_over() {
+            true => Err(hs),
+            false => Ok(Self { hs }),
+        }
+    }
+
+    pub fn into_hand(self) -> HandState {
+        *self.hs
+    }
+
+    pub fn hand(&self) -> &HandState {
+        &self.hs
+    }
+
+    pub fn player(&self) -> usize {
+        self.hs.current_player()
+    }
+
+    /// Plays the current player's action, and returns what the hand turned into with the
+    /// events the action caused: the action itself, any street dealt after it and, when it
+    /// ends the hand, the hands shown and the pots awarded. A raise that is too big or too
+    /// small is refused and leaves the hand waiting for the same player.
+    pub fn act(
+        mut self,
+        action: PokerAction,
+    ) -> Result<(Phase, Vec<GameEvent>), (Self, IllegalRaise)> {
+        let (street, board) = (self.hs.street(), self.hs.board.len());
+        let (result, events) = match self.hs.play_action_with_events(action) {
+            Ok(played) => played,
+            Err(e) => return Err((self, e)),
+        };
+        let phase = match result {
+            TurnResult::WonHand(winner) => Phase::HandComplete(HandComplete {
+                hs: self.hs,
+                winner,
+            }),
+            TurnResult::NextPlayer(_) if self.hs.street() != street => {
+                Phase::StreetComplete(StreetComplete { hs: self.hs, board })
+            }
+            TurnResult::NextPlayer(_) => Phase::AwaitingAction(self),
+        };
+        Ok((phase, events))
+    }
+}
+
+impl StreetComplete {
+    pub fn hand(&self) -> &HandState {
+        &self.hs
+    }
+
+    /// The street just dealt.
+    pub fn street(&self) -> Street {
+        self.hs.street()
+    }
+
+    /// The cards the action dealt to the board, every street's of them if it ever deals
+    /// more than one.
+    pub fn dealt(&self) -> &[Card] {
+        &self.hs.board[self.board..]
+    }
+
+    /// Goes on to the betting on the new street.
+    pub fn next(self) -> AwaitingAction {
+        AwaitingAction { hs: self.hs }
+    }
+}
+
+impl HandComplete {
+    pub fn hand(&self) -> &HandState {
+        &self.hs
+    }
+
+    /// Who won the hand, or the main pot when it was split into several.
+    pub fn winner(&self) -> usize {
+        self.winner
+    }
+
+    /// The winner and the five cards they won with, if the hand went to showdown.
+    pub fn showdown(&self) -> Option<(usize, Hand)> {
+        self.hs.showdown_winner()
+    }
+
+    /// The game with the hand's chips moved, ready to deal the next hand.
+    pub fn next_game(self, gs: &GameState) -> GameState {
+        gs.apply_played_hand(*self.hs)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::PokerAction::*;
+
+    fn call(hand: AwaitingAction) -> Phase {
+        hand.act(CallOrCheck).map_err(|(_, e)| e).unwrap().0
+    }
+
+    #[test]
+    fn should_walk_a_hand_through_its_phases() {
+        let gs = GameState::init(2).unwrap();
+        let hand = AwaitingAction::start(&gs, Deck::ordered_deck());
+        let Err((hand, _)) = hand.act(Raise(1000)) else {
+            panic!("raised more than the stack");
+        };
+        let Ok((Phase::AwaitingAction(hand), events)) = hand.act(CallOrCheck) else {
+            panic!("the big blind still gets to act");
+        };
+        assert!(matches!(events[..], [GameEvent::Called { player: 0, .. }]));
+
+        let mut streets = vec![];
+        let mut phase = call(hand);
+        let complete = loop {
+            phase = match phase {
+                Phase::AwaitingAction(hand) => call(hand),
+                Phase::StreetComplete(street) => {
+                    streets.push((street.street(), street.dealt().len()));
+                    call(street.next())
+                }
+                Phase::HandComplete(complete) => break complete,
+            };
+        };
+
+        assert_eq!(
+            streets,
+            [(Street::Flop, 3), (Street::Turn, 1), (Street::River, 1)]
+        );
+        let (winner, _) = complete.showdown().unwrap();
+        assert_eq!(winner, complete.winner());
+        let next = complete.next_game(&gs);
+        assert_eq!(next.current_chips(0) + next.current_chips(1), 200);
+    }
+
+    #[test]
+    fn should_resume_only_a_hand_still_being_played() {
+        let gs = GameState::init(2).unwrap();
+        let hand = AwaitingAction::start(&gs, Deck::ordered_deck());
+        let hand = AwaitingAction::resume(hand.into_hand()).ok().unwrap();
+        let (phase, _) = hand.act(Fold).ok().unwrap();
+        assert!(matches!(phase.result(), TurnResult::WonHand(1)));
+        assert!(AwaitingAction::resume(phase.into_hand()).is_err());
+    }
+}
@@ -0,0 +1,329 @@
+//! Hands in the open Poker Hand History format, <https://phh.readthedocs.io>, which other
+//! open-source poker tools read and write. A hand is a TOML file listing the stacks and
+//! blinds of the players, who sit in the order they act in after the flop, and every
+//! deal and action as a line like `d dh p1 AhKd`, `p2 cbr 6` or `d db 8s9sTs`.
+//!
+//! The engine has the player after the big blind open every street, not just the first,
+//! so tools that check whose turn it is may refuse our hands once they get past the flop.
+
+use serde::{Deserialize, Serialize};
+
+use crate::core_engine::Card;
+use crate::events::GameEvent;
+use crate::history::HandHistory;
+use crate::{HandState, PokerAction, BIG_BLIND_CHIPS, SMALL_BLIND_CHIPS};
+
+/// No-limit Texas hold'em, the only variant played here.
+const VARIANT: &str = "NT";
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PhhHand {
+    pub variant: String,
+    #[serde(default)]
+    pub antes: Vec<u32>,
+    pub blinds_or_straddles: Vec<u32>,
+    pub min_bet: u32,
+    pub starting_stacks: Vec<u32>,
+    pub actions: Vec<String>,
+    /// Names in the same order as the stacks. Ours are `Player N`, with N their seat.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub players: Option<Vec<String>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub hand: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub table: Option<String>,
+}
+
+/// The seat of `p1`, the player after the button, and so on for every player.
+fn seat_order(players: usize, button: usize) -> Vec<usize> {
+    (1..=players).map(|k| (button + k) % players).collect()
+}
+
+/// The blinds by PHH position: heads-up the button posts the small blind and acts last.
+fn nominal_blinds(players: usize) -> Vec<u32> {
+    let mut blinds = vec![0; players];
+    let (small, big) = if players == 2 { (1, 0) } else { (0, 1) };
+    blinds[small] = SMALL_BLIND_CHIPS;
+    blinds[big] = BIG_BLIND_CHIPS;
+    blinds
+}
+
+fn cards_text(cards: &[Card]) -> String {
+    cards.iter().map(Card::short_name).collect()
+}
+
+impl PhhHand {
+    /// The hand as PHH, everyone's hole cards included.
+    pub fn from_hand(hs: &HandState, hand: Option<u64>, table: Option<&str>) -> Self {
+        let snapshot = hs.spectator_snapshot();
+        let history = hs.history();
+        let seats = seat_order(snapshot.players, snapshot.button);
+        let position = |seat: usize| seats.iter().position(|&s| s == seat).unwrap_or(0) + 1;
+
+        let mut bets = vec![0; snapshot.players];
+        let mut in_hand = snapshot.players;
+        let mut actions = vec![];
+        for event in hs.events() {
+            match event {
+                GameEvent::HoleCardsDealt { player, cards } => {
+                    actions.push(format!("d dh p{} {}", position(*player), cards_text(cards)))
+                }
+                GameEvent::BlindPosted { player, amount } => bets[*player] += amount,
+                GameEvent::Checked { player, .. } => {
+                    actions.push(format!("p{} cc", position(*player)))
+                }
+                GameEvent::Called { player, amount, .. } => {
+                    bets[*player] += amount;
+                    actions.push(format!("p{} cc", position(*player)));
+                }
+                GameEvent::Raised { player, amount, .. } => {
+                    let highest = bets.iter().copied().max().unwrap_or(0);
+                    bets[*player] += amount;
+                    let action = match bets[*player] {
+                        total if total <= highest => "cc".to_owned(),
+                        total => format!("cbr {total}"),
+                    };
+                    actions.push(format!("p{} {action}", position(*player)));
+                }
+                GameEvent::Folded { player, .. } => {
+                    in_hand -= 1;
+                    actions.push(format!("p{} f", position(*player)));
+                }
+                // The engine deals the next street before it sees everyone else folded.
+                GameEvent::StreetDealt { .. } if in_hand < 2 => {}
+                GameEvent::StreetDealt { cards, .. } => {
+                    bets.iter_mut().for_each(|b| *b = 0);
+                    actions.push(format!("d db {}", cards_text(cards)));
+                }
+                GameEvent::HandShown { player, .. } => {
+                    let cards = hs.events().iter().find_map(|e| match e {
+                        GameEvent::HoleCardsDealt { player: p, cards } if p == player => {
+                            Some(cards_text(cards))
+                        }
+                        _ => None,
+                    });
+                    actions.push(format!(
+                        "p{} sm {}",
+                        position(*player),
+                        cards.unwrap_or_default()
+                    ));
+                }
+                GameEvent::BetReturned { .. }
+                | GameEvent::PotAwarded { .. }
+                | GameEvent::PlayerEliminated { .. }
+                | GameEvent::TimedOut { .. }
+                | GameEvent::SatOut { .. }
+                | GameEvent::SatIn { .. } => {}
+            }
+        }
+
+        PhhHand {
+            variant: VARIANT.to_owned(),
+            antes: vec![0; snapshot.players],
+            blinds_or_straddles: nominal_blinds(snapshot.players),
+            min_bet: BIG_BLIND_CHIPS,
+            starting_stacks: seats.iter().map(|&s| history.stacks[s]).collect(),
+            actions,
+            players: Some(seats.iter().map(|s| format!("Player {s}")).collect()),
+            hand,
+            table: table.map(str::to_owned),
+        }
+    }
+
+    pub fn to_toml(&self) -> String {
+        toml::to_string(self).expect("hands serialize")
+    }
+
+    pub fn from_toml(text: &str) -> Result<Self, String> {
+        toml::from_str(text).map_err(|e| e.to_string())
+    }
+
+    /// The hand as our own history, which replays it. Players named `Player N` keep seat
+    /// N, and the cards nobody saw are filled in from the rest of the deck.
+    pub fn to_history(&self) -> Result<HandHistory, String> {
+        let players = self.starting_stacks.len();
+        if self.variant != VARIANT {
+            return Err(format!(
+                "only {VARIANT} hands are played, not {}",
+                self.variant
+            ));
+        }
+        if players < 2 {
+            return Err("a hand needs at least 2 players".into());
+        }
+        if self.antes.iter().any(|&a| a > 0) || self.blinds_or_straddles != nominal_blinds(players)
+        {
+            return Err(format!(
+                "only blinds of {SMALL_BLIND_CHIPS}/{BIG_BLIND_CHIPS} without antes are played"
+            ));
+        }
+        let p1_seat = self
+            .players
+            .as_ref()
+            .and_then(|names| names.first()?.strip_prefix("Player ")?.parse().ok())
+            .filter(|&seat: &usize| seat < players)
+            .unwrap_or(0);
+        let seats = seat_order(players, (p1_seat + players - 1) % players);
+        let mut stacks = vec![0; players];
+        for (&seat, &stack) in seats.iter().zip(&self.starting_stacks) {
+            stacks[seat] = stack;
+        }
+        let big_blind = seats[if players == 2 { 0 } else { 1 }];
+
+        let mut hole_cards = vec![[None; 2]; players];
+        let mut board = vec![];
+        let mut actions = vec![];
+        let mut left = stacks.clone();
+        let mut bets = vec![0; players];
+        let small_blind = (big_blind + players - 1) % players;
+        for (seat, blind) in [
+            (small_blind, SMALL_BLIND_CHIPS),
+            (big_blind, BIG_BLIND_CHIPS),
+        ] {
+            let amount = blind.min(left[seat]);
+            left[seat] -= amount;
+            bets[seat] += amount;
+        }
+        for line in &self.actions {
+            // Everything after a # is a comment.
+            let line = line.split('#').next().unwrap_or_default();
+            let bad = || format!("can't play {line:?}");
+            let seat_of = |player: &str| {
+                let k: usize = player.strip_prefix('p')?.parse().ok()?;
+                seats.get(k.checked_sub(1)?).copied()
+            };
+            match line.split_whitespace().collect::<Vec<_>>()[..] {
+                ["d", "dh", player, cards] => {
+                    let seat = seat_of(player).ok_or_else(bad)?;
+                    hole_cards[seat] = match parse_cards(cards).as_deref() {
+                        Some(&[first, second]) => [first, second],
+                        _ => return Err(bad()),
+                    };
+                }
+                ["d", "db", cards] => {
+                    bets.iter_mut().for_each(|b| *b = 0);
+                    board.extend(parse_cards(cards).ok_or_else(bad)?);
+                }
+                [player, "f"] => {
+                    let seat = seat_of(player).ok_or_else(bad)?;
+                    actions.push((seat, PokerAction::Fold));
+                }
+                [player, "cc"] => {
+                    let seat = seat_of(player).ok_or_else(bad)?;
+                    let highest = bets.iter().copied().max().unwrap_or(0);
+                    let amount = (highest - bets[seat]).min(left[seat]);
+                    left[seat] -= amount;
+                    bets[seat] += amount;
+                    actions.push((seat, PokerAction::CallOrCheck));
+                }
+                [player, "cbr", total] => {
+                    let seat = seat_of(player).ok_or_else(bad)?;
+                    let total: u32 = total.parse().map_err(|_| bad())?;
+                    let amount = total.checked_sub(bets[seat]).ok_or_else(bad)?;
+                    left[seat] = left[seat].checked_sub(amount).ok_or_else(bad)?;
+                    bets[seat] = total;
+                    actions.push((seat, PokerAction::Raise(amount)));
+                }
+                [player, "sm", ..] | [player, "sd", ..] => {
+                    seat_of(player).ok_or_else(bad)?;
+                }
+                [] => {}
+                _ => return Err(bad()),
+            }
+        }
+
+        HandHistory::from_deal(stacks, big_blind, &hole_cards, &board, &actions)
+    }
+}
+
+/// Cards run together like `AhKd`, with `??` for one nobody saw.
+fn parse_cards(cards: &str) -> Option<Vec<Option<Card>>> {
+    if !cards.is_ascii() || !cards.len().is_multiple_of(2) {
+        return None;
+    }
+    (0..cards.len())
+        .step_by(2)
+        .map(|i| match &cards[i..i + 2] {
+            "??" => Some(None),
+            card => Card::from_short_name(card).map(Some),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core_engine::Deck;
+    use crate::PokerAction::*;
+    use crate::{GameState, TurnResult::NextPlayer};
+
+    #[test]
+    fn should_write_and_read_back_hands() {
+        let gs = GameState::init(3).unwrap();
+        let (mut hs, _) = gs.start_play_hand(Deck::shuffled_deck());
+        for action in [Raise(6), Fold, CallOrCheck, Raise(10)] {
+            hs.play_action(action).unwrap();
+        }
+        while let NextPlayer(_) = hs.play_action(CallOrCheck).unwrap() {}
+
+        let phh = PhhHand::from_hand(&hs, Some(7), Some("main"));
+        assert_eq!(phh.blinds_or_straddles, [1, 2, 0]);
+        assert_eq!(
+            phh.players.as_deref().unwrap(),
+            ["Player 1", "Player 2", "Player 0"]
+        );
+        let text = phh.to_toml();
+        assert!(text.contains("\"p3 cbr 6\""));
+        assert!(text.contains("\"p1 f\""));
+        let read = PhhHand::from_toml(&text).unwrap();
+        assert_eq!(read, phh);
+        let history = read.to_history().unwrap();
+        assert_eq!(
+            (&history.stacks, history.big_blind, &history.actions),
+            (
+                &hs.history().stacks,
+                hs.history().big_blind,
+                &hs.history().actions
+            )
+        );
+        let mut replayed = history.start();
+        for action in &history.actions {
+            replayed.play_action(*action).unwrap();
+        }
+        assert_eq!(replayed.events(), hs.events());
+    }
+
+    #[test]
+    fn should_read_hands_written_elsewhere() {
+        let text = r#"
+            variant = "NT"
+            antes = [0, 0]
+            blinds_or_straddles = [2, 1]
+            min_bet = 2
+            starting_stacks = [200, 150]
+            actions = ["d dh p1 AcAd", "d dh p2 ????", "p2 cbr 6", "p1 cbr 20", "p2 f"]
+        "#;
+        let history = PhhHand::from_toml(text).unwrap().to_history().unwrap();
+        assert_eq!(
+            (history.stacks.clone(), history.big_blind),
+            (vec![200, 150], 0)
+        );
+        assert_eq!(history.actions, [Raise(5), Raise(18), Fold]);
+        let hs = history.start();
+        assert_eq!(
+            hs.events()[0],
+            GameEvent::HoleCardsDealt {
+                player: 0,
+                cards: [
+                    Card::from_short_name("Ac").unwrap(),
+                    Card::from_short_name("Ad").unwrap()
+                ]
+            }
+        );
+
+        let cheating = text.replace("????", "AcKd");
+        assert!(PhhHand::from_toml(&cheating).unwrap().to_history().is_err());
+        let ante = text.replace("antes = [0, 0]", "antes = [1, 1]");
+        assert!(PhhHand::from_toml(&ante).unwrap().to_history().is_err());
+    }
+}
@@ -0,0 +1,265 @@
+//! Finished hands written the way PokerStars writes its hand histories, which trackers and
+//! replayers read. Players are called `Player N` and sit in seat N + 1, and amounts are in
+//! chips, as in a play money game.
+
+use crate::archive::date_time;
+use crate::core_engine::Card;
+use crate::events::{GameEvent, Street};
+use crate::{HandState, BIG_BLIND_CHIPS, SMALL_BLIND_CHIPS};
+
+/// The hand history of a finished hand. `hero` is the player whose hole cards are shown
+/// from the start, PokerStars only ever shows one player's. Everyone's at a showdown are
+/// shown there.
+pub fn hand_history(
+    hs: &HandState,
+    id: u64,
+    table: &str,
+    finished_ms: u64,
+    hero: Option<usize>,
+) -> String {
+    let snapshot = hs.spectator_snapshot();
+    let stacks = &hs.history().stacks;
+    let events = hs.events();
+    let name = |player: usize| format!("Player {player}");
+    let hole_cards = |player: usize| {
+        events.iter().find_map(|e| match e {
+            GameEvent::HoleCardsDealt { player: p, cards } if *p == player => {
+                Some(format!("[{}]", cards_text(cards)))
+            }
+            _ => None,
+        })
+    };
+
+    let mut lines = vec![
+        format!(
+            "PokerStars Hand #{id}: Hold'em No Limit ({SMALL_BLIND_CHIPS}/{BIG_BLIND_CHIPS}) - {} UTC",
+            date_time(finished_ms)
+        ),
+        format!(
+            "Table '{table}' {}-max Seat #{} is the button",
+            snapshot.players,
+            snapshot.button + 1
+        ),
+    ];
+    for (player, stack) in stacks.iter().enumerate() {
+        lines.push(format!(
+            "Seat {}: {} ({stack} in chips)",
+            player + 1,
+            name(player)
+        ));
+    }
+
+    let mut left = stacks.clone();
+    let mut bets = vec![0; stacks.len()];
+    let mut board: Vec<Card> = vec![];
+    let mut folded = vec![None; stacks.len()];
+    let mut shown = vec![None; stacks.len()];
+    let mut won = vec![0; stacks.len()];
+    let mut showdown = false;
+    let mut blinds = ["small", "big"].into_iter();
+    let mut in_hand = stacks.len();
+    for event in events {
+        match event {
+            GameEvent::BlindPosted { player, amount } => {
+                let blind = blinds.next().unwrap_or("big");
+                left[*player] -= amount;
+                bets[*player] += amount;
+                lines.push(format!(
+                    "{}: posts {blind} blind {amount}{}",
+                    name(*player),
+                    all_in(left[*player])
+                ));
+                if blind == "big" {
+                    lines.push("*** HOLE CARDS ***".to_owned());
+                    if let Some(hero) = hero {
+                        lines.push(format!(
+                            "Dealt to {} {}",
+                            name(hero),
+                            hole_cards(hero).unwrap_or_default()
+                        ));
+                    }
+                }
+            }
+            GameEvent::Checked { player, .. } => lines.push(format!("{}: checks", name(*player))),
+            GameEvent::Called { player, amount, .. } => {
+                left[*player] -= amount;
+                bets[*player] += amount;
+                lines.push(format!(
+                    "{}: calls {amount}{}",
+                    name(*player),
+                    all_in(left[*player])
+                ));
+            }
+            GameEvent::Raised { player, amount, .. } => {
+                let highest = bets.iter().copied().max().unwrap_or(0);
+                left[*player] -= amount;
+                bets[*player] += amount;
+                let total = bets[*player];
+                let action = if total <= highest {
+                    format!("calls {amount}")
+                } else if highest == 0 {
+                    format!("bets {amount}")
+                } else {
+                    format!("raises {} to {total}", total - highest)
+                };
+                lines.push(format!(
+                    "{}: {action}{}",
+                    name(*player),
+                    all_in(left[*player])
+                ));
+            }
+            GameEvent::Folded { player, street } => {
+                folded[*player] = Some(*street);
+                in_hand -= 1;
+                lines.push(format!("{}: folds", name(*player)));
+            }
+            // The engine deals the next street before it sees everyone else folded.
+            GameEvent::StreetDealt { .. } if in_hand < 2 => {}
+            GameEvent::StreetDealt { street, cards } => {
+                bets.iter_mut().for_each(|b| *b = 0);
+                let header = match street {
+                    Street::Preflop => continue,
+                    Street::Flop => "FLOP",
+                    Street::Turn => "TURN",
+                    Street::River => "RIVER",
+                };
+                let before = if board.is_empty() {
+                    String::new()
+                } else {
+                    format!(" [{}]", cards_text(&board))
+                };
+                lines.push(format!("*** {header} ***{before} [{}]", cards_text(cards)));
+                board.extend(cards);
+            }
+            GameEvent::HandShown { player, hand } => {
+                if !showdown {
+                    showdown = true;
+                    lines.push("*** SHOW DOWN ***".to_owned());
+                }
+                let description = hand.ranking_name().to_lowercase();
+                let cards = hole_cards(*player).unwrap_or_default();
+                lines.push(format!("{}: shows {cards} ({description})", name(*player)));
+                shown[*player] = Some((cards, description));
+            }
+            GameEvent::BetReturned { player, amount } => {
+                lines.push(format!(
+                    "Uncalled bet ({amount}) returned to {}",
+                    name(*player)
+                ));
+            }
+            GameEvent::PotAwarded { player, amount, .. } => {
+                won[*player] += amount;
+                lines.push(format!("{} collected {amount} from pot", name(*player)));
+            }
+            GameEvent::HoleCardsDealt { .. }
+            | GameEvent::PlayerEliminated { .. }
+            | GameEvent::TimedOut { .. }
+            | GameEvent::SatOut { .. }
+            | GameEvent::SatIn { .. } => {}
+        }
+    }
+
+    lines.push("*** SUMMARY ***".to_owned());
+    lines.push(format!("Total pot {} | Rake 0", won.iter().sum::<u32>()));
+    if !board.is_empty() {
+        lines.push(format!("Board [{}]", cards_text(&board)));
+    }
+    for player in 0..stacks.len() {
+        let mut seat = format!("Seat {}: {}", player + 1, name(player));
+        if player == snapshot.button {
+            seat += " (button)";
+        }
+        if player == snapshot.small_blind {
+            seat += " (small blind)";
+        } else if player == snapshot.big_blind {
+            seat += " (big blind)";
+        }
+        let outcome = match (&folded[player], &shown[player]) {
+            (Some(Street::Preflop), _) => "folded before Flop".to_owned(),
+            (Some(street), _) => format!("folded on the {street:?}"),
+            (None, Some((cards, description))) if won[player] > 0 => {
+                format!(
+                    "showed {cards} and won ({}) with {description}",
+                    won[player]
+                )
+            }
+            (None, Some((cards, description))) => {
+                format!("showed {cards} and lost with {description}")
+            }
+            (None, None) => format!("collected ({})", won[player]),
+        };
+        lines.push(format!("{seat} {outcome}"));
+    }
+    lines.join("\n") + "\n"
+}
+
+fn all_in(left: u32) -> &'static str {
+    if left == 0 {
+        " and is all-in"
+    } else {
+        ""
+    }
+}
+
+/// Cards like `Ah Td`.
+fn cards_text(cards: &[Card]) -> String {
+    cards
+        .iter()
+        .map(Card::short_name)
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core_engine::Deck;
+    use crate::GameState;
+    use crate::PokerAction::*;
+    use crate::TurnResult::NextPlayer;
+
+    #[test]
+    fn should_write_hands_the_way_pokerstars_does() {
+        let gs = GameState::init(3).unwrap();
+        let (mut hs, _) = gs.start_play_hand(Deck::ordered_deck());
+        for action in [Raise(6), Fold, CallOrCheck] {
+            hs.play_action(action).unwrap();
+        }
+        while let NextPlayer(_) = hs.play_action(CallOrCheck).unwrap() {}
+
+        let text = hand_history(&hs, 42, "main", 1_700_000_000_000, Some(0));
+        let lines: Vec<_> = text.lines().collect();
+        assert_eq!(
+            lines[..9],
+            [
+                "PokerStars Hand #42: Hold'em No Limit (1/2) - 2023/11/14 22:13:20 UTC",
+                "Table 'main' 3-max Seat #1 is the button",
+                "Seat 1: Player 0 (100 in chips)",
+                "Seat 2: Player 1 (100 in chips)",
+                "Seat 3: Player 2 (100 in chips)",
+                "Player 1: posts small blind 1",
+                "Player 2: posts big blind 2",
+                "*** HOLE CARDS ***",
+                "Dealt to Player 0 [2s 3s]",
+            ]
+        );
+        assert_eq!(
+            lines[9..12],
+            [
+                "Player 0: raises 4 to 6",
+                "Player 1: folds",
+                "Player 2: calls 4",
+            ]
+        );
+        assert!(lines.contains(&"*** TURN *** [8s 9s Ts] [Js]"));
+        assert!(lines.contains(&"Player 2: shows [6s 7s] (straight flush)"));
+        assert_eq!(
+            lines[lines.len() - 3..],
+            [
+                "Seat 1: Player 0 (button) showed [2s 3s] and won (6) with straight flush",
+                "Seat 2: Player 1 (small blind) folded before Flop",
+                "Seat 3: Player 2 (big blind) showed [6s 7s] and won (7) with straight flush",
+            ]
+        );
+    }
+}
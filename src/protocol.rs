@@ -0,0 +1,789 @@
+//! The messages of a networked game, shared by the server and its clients. Each message is
+//! one JSON object tagged by `type`.
+//!
+//! A client opens with `Join`, naming the protocol versions it speaks. The server answers
+//! with `Seat` and the newest version both sides speak, or with `Error` and hangs up when
+//! there is none. Everything after that follows the version agreed on.
+//!
+//! `Seat` also hands the client a token. A client that lost its connection opens a new one
+//! with `Resume` and the token instead of `Join`, gets its seat back and a `Sync` with the
+//! hand so far.
+//!
+//! A server may host several named tables. `Join` names the table, or takes the first one
+//! when it doesn't, and brings the table's password if it has one, or an invite the host
+//! handed out. Tables can also be by invite only. A client can also open with
+//! `ListTables` instead, and gets the `Tables` before the server hangs up.
+//!
+//! A client that opens with `Watch` instead of `Join` doesn't take a seat. It gets the
+//! table as anyone watching may see it, some time after it happened if the table is set
+//! up that way.
+//!
+//! The server pings every client now and then, and takes one that stops answering with
+//! `Pong` for gone.
+//!
+//! A client that asks for it in `Join` or `Resume` gets its messages in MessagePack
+//! rather than JSON, and `TableChanges` instead of the whole table after every action.
+//! It sends in MessagePack too. Either side reads either encoding at any time.
+//!
+//! At a table set up for mental poker the players shuffle the deck together before every
+//! hand, as `mental` describes: the server passes the deck around with `Shuffle`, then
+//! with `Lock`. It asks every player with `RevealCards` for their keys to the cards dealt
+//! to the others, and sends each player their own still locked in `HoleCards`, so that
+//! only they can open them. It asks for everyone's keys to the board with `RevealCards` as
+//! it is dealt, and for the rest with `RevealKeys` once the hand is over. Then it sends
+//! everyone a `ShuffleAudit` to check the deal with, or `CalledOff` if a player didn't
+//! hand over their keys.
+
+use std::ops::RangeInclusive;
+
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+use crate::archive::ArchivedHand;
+use crate::bots::LegalActions;
+use crate::core_engine::{Card, Hand};
+use crate::events::{GameEvent, Street};
+use crate::mental::{Audit, PartyKeys};
+use crate::transport::Frame;
+use crate::view::{PublicView, SeatView};
+use crate::PokerAction;
+
+/// The newest version of the protocol, spoken by this build. Version 2 sends the table as
+/// a `SeatView` rather than a snapshot, version 3 adds `Resume`, version 4 named tables,
+/// version 5 spectators, version 6 turn timeouts and sitting out, version 7 heartbeats,
+/// version 8 mental poker, version 9 compact encodings, version 10 hand histories and
+/// version 11 deadlines everyone at the table sees, version 12 uncalled bets given back and
+/// version 13 mental poker cards only the player dealt them sees.
+pub const PROTOCOL_VERSION: u32 = 13;
+
+/// The first version in which clients can resume their seat.
+pub const RESUME_VERSION: u32 = 3;
+
+/// The first version whose clients hear about timeouts and players sitting out.
+pub const SIT_OUT_VERSION: u32 = 6;
+
+/// The first version whose clients answer pings.
+pub const HEARTBEAT_VERSION: u32 = 7;
+
+/// The first version whose clients take part in mental poker shuffles.
+pub const MENTAL_VERSION: u32 = 8;
+
+/// The first version whose clients can ask for a compact encoding.
+pub const COMPACT_VERSION: u32 = 9;
+
+/// The first version whose servers send players the hands they played when asked.
+pub const HISTORY_VERSION: u32 = 10;
+
+/// The first version whose clients are told every player's deadline to act.
+pub const DEADLINE_VERSION: u32 = 11;
+
+/// The first version whose clients are told about uncalled bets given back. Older ones
+/// hear of them as a pot the player won, which moves the chips the same way.
+pub const RETURN_VERSION: u32 = 12;
+
+/// The first version whose clients open the cards dealt to them from a mental shuffle
+/// themselves, rather than leave it to the host. Mental poker tables take no older ones.
+pub const HOLE_KEYS_VERSION: u32 = 13;
+
+/// The versions this build can still speak.
+pub const SUPPORTED_VERSIONS: RangeInclusive<u32> = 2..=PROTOCOL_VERSION;
+
+/// The newest version both this build and a client speaking `min..=max` understand.
+pub fn negotiate(min: u32, max: u32) -> Option<u32> {
+    let newest = max.min(*SUPPORTED_VERSIONS.end());
+    (newest >= min && SUPPORTED_VERSIONS.contains(&newest)).then_some(newest)
+}
+
+/// What a client tells the server.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ClientMessage {
+    /// The first message of every client that wants a seat.
+    Join {
+        min_version: u32,
+        max_version: u32,
+        #[serde(default, skip_serializing_if = "String::is_empty")]
+        table: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        password: Option<String>,
+        /// Lets the client in whatever the password.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        invite: Option<String>,
+        /// How the client would like to be sent messages.
+        #[serde(default, skip_serializing_if = "Encoding::is_json")]
+        encoding: Encoding,
+    },
+    /// Instead of `Join`, by a client taking back the seat its token belongs to.
+    Resume {
+        token: String,
+        min_version: u32,
+        max_version: u32,
+        #[serde(default, skip_serializing_if = "Encoding::is_json")]
+        encoding: Encoding,
+    },
+    Action {
+        action: PokerAction,
+    },
+    Chat {
+        text: String,
+    },
+    /// Instead of `Join`, by a client looking for a table.
+    ListTables,
+    /// Ends sitting out.
+    SitIn,
+    /// The answer to `Ping`.
+    Pong,
+    /// Instead of `Join`, by a client that only wants to watch.
+    Watch {
+        min_version: u32,
+        max_version: u32,
+        #[serde(default, skip_serializing_if = "String::is_empty")]
+        table: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        password: Option<String>,
+    },
+    /// The answer to `Shuffle`.
+    Shuffled {
+        deck: Vec<String>,
+    },
+    /// The answer to `Lock`.
+    Locked {
+        deck: Vec<String>,
+    },
+    /// The answer to `RevealCards`: the client's key to each card asked for, in order.
+    CardKeys {
+        keys: Vec<String>,
+    },
+    /// The answer to `RevealKeys`.
+    Keys {
+        keys: PartyKeys,
+    },
+    /// Asks for the hands of the game so far.
+    History,
+}
+
+impl ClientMessage {
+    /// A `Join` of `table` for the versions this build speaks. An empty name joins the
+    /// first table.
+    pub fn join(table: &str, password: Option<&str>) -> Self {
+        ClientMessage::Join {
+            min_version: *SUPPORTED_VERSIONS.start(),
+            max_version: *SUPPORTED_VERSIONS.end(),
+            table: table.to_owned(),
+            password: password.map(str::to_owned),
+            invite: None,
+            encoding: Encoding::Json,
+        }
+    }
+
+    /// Like `join`, but with an invite instead of a password.
+    pub fn join_invited(table: &str, invite: &str) -> Self {
+        ClientMessage::Join {
+            min_version: *SUPPORTED_VERSIONS.start(),
+            max_version: *SUPPORTED_VERSIONS.end(),
+            table: table.to_owned(),
+            password: None,
+            invite: Some(invite.to_owned()),
+            encoding: Encoding::Json,
+        }
+    }
+
+    /// Like `join`, but to watch the table.
+    pub fn watch(table: &str, password: Option<&str>) -> Self {
+        ClientMessage::Watch {
+            min_version: *SUPPORTED_VERSIONS.start(),
+            max_version: *SUPPORTED_VERSIONS.end(),
+            table: table.to_owned(),
+            password: password.map(str::to_owned),
+        }
+    }
+
+    /// A `Resume` of the seat `token` belongs to. Only servers that resume seats hand out
+    /// tokens, so it asks for those.
+    pub fn resume(token: &str) -> Self {
+        ClientMessage::Resume {
+            token: token.to_owned(),
+            min_version: RESUME_VERSION,
+            max_version: *SUPPORTED_VERSIONS.end(),
+            encoding: Encoding::Json,
+        }
+    }
+
+    /// Asks for messages in `encoding`, if the message takes a seat. Only servers that
+    /// speak `COMPACT_VERSION` will, so it asks for those.
+    pub fn with_encoding(mut self, wanted: Encoding) -> Self {
+        if let ClientMessage::Join {
+            min_version,
+            encoding,
+            ..
+        }
+        | ClientMessage::Resume {
+            min_version,
+            encoding,
+            ..
+        } = &mut self
+        {
+            *encoding = wanted;
+            if wanted != Encoding::Json {
+                *min_version = COMPACT_VERSION;
+            }
+        }
+        self
+    }
+}
+
+/// How messages are written down.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Encoding {
+    /// Text, which every version speaks.
+    #[default]
+    Json,
+    /// Binary and smaller, for slow or metered connections.
+    MessagePack,
+}
+
+impl Encoding {
+    fn is_json(&self) -> bool {
+        *self == Encoding::Json
+    }
+
+    pub fn encode(self, message: &impl Serialize) -> Frame {
+        match self {
+            Encoding::Json => {
+                Frame::Text(serde_json::to_string(message).expect("messages serialize"))
+            }
+            // With field names, since tagged enums can't be read back without them.
+            Encoding::MessagePack => {
+                Frame::Binary(rmp_serde::to_vec_named(message).expect("messages serialize"))
+            }
+        }
+    }
+}
+
+/// Reads a message in whichever encoding it came in.
+pub fn decode<T: DeserializeOwned>(frame: &Frame) -> Result<T, String> {
+    match frame {
+        Frame::Text(text) => serde_json::from_str(text).map_err(|e| e.to_string()),
+        Frame::Binary(bytes) => rmp_serde::from_slice(bytes).map_err(|e| e.to_string()),
+    }
+}
+
+/// The changes that turn the JSON value `from` into `to`, as a JSON merge patch (RFC 7386).
+pub fn merge_patch(from: &Value, to: &Value) -> Value {
+    let (Value::Object(from), Value::Object(to)) = (from, to) else {
+        return to.clone();
+    };
+    let mut patch = Map::new();
+    for (key, value) in to {
+        match from.get(key) {
+            Some(old) if old == value => {}
+            Some(old) => {
+                patch.insert(key.clone(), merge_patch(old, value));
+            }
+            None => {
+                patch.insert(key.clone(), value.clone());
+            }
+        }
+    }
+    for key in from.keys().filter(|key| !to.contains_key(*key)) {
+        patch.insert(key.clone(), Value::Null);
+    }
+    Value::Object(patch)
+}
+
+/// Makes the changes of a JSON merge patch to `target`.
+pub fn apply_merge_patch(target: &mut Value, patch: &Value) {
+    let Value::Object(patch) = patch else {
+        *target = patch.clone();
+        return;
+    };
+    if !target.is_object() {
+        *target = Value::Object(Map::new());
+    }
+    let Value::Object(target) = target else {
+        unreachable!("just made an object");
+    };
+    for (key, value) in patch {
+        if value.is_null() {
+            target.remove(key);
+        } else {
+            apply_merge_patch(target.entry(key.clone()).or_insert(Value::Null), value);
+        }
+    }
+}
+
+/// A move at the table, as the other players see it.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum TableAction {
+    Blind { amount: u32 },
+    Check,
+    Call { amount: u32 },
+    Raise { amount: u32 },
+    Fold,
+}
+
+/// A table as `Tables` lists it.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TableInfo {
+    pub name: String,
+    pub players: usize,
+    /// How many players have taken a seat.
+    pub seated: usize,
+    pub stack: u32,
+    /// Whether joining takes a password.
+    pub password: bool,
+    /// Whether only players with an invite may sit down.
+    #[serde(default)]
+    pub invite_only: bool,
+    /// Whether the players shuffle every deck together.
+    #[serde(default)]
+    pub mental_shuffle: bool,
+    /// Whether joining waits for another player and seats the two at a table of their own.
+    #[serde(default)]
+    pub queue: bool,
+}
+
+/// What the server tells a client.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ServerMessage {
+    /// The answer to `Join` and `Resume`: the seat the client plays, how many seats there
+    /// are, the protocol version agreed on and the token to resume the seat with. Servers
+    /// before `RESUME_VERSION` send no token.
+    Seat {
+        seat: usize,
+        players: usize,
+        version: u32,
+        #[serde(default)]
+        token: String,
+        /// The encoding of everything after, which servers before `COMPACT_VERSION`
+        /// don't send.
+        #[serde(default)]
+        encoding: Encoding,
+    },
+    /// Everything about the hand in progress a resuming client missed: the messages about
+    /// it so far, as its seat saw them, and the table now.
+    Sync {
+        hand: Vec<ServerMessage>,
+        view: SeatView,
+    },
+    /// The client's own hole cards for a new hand.
+    Deal {
+        player: usize,
+        cards: [Card; 2],
+    },
+    /// A player posted a blind or acted.
+    Action {
+        player: usize,
+        street: Street,
+        action: TableAction,
+    },
+    /// New cards on the board.
+    Street {
+        street: Street,
+        cards: Vec<Card>,
+    },
+    /// The best five cards of a player still in the hand at showdown.
+    Showdown {
+        player: usize,
+        hand: Hand,
+    },
+    Returned {
+        player: usize,
+        amount: u32,
+    },
+    Pot {
+        player: usize,
+        amount: u32,
+        showdown: bool,
+    },
+    Eliminated {
+        player: usize,
+    },
+    TimedOut {
+        player: usize,
+    },
+    SatOut {
+        player: usize,
+    },
+    SatIn {
+        player: usize,
+    },
+    /// The table as the client's seat sees it, after every action.
+    Table {
+        view: SeatView,
+    },
+    /// Instead of `Table` to clients on a compact encoding, once they have one: how the
+    /// JSON of the table changed since the last one, as a JSON merge patch.
+    TableChanges {
+        changes: Value,
+    },
+    /// It is the client's turn, and these are the actions the server accepts. Without an
+    /// answer within `seconds`, if given, the server checks or folds for the player.
+    ActionRequest {
+        legal: LegalActions,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        seconds: Option<u64>,
+    },
+    /// Whose turn it is, at tables with a turn timeout, and when the server checks or folds
+    /// for them: at `deadline_ms` milliseconds since the Unix epoch by the server's clock,
+    /// which is `remaining_ms` from when it was sent. Sent to every player whenever the
+    /// deadline is set, so that they all count down to the same moment.
+    Turn {
+        player: usize,
+        deadline_ms: u64,
+        remaining_ms: u64,
+    },
+    Chat {
+        player: usize,
+        text: String,
+    },
+    /// The client's last message was not accepted.
+    Error {
+        message: String,
+    },
+    /// A player can no longer cover the big blind, so the game is over.
+    GameOver,
+    /// The answer to `ListTables`.
+    Tables {
+        tables: Vec<TableInfo>,
+    },
+    /// The answer to `Watch`: how many seats there are, the protocol version agreed on and
+    /// how long after it happened the spectator gets to see everything.
+    Watching {
+        players: usize,
+        version: u32,
+        delay_secs: u64,
+    },
+    /// The table as a spectator sees it, after every action.
+    PublicTable {
+        view: PublicView,
+    },
+    /// Asks for a `Pong`, and tells how long the answer to the last ping took to arrive.
+    Ping {
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        rtt_ms: Option<u64>,
+    },
+    /// Asks for the first pass of a mental shuffle over `deck`.
+    Shuffle {
+        deck: Vec<String>,
+    },
+    /// Asks for the second pass of a mental shuffle over `deck`.
+    Lock {
+        deck: Vec<String>,
+    },
+    /// Asks for the client's keys to the cards at `positions` of the deck, which are dealt
+    /// to the board or to other players.
+    RevealCards {
+        positions: Vec<usize>,
+    },
+    /// The client's two cards, as the deck is locked, with every other player's key to
+    /// each. Only the client's own keys are missing to open them.
+    HoleCards {
+        locked: Vec<String>,
+        keys: Vec<Vec<String>>,
+    },
+    /// Asks for the keys of the client's part in the shuffle, now that the hand is over.
+    RevealKeys,
+    /// How the deck of the hand just over was shuffled, or `None` if the server had to
+    /// shuffle it alone because a player didn't do their part.
+    ShuffleAudit {
+        audit: Option<Audit>,
+    },
+    /// The hand was called off before it was over, because a player didn't hand over
+    /// their keys to the deck, and nobody's chips changed.
+    CalledOff,
+    /// The answer to `History`: every hand of the game finished so far, as the client's
+    /// seat saw it.
+    History {
+        hands: Vec<ArchivedHand>,
+    },
+}
+
+impl ServerMessage {
+    /// The message telling a client about `event`. Blinds are posted before the first
+    /// street is dealt, so they count as preflop.
+    pub fn from_event(event: &GameEvent) -> Self {
+        let action = |player, street, action| ServerMessage::Action {
+            player,
+            street,
+            action,
+        };
+        match event.clone() {
+            GameEvent::HoleCardsDealt { player, cards } => ServerMessage::Deal { player, cards },
+            GameEvent::BlindPosted { player, amount } => {
+                action(player, Street::Preflop, TableAction::Blind { amount })
+            }
+            GameEvent::Checked { player, street } => action(player, street, TableAction::Check),
+            GameEvent::Called {
+                player,
+                street,
+                amount,
+            } => action(player, street, TableAction::Call { amount }),
+            GameEvent::Raised {
+                player,
+                street,
+                amount,
+            } => action(player, street, TableAction::Raise { amount }),
+            GameEvent::Folded { player, street } => action(player, street, TableAction::Fold),
+            GameEvent::StreetDealt { street, cards } => ServerMessage::Street { street, cards },
+            GameEvent::HandShown { player, hand } => ServerMessage::Showdown { player, hand },
+            GameEvent::BetReturned { player, amount } => ServerMessage::Returned { player, amount },
+            GameEvent::PotAwarded {
+                player,
+                amount,
+                showdown,
+            } => ServerMessage::Pot {
+                player,
+                amount,
+                showdown,
+            },
+            GameEvent::PlayerEliminated { player } => ServerMessage::Eliminated { player },
+            GameEvent::TimedOut { player } => ServerMessage::TimedOut { player },
+            GameEvent::SatOut { player } => ServerMessage::SatOut { player },
+            GameEvent::SatIn { player } => ServerMessage::SatIn { player },
+        }
+    }
+
+    /// The event a message tells about, if it tells about one.
+    pub fn event(&self) -> Option<GameEvent> {
+        Some(match *self {
+            ServerMessage::Deal { player, cards } => GameEvent::HoleCardsDealt { player, cards },
+            ServerMessage::Action {
+                player,
+                street,
+                action,
+            } => match action {
+                TableAction::Blind { amount } => GameEvent::BlindPosted { player, amount },
+                TableAction::Check => GameEvent::Checked { player, street },
+                TableAction::Call { amount } => GameEvent::Called {
+                    player,
+                    street,
+                    amount,
+                },
+                TableAction::Raise { amount } => GameEvent::Raised {
+                    player,
+                    street,
+                    amount,
+                },
+                TableAction::Fold => GameEvent::Folded { player, street },
+            },
+            ServerMessage::Street { street, ref cards } => GameEvent::StreetDealt {
+                street,
+                cards: cards.clone(),
+            },
+            ServerMessage::Showdown { player, hand } => GameEvent::HandShown { player, hand },
+            ServerMessage::Returned { player, amount } => GameEvent::BetReturned { player, amount },
+            ServerMessage::Pot {
+                player,
+                amount,
+                showdown,
+            } => GameEvent::PotAwarded {
+                player,
+                amount,
+                showdown,
+            },
+            ServerMessage::Eliminated { player } => GameEvent::PlayerEliminated { player },
+            ServerMessage::TimedOut { player } => GameEvent::TimedOut { player },
+            ServerMessage::SatOut { player } => GameEvent::SatOut { player },
+            ServerMessage::SatIn { player } => GameEvent::SatIn { player },
+            _ => return None,
+        })
+    }
+
+    /// The message as a client speaking `version` understands it.
+    pub fn for_version(&self, version: u32) -> ServerMessage {
+        match self {
+            &ServerMessage::Returned { player, amount } if version < RETURN_VERSION => {
+                ServerMessage::Pot {
+                    player,
+                    amount,
+                    showdown: false,
+                }
+            }
+            ServerMessage::Sync { hand, view } => ServerMessage::Sync {
+                hand: hand.iter().map(|m| m.for_version(version)).collect(),
+                view: view.clone(),
+            },
+            message => message.clone(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core_engine::Deck;
+    use crate::mental;
+    use crate::{GameState, TurnResult};
+    use rand::rngs::StdRng;
+    use rand::{Rng, SeedableRng};
+
+    /// A message of every kind a client sends.
+    fn client_messages() -> Vec<ClientMessage> {
+        let deck = mental::plain_deck();
+        vec![
+            ClientMessage::join("high", Some("stakes")).with_encoding(Encoding::MessagePack),
+            ClientMessage::Resume {
+                token: "7-abc.def".to_owned(),
+                min_version: RESUME_VERSION,
+                max_version: PROTOCOL_VERSION,
+                encoding: Encoding::Json,
+            },
+            ClientMessage::watch("", None),
+            ClientMessage::ListTables,
+            ClientMessage::Action {
+                action: PokerAction::Raise(10),
+            },
+            ClientMessage::Chat {
+                text: "nice hand".to_owned(),
+            },
+            ClientMessage::SitIn,
+            ClientMessage::Pong,
+            ClientMessage::Shuffled { deck: deck.clone() },
+            ClientMessage::Locked { deck: deck.clone() },
+            ClientMessage::CardKeys {
+                keys: deck[..2].to_vec(),
+            },
+            ClientMessage::Keys {
+                keys: PartyKeys {
+                    shuffle: deck[0].clone(),
+                    cards: deck,
+                },
+            },
+            ClientMessage::History,
+        ]
+    }
+
+    /// Changes `bytes` a little at random, the way a broken or hostile client might.
+    fn mutate(bytes: &mut Vec<u8>, rng: &mut impl Rng) {
+        for _ in 0..rng.random_range(1..=4) {
+            let at = rng.random_range(0..=bytes.len());
+            match rng.random_range(0..6) {
+                0 if at < bytes.len() => bytes[at] ^= 1 << rng.random_range(0..8),
+                1 => bytes.insert(at, rng.random()),
+                2 if at < bytes.len() => {
+                    bytes.remove(at);
+                }
+                3 => bytes.truncate(at),
+                // Lengths and numbers as big as they go.
+                4 => bytes
+                    .splice(at..at, [0xdd, 0xff, 0xff, 0xff, 0xff])
+                    .for_each(drop),
+                5 => bytes
+                    .splice(at..at, *b"99999999999999999999")
+                    .for_each(drop),
+                _ => {}
+            }
+        }
+    }
+
+    #[test]
+    fn should_decode_mutated_messages_without_panicking() {
+        // Seeded, so that a failure can be replayed.
+        let mut rng = StdRng::seed_from_u64(186);
+        for message in client_messages() {
+            for encoding in [Encoding::Json, Encoding::MessagePack] {
+                let frame = encoding.encode(&message);
+                assert_eq!(decode::<ClientMessage>(&frame).as_ref(), Ok(&message));
+                for _ in 0..2000 {
+                    let frame = match &frame {
+                        Frame::Text(text) => {
+                            let mut bytes = text.clone().into_bytes();
+                            mutate(&mut bytes, &mut rng);
+                            Frame::Text(String::from_utf8_lossy(&bytes).into_owned())
+                        }
+                        Frame::Binary(bytes) => {
+                            let mut bytes = bytes.clone();
+                            mutate(&mut bytes, &mut rng);
+                            Frame::Binary(bytes)
+                        }
+                    };
+                    // Whatever still decodes has to survive being sent on.
+                    if let Ok(decoded) = decode::<ClientMessage>(&frame) {
+                        let again = decode::<ClientMessage>(&encoding.encode(&decoded));
+                        assert_eq!(again, Ok(decoded));
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn should_agree_on_the_newest_shared_version() {
+        assert_eq!(negotiate(1, PROTOCOL_VERSION + 5), Some(PROTOCOL_VERSION));
+        assert_eq!(negotiate(0, PROTOCOL_VERSION), Some(PROTOCOL_VERSION));
+        assert_eq!(negotiate(PROTOCOL_VERSION + 1, PROTOCOL_VERSION + 2), None);
+        // Version 1 clients expect snapshots.
+        assert_eq!(negotiate(1, 1), None);
+    }
+
+    #[test]
+    fn should_tell_every_event_as_a_message_and_back() {
+        let (mut hs, _) = GameState::init(2)
+            .unwrap()
+            .start_play_hand(Deck::ordered_deck());
+        hs.play_action(PokerAction::Raise(4)).unwrap();
+        while let TurnResult::NextPlayer(_) = hs.play_action(PokerAction::CallOrCheck).unwrap() {}
+
+        for event in hs.events() {
+            let message = ServerMessage::from_event(event);
+            let json = serde_json::to_string(&message).unwrap();
+            let message: ServerMessage = serde_json::from_str(&json).unwrap();
+            assert_eq!(message.event().as_ref(), Some(event));
+        }
+    }
+
+    #[test]
+    fn should_read_messages_by_their_type() {
+        let message: ClientMessage =
+            serde_json::from_str(r#"{"type":"action","action":{"Raise":10}}"#).unwrap();
+        assert_eq!(
+            message,
+            ClientMessage::Action {
+                action: PokerAction::Raise(10)
+            }
+        );
+        let json = serde_json::to_string(&ClientMessage::join("", None)).unwrap();
+        assert_eq!(json, r#"{"type":"join","min_version":2,"max_version":13}"#);
+        let json = serde_json::to_string(&ClientMessage::join("high", Some("stakes"))).unwrap();
+        assert_eq!(
+            json,
+            r#"{"type":"join","min_version":2,"max_version":13,"table":"high","password":"stakes"}"#
+        );
+        let seat: ServerMessage =
+            serde_json::from_str(r#"{"type":"seat","seat":1,"players":2,"version":2}"#).unwrap();
+        assert!(matches!(seat, ServerMessage::Seat { token, .. } if token.is_empty()));
+    }
+
+    #[test]
+    fn should_send_compact_clients_the_changes_to_the_table() {
+        let (mut hs, _) = GameState::init(3)
+            .unwrap()
+            .start_play_hand(Deck::shuffled_deck());
+        let before = serde_json::to_value(hs.seat_view(0)).unwrap();
+        hs.play_action(PokerAction::Raise(6)).unwrap();
+        let after = serde_json::to_value(hs.seat_view(0)).unwrap();
+
+        let changes = merge_patch(&before, &after);
+        let frame = Encoding::MessagePack.encode(&ServerMessage::TableChanges { changes });
+        let Ok(ServerMessage::TableChanges { changes }) = decode(&frame) else {
+            panic!("expected the changes back");
+        };
+        let mut view = before;
+        apply_merge_patch(&mut view, &changes);
+        assert_eq!(view, after);
+
+        let table = Encoding::Json.encode(&ServerMessage::Table {
+            view: hs.seat_view(0),
+        });
+        let size = |frame: &Frame| match frame {
+            Frame::Text(text) => text.len(),
+            Frame::Binary(bytes) => bytes.len(),
+        };
+        assert!(size(&frame) * 3 < size(&table));
+    }
+}
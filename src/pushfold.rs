@@ -0,0 +1,349 @@
+use std::sync::OnceLock;
+
+use rand::prelude::*;
+
+use crate::best_hand_from_cards;
+use crate::bots::{check_or_fold, raise_or_call_by, BotContext, BotPlayer, RuleBot};
+use crate::charts::{class, label};
+use crate::core_engine::{Card, Deck, Suit};
+use crate::{PokerAction, BIG_BLIND_CHIPS};
+
+/// The table used by `PushFoldBot`, regenerated with `poker-tui pushfold-table`.
+const EMBEDDED_TABLE: &str = include_str!("../charts/pushfold.txt");
+
+/// Stacks up to this many big blinds are played push or fold.
+pub const PUSH_FOLD_MAX_BB: f64 = 15.0;
+
+/// Effective stacks the table is solved for, in half big blinds up to 20.
+const SOLVED_STACKS: std::ops::RangeInclusive<u32> = 2..=40;
+const FICTITIOUS_PLAY_ROUNDS: usize = 300;
+
+/// The 169 distinct starting hands as (high card, low card, suited), strongest ranks first.
+fn classes() -> Vec<(u8, u8, bool)> {
+    let mut classes = vec![];
+    for high in (2..=14).rev() {
+        for low in (2..=high).rev() {
+            if high == low {
+                classes.push((high, low, false));
+            } else {
+                classes.push((high, low, true));
+                classes.push((high, low, false));
+            }
+        }
+    }
+    classes
+}
+
+/// How many of the 1326 two-card combinations fall in a class.
+fn combos((high, low, suited): (u8, u8, bool)) -> f64 {
+    match (high == low, suited) {
+        (true, _) => 6.0,
+        (false, true) => 4.0,
+        (false, false) => 12.0,
+    }
+}
+
+/// Deals random cards of `class` that are not in `used`.
+fn deal_class(
+    (high, low, suited): (u8, u8, bool),
+    used: &[Card],
+    rng: &mut impl Rng,
+) -> (Card, Card) {
+    const SUITS: [Suit; 4] = [Suit::Hearts, Suit::Spades, Suit::Diamonds, Suit::Clubs];
+    loop {
+        let first = *SUITS.choose(rng).unwrap();
+        let second = if suited {
+            first
+        } else {
+            *SUITS.iter().filter(|&&s| s != first).choose(rng).unwrap()
+        };
+        let cards = (
+            Card {
+                suit: first,
+                value: high,
+            },
+            Card {
+                suit: second,
+                value: low,
+            },
+        );
+        if !used.contains(&cards.0) && !used.contains(&cards.1) {
+            return cards;
+        }
+    }
+}
+
+/// Preflop all-in equity of every class against every other, each pair from `samples`
+/// random boards. `equities[i][j]` is the share class `i` wins against class `j`.
+pub fn equity_matrix(samples: usize) -> Vec<Vec<f64>> {
+    let classes = classes();
+    let mut rng = rand::rng();
+    let mut equities = vec![vec![0.5; classes.len()]; classes.len()];
+    for i in 0..classes.len() {
+        for j in i + 1..classes.len() {
+            let mut share = 0.0;
+            for _ in 0..samples {
+                let hero = deal_class(classes[i], &[], &mut rng);
+                let villain = deal_class(classes[j], &[hero.0, hero.1], &mut rng);
+                let used = [hero.0, hero.1, villain.0, villain.1];
+                let mut deck: Vec<Card> = Deck::ordered_deck()
+                    .cards()
+                    .iter()
+                    .filter(|c| !used.contains(c))
+                    .copied()
+                    .collect();
+                let (board, _) = deck.partial_shuffle(&mut rng, 5);
+                let best = |(c1, c2): (Card, Card)| {
+                    best_hand_from_cards([c1, c2, board[0], board[1], board[2], board[3], board[4]])
+                };
+                share += match best(hero).cmp(&best(villain)) {
+                    std::cmp::Ordering::Greater => 1.0,
+                    std::cmp::Ordering::Equal => 0.5,
+                    std::cmp::Ordering::Less => 0.0,
+                };
+            }
+            equities[i][j] = share / samples as f64;
+            equities[j][i] = 1.0 - equities[i][j];
+        }
+    }
+    equities
+}
+
+/// Heads-up push or fold: the small blind (0.5bb) moves all-in or folds, the big blind
+/// (1bb) calls or folds. Returns how often each class pushes and calls at equilibrium,
+/// found by fictitious play.
+fn solve_stack(equities: &[Vec<f64>], weights: &[f64], stack: f64) -> (Vec<f64>, Vec<f64>) {
+    let hands = weights.len();
+    let mut push = vec![1.0; hands];
+    let mut call = vec![1.0; hands];
+    // Equity of a class against a range, weighted by the combinations the range holds.
+    let against = |hand: usize, range: &[f64]| {
+        let total: f64 = (0..hands).map(|j| range[j] * weights[j]).sum();
+        let won: f64 = (0..hands)
+            .map(|j| range[j] * weights[j] * equities[hand][j])
+            .sum();
+        (won / total.max(f64::EPSILON), total)
+    };
+
+    for round in 1..=FICTITIOUS_PLAY_ROUNDS {
+        let step = 1.0 / (round + 1) as f64;
+        for (j, calls) in call.iter_mut().enumerate() {
+            // Calling risks the stack for the pot, folding gives up the big blind.
+            let (equity, _) = against(j, &push);
+            let best = if equity * 2.0 * stack - stack > -1.0 {
+                1.0
+            } else {
+                0.0
+            };
+            *calls += step * (best - *calls);
+        }
+        for (i, pushes) in push.iter_mut().enumerate() {
+            let (equity, called) = against(i, &call);
+            let called = called / 1326.0;
+            let ev = (1.0 - called) + called * (equity * 2.0 * stack - stack);
+            let best = if ev > -0.5 { 1.0 } else { 0.0 };
+            *pushes += step * (best - *pushes);
+        }
+    }
+    (push, call)
+}
+
+/// For every starting hand, the largest effective stack in big blinds at which the small
+/// blind pushes it and the big blind calls an all-in with it.
+#[derive(Clone, Debug, PartialEq)]
+pub struct PushFoldTable {
+    push: Vec<f64>,
+    call: Vec<f64>,
+}
+
+impl PushFoldTable {
+    pub fn solve(equities: &[Vec<f64>]) -> Self {
+        let weights: Vec<f64> = classes().into_iter().map(combos).collect();
+        let mut push = vec![0.0; weights.len()];
+        let mut call = vec![0.0; weights.len()];
+        for half_bbs in SOLVED_STACKS {
+            let stack = half_bbs as f64 / 2.0;
+            let (pushes, calls) = solve_stack(equities, &weights, stack);
+            for i in 0..weights.len() {
+                if pushes[i] >= 0.5 {
+                    push[i] = stack;
+                }
+                if calls[i] >= 0.5 {
+                    call[i] = stack;
+                }
+            }
+        }
+        PushFoldTable { push, call }
+    }
+
+    /// The table that ships with the game, read once.
+    pub fn embedded() -> &'static Self {
+        static TABLE: OnceLock<PushFoldTable> = OnceLock::new();
+        TABLE.get_or_init(|| PushFoldTable::parse(EMBEDDED_TABLE).expect("the table is valid"))
+    }
+
+    /// Reads lines of `HAND PUSH CALL` covering every starting hand, skipping `#` comments.
+    pub fn parse(text: &str) -> Option<Self> {
+        let classes = classes();
+        let mut push = vec![None; classes.len()];
+        let mut call = vec![None; classes.len()];
+        for line in text.lines() {
+            let line = line.split('#').next().unwrap_or_default();
+            let mut words = line.split_ascii_whitespace();
+            let Some(hand) = words.next() else {
+                continue;
+            };
+            let i = classes.iter().position(|&c| label(c) == hand)?;
+            push[i] = Some(words.next()?.parse().ok()?);
+            call[i] = Some(words.next()?.parse().ok()?);
+        }
+        Some(PushFoldTable {
+            push: push.into_iter().collect::<Option<_>>()?,
+            call: call.into_iter().collect::<Option<_>>()?,
+        })
+    }
+
+    pub fn to_text(&self) -> String {
+        let mut text =
+            "# Heads-up push/fold thresholds, generated by `poker-tui pushfold-table`.\n\
+             # HAND PUSH CALL: the largest effective stack in big blinds at which the small\n\
+             # blind moves all-in with HAND, and the big blind calls an all-in with it.\n"
+                .to_owned();
+        for (i, class) in classes().into_iter().enumerate() {
+            text += &format!("{} {} {}\n", label(class), self.push[i], self.call[i]);
+        }
+        text
+    }
+
+    fn index(cards: (Card, Card)) -> usize {
+        let hand = class(cards);
+        classes().iter().position(|&c| c == hand).unwrap()
+    }
+
+    pub fn push_up_to(&self, cards: (Card, Card)) -> f64 {
+        self.push[Self::index(cards)]
+    }
+
+    pub fn call_up_to(&self, cards: (Card, Card)) -> f64 {
+        self.call[Self::index(cards)]
+    }
+}
+
+/// Moves all-in or folds preflop by the `PushFoldTable` once stacks are short, otherwise
+/// plays like a `RuleBot`. The table is solved heads-up, at bigger tables it is only an
+/// approximation.
+pub struct PushFoldBot;
+
+impl BotPlayer for PushFoldBot {
+    fn decide(&mut self, view: &BotContext) -> PokerAction {
+        self.decide_explained(view).0
+    }
+
+    fn decide_explained(&mut self, view: &BotContext) -> (PokerAction, Option<String>) {
+        let effective = view.effective_stack as f64 / BIG_BLIND_CHIPS as f64;
+        if !view.board.is_empty() || effective > PUSH_FOLD_MAX_BB {
+            return RuleBot.decide_explained(view);
+        }
+
+        let table = PushFoldTable::embedded();
+        let all_in = raise_or_call_by(view, view.stacks[view.player]);
+        let unopened = view.bets.iter().all(|&b| b <= BIG_BLIND_CHIPS);
+        let (limit, verb) = if unopened {
+            (table.push_up_to(view.cards), "push")
+        } else {
+            (table.call_up_to(view.cards), "call")
+        };
+        let action = match (limit >= effective, unopened) {
+            (true, _) => all_in,
+            (false, true) => check_or_fold(view),
+            (false, false) => PokerAction::Fold,
+        };
+        let reason = format!(
+            "{} can {verb} up to {limit}bb, effective stack is {effective}bb",
+            label(class(view.cards))
+        );
+        (action, Some(reason))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bots::PlayerView;
+    use crate::events::Street;
+
+    fn cards(text: &str) -> (Card, Card) {
+        let mut cards = text
+            .split_ascii_whitespace()
+            .map(|c| Card::try_from(c).unwrap());
+        (cards.next().unwrap(), cards.next().unwrap())
+    }
+
+    fn view(hand: &str, stacks: Vec<u32>, bets: Vec<u32>) -> BotContext {
+        let expected_call = bets.iter().max().unwrap() - bets[0];
+        BotContext::from(PlayerView {
+            player: 0,
+            button: 0,
+            cards: cards(hand),
+            board: vec![],
+            street: Street::Preflop,
+            expected_call,
+            min_raise: expected_call + BIG_BLIND_CHIPS,
+            active: vec![true; stacks.len()],
+            stacks,
+            bets,
+            pot: 0,
+        })
+    }
+
+    #[test]
+    fn should_list_every_starting_hand_once() {
+        let classes = classes();
+        assert_eq!(classes.len(), 169);
+        assert_eq!(classes.iter().copied().map(combos).sum::<f64>(), 1326.0);
+        assert_eq!(label(classes[0]), "AA");
+        assert_eq!(label(classes[1]), "AKs");
+    }
+
+    #[test]
+    fn should_read_back_the_embedded_table() {
+        let table = PushFoldTable::embedded();
+        assert_eq!(PushFoldTable::parse(&table.to_text()).as_ref(), Some(table));
+        assert_eq!(PushFoldTable::parse("AA 20 20"), None);
+
+        assert_eq!(table.push_up_to(cards("S14 H14")), 20.0);
+        assert!(table.push_up_to(cards("S7 H2")) < 3.0);
+        assert!(table.call_up_to(cards("S14 H13")) > table.call_up_to(cards("S9 H8")));
+    }
+
+    #[test]
+    fn should_push_everything_when_every_hand_is_a_coin_flip() {
+        let weights: Vec<f64> = classes().into_iter().map(combos).collect();
+        let flips = vec![vec![0.5; weights.len()]; weights.len()];
+        let (push, call) = solve_stack(&flips, &weights, 10.0);
+        assert!(push.iter().chain(&call).all(|&f| f > 0.99));
+    }
+
+    #[test]
+    fn should_push_or_fold_short_stacks() {
+        let mut bot = PushFoldBot;
+        let short = vec![10, 10];
+        assert_eq!(
+            bot.decide(&view("S14 H13", short.clone(), vec![1, 2])),
+            PokerAction::Raise(10)
+        );
+        assert_eq!(
+            bot.decide(&view("S7 H2", vec![30, 30], vec![1, 2])),
+            PokerAction::Fold
+        );
+        // Facing an all-in.
+        assert_eq!(
+            bot.decide(&view("S14 H14", vec![20, 0], vec![2, 30])),
+            PokerAction::CallOrCheck
+        );
+        assert_eq!(
+            bot.decide(&view("S7 H2", vec![20, 0], vec![2, 30])),
+            PokerAction::Fold
+        );
+    }
+}
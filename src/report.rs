@@ -0,0 +1,222 @@
+//! A report over a batch of stored hands, for `poker-tui analyze`: how each position and
+//! each player did, how often hands went to showdown and which pots were the biggest.
+
+use crate::charts::Position;
+use crate::equity::{all_in, ALL_IN_SAMPLES};
+use crate::events::GameEvent;
+use crate::stats::{percentage, SessionStats};
+use crate::HandState;
+
+/// How many of the biggest pots the report lists.
+pub const BIGGEST_POTS: usize = 5;
+
+const POSITIONS: [Position; 4] = [
+    Position::Early,
+    Position::Middle,
+    Position::Late,
+    Position::Blinds,
+];
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Record {
+    pub hands: u32,
+    /// Hands where some of the pot went to this position or player.
+    pub won: u32,
+    pub net_chips: i64,
+    pub showdowns: u32,
+    pub showdowns_won: u32,
+    /// Chips won in all-ins before the river beyond what the equity was worth.
+    pub all_in_luck: f64,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Pot {
+    /// Where the hand came from, like the file and its number in it.
+    pub hand: String,
+    pub chips: u32,
+    pub winners: Vec<usize>,
+    pub showdown: bool,
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct HistoryReport {
+    hands: u32,
+    showdowns: u32,
+    positions: [Record; 4],
+    players: Vec<Record>,
+    biggest_pots: Vec<Pot>,
+}
+
+impl HistoryReport {
+    pub fn hands(&self) -> u32 {
+        self.hands
+    }
+
+    pub fn position(&self, position: Position) -> &Record {
+        &self.positions[position as usize]
+    }
+
+    /// The records by seat, as many as the most players any hand had.
+    pub fn players(&self) -> &[Record] {
+        &self.players
+    }
+
+    /// The biggest pots, biggest first.
+    pub fn biggest_pots(&self) -> &[Pot] {
+        &self.biggest_pots
+    }
+
+    /// Adds a finished hand, `hand` saying where it came from.
+    pub fn record_hand(&mut self, hand: String, hs: &HandState) {
+        let players = hs.history().stacks.len();
+        let mut stats = SessionStats::init(players);
+        stats.record_hand(hs.events());
+        let results = &stats.results()[0];
+        if self.players.len() < players {
+            self.players.resize(players, Record::default());
+        }
+
+        let shown: Vec<_> = hs
+            .events()
+            .iter()
+            .filter_map(|e| match e {
+                GameEvent::HandShown { player, .. } => Some(*player),
+                _ => None,
+            })
+            .collect();
+        self.hands += 1;
+        self.showdowns += u32::from(!shown.is_empty());
+        let button = hs.button();
+        let luck = all_in(hs, ALL_IN_SAMPLES).map_or(vec![0.0; players], |a| a.luck);
+        for (seat, result) in results.iter().enumerate() {
+            let position = Position::at(seat, button, players) as usize;
+            for record in [&mut self.positions[position], &mut self.players[seat]] {
+                record.hands += 1;
+                record.won += u32::from(result.won > 0);
+                record.net_chips += result.net_chips;
+                record.showdowns += u32::from(shown.contains(&seat));
+                record.showdowns_won += u32::from(result.showdown_won);
+                record.all_in_luck += luck[seat];
+            }
+        }
+
+        let pot = Pot {
+            hand,
+            chips: results.iter().map(|r| r.won).sum(),
+            winners: (0..players).filter(|&p| results[p].won > 0).collect(),
+            showdown: !shown.is_empty(),
+        };
+        let at = self.biggest_pots.partition_point(|p| p.chips >= pot.chips);
+        if at < BIGGEST_POTS {
+            self.biggest_pots.insert(at, pot);
+            self.biggest_pots.truncate(BIGGEST_POTS);
+        }
+    }
+
+    pub fn to_text(&self) -> String {
+        let mut lines = vec![format!(
+            "{} hands, {} went to showdown ({:.0}%)",
+            self.hands,
+            self.showdowns,
+            percentage(self.showdowns, self.hands)
+        )];
+
+        lines.push(String::new());
+        lines.push("Position  Hands   Won  Net chips  Per hand".to_owned());
+        for position in POSITIONS {
+            let record = self.position(position);
+            if record.hands > 0 {
+                lines.push(format!(
+                    "{:<8} {:>6} {:>4.0}% {:>+10} {:>+9.2}",
+                    position.name(),
+                    record.hands,
+                    percentage(record.won, record.hands),
+                    record.net_chips,
+                    record.net_chips as f64 / record.hands as f64
+                ));
+            }
+        }
+
+        lines.push(String::new());
+        lines.push(
+            "Player  Hands   Won  Showdowns  Won at showdown  Net chips  All-in EV".to_owned(),
+        );
+        for (seat, record) in self.players.iter().enumerate() {
+            lines.push(format!(
+                "{:>6} {:>6} {:>4.0}% {:>9.0}% {:>15.0}% {:>+10} {:>+10.0}",
+                seat,
+                record.hands,
+                percentage(record.won, record.hands),
+                percentage(record.showdowns, record.hands),
+                percentage(record.showdowns_won, record.showdowns),
+                record.net_chips,
+                record.net_chips as f64 - record.all_in_luck
+            ));
+        }
+
+        lines.push(String::new());
+        lines.push("Biggest pots".to_owned());
+        for pot in &self.biggest_pots {
+            let winners: Vec<_> = pot.winners.iter().map(|p| format!("Player {p}")).collect();
+            let how = if pot.showdown {
+                "at showdown"
+            } else {
+                "without a showdown"
+            };
+            lines.push(format!(
+                "{:>6} chips  {}, won by {} {how}",
+                pot.chips,
+                pot.hand,
+                winners.join(" and ")
+            ));
+        }
+        lines.join("\n") + "\n"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core_engine::Deck;
+    use crate::PokerAction::*;
+    use crate::{GameState, TurnResult::NextPlayer};
+
+    #[test]
+    fn should_add_up_hands_by_position_and_player() {
+        let mut report = HistoryReport::default();
+        let mut gs = GameState::init(3).unwrap();
+        for hand in 0..3 {
+            let (mut hs, _) = gs.start_play_hand(Deck::ordered_deck());
+            if hand == 1 {
+                while let NextPlayer(_) = hs.play_action(CallOrCheck).unwrap() {}
+            } else {
+                hs.play_action(Raise(4)).unwrap();
+                hs.play_action(Fold).unwrap();
+                hs.play_action(Fold).unwrap();
+            }
+            report.record_hand(format!("hand {hand}"), &hs);
+            gs = gs.apply_played_hand(hs);
+        }
+
+        assert_eq!(report.hands(), 3);
+        assert_eq!(report.players().len(), 3);
+        assert!(report.players().iter().all(|r| r.hands == 3));
+        let net: i64 = report.players().iter().map(|r| r.net_chips).sum();
+        assert_eq!(net, 0);
+        // Three-handed the big blind sits right before the button, so it plays late too.
+        assert_eq!(report.position(Position::Late).hands, 6);
+        assert_eq!(report.position(Position::Late).won, 4);
+        assert_eq!(report.position(Position::Blinds).hands, 3);
+        assert_eq!(report.position(Position::Blinds).showdowns, 1);
+
+        let pots: Vec<_> = report.biggest_pots().iter().map(|p| p.chips).collect();
+        // The raises nobody called are given back, and the board splits the showdown.
+        assert_eq!(pots, [6, 5, 5]);
+        assert_eq!(report.biggest_pots()[0].hand, "hand 1");
+        assert!(report.biggest_pots()[0].showdown);
+        assert_eq!(report.biggest_pots()[0].winners.len(), 3);
+        assert!(report
+            .to_text()
+            .starts_with("3 hands, 1 went to showdown (33%)"));
+    }
+}
@@ -0,0 +1,231 @@
+use serde::de::IgnoredAny;
+use serde::{Deserialize, Serialize};
+
+use crate::schema::Schema;
+use crate::{GameState, HandState, TurnResult, BIG_BLIND_CHIPS, SMALL_BLIND_CHIPS};
+
+const SAVE: Schema = Schema {
+    name: "save",
+    version: 1,
+    migrations: &[],
+};
+
+/// A game as `GameState::to_json` writes it, with `H` a `HandState` or a reference to one.
+#[derive(Serialize, Deserialize)]
+struct SavedGame<H> {
+    /// The stacks before the hand in progress, if there is one.
+    stacks: Vec<u32>,
+    /// The seat that posts the big blind, which moves one seat on every hand.
+    big_blind: usize,
+    /// The small and the big blind in chips, the same for every hand.
+    blinds: [u32; 2],
+    /// The seats without chips left.
+    eliminated: Vec<usize>,
+    hand: Option<H>,
+    /// Who plays each seat, if the game kept it, in whatever form it writes it down.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    seats: Option<String>,
+}
+
+impl GameState {
+    /// Serializes everything about the game, with the hand in progress if there is one,
+    /// down to the cards left in its deck.
+    pub fn to_json(&self, hand: Option<&HandState>) -> String {
+        self.save(hand, None)
+    }
+
+    /// Like `to_json`, also keeping `seats`, who plays each seat, for `seats_from_json`.
+    pub fn to_json_with_seats(&self, hand: Option<&HandState>, seats: &str) -> String {
+        self.save(hand, Some(seats.to_owned()))
+    }
+
+    fn save(&self, hand: Option<&HandState>, seats: Option<String>) -> String {
+        let saved = SavedGame {
+            stacks: self.chips.clone(),
+            big_blind: self.big_blind,
+            blinds: [SMALL_BLIND_CHIPS, BIG_BLIND_CHIPS],
+            eliminated: self.eliminated(),
+            hand,
+            seats,
+        };
+        SAVE.stamp(&saved).to_string()
+    }
+
+    /// Who plays each seat of a game written by `to_json_with_seats`, `None` for other
+    /// saves.
+    pub fn seats_from_json(json: &str) -> Option<String> {
+        let saved: SavedGame<IgnoredAny> = SAVE.read(json).ok()?;
+        saved.seats
+    }
+
+    /// Restores a game written by `to_json`. The hand in progress has to be the one its
+    /// own history plays out to, so a save that was tampered with or cut short is refused
+    /// rather than dealt from.
+    pub fn from_json(json: &str) -> Result<(Self, Option<HandState>), String> {
+        let saved: SavedGame<HandState> = SAVE.read(json)?;
+        let players = saved.stacks.len();
+        if players < 2 || saved.big_blind >= players {
+            return Err("a game needs at least 2 players and one of them on the big blind".into());
+        }
+        if saved.blinds != [SMALL_BLIND_CHIPS, BIG_BLIND_CHIPS] {
+            return Err(format!(
+                "only blinds of {SMALL_BLIND_CHIPS}/{BIG_BLIND_CHIPS} are played"
+            ));
+        }
+        let gs = GameState::from_stacks(saved.stacks, saved.big_blind);
+        if saved.eliminated != gs.eliminated() {
+            return Err("the eliminated players don't match the stacks".into());
+        }
+        let Some(hand) = saved.hand else {
+            return Ok((gs, None));
+        };
+        let history = &hand.history;
+        if history.stacks != gs.chips || history.big_blind != gs.big_blind {
+            return Err("the hand in progress wasn't dealt from these stacks".into());
+        }
+        let cards = &history.deck;
+        let distinct = cards
+            .iter()
+            .enumerate()
+            .all(|(i, card)| (2..=14).contains(&card.value) && !cards[..i].contains(card));
+        if !distinct || cards.len() < 2 * players + 5 {
+            return Err("the hand in progress wasn't dealt from a deck".into());
+        }
+        let mut replayed = history.start();
+        for action in &history.actions {
+            match replayed.play_action(*action) {
+                Ok(TurnResult::NextPlayer(_)) => {}
+                Ok(TurnResult::WonHand(_)) => return Err("the hand is already over".into()),
+                Err(_) => return Err("the hand in progress has an illegal action".into()),
+            }
+        }
+        if serde_json::to_value(&replayed).ok() != serde_json::to_value(&hand).ok() {
+            return Err("the hand in progress doesn't match its history".into());
+        }
+        Ok((gs, Some(hand)))
+    }
+
+    fn eliminated(&self) -> Vec<usize> {
+        (0..self.players).filter(|&p| self.chips[p] == 0).collect()
+    }
+
+    /// Serializes the stacks and button position between hands. A hand in progress is not
+    /// part of the save; restoring deals a fresh hand with the same stacks.
+    pub fn to_text(&self) -> String {
+        let stacks = self
+            .chips
+            .iter()
+            .map(|c| c.to_string())
+            .collect::<Vec<_>>()
+            .join(" ");
+        format!("stacks {stacks}\nbig_blind {}\n", self.big_blind)
+    }
+
+    pub fn from_text(text: &str) -> Option<Self> {
+        let mut chips = None;
+        let mut big_blind = None;
+
+        for line in text.lines().filter(|l| !l.trim().is_empty()) {
+            let (key, rest) = line.split_once(' ')?;
+            match key {
+                "stacks" => {
+                    chips = Some(
+                        rest.split_ascii_whitespace()
+                            .map(|s| s.parse().ok())
+                            .collect::<Option<Vec<u32>>>()?,
+                    )
+                }
+                "big_blind" => big_blind = Some(rest.trim().parse().ok()?),
+                _ => return None,
+            }
+        }
+
+        let chips = chips?;
+        let big_blind = big_blind?;
+        let players = chips.len();
+        if players < 2 || big_blind >= players {
+            return None;
+        }
+        Some(GameState::from_stacks(chips, big_blind))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core_engine::Deck;
+    use crate::PokerAction::*;
+    use crate::TurnResult::NextPlayer;
+
+    #[test]
+    fn should_restore_saved_game() {
+        let gs = GameState::init(3).unwrap();
+        let (mut hs, _) = gs.start_play_hand(Deck::ordered_deck());
+        hs.play_action(Raise(10)).unwrap();
+        hs.play_action(Fold).unwrap();
+        hs.play_action(Fold).unwrap();
+        let gs = gs.apply_played_hand(hs);
+
+        let restored = GameState::from_text(&gs.to_text()).unwrap();
+
+        assert_eq!(restored.to_text(), gs.to_text());
+        for player in 0..3 {
+            assert_eq!(restored.current_chips(player), gs.current_chips(player));
+        }
+    }
+
+    #[test]
+    fn should_restore_hand_in_progress_from_json() {
+        let gs = GameState::init_with_stack(3, 50).unwrap();
+        let (mut first, _) = gs.start_play_hand(Deck::shuffled_deck());
+        first.play_action(Fold).unwrap();
+        first.play_action(Fold).unwrap();
+        let gs = gs.apply_played_hand(first);
+        let (mut hs, _) = gs.start_play_hand(Deck::shuffled_deck());
+        hs.play_action(Raise(10)).unwrap();
+        hs.play_action(CallOrCheck).unwrap();
+
+        let json = gs.to_json(Some(&hs));
+        let (restored, hand) = GameState::from_json(&json).unwrap();
+        let mut hand = hand.unwrap();
+        assert_eq!(restored.to_json(Some(&hand)), json);
+        assert_eq!(hand.history().deck, hs.history().deck);
+        while let (Ok(NextPlayer(_)), Ok(NextPlayer(_))) =
+            (hs.play_action(CallOrCheck), hand.play_action(CallOrCheck))
+        {}
+        assert_eq!(hand.events(), hs.events());
+
+        let (between_hands, none) = GameState::from_json(&gs.to_json(None)).unwrap();
+        assert!(none.is_none());
+        assert_eq!(between_hands.to_text(), gs.to_text());
+
+        let with_seats = gs.to_json_with_seats(None, "h,b:easy,h");
+        assert!(GameState::from_json(&with_seats).is_ok());
+        let seats = GameState::seats_from_json(&with_seats);
+        assert_eq!(seats.as_deref(), Some("h,b:easy,h"));
+        assert_eq!(GameState::seats_from_json(&json), None);
+    }
+
+    #[test]
+    fn should_refuse_json_saves_that_dont_add_up() {
+        let gs = GameState::init(2).unwrap();
+        let (mut hs, _) = gs.start_play_hand(Deck::ordered_deck());
+        hs.play_action(CallOrCheck).unwrap();
+        let json = gs.to_json(Some(&hs));
+        let mut value: serde_json::Value = serde_json::from_str(&json).unwrap();
+        value["hand"]["chips"]["pot"] = 50.into();
+        assert!(GameState::from_json(&value.to_string()).is_err());
+
+        let mut value: serde_json::Value = serde_json::from_str(&json).unwrap();
+        value["eliminated"] = serde_json::json!([1]);
+        assert!(GameState::from_json(&value.to_string()).is_err());
+        assert!(GameState::from_json("{}").is_err());
+    }
+
+    #[test]
+    fn should_reject_malformed_save() {
+        assert!(GameState::from_text("").is_none());
+        assert!(GameState::from_text("stacks 100 100\nbig_blind 2").is_none());
+        assert!(GameState::from_text("stacks 100 x\nbig_blind 0").is_none());
+    }
+}
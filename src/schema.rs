@@ -0,0 +1,112 @@
+//! Versions of the JSON files poker-tui writes, so that files from older versions keep
+//! loading as the formats change. Every file is stamped with the version of its format
+//! when written, and reading one runs it through the migrations from its version to the
+//! current one before it is deserialized. Files from before versions were stamped count
+//! as version 1.
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serde_json::{Map, Value};
+
+const VERSION_FIELD: &str = "version";
+
+/// Changes a file in one version of a format into the next version.
+pub type Migration = fn(&mut Map<String, Value>) -> Result<(), String>;
+
+pub struct Schema {
+    /// What the file holds, for error messages.
+    pub name: &'static str,
+    pub version: u64,
+    /// The migration from every version to the next, starting with the one from 1 to 2,
+    /// so there is one less than `version`.
+    pub migrations: &'static [Migration],
+}
+
+impl Schema {
+    /// `value` as JSON, stamped with the current version.
+    pub fn stamp(&self, value: &impl Serialize) -> Value {
+        let mut value = serde_json::to_value(value).expect("files serialize");
+        if let Value::Object(fields) = &mut value {
+            fields.insert(VERSION_FIELD.to_owned(), self.version.into());
+        }
+        value
+    }
+
+    /// Reads JSON written in any version up to the current one, migrated to the current
+    /// one. The version stamp itself is gone from what is deserialized.
+    pub fn read<T: DeserializeOwned>(&self, json: &str) -> Result<T, String> {
+        let value: Value = serde_json::from_str(json).map_err(|e| e.to_string())?;
+        let Value::Object(mut fields) = value else {
+            return Err(format!("a {} has to be a JSON object", self.name));
+        };
+        let version = match fields.remove(VERSION_FIELD) {
+            None => 1,
+            Some(version) => version
+                .as_u64()
+                .filter(|&v| v >= 1)
+                .ok_or_else(|| format!("the {} version has to be a number from 1", self.name))?,
+        };
+        if version > self.version {
+            return Err(format!(
+                "the {} is version {version}, this poker-tui reads up to version {}",
+                self.name, self.version
+            ));
+        }
+        for migrate in &self.migrations[version as usize - 1..] {
+            migrate(&mut fields)?;
+        }
+        serde_json::from_value(Value::Object(fields)).map_err(|e| e.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde::Deserialize;
+
+    use super::*;
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    #[serde(deny_unknown_fields)]
+    struct Settings {
+        players: usize,
+        stack: u32,
+    }
+
+    /// Version 1 called the stack `chips`, version 2 counted it in big blinds.
+    const SETTINGS: Schema = Schema {
+        name: "settings file",
+        version: 3,
+        migrations: &[
+            |fields| {
+                let chips = fields.remove("chips").ok_or("no chips")?;
+                fields.insert("stack".into(), chips);
+                Ok(())
+            },
+            |fields| {
+                let big_blinds = fields["stack"].as_u64().ok_or("no stack")?;
+                fields.insert("stack".into(), (big_blinds * 2).into());
+                Ok(())
+            },
+        ],
+    };
+
+    #[test]
+    fn should_migrate_older_versions_and_refuse_newer_ones() {
+        let current = Settings {
+            players: 6,
+            stack: 200,
+        };
+        let stamped = SETTINGS.stamp(&current);
+        assert_eq!(stamped["version"], 3);
+        assert_eq!(SETTINGS.read::<Settings>(&stamped.to_string()), Ok(current));
+
+        let unversioned = r#"{ "players": 6, "chips": 100 }"#;
+        assert_eq!(SETTINGS.read::<Settings>(unversioned).unwrap().stack, 200);
+        let second = r#"{ "version": 2, "players": 6, "stack": 100 }"#;
+        assert_eq!(SETTINGS.read::<Settings>(second).unwrap().stack, 200);
+
+        let newer = SETTINGS.read::<Settings>(r#"{ "version": 4, "players": 6 }"#);
+        assert!(newer.unwrap_err().contains("up to version 3"));
+        assert!(SETTINGS.read::<Settings>(r#"{ "version": 0 }"#).is_err());
+    }
+}
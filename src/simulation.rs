@@ -0,0 +1,446 @@
+use std::io::{self, Write};
+use std::mem;
+use std::sync::{Arc, Mutex};
+
+use rand::Rng;
+use serde::Serialize;
+
+use crate::bots::{seeded_rng, BotPlayer};
+use crate::core_engine::Deck;
+use crate::equity::equity;
+use crate::events::{EventObserver, GameEvent, Street};
+use crate::{GameState, HandState, PokerAction, TurnResult, BIG_BLIND_CHIPS};
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct SeatResult {
+    pub pots_won: u32,
+    pub net_chips: i64,
+    /// Net chips with every hand where the money went in before the river counted at what
+    /// the players' equity at that point was worth, taking the luck of the run-out away.
+    pub all_in_ev_chips: f64,
+    /// Sum of the squared result of every hand in big blinds, for the variance.
+    squared_bb: f64,
+}
+
+impl SeatResult {
+    /// Share of the hands where this seat won the pot.
+    pub fn win_rate(&self, hands: u32) -> f64 {
+        self.pots_won as f64 / hands.max(1) as f64
+    }
+
+    /// Big blinds won per 100 hands.
+    pub fn bb_per_100(&self, hands: u32) -> f64 {
+        100.0 * self.net_chips as f64 / BIG_BLIND_CHIPS as f64 / hands.max(1) as f64
+    }
+
+    /// Like `bb_per_100`, with all-in hands counted at their expected value.
+    pub fn all_in_ev_bb_per_100(&self, hands: u32) -> f64 {
+        100.0 * self.all_in_ev_chips / BIG_BLIND_CHIPS as f64 / hands.max(1) as f64
+    }
+
+    /// Standard deviation of the result of a single hand, in big blinds.
+    pub fn std_dev_bb(&self, hands: u32) -> f64 {
+        let hands = hands.max(1) as f64;
+        let mean = self.net_chips as f64 / BIG_BLIND_CHIPS as f64 / hands;
+        (self.squared_bb / hands - mean * mean).max(0.0).sqrt()
+    }
+
+    /// Half the width of the 95% confidence interval around `bb_per_100`.
+    pub fn confidence_95(&self, hands: u32) -> f64 {
+        1.96 * 100.0 * self.std_dev_bb(hands) / (hands.max(1) as f64).sqrt()
+    }
+
+    /// Adds the results of another set of hands played by the same bot.
+    pub fn add(&mut self, other: &SeatResult) {
+        self.pots_won += other.pots_won;
+        self.net_chips += other.net_chips;
+        self.all_in_ev_chips += other.all_in_ev_chips;
+        self.squared_bb += other.squared_bb;
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct SimulationResult {
+    pub hands: u32,
+    pub seats: Vec<SeatResult>,
+    /// Every seat's net chips after each hand.
+    pub bankrolls: Vec<Vec<i64>>,
+    /// Every seat's all-in expected net chips after each hand.
+    pub all_in_ev_bankrolls: Vec<Vec<f64>>,
+}
+
+/// One seat of the summary written by `SimulationResult::write_json`.
+#[derive(Serialize)]
+struct SeatSeries<'a> {
+    bot: &'a str,
+    net_chips: i64,
+    bb_per_100: f64,
+    std_dev_bb: f64,
+    all_in_ev_bb_per_100: f64,
+    bankroll: &'a [i64],
+    all_in_ev_bankroll: &'a [f64],
+}
+
+impl SimulationResult {
+    /// Writes the bankroll of every seat after each hand as CSV, with a net chips and an
+    /// all-in EV column for each of the `names`.
+    pub fn write_csv(&self, names: &[String], out: &mut dyn Write) -> io::Result<()> {
+        write!(out, "hand")?;
+        for name in names {
+            write!(out, ",{name} net,{name} all-in ev")?;
+        }
+        writeln!(out)?;
+        for hand in 0..self.hands as usize {
+            write!(out, "{}", hand + 1)?;
+            for (net, ev) in self.bankrolls.iter().zip(&self.all_in_ev_bankrolls) {
+                write!(out, ",{},{:.2}", net[hand], ev[hand])?;
+            }
+            writeln!(out)?;
+        }
+        Ok(())
+    }
+
+    /// Writes the summary and bankroll of every seat, named by `names`, as JSON.
+    pub fn write_json(&self, names: &[String], out: &mut dyn Write) -> io::Result<()> {
+        let seats: Vec<SeatSeries> = (0..self.seats.len())
+            .map(|p| {
+                let seat = &self.seats[p];
+                SeatSeries {
+                    bot: &names[p],
+                    net_chips: seat.net_chips,
+                    bb_per_100: seat.bb_per_100(self.hands),
+                    std_dev_bb: seat.std_dev_bb(self.hands),
+                    all_in_ev_bb_per_100: seat.all_in_ev_bb_per_100(self.hands),
+                    bankroll: &self.bankrolls[p],
+                    all_in_ev_bankroll: &self.all_in_ev_bankrolls[p],
+                }
+            })
+            .collect();
+        serde_json::to_writer_pretty(&mut *out, &seats)?;
+        writeln!(out)
+    }
+}
+
+/// One line of the decision log written by `write_decision`.
+#[derive(Serialize)]
+struct Decision<'a> {
+    hand: u32,
+    player: usize,
+    street: Street,
+    action: PokerAction,
+    reason: Option<&'a str>,
+}
+
+/// Writes a decision `player` made in `hand` and the bot's reason for it to a decision
+/// log, as one JSON object a line.
+pub fn write_decision(
+    log: &mut dyn Write,
+    hand: u32,
+    player: usize,
+    street: Street,
+    action: PokerAction,
+    reason: Option<&str>,
+) -> io::Result<()> {
+    let decision = Decision {
+        hand,
+        player,
+        street,
+        action,
+        reason,
+    };
+    serde_json::to_writer(&mut *log, &decision)?;
+    writeln!(log)
+}
+
+/// Plays `hands` hands between the bots without any output. A bot that can no longer cover
+/// the big blind buys back in to `stack`, so results are measured per hand rather than by
+/// who busts first. With a `seed` the same cards are dealt on every run, which together
+/// with seeded bots makes the whole simulation repeatable.
+///
+/// Panics if chips are created or lost during a hand.
+pub fn simulate(
+    bots: &mut [Box<dyn BotPlayer>],
+    hands: u32,
+    stack: u32,
+    seed: Option<u64>,
+) -> SimulationResult {
+    simulate_with_log(bots, hands, stack, seed, &mut io::sink()).expect("the sink never fails")
+}
+
+/// Like `simulate`, but also writes every decision and the bot's reason for it to `log`,
+/// one JSON object per line.
+pub fn simulate_with_log(
+    bots: &mut [Box<dyn BotPlayer>],
+    hands: u32,
+    stack: u32,
+    seed: Option<u64>,
+    log: &mut dyn Write,
+) -> io::Result<SimulationResult> {
+    simulate_with(bots.len(), hands, stack, seed, |hand, gs, mut hs| {
+        let mut cur = hs.current_player();
+        loop {
+            let view = hs.bot_context(cur);
+            let (action, reason) = bots[cur].decide_explained(&view);
+            write_decision(log, hand, cur, view.street, action, reason.as_deref())?;
+            match hs.play_action(action).expect("bots only make legal raises") {
+                TurnResult::NextPlayer(next) => cur = next,
+                TurnResult::WonHand(_) => break,
+            }
+        }
+        for bot in bots.iter_mut() {
+            bot.hand_finished(hs.events());
+        }
+        Ok(gs.apply_played_hand(hs))
+    })
+}
+
+/// Every event of the hand being played.
+#[derive(Default)]
+struct HandEvents(Vec<GameEvent>);
+
+impl EventObserver for HandEvents {
+    fn on_event(&mut self, event: &GameEvent) {
+        self.0.push(event.clone());
+    }
+}
+
+/// Like `simulate`, for `players` seats that `play` decides for. It is given the number of
+/// each hand, the game and the hand just dealt, and returns the game after it, so the hand
+/// can be played by whatever plays the interactive game as well.
+///
+/// Panics if chips are created or lost during a hand.
+pub fn simulate_with<E>(
+    players: usize,
+    hands: u32,
+    stack: u32,
+    seed: Option<u64>,
+    mut play: impl FnMut(u32, &GameState, HandState) -> Result<GameState, E>,
+) -> Result<SimulationResult, E> {
+    let mut gs = GameState::init_with_stack(players, stack).expect("at least two players");
+    let events = Arc::new(Mutex::new(HandEvents::default()));
+    gs.subscribe(events.clone());
+    let mut seats = vec![SeatResult::default(); players];
+    let mut dealer = seeded_rng(seed);
+    let mut ev_rng = seeded_rng(seed.map(|s| s.wrapping_add(1)));
+    let mut bankrolls = vec![vec![]; players];
+    let mut all_in_ev_bankrolls = vec![vec![]; players];
+
+    for hand in 0..hands {
+        for chips in &mut gs.chips {
+            if *chips < BIG_BLIND_CHIPS {
+                *chips = stack;
+            }
+        }
+        let before = gs.chips.clone();
+
+        let (hs, _) = gs.start_play_hand(Deck::shuffled_with(&mut dealer));
+        gs = play(hand, &gs, hs)?;
+        let events = mem::take(&mut events.lock().unwrap().0);
+        // Winning a side pot or a share of a split one still counts once a hand.
+        for (p, seat) in seats.iter_mut().enumerate() {
+            seat.pots_won += u32::from(
+                events
+                    .iter()
+                    .any(|e| matches!(*e, GameEvent::PotAwarded { player, .. } if player == p)),
+            );
+        }
+        let evs = all_in_ev(&events, &before, &mut ev_rng);
+
+        assert_eq!(
+            gs.chips.iter().sum::<u32>(),
+            before.iter().sum::<u32>(),
+            "chips were created or lost during a hand"
+        );
+        for (p, (after, before)) in gs.chips.iter().zip(&before).enumerate() {
+            let net = *after as i64 - *before as i64;
+            let seat = &mut seats[p];
+            seat.net_chips += net;
+            seat.all_in_ev_chips += evs
+                .iter()
+                .find(|(player, _)| *player == p)
+                .map_or(net as f64, |(_, ev)| *ev);
+            seat.squared_bb += (net as f64 / BIG_BLIND_CHIPS as f64).powi(2);
+            bankrolls[p].push(seat.net_chips);
+            all_in_ev_bankrolls[p].push(seat.all_in_ev_chips);
+        }
+    }
+
+    Ok(SimulationResult {
+        hands,
+        seats,
+        bankrolls,
+        all_in_ev_bankrolls,
+    })
+}
+
+/// Boards dealt to estimate the equity of an all-in before the flop or on it.
+const ALL_IN_EV_SAMPLES: usize = 1000;
+
+/// What the two players of a heads-up showdown were expected to net, when one of them was
+/// all in before the river. Each expects their equity when the last chips went in times
+/// the pot, minus what they put in, leaving out chips given back uncalled. Empty for every
+/// other hand. `stacks` are the players' chips before the hand.
+fn all_in_ev(events: &[GameEvent], stacks: &[u32], rng: &mut impl Rng) -> Vec<(usize, f64)> {
+    let mut hands = vec![];
+    let mut invested = vec![0; stacks.len()];
+    let mut board = vec![];
+    let mut board_at_last_bet = 0;
+    let mut shown = vec![];
+    for event in events {
+        match event {
+            GameEvent::HoleCardsDealt { player, cards } => {
+                hands.push((*player, (cards[0], cards[1])))
+            }
+            GameEvent::BlindPosted { player, amount }
+            | GameEvent::Called { player, amount, .. }
+            | GameEvent::Raised { player, amount, .. } => {
+                invested[*player] += amount;
+                board_at_last_bet = board.len();
+            }
+            GameEvent::BetReturned { player, amount } => invested[*player] -= amount,
+            GameEvent::StreetDealt { cards, .. } => board.extend(cards),
+            GameEvent::HandShown { player, .. } => shown.push(*player),
+            _ => {}
+        }
+    }
+    let all_in = shown.iter().any(|&p| invested[p] == stacks[p]);
+    if shown.len() != 2 || !all_in || board_at_last_bet == 5 {
+        return vec![];
+    }
+
+    let hole = |player: usize| hands.iter().find(|(p, _)| *p == player).unwrap().1;
+    let equities = equity(
+        &[hole(shown[0]), hole(shown[1])],
+        &board[..board_at_last_bet],
+        ALL_IN_EV_SAMPLES,
+        rng,
+    );
+    let pot = invested.iter().sum::<u32>() as f64;
+    shown
+        .iter()
+        .zip(equities)
+        .map(|(&p, equity)| (p, equity * pot - invested[p] as f64))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bots::{BotKind, CallingStation, RuleBot};
+
+    #[test]
+    fn should_simulate_hands_between_bots() {
+        let mut bots: Vec<Box<dyn BotPlayer>> = vec![
+            Box::new(CallingStation),
+            Box::new(RuleBot),
+            Box::new(RuleBot),
+        ];
+        let result = simulate(&mut bots, 200, 100, None);
+
+        assert_eq!(result.hands, 200);
+        let net: i64 = result.seats.iter().map(|s| s.net_chips).sum();
+        assert_eq!(net, 0);
+        let pots: u32 = result.seats.iter().map(|s| s.pots_won).sum();
+        // Split pots count for everyone who shared them.
+        assert!(pots >= 200);
+        assert!(result.seats.iter().all(|s| s.std_dev_bb(200) > 0.0));
+        let ev: f64 = result.seats.iter().map(|s| s.all_in_ev_chips).sum();
+        assert!(ev.abs() < 1e-6);
+        for (seat, bankroll) in result.seats.iter().zip(&result.bankrolls) {
+            assert_eq!(bankroll.len(), 200);
+            assert_eq!(bankroll.last(), Some(&seat.net_chips));
+        }
+    }
+
+    #[test]
+    fn should_count_an_all_in_at_its_equity() {
+        let gs = GameState::init_with_stack(2, 20).unwrap();
+        let (mut hs, cur) = gs.start_play_hand(Deck::ordered_deck());
+        let stack = gs.current_chips(cur) - hs.bot_context(cur).bets[cur];
+        hs.play_action(PokerAction::Raise(stack)).unwrap();
+        while let TurnResult::NextPlayer(_) = hs.play_action(PokerAction::CallOrCheck).unwrap() {}
+
+        let evs = all_in_ev(hs.events(), &[20, 20], &mut seeded_rng(Some(1)));
+        assert_eq!(evs.len(), 2);
+        assert!((evs[0].1 + evs[1].1).abs() < 1e-9);
+        assert!(evs.iter().all(|(_, ev)| ev.abs() < 20.0));
+
+        let (mut hs, _) = gs.start_play_hand(Deck::ordered_deck());
+        while let TurnResult::NextPlayer(_) = hs.play_action(PokerAction::CallOrCheck).unwrap() {}
+        assert!(all_in_ev(hs.events(), &[20, 20], &mut seeded_rng(Some(1))).is_empty());
+    }
+
+    #[test]
+    fn should_write_the_bankrolls_as_csv_and_json() {
+        let mut bots: Vec<Box<dyn BotPlayer>> = vec![Box::new(CallingStation), Box::new(RuleBot)];
+        let result = simulate(&mut bots, 5, 100, Some(2));
+        let names = vec!["station".to_string(), "normal".to_string()];
+
+        let mut csv = vec![];
+        result.write_csv(&names, &mut csv).unwrap();
+        let csv = String::from_utf8(csv).unwrap();
+        let lines: Vec<&str> = csv.lines().collect();
+        assert_eq!(
+            lines[0],
+            "hand,station net,station all-in ev,normal net,normal all-in ev"
+        );
+        assert_eq!(lines.len(), 6);
+        assert!(lines[5].starts_with(&format!("5,{},", result.seats[0].net_chips)));
+
+        let mut json = vec![];
+        result.write_json(&names, &mut json).unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&json).unwrap();
+        assert_eq!(json[1]["bot"], "normal");
+        assert_eq!(json[1]["bankroll"].as_array().unwrap().len(), 5);
+    }
+
+    #[test]
+    fn should_log_every_decision_with_its_reason() {
+        let mut bots: Vec<Box<dyn BotPlayer>> = vec![Box::new(CallingStation), Box::new(RuleBot)];
+        let mut log = vec![];
+        simulate_with_log(&mut bots, 10, 100, None, &mut log).unwrap();
+
+        let lines: Vec<serde_json::Value> = String::from_utf8(log)
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert!(lines.len() >= 10);
+        assert_eq!(lines[0]["hand"], 0);
+        assert!(lines
+            .iter()
+            .all(|l| l["reason"].is_null() == (l["player"] == 0)));
+    }
+
+    #[test]
+    fn should_repeat_a_seeded_simulation_exactly() {
+        let run = |seed| {
+            let mut bots: Vec<Box<dyn BotPlayer>> = ["mc=20", "maniac", "normal@bluff=0.3"]
+                .iter()
+                .enumerate()
+                .map(|(p, spec)| {
+                    let kind = BotKind::parse(spec).unwrap();
+                    kind.build_seeded(Some(seed + p as u64)).unwrap()
+                })
+                .collect();
+            let mut log = vec![];
+            let result = simulate_with_log(&mut bots, 20, 100, Some(seed), &mut log).unwrap();
+            (result, log)
+        };
+        assert_eq!(run(7), run(7));
+        assert_ne!(run(7).1, run(8).1);
+    }
+
+    #[test]
+    fn should_compute_rates_per_hand() {
+        let seat = SeatResult {
+            pots_won: 5,
+            net_chips: 20,
+            squared_bb: 100.0,
+            ..SeatResult::default()
+        };
+        assert_eq!(seat.win_rate(10), 0.5);
+        assert_eq!(seat.bb_per_100(10), 100.0);
+        assert_eq!(seat.std_dev_bb(10), 3.0);
+    }
+}
@@ -0,0 +1,639 @@
+use std::fmt::Display;
+use std::fs;
+use std::io::{self, Write};
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use crate::core_engine::Hand;
+use crate::equity::AllIn;
+use crate::events::{GameEvent, Street};
+use crate::schema::Schema;
+
+const PROFILE_STATS: Schema = Schema {
+    name: "profile stats file",
+    version: 1,
+    migrations: &[],
+};
+
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PlayerStats {
+    pub hands_played: u32,
+    pub vpip_hands: u32,
+    pub pfr_hands: u32,
+    pub showdowns_won: u32,
+    pub biggest_pot: u32,
+    pub net_chips: i64,
+    pub best_hand: Option<Hand>,
+    pub raises: u32,
+    pub calls: u32,
+    /// Flop bets by the preflop raiser this player had to answer.
+    pub cbets_faced: u32,
+    pub cbet_folds: u32,
+    /// Hands where the player acted preflop facing a single raise.
+    pub three_bet_chances: u32,
+    pub three_bets: u32,
+}
+
+impl PlayerStats {
+    /// Percentage of hands where the player voluntarily put chips in preflop.
+    pub fn vpip(&self) -> f64 {
+        percentage(self.vpip_hands, self.hands_played)
+    }
+
+    /// Percentage of hands where the player raised preflop.
+    pub fn pfr(&self) -> f64 {
+        percentage(self.pfr_hands, self.hands_played)
+    }
+
+    /// Percentage of continuation bets the player folded to.
+    pub fn fold_to_cbet(&self) -> f64 {
+        percentage(self.cbet_folds, self.cbets_faced)
+    }
+
+    /// Percentage of the player's raises and calls that were raises.
+    pub fn raise_frequency(&self) -> f64 {
+        percentage(self.raises, self.raises + self.calls)
+    }
+
+    /// Percentage of the chances to re-raise a single preflop raise that the player took.
+    pub fn three_bet(&self) -> f64 {
+        percentage(self.three_bets, self.three_bet_chances)
+    }
+
+    /// Raises for every call, the aggression factor. Every raise counts when the player
+    /// never called.
+    pub fn aggression(&self) -> f64 {
+        self.raises as f64 / self.calls.max(1) as f64
+    }
+
+    /// Adds the totals of `other`, like another session of the same player.
+    pub fn merge(&mut self, other: &PlayerStats) {
+        self.hands_played += other.hands_played;
+        self.vpip_hands += other.vpip_hands;
+        self.pfr_hands += other.pfr_hands;
+        self.showdowns_won += other.showdowns_won;
+        self.biggest_pot = self.biggest_pot.max(other.biggest_pot);
+        self.net_chips += other.net_chips;
+        if other.best_hand > self.best_hand {
+            self.best_hand = other.best_hand;
+        }
+        self.raises += other.raises;
+        self.calls += other.calls;
+        self.cbets_faced += other.cbets_faced;
+        self.cbet_folds += other.cbet_folds;
+        self.three_bet_chances += other.three_bet_chances;
+        self.three_bets += other.three_bets;
+    }
+
+    /// The stats saved at `path`, or none yet if nothing is saved there.
+    pub fn load(path: impl AsRef<Path>) -> io::Result<Self> {
+        match fs::read_to_string(path) {
+            Ok(text) => PROFILE_STATS.read(&text).map_err(io::Error::other),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(e) => Err(e),
+        }
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        let json = serde_json::to_string_pretty(&PROFILE_STATS.stamp(self))?;
+        fs::write(path, json)
+    }
+}
+
+pub(crate) fn percentage(count: u32, total: u32) -> f64 {
+    if total == 0 {
+        0.0
+    } else {
+        100.0 * count as f64 / total as f64
+    }
+}
+
+/// How one hand went for one player.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct HandResult {
+    pub net_chips: i64,
+    pub vpip: bool,
+    pub pfr: bool,
+    /// Chips won from the pot, with or without a showdown.
+    pub won: u32,
+    pub showdown_won: bool,
+}
+
+pub struct SessionStats {
+    players: Vec<PlayerStats>,
+    /// Every player's result in each hand, in the order the hands were played.
+    results: Vec<Vec<HandResult>>,
+    all_ins: u32,
+    /// Chips each player won in all-ins beyond what their equity was worth.
+    all_in_luck: Vec<f64>,
+}
+
+impl SessionStats {
+    pub fn init(players: usize) -> Self {
+        Self {
+            players: vec![PlayerStats::default(); players],
+            results: vec![],
+            all_ins: 0,
+            all_in_luck: vec![0.0; players],
+        }
+    }
+
+    pub fn player(&self, player: usize) -> &PlayerStats {
+        &self.players[player]
+    }
+
+    pub fn players(&self) -> &[PlayerStats] {
+        &self.players
+    }
+
+    pub fn hands(&self) -> u32 {
+        self.results.len() as u32
+    }
+
+    pub fn results(&self) -> &[Vec<HandResult>] {
+        &self.results
+    }
+
+    /// Hands that were all in before the river.
+    pub fn all_ins(&self) -> u32 {
+        self.all_ins
+    }
+
+    /// Chips the player won in all-ins beyond their equity, below 0 if they ran bad.
+    pub fn all_in_luck(&self, player: usize) -> f64 {
+        self.all_in_luck[player]
+    }
+
+    /// The player's net chips had every all-in paid out by equity.
+    pub fn expected_net_chips(&self, player: usize) -> f64 {
+        self.players[player].net_chips as f64 - self.all_in_luck[player]
+    }
+
+    /// Adds a hand's all-in, recorded with `record_hand` as well.
+    pub fn record_all_in(&mut self, all_in: &AllIn) {
+        self.all_ins += 1;
+        for (luck, hand_luck) in self.all_in_luck.iter_mut().zip(&all_in.luck) {
+            *luck += hand_luck;
+        }
+    }
+
+    pub fn biggest_pot(&self) -> u32 {
+        self.players
+            .iter()
+            .map(|p| p.biggest_pot)
+            .max()
+            .unwrap_or(0)
+    }
+
+    /// The strongest hand shown down this session and the player who made it.
+    pub fn best_hand(&self) -> Option<(usize, Hand)> {
+        self.players
+            .iter()
+            .enumerate()
+            .filter_map(|(p, s)| s.best_hand.map(|h| (p, h)))
+            .max_by(|a, b| a.1.cmp(&b.1))
+    }
+
+    /// Folds the events of one completed hand into the session totals.
+    pub fn record_hand(&mut self, events: &[GameEvent]) {
+        let mut results = vec![HandResult::default(); self.players.len()];
+        let mut preflop_raiser = None;
+        let mut preflop_raises = 0;
+        let mut three_bet_chance = vec![false; self.players.len()];
+        let mut flop_bet = false;
+        // Set from the continuation bet until someone raises it.
+        let mut facing_cbet = false;
+
+        for event in events {
+            if let GameEvent::Checked {
+                player,
+                street: Street::Preflop,
+            }
+            | GameEvent::Called {
+                player,
+                street: Street::Preflop,
+                ..
+            }
+            | GameEvent::Raised {
+                player,
+                street: Street::Preflop,
+                ..
+            }
+            | GameEvent::Folded {
+                player,
+                street: Street::Preflop,
+            } = *event
+            {
+                if preflop_raises == 1
+                    && preflop_raiser != Some(player)
+                    && !three_bet_chance[player]
+                {
+                    three_bet_chance[player] = true;
+                    self.players[player].three_bet_chances += 1;
+                    if let GameEvent::Raised { .. } = event {
+                        self.players[player].three_bets += 1;
+                    }
+                }
+            }
+            match *event {
+                GameEvent::BlindPosted { player, amount } => {
+                    results[player].net_chips -= amount as i64
+                }
+                GameEvent::Called {
+                    player,
+                    street,
+                    amount,
+                } => {
+                    results[player].net_chips -= amount as i64;
+                    results[player].vpip |= street == Street::Preflop;
+                    self.players[player].calls += 1;
+                    if facing_cbet && street == Street::Flop {
+                        self.players[player].cbets_faced += 1;
+                    }
+                }
+                GameEvent::Raised {
+                    player,
+                    street,
+                    amount,
+                } => {
+                    results[player].net_chips -= amount as i64;
+                    results[player].vpip |= street == Street::Preflop;
+                    results[player].pfr |= street == Street::Preflop;
+                    self.players[player].raises += 1;
+                    match street {
+                        Street::Preflop => {
+                            preflop_raiser = Some(player);
+                            preflop_raises += 1;
+                        }
+                        Street::Flop if facing_cbet => {
+                            self.players[player].cbets_faced += 1;
+                            facing_cbet = false;
+                        }
+                        Street::Flop if !flop_bet => facing_cbet = preflop_raiser == Some(player),
+                        _ => {}
+                    }
+                    flop_bet |= street == Street::Flop;
+                }
+                GameEvent::Folded { player, street } if facing_cbet && street == Street::Flop => {
+                    self.players[player].cbets_faced += 1;
+                    self.players[player].cbet_folds += 1;
+                }
+                GameEvent::PotAwarded {
+                    player,
+                    amount,
+                    showdown,
+                } => {
+                    results[player].net_chips += amount as i64;
+                    results[player].won += amount;
+                    results[player].showdown_won |= showdown;
+                }
+                GameEvent::BetReturned { player, amount } => {
+                    results[player].net_chips += amount as i64
+                }
+                GameEvent::HandShown { player, hand } => {
+                    let best = &mut self.players[player].best_hand;
+                    if best.is_none_or(|b| hand > b) {
+                        *best = Some(hand);
+                    }
+                }
+                GameEvent::HoleCardsDealt { .. }
+                | GameEvent::Checked { .. }
+                | GameEvent::Folded { .. }
+                | GameEvent::StreetDealt { .. }
+                | GameEvent::PlayerEliminated { .. }
+                | GameEvent::TimedOut { .. }
+                | GameEvent::SatOut { .. }
+                | GameEvent::SatIn { .. } => {}
+            }
+        }
+
+        for (stats, result) in self.players.iter_mut().zip(&results) {
+            stats.hands_played += 1;
+            stats.vpip_hands += result.vpip as u32;
+            stats.pfr_hands += result.pfr as u32;
+            stats.net_chips += result.net_chips;
+            // A player can win the main pot and side pots of the same hand.
+            stats.biggest_pot = stats.biggest_pot.max(result.won);
+            stats.showdowns_won += result.showdown_won as u32;
+        }
+        self.results.push(results);
+    }
+
+    /// Writes every player's result in every hand as CSV, a row for each player in each
+    /// hand, with the players named by `names`.
+    pub fn write_hands_csv(&self, names: &[String], out: &mut dyn Write) -> io::Result<()> {
+        let header = [
+            "hand",
+            "player",
+            "net chips",
+            "vpip",
+            "pfr",
+            "won",
+            "showdown won",
+        ];
+        write_csv_row(out, &header.each_ref().map(|h| h as &dyn Display))?;
+        for (hand, results) in self.results.iter().enumerate() {
+            for (name, r) in names.iter().zip(results) {
+                write_csv_row(
+                    out,
+                    &[
+                        &(hand + 1),
+                        name,
+                        &r.net_chips,
+                        &(r.vpip as u8),
+                        &(r.pfr as u8),
+                        &r.won,
+                        &(r.showdown_won as u8),
+                    ],
+                )?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Writes every player's totals as CSV, a row for each of the `names`.
+    pub fn write_players_csv(&self, names: &[String], out: &mut dyn Write) -> io::Result<()> {
+        let header = [
+            "player",
+            "hands",
+            "vpip %",
+            "pfr %",
+            "raise %",
+            "fold to cbet %",
+            "showdowns won",
+            "biggest pot",
+            "net chips",
+            "best hand",
+        ];
+        write_csv_row(out, &header.each_ref().map(|h| h as &dyn Display))?;
+        for (name, s) in names.iter().zip(&self.players) {
+            write_csv_row(
+                out,
+                &[
+                    name,
+                    &s.hands_played,
+                    &format!("{:.1}", s.vpip()),
+                    &format!("{:.1}", s.pfr()),
+                    &format!("{:.1}", s.raise_frequency()),
+                    &format!("{:.1}", s.fold_to_cbet()),
+                    &s.showdowns_won,
+                    &s.biggest_pot,
+                    &s.net_chips,
+                    &s.best_hand.map(|h| h.ranking_name()).unwrap_or_default(),
+                ],
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// Writes one CSV record the way RFC 4180 has it: every field quoted, quotes inside a field
+/// doubled and the record ended by CRLF, so that a name with a comma, a quote or a line
+/// break in it stays one field.
+fn write_csv_row(out: &mut dyn Write, fields: &[&dyn Display]) -> io::Result<()> {
+    let fields: Vec<_> = fields
+        .iter()
+        .map(|field| format!("\"{}\"", field.to_string().replace('"', "\"\"")))
+        .collect();
+    write!(out, "{}\r\n", fields.join(","))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core_engine::Deck;
+    use crate::GameState;
+    use crate::PokerAction::*;
+    use crate::TurnResult;
+
+    #[test]
+    fn should_aggregate_stats_over_hands() {
+        let mut stats = SessionStats::init(2);
+        let mut gs = GameState::init(2).unwrap();
+
+        let (mut hs, _) = gs.start_play_hand(Deck::ordered_deck());
+        hs.play_action(Raise(5)).unwrap();
+        hs.play_action(Fold).unwrap();
+        stats.record_hand(hs.events());
+        gs = gs.apply_played_hand(hs);
+
+        let (mut hs, _) = gs.start_play_hand(Deck::ordered_deck());
+        hs.play_action(Fold).unwrap();
+        stats.record_hand(hs.events());
+
+        let p0 = stats.player(0);
+        assert_eq!(p0.hands_played, 2);
+        assert_eq!(p0.vpip_hands, 1);
+        assert_eq!(p0.pfr_hands, 1);
+        assert_eq!(p0.biggest_pot, 4);
+        assert_eq!(p0.net_chips, 3);
+        assert_eq!(p0.vpip(), 50.0);
+
+        let p1 = stats.player(1);
+        assert_eq!(p1.vpip_hands, 0);
+        assert_eq!(p1.net_chips, -3);
+    }
+
+    #[test]
+    fn should_track_folds_to_continuation_bets() {
+        use GameEvent::*;
+        use Street::*;
+        let mut stats = SessionStats::init(3);
+        stats.record_hand(&[
+            Raised {
+                player: 0,
+                street: Preflop,
+                amount: 6,
+            },
+            Called {
+                player: 1,
+                street: Preflop,
+                amount: 5,
+            },
+            Called {
+                player: 2,
+                street: Preflop,
+                amount: 4,
+            },
+            Checked {
+                player: 1,
+                street: Flop,
+            },
+            Raised {
+                player: 0,
+                street: Flop,
+                amount: 10,
+            },
+            Folded {
+                player: 1,
+                street: Flop,
+            },
+            Raised {
+                player: 2,
+                street: Flop,
+                amount: 30,
+            },
+            Folded {
+                player: 0,
+                street: Flop,
+            },
+        ]);
+
+        assert_eq!(stats.player(1).cbets_faced, 1);
+        assert_eq!(stats.player(1).fold_to_cbet(), 100.0);
+        assert_eq!(stats.player(2).cbets_faced, 1);
+        assert_eq!(stats.player(2).fold_to_cbet(), 0.0);
+        assert_eq!(stats.player(0).cbets_faced, 0);
+        assert_eq!(stats.player(0).raise_frequency(), 100.0);
+        assert_eq!(stats.player(2).raise_frequency(), 50.0);
+    }
+
+    #[test]
+    fn should_count_three_bets_and_aggression() {
+        use GameEvent::*;
+        use Street::*;
+        let raise = |player, amount| Raised {
+            player,
+            street: Preflop,
+            amount,
+        };
+        let mut stats = SessionStats::init(3);
+        stats.record_hand(&[
+            raise(0, 6),
+            raise(1, 18),
+            Folded {
+                player: 2,
+                street: Preflop,
+            },
+            Called {
+                player: 0,
+                street: Preflop,
+                amount: 12,
+            },
+        ]);
+        stats.record_hand(&[
+            raise(0, 6),
+            Called {
+                player: 1,
+                street: Preflop,
+                amount: 5,
+            },
+            Folded {
+                player: 2,
+                street: Preflop,
+            },
+        ]);
+
+        let p1 = stats.player(1);
+        assert_eq!((p1.three_bet_chances, p1.three_bets), (2, 1));
+        assert_eq!(p1.three_bet(), 50.0);
+        assert_eq!(stats.player(2).three_bet_chances, 1);
+        assert_eq!(stats.player(0).three_bet_chances, 0);
+        assert_eq!(stats.player(0).aggression(), 2.0);
+        assert_eq!(stats.player(2).aggression(), 0.0);
+
+        let mut merged = p1.clone();
+        merged.merge(stats.player(0));
+        assert_eq!(merged.hands_played, 4);
+        assert_eq!((merged.raises, merged.calls), (3, 2));
+        assert_eq!(merged.three_bet(), 50.0);
+    }
+
+    #[test]
+    fn should_adjust_net_chips_for_all_in_luck() {
+        let mut stats = SessionStats::init(2);
+        let (mut hs, _) = GameState::init_with_stack(2, 20)
+            .unwrap()
+            .start_play_hand(Deck::ordered_deck());
+        hs.play_action(Raise(19)).unwrap();
+        while let Ok(TurnResult::NextPlayer(_)) = hs.play_action(CallOrCheck) {}
+        stats.record_hand(hs.events());
+        stats.record_all_in(&AllIn {
+            street: Street::Preflop,
+            equities: vec![Some(0.25), Some(0.75)],
+            luck: vec![30.0, -30.0],
+        });
+
+        assert_eq!(stats.all_ins(), 1);
+        let net = stats.player(0).net_chips as f64;
+        assert_eq!(stats.expected_net_chips(0), net - 30.0);
+        assert_eq!(stats.expected_net_chips(1), -net + 30.0);
+    }
+
+    #[test]
+    fn should_count_showdowns_won() {
+        let mut stats = SessionStats::init(2);
+        let (mut hs, _) = GameState::init(2)
+            .unwrap()
+            .start_play_hand(Deck::ordered_deck());
+        while let Ok(TurnResult::NextPlayer(_)) = hs.play_action(CallOrCheck) {}
+        stats.record_hand(hs.events());
+
+        // The board plays for both, so they split the pot.
+        let showdowns: u32 = stats.players().iter().map(|p| p.showdowns_won).sum();
+        assert_eq!(showdowns, 2);
+        let net: i64 = stats.players().iter().map(|p| p.net_chips).sum();
+        assert_eq!(net, 0);
+        assert_eq!(stats.player(0).vpip_hands, 1);
+        assert_eq!(stats.player(1).vpip_hands, 0);
+        assert_eq!(stats.hands(), 1);
+        assert!(stats.player(0).best_hand.is_some());
+        assert!(stats.player(1).best_hand.is_some());
+        assert!(stats.best_hand().is_some());
+    }
+
+    #[test]
+    fn should_export_hands_and_players_as_csv() {
+        let mut stats = SessionStats::init(2);
+        let (mut hs, _) = GameState::init(2)
+            .unwrap()
+            .start_play_hand(Deck::ordered_deck());
+        hs.play_action(Raise(5)).unwrap();
+        hs.play_action(Fold).unwrap();
+        stats.record_hand(hs.events());
+        let names = ["ada".to_owned(), "bob".to_owned()];
+
+        let mut hands = vec![];
+        stats.write_hands_csv(&names, &mut hands).unwrap();
+        assert_eq!(
+            String::from_utf8(hands).unwrap(),
+            "\"hand\",\"player\",\"net chips\",\"vpip\",\"pfr\",\"won\",\"showdown won\"\r\n\
+             \"1\",\"ada\",\"2\",\"1\",\"1\",\"4\",\"0\"\r\n\
+             \"1\",\"bob\",\"-2\",\"0\",\"0\",\"0\",\"0\"\r\n"
+        );
+
+        let mut players = vec![];
+        stats.write_players_csv(&names, &mut players).unwrap();
+        let players = String::from_utf8(players).unwrap();
+        let lines: Vec<_> = players.lines().collect();
+        assert_eq!(lines.len(), 3);
+        assert_eq!(
+            lines[1],
+            r#""ada","1","100.0","100.0","100.0","0.0","0","4","2","""#
+        );
+    }
+
+    #[test]
+    fn should_keep_names_with_commas_quotes_and_line_breaks_in_one_csv_field() {
+        let mut stats = SessionStats::init(2);
+        let (mut hs, _) = GameState::init(2)
+            .unwrap()
+            .start_play_hand(Deck::ordered_deck());
+        hs.play_action(Fold).unwrap();
+        stats.record_hand(hs.events());
+        let names = ["Doe, \"Ace\"\nJane".to_owned(), "bob".to_owned()];
+
+        let mut players = vec![];
+        stats.write_players_csv(&names, &mut players).unwrap();
+        let players = String::from_utf8(players).unwrap();
+        let rows: Vec<_> = players.split("\r\n").collect();
+        assert_eq!(rows.len(), 4);
+        assert!(rows[1].starts_with("\"Doe, \"\"Ace\"\"\nJane\",\"1\","));
+
+        let mut hands = vec![];
+        stats.write_hands_csv(&names, &mut hands).unwrap();
+        let hands = String::from_utf8(hands).unwrap();
+        assert!(hands.contains("\r\n\"1\",\"Doe, \"\"Ace\"\"\nJane\",\"-1\","));
+    }
+}
@@ -0,0 +1,155 @@
+//! A small read-only HTTP endpoint for keeping an eye on a long-running host: `/status`
+//! answers with a `StatusReport` as JSON, and `/metrics` with the same in the text format
+//! Prometheus scrapes. Anything else is not found, and nothing can be changed through it.
+
+use std::fmt::Write as _;
+use std::io::{self, BufRead, BufReader, Write};
+use std::net::{TcpListener, TcpStream};
+use std::thread;
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+
+/// How long a client of the endpoint has to send its request.
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(5);
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StatusReport {
+    pub uptime_secs: u64,
+    pub tables: Vec<TableStatus>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TableStatus {
+    pub name: String,
+    pub players: usize,
+    pub seated: usize,
+    pub spectators: usize,
+    /// Hands dealt so far, counting the one in progress.
+    pub hands: usize,
+    /// Whether the game at the table is over.
+    pub over: bool,
+}
+
+/// The name, type and help of each metric `/metrics` reports per table.
+const METRICS: [(&str, &str, &str); 5] = [
+    ("poker_seats", "gauge", "Seats at the table."),
+    ("poker_seated_players", "gauge", "Players who took a seat."),
+    ("poker_spectators", "gauge", "Clients watching the table."),
+    ("poker_hands_total", "counter", "Hands dealt at the table."),
+    ("poker_game_over", "gauge", "1 once the game is over."),
+];
+
+impl TableStatus {
+    /// The value of each of `METRICS` for the table.
+    fn metrics(&self) -> [u64; 5] {
+        [
+            self.players as u64,
+            self.seated as u64,
+            self.spectators as u64,
+            self.hands as u64,
+            self.over as u64,
+        ]
+    }
+}
+
+impl StatusReport {
+    /// The report in the Prometheus text format.
+    pub fn prometheus(&self) -> String {
+        let mut text = String::new();
+        for (i, (name, kind, help)) in METRICS.iter().enumerate() {
+            let _ = writeln!(text, "# HELP {name} {help}\n# TYPE {name} {kind}");
+            for table in &self.tables {
+                let label = table
+                    .name
+                    .replace('\\', "\\\\")
+                    .replace('"', "\\\"")
+                    .replace('\n', "\\n");
+                let value = table.metrics()[i];
+                let _ = writeln!(text, "{name}{{table=\"{label}\"}} {value}");
+            }
+        }
+        let uptime = "poker_uptime_seconds";
+        let _ = writeln!(text, "# HELP {uptime} Seconds since the host opened.");
+        let _ = writeln!(text, "# TYPE {uptime} gauge\n{uptime} {}", self.uptime_secs);
+        text
+    }
+}
+
+/// Answers requests on `listener` on a thread of its own with what `report` says at the
+/// time, one request at a time.
+pub fn serve_status(listener: TcpListener, report: impl Fn() -> StatusReport + Send + 'static) {
+    thread::spawn(move || {
+        for stream in listener.incoming() {
+            // A client that went away doesn't matter to the next one.
+            let _ = stream.and_then(|stream| answer(stream, &report));
+        }
+    });
+}
+
+fn answer(mut stream: TcpStream, report: &impl Fn() -> StatusReport) -> io::Result<()> {
+    stream.set_read_timeout(Some(REQUEST_TIMEOUT))?;
+    let mut request = String::new();
+    BufReader::new(&stream).read_line(&mut request)?;
+    let (status, kind, body) = match request.split_whitespace().collect::<Vec<_>>()[..] {
+        ["GET", "/status", ..] => {
+            let json = serde_json::to_string(&report()).expect("reports serialize");
+            ("200 OK", "application/json", json)
+        }
+        ["GET", "/metrics", ..] => ("200 OK", "text/plain; version=0.0.4", report().prometheus()),
+        ["GET", ..] => ("404 Not Found", "text/plain", "Not found\n".to_owned()),
+        _ => (
+            "405 Method Not Allowed",
+            "text/plain",
+            "Only GET\n".to_owned(),
+        ),
+    };
+    write!(
+        stream,
+        "HTTP/1.1 {status}\r\nContent-Type: {kind}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+        body.len()
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Read;
+
+    fn get(addr: &str, path: &str) -> String {
+        let mut stream = TcpStream::connect(addr).unwrap();
+        write!(stream, "GET {path} HTTP/1.1\r\nHost: localhost\r\n\r\n").unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).unwrap();
+        response
+    }
+
+    #[test]
+    fn should_report_the_tables_as_json_and_as_metrics() {
+        let report = StatusReport {
+            uptime_secs: 42,
+            tables: vec![TableStatus {
+                name: "the \"club\"".to_owned(),
+                players: 6,
+                seated: 4,
+                spectators: 1,
+                hands: 17,
+                over: false,
+            }],
+        };
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap().to_string();
+        let served = report.clone();
+        serve_status(listener, move || served.clone());
+
+        let response = get(&addr, "/status");
+        assert!(response.starts_with("HTTP/1.1 200 OK"));
+        let (_, body) = response.split_once("\r\n\r\n").unwrap();
+        assert_eq!(serde_json::from_str::<StatusReport>(body).unwrap(), report);
+
+        let response = get(&addr, "/metrics");
+        assert!(response.contains("poker_hands_total{table=\"the \\\"club\\\"\"} 17\n"));
+        assert!(response.contains("poker_uptime_seconds 42\n"));
+        assert!(get(&addr, "/admin").starts_with("HTTP/1.1 404"));
+    }
+}
@@ -0,0 +1,97 @@
+//! Proptest strategies for the engine's types, for property tests of the engine and of code
+//! built on it: `any::<Card>()`, `any::<Deck>()` and `any::<PokerAction>()`, hole cards for a
+//! table and sequences of actions. Behind the `proptest` feature.
+
+use proptest::prelude::*;
+
+use crate::core_engine::{Card, Deck, Suit};
+use crate::{PokerAction, MAX_RAISE};
+
+impl Arbitrary for Suit {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<Self>;
+
+    fn arbitrary_with(_: ()) -> Self::Strategy {
+        prop_oneof![
+            Just(Suit::Hearts),
+            Just(Suit::Spades),
+            Just(Suit::Diamonds),
+            Just(Suit::Clubs),
+        ]
+        .boxed()
+    }
+}
+
+impl Arbitrary for Card {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<Self>;
+
+    fn arbitrary_with(_: ()) -> Self::Strategy {
+        (any::<Suit>(), 2..=14u8)
+            .prop_map(|(suit, value)| Card { suit, value })
+            .boxed()
+    }
+}
+
+/// A full deck of 52 cards in any order.
+impl Arbitrary for Deck {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<Self>;
+
+    fn arbitrary_with(_: ()) -> Self::Strategy {
+        Just(Deck::ordered_deck().cards().to_vec())
+            .prop_shuffle()
+            .prop_map(Deck::init)
+            .boxed()
+    }
+}
+
+/// Raises go from one chip to `MAX_RAISE`, so some are more than a stack holds.
+impl Arbitrary for PokerAction {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<Self>;
+
+    fn arbitrary_with(_: ()) -> Self::Strategy {
+        prop_oneof![
+            Just(PokerAction::CallOrCheck),
+            Just(PokerAction::Fold),
+            (1..=MAX_RAISE).prop_map(PokerAction::Raise),
+        ]
+        .boxed()
+    }
+}
+
+/// Two cards for each of `players` players, no card dealt twice.
+pub fn hole_cards(players: usize) -> impl Strategy<Value = Vec<(Card, Card)>> {
+    any::<Deck>().prop_map(move |deck| {
+        deck.cards()
+            .chunks(2)
+            .take(players)
+            .map(|pair| (pair[0], pair[1]))
+            .collect()
+    })
+}
+
+/// Between `min` and `max` actions, to play one after the other.
+pub fn actions(min: usize, max: usize) -> impl Strategy<Value = Vec<PokerAction>> {
+    prop::collection::vec(any::<PokerAction>(), min..=max)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    proptest! {
+        #[test]
+        fn should_deal_distinct_valid_cards(hands in hole_cards(9), deck in any::<Deck>()) {
+            let cards: Vec<Card> = hands.iter().flat_map(|&(a, b)| [a, b]).collect();
+            prop_assert_eq!(cards.len(), 18);
+            for (i, card) in cards.iter().enumerate() {
+                prop_assert!((2..=14).contains(&card.value));
+                prop_assert!(!cards[i + 1..].contains(card));
+            }
+            prop_assert_eq!(deck.cards().len(), 52);
+            prop_assert!(Deck::ordered_deck().cards().iter().all(|c| deck.cards().contains(c)));
+        }
+    }
+}
@@ -0,0 +1,160 @@
+use std::io;
+
+use crate::bots::BotKind;
+use crate::simulation::{simulate, SeatResult};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Format {
+    /// Every bot plays a heads-up match against every other bot.
+    RoundRobin,
+    /// Single elimination, the winner of each heads-up match moves on to the next round.
+    Bracket,
+}
+
+impl Format {
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "round-robin" => Some(Format::RoundRobin),
+            "bracket" => Some(Format::Bracket),
+            _ => None,
+        }
+    }
+}
+
+/// How one entrant did over all of its matches.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Standing {
+    /// Index of the bot in the list of entrants.
+    pub entrant: usize,
+    pub matches_played: u32,
+    pub matches_won: u32,
+    pub hands: u32,
+    pub result: SeatResult,
+}
+
+impl Standing {
+    pub fn bb_per_100(&self) -> f64 {
+        self.result.bb_per_100(self.hands)
+    }
+
+    /// Half the width of the 95% confidence interval around `bb_per_100`.
+    pub fn confidence_95(&self) -> f64 {
+        self.result.confidence_95(self.hands)
+    }
+}
+
+/// Plays the entrants against each other in heads-up matches of `hands` hands and returns
+/// the leaderboard, best bot first. Round robins are ranked by big blinds won per 100
+/// hands, brackets by how far the bot got and then by big blinds won. Every match starts
+/// with freshly built bots, so nothing a bot learns carries over to its next opponent.
+pub fn run_tournament(
+    entrants: &[BotKind],
+    format: Format,
+    hands: u32,
+    stack: u32,
+    seed: Option<u64>,
+) -> io::Result<Vec<Standing>> {
+    let mut standings: Vec<Standing> = (0..entrants.len())
+        .map(|entrant| Standing {
+            entrant,
+            ..Standing::default()
+        })
+        .collect();
+    let mut matches = 0;
+    let mut play = |a: usize, b: usize, standings: &mut [Standing]| -> io::Result<usize> {
+        let seed = seed.map(|s| s.wrapping_add(3 * matches));
+        matches += 1;
+        let mut bots = vec![
+            entrants[a].build_seeded(seed)?,
+            entrants[b].build_seeded(seed.map(|s| s.wrapping_add(1)))?,
+        ];
+        let result = simulate(&mut bots, hands, stack, seed.map(|s| s.wrapping_add(2)));
+        let winner = if result.seats[1].net_chips > result.seats[0].net_chips {
+            b
+        } else {
+            a
+        };
+        for (entrant, seat) in [a, b].into_iter().zip(&result.seats) {
+            let standing = &mut standings[entrant];
+            standing.matches_played += 1;
+            standing.matches_won += (entrant == winner) as u32;
+            standing.hands += hands;
+            standing.result.add(seat);
+        }
+        Ok(winner)
+    };
+
+    let mut rounds_won = vec![0; entrants.len()];
+    match format {
+        Format::RoundRobin => {
+            for a in 0..entrants.len() {
+                for b in a + 1..entrants.len() {
+                    play(a, b, &mut standings)?;
+                }
+            }
+        }
+        Format::Bracket => {
+            let mut remaining: Vec<usize> = (0..entrants.len()).collect();
+            while remaining.len() > 1 {
+                let mut next = vec![];
+                for pair in remaining.chunks(2) {
+                    // The odd one out gets a bye into the next round.
+                    let winner = match pair {
+                        [a, b] => play(*a, *b, &mut standings)?,
+                        [a] => *a,
+                        _ => unreachable!(),
+                    };
+                    rounds_won[winner] += 1;
+                    next.push(winner);
+                }
+                remaining = next;
+            }
+        }
+    }
+
+    standings.sort_by(|a, b| {
+        rounds_won[b.entrant]
+            .cmp(&rounds_won[a.entrant])
+            .then(b.bb_per_100().total_cmp(&a.bb_per_100()))
+    });
+    Ok(standings)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_play_every_pair_in_a_round_robin() {
+        let entrants = [BotKind::Easy, BotKind::Normal, BotKind::Easy];
+        let standings = run_tournament(&entrants, Format::RoundRobin, 50, 100, Some(1)).unwrap();
+
+        assert_eq!(standings.len(), 3);
+        assert!(standings.iter().all(|s| s.matches_played == 2));
+        assert!(standings.iter().all(|s| s.hands == 100));
+        assert_eq!(standings.iter().map(|s| s.matches_won).sum::<u32>(), 3);
+        assert_eq!(standings.iter().map(|s| s.result.net_chips).sum::<i64>(), 0);
+        assert!(standings
+            .windows(2)
+            .all(|w| w[0].bb_per_100() >= w[1].bb_per_100()));
+        assert!(standings.iter().all(|s| s.confidence_95() > 0.0));
+    }
+
+    #[test]
+    fn should_eliminate_losers_in_a_bracket() {
+        let entrants = [BotKind::Easy, BotKind::Normal, BotKind::Easy];
+        let standings = run_tournament(&entrants, Format::Bracket, 50, 100, None).unwrap();
+
+        // Seats 0 and 1 play, the winner meets seat 2 in the final.
+        assert_eq!(standings.iter().map(|s| s.matches_played).sum::<u32>(), 4);
+        assert_eq!(standings[0].matches_won, standings[0].matches_played);
+        assert!(standings.iter().any(|s| s.matches_played == 1));
+    }
+
+    #[test]
+    fn should_parse_formats() {
+        assert_eq!(Format::parse("bracket"), Some(Format::Bracket));
+        assert_eq!(Format::parse("round-robin"), Some(Format::RoundRobin));
+        assert_eq!(Format::parse("swiss"), None);
+    }
+}
@@ -0,0 +1,279 @@
+//! The ways a networked game's messages travel: lines of text over plain TCP, or frames
+//! over WebSocket for browsers and networks that only let HTTP through. The session in
+//! `net` only sees whole messages and doesn't care which one a client uses.
+//!
+//! Messages are text or binary, and every one says which: binary messages go in binary
+//! frames over WebSocket, and over plain TCP after a zero byte and their length, which no
+//! line of text starts with.
+
+use std::io::{self, BufRead, BufReader, ErrorKind, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::sync::mpsc::Sender;
+use std::thread;
+use std::time::Duration;
+
+use tungstenite::protocol::WebSocketConfig;
+use tungstenite::{Message, WebSocket};
+
+/// The longest message a server reads from a client. The longest a client has reason to
+/// send, its keys after a mental shuffle, take about 15 KiB.
+pub const MAX_CLIENT_MESSAGE: usize = 64 * 1024;
+
+/// One message as it travels.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Frame {
+    Text(String),
+    Binary(Vec<u8>),
+}
+
+/// Carries one message at a time in both directions.
+pub trait Transport: Send {
+    fn send(&mut self, frame: &Frame) -> io::Result<()>;
+
+    /// The next message, or `None` when none arrived within the read timeout of the
+    /// underlying stream. A connection closed by the other side is an `UnexpectedEof`.
+    fn recv(&mut self) -> io::Result<Option<Frame>>;
+}
+
+fn hung_up() -> io::Error {
+    io::Error::new(ErrorKind::UnexpectedEof, "the other side hung up")
+}
+
+fn too_long() -> io::Error {
+    io::Error::new(ErrorKind::InvalidData, "message too long")
+}
+
+fn timed_out(e: &io::Error) -> bool {
+    matches!(e.kind(), ErrorKind::WouldBlock | ErrorKind::TimedOut)
+}
+
+/// What starts a binary message over plain TCP.
+const BINARY: u8 = 0;
+
+/// Messages as lines of text over TCP, and binary ones between them.
+pub struct LineTransport {
+    reader: BufReader<TcpStream>,
+    writer: TcpStream,
+    /// What was read of a message that hadn't fully arrived before the read timed out.
+    partial: Vec<u8>,
+    /// The longest message the other side may send.
+    max_message: usize,
+}
+
+impl LineTransport {
+    pub fn new(stream: TcpStream) -> io::Result<Self> {
+        Ok(Self {
+            reader: BufReader::new(stream.try_clone()?),
+            writer: stream,
+            partial: vec![],
+            max_message: usize::MAX,
+        })
+    }
+
+    /// Takes a message longer than `bytes` for a broken connection.
+    pub fn max_message(mut self, bytes: usize) -> Self {
+        self.max_message = bytes;
+        self
+    }
+}
+
+impl LineTransport {
+    /// Takes the first whole message off what was read so far, if there is one, or fails
+    /// once it is sure to be too long.
+    fn take_frame(&mut self) -> io::Result<Option<Frame>> {
+        if self.partial.first() == Some(&BINARY) {
+            let Some(length) = self.partial.get(1..5) else {
+                return Ok(None);
+            };
+            let length = u32::from_be_bytes(length.try_into().unwrap()) as usize;
+            if length > self.max_message {
+                return Err(too_long());
+            }
+            if self.partial.len() < 5 + length {
+                return Ok(None);
+            }
+            let message = self.partial[5..5 + length].to_vec();
+            self.partial.drain(..5 + length);
+            return Ok(Some(Frame::Binary(message)));
+        }
+        let Some(end) = self.partial.iter().position(|&b| b == b'\n') else {
+            return match self.partial.len() > self.max_message {
+                true => Err(too_long()),
+                false => Ok(None),
+            };
+        };
+        if end > self.max_message {
+            return Err(too_long());
+        }
+        let line = String::from_utf8_lossy(&self.partial[..end])
+            .trim_end()
+            .to_owned();
+        self.partial.drain(..=end);
+        Ok(Some(Frame::Text(line)))
+    }
+}
+
+impl Transport for LineTransport {
+    fn send(&mut self, frame: &Frame) -> io::Result<()> {
+        match frame {
+            Frame::Text(text) => writeln!(self.writer, "{text}"),
+            Frame::Binary(message) => {
+                let length = u32::try_from(message.len())
+                    .map_err(|_| io::Error::new(ErrorKind::InvalidInput, "message too long"))?;
+                let mut bytes = vec![BINARY];
+                bytes.extend(length.to_be_bytes());
+                bytes.extend(message);
+                self.writer.write_all(&bytes)
+            }
+        }
+    }
+
+    fn recv(&mut self) -> io::Result<Option<Frame>> {
+        loop {
+            if let Some(frame) = self.take_frame()? {
+                return Ok(Some(frame));
+            }
+            let read = match self.reader.fill_buf() {
+                // Whatever was read of the next message is cut off for good.
+                Ok([]) => return Err(hung_up()),
+                Ok(read) => read.to_vec(),
+                Err(e) if timed_out(&e) => return Ok(None),
+                Err(e) => return Err(e),
+            };
+            self.reader.consume(read.len());
+            self.partial.extend(read);
+        }
+    }
+}
+
+fn ws_error(e: tungstenite::Error) -> io::Error {
+    match e {
+        tungstenite::Error::Io(e) => e,
+        tungstenite::Error::ConnectionClosed | tungstenite::Error::AlreadyClosed => hung_up(),
+        e => io::Error::other(e),
+    }
+}
+
+impl Transport for WebSocket<TcpStream> {
+    fn send(&mut self, frame: &Frame) -> io::Result<()> {
+        let message = match frame {
+            Frame::Text(text) => Message::text(text.as_str()),
+            Frame::Binary(message) => Message::binary(message.clone()),
+        };
+        WebSocket::send(self, message).map_err(ws_error)
+    }
+
+    fn recv(&mut self) -> io::Result<Option<Frame>> {
+        match self.read().map_err(ws_error) {
+            Ok(Message::Text(text)) => Ok(Some(Frame::Text(text.as_str().to_owned()))),
+            Ok(Message::Binary(message)) => Ok(Some(Frame::Binary(message.to_vec()))),
+            Ok(Message::Close(_)) => Err(hung_up()),
+            // Pings are answered by the next read or send.
+            Ok(_) => Ok(None),
+            Err(e) if timed_out(&e) => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+}
+
+/// Opens a WebSocket to a `ws://host:port` address.
+pub fn connect_ws(url: &str) -> io::Result<WebSocket<TcpStream>> {
+    let authority = url
+        .strip_prefix("ws://")
+        .and_then(|rest| rest.split('/').next())
+        .ok_or_else(|| io::Error::new(ErrorKind::InvalidInput, "expected ws://host:port"))?;
+    let stream = TcpStream::connect(authority)?;
+    let (socket, _) =
+        tungstenite::client(url, stream).map_err(|e| io::Error::other(e.to_string()))?;
+    Ok(socket)
+}
+
+/// A port the server takes players on, and the transport they speak there.
+pub enum Listener {
+    Tcp(TcpListener),
+    WebSocket(TcpListener),
+}
+
+/// How often a seat waiting for its client's next message checks for messages to send.
+const POLL_INTERVAL: Duration = Duration::from_millis(20);
+
+impl Listener {
+    /// Accepts connections on a background thread, handing over each client through
+    /// `joined` once it is ready to exchange messages.
+    pub fn spawn(self, joined: Sender<(Box<dyn Transport>, SocketAddr)>) {
+        thread::spawn(move || {
+            let listener = match &self {
+                Listener::Tcp(listener) | Listener::WebSocket(listener) => listener,
+            };
+            for stream in listener.incoming() {
+                let Ok(stream) = stream else {
+                    continue;
+                };
+                let websocket = matches!(self, Listener::WebSocket(_));
+                let joined = joined.clone();
+                // A slow handshake shouldn't hold up the next player.
+                thread::spawn(move || {
+                    if let Ok(client) = Self::ready(stream, websocket) {
+                        let _ = joined.send(client);
+                    }
+                });
+            }
+        });
+    }
+
+    fn ready(stream: TcpStream, websocket: bool) -> io::Result<(Box<dyn Transport>, SocketAddr)> {
+        let addr = stream.peer_addr()?;
+        let transport: Box<dyn Transport> = if websocket {
+            let config = WebSocketConfig::default()
+                .max_message_size(Some(MAX_CLIENT_MESSAGE))
+                .max_frame_size(Some(MAX_CLIENT_MESSAGE));
+            let socket = tungstenite::accept_with_config(stream, Some(config))
+                .map_err(|e| io::Error::other(e.to_string()))?;
+            socket.get_ref().set_read_timeout(Some(POLL_INTERVAL))?;
+            Box::new(socket)
+        } else {
+            stream.set_read_timeout(Some(POLL_INTERVAL))?;
+            Box::new(LineTransport::new(stream)?.max_message(MAX_CLIENT_MESSAGE))
+        };
+        Ok((transport, addr))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_join_messages_that_arrive_in_pieces() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let mut client = TcpStream::connect(listener.local_addr().unwrap()).unwrap();
+        let (stream, _) = listener.accept().unwrap();
+        stream.set_read_timeout(Some(POLL_INTERVAL)).unwrap();
+        let mut transport = LineTransport::new(stream).unwrap();
+
+        write!(client, "{{\"type\":").unwrap();
+        assert_eq!(transport.recv().unwrap(), None);
+        writeln!(client, "\"game_over\"}}").unwrap();
+        let line = loop {
+            if let Some(line) = transport.recv().unwrap() {
+                break line;
+            }
+        };
+        assert_eq!(line, Frame::Text("{\"type\":\"game_over\"}".to_owned()));
+
+        client.write_all(&[BINARY, 0, 0, 0, 3, 1]).unwrap();
+        assert_eq!(transport.recv().unwrap(), None);
+        client.write_all(&[b'\n', 2]).unwrap();
+        let message = loop {
+            if let Some(message) = transport.recv().unwrap() {
+                break message;
+            }
+        };
+        assert_eq!(message, Frame::Binary(vec![1, b'\n', 2]));
+        drop(client);
+        assert_eq!(
+            transport.recv().unwrap_err().kind(),
+            ErrorKind::UnexpectedEof
+        );
+    }
+}
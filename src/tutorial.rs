@@ -0,0 +1,137 @@
+use crate::core_engine::{Card, Deck};
+use crate::PokerAction::{self, *};
+
+/// The part of the table a tutorial step is talking about.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Highlight {
+    Nothing,
+    Pot,
+    Board,
+    Hands,
+    Bets,
+    Stacks,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum StepKind {
+    /// Only explains something, the learner continues when ready.
+    Read,
+    /// The learner, sitting as player 0, has to play this action to continue.
+    Play(PokerAction),
+    /// Player 1 plays this action on their own.
+    Opponent(PokerAction),
+}
+
+pub struct TutorialStep {
+    pub text: &'static str,
+    pub highlight: Highlight,
+    pub kind: StepKind,
+}
+
+const DEALT_CARDS: &str = "S14 S13 H9 D9 S12 D4 C7 S11 H10";
+
+/// A deck that deals the learner ace-king of spades against a pair of nines, running out
+/// into a straight for the learner.
+pub fn deck() -> Deck {
+    let mut cards: Vec<Card> = DEALT_CARDS
+        .split_ascii_whitespace()
+        .map(|c| Card::try_from(c).unwrap())
+        .collect();
+    let rest: Vec<Card> = Deck::ordered_deck()
+        .cards()
+        .iter()
+        .filter(|c| !cards.contains(c))
+        .copied()
+        .collect();
+    cards.extend(rest);
+    cards.reverse();
+    Deck::init(cards)
+}
+
+pub fn steps() -> Vec<TutorialStep> {
+    use Highlight::*;
+    use StepKind::*;
+
+    let step = |text, highlight, kind| TutorialStep {
+        text,
+        highlight,
+        kind,
+    };
+
+    vec![
+        step(
+            "Welcome! You are player 0 and hold the two cards on the left. Only you get to use them.",
+            Hands,
+            Read,
+        ),
+        step(
+            "Before any cards are seen two players post blinds: the small blind puts in 1 chip and the big blind 2. These forced bets give everyone something to play for.",
+            Bets,
+            Read,
+        ),
+        step(
+            "Ace-king is a strong start. To stay in you must match the big blind. Type 'c' to call 1 chip.",
+            Bets,
+            Play(CallOrCheck),
+        ),
+        step(
+            "Player 1 already paid the big blind, so they can check.",
+            Bets,
+            Opponent(CallOrCheck),
+        ),
+        step(
+            "Everyone has matched the highest bet, so the betting round is over. The bets went into the pot and three shared cards, the flop, were dealt.",
+            Board,
+            Read,
+        ),
+        step(
+            "You hold the A-K-Q of a straight and three spades. Bet to build the pot: type 'r 4' to raise by 4 chips.",
+            Pot,
+            Play(Raise(4)),
+        ),
+        step(
+            "Player 1 calls your raise with their pair of nines.",
+            Bets,
+            Opponent(CallOrCheck),
+        ),
+        step(
+            "The turn brings the jack of spades. Type 'c' to check, passing the action without betting.",
+            Board,
+            Play(CallOrCheck),
+        ),
+        step("Player 1 checks as well.", Board, Opponent(CallOrCheck)),
+        step(
+            "The river completes your straight: ace, king, queen, jack, ten! Check once more to reach the showdown.",
+            Board,
+            Play(CallOrCheck),
+        ),
+        step("Player 1 checks behind.", Board, Opponent(CallOrCheck)),
+        step(
+            "At showdown the best five-card hand wins. From weakest to strongest: high card, pair, two pairs, three of a kind, straight, flush, full house, four of a kind, straight flush. Your straight beats their pair of nines and the pot is yours!",
+            Stacks,
+            Read,
+        ),
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{GameState, TurnResult};
+
+    #[test]
+    fn should_play_the_scripted_hand_to_a_win_for_the_learner() {
+        let (mut hs, first) = GameState::init(2).unwrap().start_play_hand(deck());
+        assert_eq!(first, 0);
+
+        let mut result = None;
+        for step in steps() {
+            if let StepKind::Play(action) | StepKind::Opponent(action) = step.kind {
+                result = Some(hs.play_action(action).unwrap());
+            }
+        }
+
+        assert!(matches!(result, Some(TurnResult::WonHand(0))));
+        assert!(hs.is_showdown());
+    }
+}
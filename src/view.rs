@@ -0,0 +1,189 @@
+//! What one seat may know about a hand, projected from the full `HandState` that only the
+//! server holds. A `SeatView` has no place for another player's hole cards until they are
+//! shown, so a client can't learn them whatever it does with the data. A `PublicView` is
+//! the same for someone watching, with no hole cards at all.
+
+use serde::{Deserialize, Serialize};
+
+use crate::bots::PlayerView;
+use crate::core_engine::Card;
+use crate::events::Street;
+use crate::{HandSnapshot, HandState, HandVisibility, PlayerChips, Pot};
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SeatView {
+    /// The seat's own cards and the public state of the table.
+    pub view: PlayerView,
+    pub current_player: usize,
+    pub small_blind: usize,
+    pub big_blind: usize,
+    pub pots: Vec<Pot>,
+    /// The hole cards of every player who showed them at showdown, by seat.
+    pub shown: Vec<Option<(Card, Card)>>,
+}
+
+/// What anyone watching the table may know.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PublicView {
+    pub button: usize,
+    pub board: Vec<Card>,
+    pub street: Street,
+    pub stacks: Vec<u32>,
+    pub bets: Vec<u32>,
+    /// Players who have not folded.
+    pub active: Vec<bool>,
+    pub pot: u32,
+    pub current_player: usize,
+    pub small_blind: usize,
+    pub big_blind: usize,
+    pub pots: Vec<Pot>,
+    /// The hole cards of every player who showed them at showdown, by seat.
+    pub shown: Vec<Option<(Card, Card)>>,
+}
+
+impl HandState {
+    pub fn seat_view(&self, player: usize) -> SeatView {
+        SeatView {
+            view: self.player_view(player),
+            current_player: self.turn.current_player,
+            small_blind: self.small_blind(),
+            big_blind: self.big_blind,
+            pots: self.chips.pots(&self.turn.active_players, &self.folds()),
+            shown: self.shown(),
+        }
+    }
+
+    pub fn public_view(&self) -> PublicView {
+        PublicView::from(&self.seat_view(self.turn.current_player))
+    }
+
+    fn shown(&self) -> Vec<Option<(Card, Card)>> {
+        (0..self.players)
+            .map(|p| {
+                (self.is_showdown() && self.turn.active_players[p]).then(|| self.hole_cards(p))
+            })
+            .collect()
+    }
+}
+
+impl From<&SeatView> for PublicView {
+    /// The seat's view without its cards.
+    fn from(seat: &SeatView) -> Self {
+        let view = &seat.view;
+        PublicView {
+            button: view.button,
+            board: view.board.clone(),
+            street: view.street,
+            stacks: view.stacks.clone(),
+            bets: view.bets.clone(),
+            active: view.active.clone(),
+            pot: view.pot,
+            current_player: seat.current_player,
+            small_blind: seat.small_blind,
+            big_blind: seat.big_blind,
+            pots: seat.pots.clone(),
+            shown: seat.shown.clone(),
+        }
+    }
+}
+
+impl From<&PublicView> for HandSnapshot {
+    /// The table for drawing on a spectator's screen.
+    fn from(view: &PublicView) -> Self {
+        let players = view.stacks.len();
+        let hands = (0..players)
+            .map(|p| match view.shown[p] {
+                Some((a, b)) => HandVisibility::Visible(a, b),
+                None if !view.active[p] => HandVisibility::Folded,
+                None => HandVisibility::Hidden,
+            })
+            .collect();
+        let highest_bet = view.bets.iter().copied().max().unwrap_or(0);
+        HandSnapshot {
+            board: view.board.clone(),
+            hands,
+            chips: (0..players)
+                .map(|p| PlayerChips {
+                    stack: view.stacks[p],
+                    bet: view.bets[p],
+                })
+                .collect(),
+            pot: view.pot,
+            pots: view.pots.clone(),
+            current_player: view.current_player,
+            expected_call: highest_bet - view.bets[view.current_player],
+            players,
+            button: view.button,
+            small_blind: view.small_blind,
+            big_blind: view.big_blind,
+        }
+    }
+}
+
+impl From<&SeatView> for HandSnapshot {
+    /// The table for drawing on the seat's screen.
+    fn from(seat: &SeatView) -> Self {
+        let mut snapshot = HandSnapshot::from(&PublicView::from(seat));
+        let view = &seat.view;
+        if snapshot.hands[view.player] == HandVisibility::Hidden {
+            snapshot.hands[view.player] = HandVisibility::Visible(view.cards.0, view.cards.1);
+        }
+        snapshot
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core_engine::Deck;
+    use crate::{GameState, PokerAction, TurnResult};
+
+    #[test]
+    fn should_leave_out_other_players_cards_until_the_showdown() {
+        let (mut hs, cur) = GameState::init(3)
+            .unwrap()
+            .start_play_hand(Deck::ordered_deck());
+        hs.play_action(PokerAction::Fold).unwrap();
+
+        let seat = (cur + 1) % 3;
+        let view = hs.seat_view(seat);
+        let json = serde_json::to_string(&view).unwrap();
+        for p in (0..3).filter(|&p| p != seat) {
+            let (a, b) = hs.player_view(p).cards;
+            assert!(!json.contains(&serde_json::to_string(&a).unwrap()));
+            assert!(!json.contains(&serde_json::to_string(&b).unwrap()));
+        }
+        let snapshot = HandSnapshot::from(&view);
+        assert_eq!(snapshot.hands[cur], HandVisibility::Folded);
+        assert!(matches!(snapshot.hands[seat], HandVisibility::Visible(..)));
+        assert_eq!(snapshot.hands[(seat + 1) % 3], HandVisibility::Hidden);
+        assert_eq!(
+            snapshot.expected_call,
+            hs.spectator_snapshot().expected_call
+        );
+
+        while let TurnResult::NextPlayer(_) = hs.play_action(PokerAction::CallOrCheck).unwrap() {}
+        let snapshot = HandSnapshot::from(&hs.seat_view(seat));
+        assert_eq!(snapshot.hands, hs.public_snapshot().hands);
+    }
+
+    #[test]
+    fn should_show_spectators_no_hole_cards_until_the_showdown() {
+        let (mut hs, _) = GameState::init(3)
+            .unwrap()
+            .start_play_hand(Deck::ordered_deck());
+        hs.play_action(PokerAction::Fold).unwrap();
+        let json = serde_json::to_string(&hs.public_view()).unwrap();
+        for p in 0..3 {
+            let (a, b) = hs.player_view(p).cards;
+            assert!(!json.contains(&serde_json::to_string(&a).unwrap()));
+            assert!(!json.contains(&serde_json::to_string(&b).unwrap()));
+        }
+        let snapshot = HandSnapshot::from(&hs.public_view());
+        assert_eq!(snapshot.hands, hs.public_snapshot().hands);
+
+        while let TurnResult::NextPlayer(_) = hs.play_action(PokerAction::CallOrCheck).unwrap() {}
+        let snapshot = HandSnapshot::from(&hs.public_view());
+        assert_eq!(snapshot.hands, hs.public_snapshot().hands);
+    }
+}
@@ -0,0 +1,115 @@
+//! Bindings for a browser client, which talks to the same server over WebSocket and uses
+//! these to check its actions and make sense of what the server sends before drawing it.
+//! Build them with `cargo build --lib --target wasm32-unknown-unknown --features wasm` and
+//! run `wasm-bindgen` over the result.
+//!
+//! Everything goes in and comes out as the JSON the protocol uses, and errors come out as
+//! strings, which JavaScript sees thrown.
+
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+use wasm_bindgen::prelude::wasm_bindgen;
+
+use crate::analysis::best_hand;
+use crate::bots::LegalActions;
+use crate::core_engine::Card;
+use crate::protocol::{apply_merge_patch, decode, ClientMessage, ServerMessage, PROTOCOL_VERSION};
+use crate::transport::Frame;
+use crate::view::SeatView;
+use crate::PokerAction;
+
+fn parse<T: DeserializeOwned>(json: &str) -> Result<T, String> {
+    serde_json::from_str(json).map_err(|e| e.to_string())
+}
+
+fn to_json(message: &ServerMessage) -> String {
+    serde_json::to_string(message).expect("messages serialize")
+}
+
+#[wasm_bindgen]
+pub fn protocol_version() -> u32 {
+    PROTOCOL_VERSION
+}
+
+/// The first message to send to sit down at `table`, the first one if empty.
+#[wasm_bindgen]
+pub fn join_message(table: &str, password: Option<String>) -> String {
+    let join = ClientMessage::join(table, password.as_deref());
+    serde_json::to_string(&join).expect("messages serialize")
+}
+
+/// Whether the server takes `action` when it asks for one with `legal`, the way it checks.
+#[wasm_bindgen]
+pub fn is_legal(legal: &str, action: &str) -> Result<bool, String> {
+    let legal: LegalActions = parse(legal)?;
+    let action: PokerAction = parse(action)?;
+    Ok(legal.allows(action))
+}
+
+/// A message sent as text, checked to be one the server sends.
+#[wasm_bindgen]
+pub fn read_text(text: &str) -> Result<String, String> {
+    decode(&Frame::Text(text.to_owned())).map(|message| to_json(&message))
+}
+
+/// A message sent in MessagePack, as JSON.
+#[wasm_bindgen]
+pub fn read_binary(bytes: &[u8]) -> Result<String, String> {
+    decode(&Frame::Binary(bytes.to_vec())).map(|message| to_json(&message))
+}
+
+/// The table `TableChanges` makes out of the last one.
+#[wasm_bindgen]
+pub fn apply_table_changes(view: &str, changes: &str) -> Result<String, String> {
+    let mut view: Value = parse(view)?;
+    apply_merge_patch(&mut view, &parse(changes)?);
+    let view: SeatView = serde_json::from_value(view).map_err(|e| e.to_string())?;
+    Ok(serde_json::to_string(&view).expect("views serialize"))
+}
+
+/// The name of the best hand out of five to seven cards, like "Two pairs".
+#[wasm_bindgen]
+pub fn hand_name(cards: &str) -> Result<String, String> {
+    let cards: Vec<Card> = parse(cards)?;
+    if !(5..=7).contains(&cards.len()) {
+        return Err(format!(
+            "a hand is made of 5 to 7 cards, not {}",
+            cards.len()
+        ));
+    }
+    let hand = best_hand(&cards).expect("there are at least five cards");
+    Ok(hand.ranking_name().to_owned())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core_engine::Deck;
+    use crate::protocol::Encoding;
+    use crate::GameState;
+
+    #[test]
+    fn should_check_actions_and_read_messages_like_the_native_client() {
+        let (hs, _) = GameState::init(2)
+            .unwrap()
+            .start_play_hand(Deck::ordered_deck());
+        let legal = serde_json::to_string(&hs.bot_context(0).legal).unwrap();
+        assert_eq!(is_legal(&legal, r#""CallOrCheck""#), Ok(true));
+        assert_eq!(is_legal(&legal, r#"{"Raise": 1000}"#), Ok(false));
+        assert!(is_legal(&legal, "call").is_err());
+
+        let view = hs.seat_view(0);
+        let message = ServerMessage::Table { view: view.clone() };
+        let Frame::Binary(bytes) = Encoding::MessagePack.encode(&message) else {
+            panic!("MessagePack is binary");
+        };
+        assert_eq!(read_binary(&bytes).unwrap(), to_json(&message));
+        assert!(read_text(r#"{"type": "nonsense"}"#).is_err());
+
+        let json = serde_json::to_string(&view).unwrap();
+        let changes = r#"{"current_player": 1}"#;
+        let changed: SeatView = parse(&apply_table_changes(&json, changes).unwrap()).unwrap();
+        assert_eq!(changed.current_player, 1);
+        assert!(hand_name(r#"[{"suit": "Spades", "value": 2}]"#).is_err());
+    }
+}